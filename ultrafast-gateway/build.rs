@@ -0,0 +1,27 @@
+//! Embeds the git commit SHA of the build for the `/version` endpoint.
+//!
+//! Set `GATEWAY_GIT_SHA` to override the value, e.g. when building from a
+//! source archive without a `.git` directory.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=GATEWAY_GIT_SHA");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+
+    let sha = std::env::var("GATEWAY_GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.is_empty())
+        .or_else(|| {
+            Command::new("git")
+                .args(["rev-parse", "--short", "HEAD"])
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .and_then(|output| String::from_utf8(output.stdout).ok())
+                .map(|sha| sha.trim().to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=GATEWAY_GIT_SHA={sha}");
+}
@@ -0,0 +1,519 @@
+//! # API Key Store Module
+//!
+//! This module looks up gateway API keys in a store of hashed keys, so keys
+//! can be issued, revoked and expired without editing `[auth].api_keys`.
+//!
+//! ## Overview
+//!
+//! A [`KeyStore`] maps the SHA-256 of each key to the user it belongs to,
+//! the user's roles, optional rate limits and the usage recorded against the
+//! key. Presented keys are hashed and compared against every stored hash in
+//! constant time, so neither the keys themselves nor timing reveal which
+//! entries exist.
+//!
+//! Two stores are built in:
+//!
+//! - [`FileApiKeyStore`] reads a TOML or JSON file (by extension) and
+//!   re-reads it when it changes, so revoking a key takes effect without a
+//!   restart. If the changed file cannot be read, the keys already loaded
+//!   stay in use. Keys are managed by editing the file, so it cannot create
+//!   or revoke keys and does not record usage.
+//! - [`InMemoryKeyStore`] keeps keys in memory, for embedding the gateway
+//!   and for tests.
+//!
+//! Other backends (a database, Redis, a key management service) implement
+//! [`KeyStore`] and are passed to
+//! [`crate::server::create_server_with_key_store`].
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [auth]
+//! api_key_store = "/etc/ultrafast/keys.toml"
+//! ```
+//!
+//! ```toml
+//! # keys.toml; key_hash is the lowercase hex SHA-256 of the key
+//! [[keys]]
+//! key_hash = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+//! user_id = "alice"
+//! roles = ["admin"]
+//! rate_limit = { requests_per_minute = 60, requests_per_hour = 1000, tokens_per_minute = 10000 }
+//! expires_at = "2027-01-01T00:00:00Z"   # Optional
+//! revoked = false
+//! ```
+
+use crate::config::{AuthConfig, RateLimitConfig};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// Requests and tokens used with a key: totals in [`ApiKeyRecord`], and the
+/// increments passed to [`KeyStore::update_usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyUsage {
+    #[serde(default)]
+    pub requests: u64,
+    #[serde(default)]
+    pub tokens: u64,
+}
+
+/// Stored entry for one API key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    /// Lowercase hex SHA-256 of the key; see [`hash_api_key`]
+    pub key_hash: String,
+    /// User the key authenticates as
+    pub user_id: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    /// Limits for this key, or the gateway defaults
+    #[serde(default)]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Revoked keys are rejected
+    #[serde(default)]
+    pub revoked: bool,
+    /// Keys are rejected from this time on
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Usage recorded against the key
+    #[serde(default)]
+    pub usage: KeyUsage,
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    /// Prompt limit for this key, overriding `routing.max_prompt_tokens`
+    #[serde(default)]
+    pub max_prompt_tokens: Option<u32>,
+}
+
+impl ApiKeyRecord {
+    /// Record for `api_key`, stored by its hash, with no roles, the gateway
+    /// default rate limits and no expiry.
+    pub fn new(api_key: &str, user_id: impl Into<String>) -> Self {
+        Self {
+            key_hash: hash_api_key(api_key),
+            user_id: user_id.into(),
+            roles: Vec::new(),
+            rate_limit: None,
+            revoked: false,
+            expires_at: None,
+            usage: KeyUsage::default(),
+            last_used_at: None,
+            max_prompt_tokens: None,
+        }
+    }
+}
+
+/// Result of looking up a presented key.
+#[derive(Debug, Clone)]
+pub enum ApiKeyLookup {
+    Valid(ApiKeyRecord),
+    Revoked,
+    Expired,
+    Unknown,
+}
+
+/// Storage for gateway API keys.
+///
+/// Keys are identified by their [`hash_api_key`] everywhere except in
+/// [`KeyStore::lookup`], which is given the key a client presented.
+#[async_trait::async_trait]
+pub trait KeyStore: Send + Sync {
+    /// Find the record for a presented `api_key`.
+    async fn lookup(&self, api_key: &str) -> ApiKeyLookup;
+
+    /// Add `record`, failing if a key with the same hash exists.
+    async fn create(&self, record: ApiKeyRecord) -> anyhow::Result<()>;
+
+    /// Mark the key with `key_hash` revoked. Returns whether it exists.
+    async fn revoke(&self, key_hash: &str) -> anyhow::Result<bool>;
+
+    /// Add `usage` to the totals of the key with `key_hash`. Unknown keys
+    /// are ignored.
+    async fn update_usage(&self, key_hash: &str, usage: KeyUsage) -> anyhow::Result<()>;
+}
+
+/// The store configured in `[auth].api_key_store`, if any.
+pub fn from_config(config: &AuthConfig) -> anyhow::Result<Option<Arc<dyn KeyStore>>> {
+    match &config.api_key_store {
+        Some(path) => Ok(Some(Arc::new(FileApiKeyStore::open(path)?))),
+        None => Ok(None),
+    }
+}
+
+/// Lowercase hex SHA-256 of `api_key`, as stored in `key_hash`.
+pub fn hash_api_key(api_key: &str) -> String {
+    format!("{:x}", Sha256::digest(api_key.as_bytes()))
+}
+
+/// Compare two digests without stopping at the first differing byte.
+fn digests_equal(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Find `api_key` among `entries`, comparing against every entry so the
+/// time taken does not depend on where (or whether) the key is found.
+fn find<'a>(entries: &'a [([u8; 32], ApiKeyRecord)], api_key: &str) -> Option<&'a ApiKeyRecord> {
+    let digest: [u8; 32] = Sha256::digest(api_key.as_bytes()).into();
+    let mut found = None;
+    for (stored, record) in entries {
+        if digests_equal(stored, &digest) && found.is_none() {
+            found = Some(record);
+        }
+    }
+    found
+}
+
+fn lookup_result(record: Option<&ApiKeyRecord>) -> ApiKeyLookup {
+    match record {
+        None => ApiKeyLookup::Unknown,
+        Some(record) if record.revoked => ApiKeyLookup::Revoked,
+        Some(record) if record.expires_at.is_some_and(|at| at <= Utc::now()) => {
+            ApiKeyLookup::Expired
+        }
+        Some(record) => ApiKeyLookup::Valid(record.clone()),
+    }
+}
+
+/// Keys file format: `{ keys = [ ... ] }` in TOML or JSON.
+#[derive(Deserialize)]
+struct KeysFile {
+    #[serde(default)]
+    keys: Vec<ApiKeyRecord>,
+}
+
+struct LoadedKeys {
+    modified: Option<SystemTime>,
+    entries: Vec<([u8; 32], ApiKeyRecord)>,
+}
+
+/// API keys read from a TOML or JSON file.
+pub struct FileApiKeyStore {
+    path: PathBuf,
+    keys: RwLock<LoadedKeys>,
+}
+
+impl FileApiKeyStore {
+    /// Read the keys in `path`, failing on unreadable files, unparsable
+    /// entries and malformed hashes.
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let keys = Self::read(&path)?;
+        Ok(Self {
+            path,
+            keys: RwLock::new(keys),
+        })
+    }
+
+    fn read(path: &Path) -> anyhow::Result<LoadedKeys> {
+        let modified = std::fs::metadata(path)?.modified().ok();
+        let content = std::fs::read_to_string(path)?;
+        let file: KeysFile = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&content)?,
+            _ => toml::from_str(&content)?,
+        };
+
+        let mut entries = Vec::with_capacity(file.keys.len());
+        for record in file.keys {
+            let digest = parse_digest(&record.key_hash).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid key_hash for user {} in {}: expected 64 hex characters",
+                    record.user_id,
+                    path.display()
+                )
+            })?;
+            entries.push((digest, record));
+        }
+        Ok(LoadedKeys { modified, entries })
+    }
+
+    /// Re-read the file if it changed since it was last read.
+    fn reload_if_changed(&self) {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified == self.keys.read().unwrap().modified {
+            return;
+        }
+        match Self::read(&self.path) {
+            Ok(keys) => *self.keys.write().unwrap() = keys,
+            Err(e) => tracing::warn!(
+                "Failed to reload API keys from {}, keeping loaded keys: {}",
+                self.path.display(),
+                e
+            ),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl KeyStore for FileApiKeyStore {
+    async fn lookup(&self, api_key: &str) -> ApiKeyLookup {
+        self.reload_if_changed();
+        let keys = self.keys.read().unwrap();
+        lookup_result(find(&keys.entries, api_key))
+    }
+
+    async fn create(&self, _record: ApiKeyRecord) -> anyhow::Result<()> {
+        Err(anyhow::anyhow!(
+            "API keys in {} are added by editing the file",
+            self.path.display()
+        ))
+    }
+
+    async fn revoke(&self, _key_hash: &str) -> anyhow::Result<bool> {
+        Err(anyhow::anyhow!(
+            "API keys in {} are revoked by editing the file",
+            self.path.display()
+        ))
+    }
+
+    async fn update_usage(&self, _key_hash: &str, _usage: KeyUsage) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// API keys held in memory.
+#[derive(Default)]
+pub struct InMemoryKeyStore {
+    entries: RwLock<Vec<([u8; 32], ApiKeyRecord)>>,
+}
+
+impl InMemoryKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl KeyStore for InMemoryKeyStore {
+    async fn lookup(&self, api_key: &str) -> ApiKeyLookup {
+        let entries = self.entries.read().unwrap();
+        lookup_result(find(&entries, api_key))
+    }
+
+    async fn create(&self, record: ApiKeyRecord) -> anyhow::Result<()> {
+        let digest = parse_digest(&record.key_hash).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid key_hash for user {}: expected 64 hex characters",
+                record.user_id
+            )
+        })?;
+        let mut entries = self.entries.write().unwrap();
+        if entries.iter().any(|(stored, _)| *stored == digest) {
+            return Err(anyhow::anyhow!(
+                "An API key with this hash already exists for user {}",
+                record.user_id
+            ));
+        }
+        entries.push((digest, record));
+        Ok(())
+    }
+
+    async fn revoke(&self, key_hash: &str) -> anyhow::Result<bool> {
+        let mut entries = self.entries.write().unwrap();
+        let record = entries
+            .iter_mut()
+            .find(|(_, record)| record.key_hash.eq_ignore_ascii_case(key_hash));
+        Ok(record.map(|(_, record)| record.revoked = true).is_some())
+    }
+
+    async fn update_usage(&self, key_hash: &str, usage: KeyUsage) -> anyhow::Result<()> {
+        let mut entries = self.entries.write().unwrap();
+        if let Some((_, record)) = entries
+            .iter_mut()
+            .find(|(_, record)| record.key_hash.eq_ignore_ascii_case(key_hash))
+        {
+            record.usage.requests += usage.requests;
+            record.usage.tokens += usage.tokens;
+            record.last_used_at = Some(Utc::now());
+        }
+        Ok(())
+    }
+}
+
+fn parse_digest(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut digest = [0u8; 32];
+    for (byte, pair) in digest.iter_mut().zip(hex.as_bytes().chunks(2)) {
+        *byte = u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok()?;
+    }
+    Some(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_keys(extension: &str, content: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "ultrafast-keys-{}.{}",
+            uuid::Uuid::new_v4(),
+            extension
+        ));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn test_file_store_lookup() {
+        let path = write_keys(
+            "toml",
+            &format!(
+                r#"
+                [[keys]]
+                key_hash = "{}"
+                user_id = "alice"
+                roles = ["admin"]
+                rate_limit = {{ requests_per_minute = 5, requests_per_hour = 50, tokens_per_minute = 500 }}
+
+                [[keys]]
+                key_hash = "{}"
+                user_id = "bob"
+                revoked = true
+
+                [[keys]]
+                key_hash = "{}"
+                user_id = "carol"
+                expires_at = "2020-01-01T00:00:00Z"
+                "#,
+                hash_api_key("sk-alice"),
+                hash_api_key("sk-bob"),
+                hash_api_key("sk-carol")
+            ),
+        );
+        let store = FileApiKeyStore::open(&path).unwrap();
+
+        match store.lookup("sk-alice").await {
+            ApiKeyLookup::Valid(record) => {
+                assert_eq!(record.user_id, "alice");
+                assert_eq!(record.roles, vec!["admin".to_string()]);
+                assert_eq!(record.rate_limit.unwrap().requests_per_minute, 5);
+            }
+            other => panic!("expected a valid key, got {other:?}"),
+        }
+        assert!(matches!(
+            store.lookup("sk-bob").await,
+            ApiKeyLookup::Revoked
+        ));
+        assert!(matches!(
+            store.lookup("sk-carol").await,
+            ApiKeyLookup::Expired
+        ));
+        assert!(matches!(
+            store
+                .lookup("sk-not-issued-but-long-enough-to-pass-any-length-check")
+                .await,
+            ApiKeyLookup::Unknown
+        ));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_file_store_reads_json_and_picks_up_revocation() {
+        let key_hash = hash_api_key("sk-dave");
+        let keys = |revoked: bool| {
+            serde_json::json!({
+                "keys": [{"key_hash": key_hash, "user_id": "dave", "revoked": revoked}]
+            })
+            .to_string()
+        };
+        let path = write_keys("json", &keys(false));
+        let store = FileApiKeyStore::open(&path).unwrap();
+        assert!(matches!(
+            store.lookup("sk-dave").await,
+            ApiKeyLookup::Valid(_)
+        ));
+
+        // Force a different modification time even on coarse filesystems
+        std::fs::write(&path, keys(true)).unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(matches!(
+            store.lookup("sk-dave").await,
+            ApiKeyLookup::Revoked
+        ));
+
+        // An unreadable update keeps the loaded keys
+        std::fs::write(&path, "{ not json").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later + std::time::Duration::from_secs(5))
+            .unwrap();
+        assert!(matches!(
+            store.lookup("sk-dave").await,
+            ApiKeyLookup::Revoked
+        ));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store() {
+        let store = InMemoryKeyStore::new();
+        let mut record = ApiKeyRecord::new("sk-frank", "frank");
+        record.roles = vec!["admin".to_string()];
+        store.create(record.clone()).await.unwrap();
+        assert!(store.create(record).await.is_err());
+
+        let key_hash = hash_api_key("sk-frank");
+        store
+            .update_usage(
+                &key_hash,
+                KeyUsage {
+                    requests: 1,
+                    tokens: 25,
+                },
+            )
+            .await
+            .unwrap();
+        match store.lookup("sk-frank").await {
+            ApiKeyLookup::Valid(record) => {
+                assert_eq!(record.user_id, "frank");
+                assert_eq!(
+                    record.usage,
+                    KeyUsage {
+                        requests: 1,
+                        tokens: 25
+                    }
+                );
+                assert!(record.last_used_at.is_some());
+            }
+            other => panic!("expected a valid key, got {other:?}"),
+        }
+
+        assert!(store.revoke(&key_hash).await.unwrap());
+        assert!(matches!(
+            store.lookup("sk-frank").await,
+            ApiKeyLookup::Revoked
+        ));
+        assert!(!store.revoke(&hash_api_key("sk-unknown")).await.unwrap());
+        assert!(matches!(
+            store.lookup("sk-unknown").await,
+            ApiKeyLookup::Unknown
+        ));
+    }
+
+    #[test]
+    fn test_malformed_key_hash_rejected() {
+        let path = write_keys(
+            "toml",
+            "[[keys]]\nkey_hash = \"not-a-hash\"\nuser_id = \"eve\"\n",
+        );
+        assert!(FileApiKeyStore::open(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,341 @@
+//! # Budget Module
+//!
+//! This module enforces the spending limits in the `[budgets]` section of the
+//! gateway configuration, so a runaway API key cannot spend without bound.
+//!
+//! ## Overview
+//!
+//! Spend is the estimated cost of each chat completion (see [`crate::cost`]),
+//! tracked per API key in the metrics store and reset at the start of each
+//! UTC day and month. Before a request is handled its key's spend is checked
+//! against its limits:
+//!
+//! - Past `soft_limit_percent` of a limit, responses carry an
+//!   `x-ultrafast-budget-warning` header saying how much has been used.
+//! - At or past a limit, requests are rejected with `402 Payment Required`
+//!   until the day or month rolls over.
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [budgets."sk-team-search"]
+//! daily_usd = 50.0
+//! monthly_usd = 1000.0
+//! soft_limit_percent = 80.0
+//! ```
+//!
+//! ## Alerts
+//!
+//! With `[budget_alerts].webhook_url` set, a JSON alert is POSTed when a
+//! key's spend crosses each of `thresholds` (percentages of a limit). Each
+//! alert is sent once per key, limit period and threshold: the highest
+//! threshold alerted in the current day and month is recorded for each key,
+//! in `state_file` when set so a restart does not send it again.
+//!
+//! ```toml
+//! [budget_alerts]
+//! webhook_url = "https://hooks.example.com/budget"
+//! thresholds = [80.0, 100.0]
+//! state_file = "/var/lib/ultrafast/budget-alerts.json"
+//! ```
+
+use crate::config::{BudgetAlertsConfig, BudgetConfig};
+use crate::metrics::KeySpend;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Response header carrying the soft limit warning.
+pub const BUDGET_WARNING_HEADER: &str = "x-ultrafast-budget-warning";
+
+/// Request extension set by the auth middleware when the key is past its
+/// soft limit.
+#[derive(Debug, Clone)]
+pub struct BudgetWarning(pub String);
+
+/// Where a key's spend stands against its budget.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetStatus {
+    Within,
+    /// Past the soft limit, with the warning for the response header
+    Warning(String),
+    /// At or past a hard limit, with the reason the request is rejected
+    Exceeded(String),
+}
+
+/// Compare `spend` with the daily and monthly limits of `budget`.
+pub fn evaluate(budget: &BudgetConfig, spend: &KeySpend) -> BudgetStatus {
+    let mut warning = None;
+    for (period, limit, spent) in [
+        ("Daily", budget.daily_usd, spend.daily_usd),
+        ("Monthly", budget.monthly_usd, spend.monthly_usd),
+    ] {
+        let Some(limit) = limit else {
+            continue;
+        };
+        if spent >= limit {
+            return BudgetStatus::Exceeded(format!(
+                "{period} budget of ${limit:.2} exceeded (${spent:.2} spent)"
+            ));
+        }
+        if warning.is_none() && spent >= limit * budget.soft_limit_percent / 100.0 {
+            warning = Some(format!(
+                "{period} budget {:.0}% used (${spent:.2} of ${limit:.2})",
+                spent / limit * 100.0
+            ));
+        }
+    }
+    warning.map_or(BudgetStatus::Within, BudgetStatus::Warning)
+}
+
+/// Budget status of `api_key` from the spend recorded so far.
+pub async fn check(budgets: &HashMap<String, BudgetConfig>, api_key: &str) -> BudgetStatus {
+    match budgets.get(api_key) {
+        Some(budget) => evaluate(budget, &crate::metrics::get_spend(api_key).await),
+        None => BudgetStatus::Within,
+    }
+}
+
+/// Highest threshold alerted for one key and limit period.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct LastAlert {
+    /// First day of the period the alert was sent in
+    period_start: NaiveDate,
+    threshold_percent: f64,
+}
+
+/// Threshold to alert for `percent_used` of a limit in the period starting
+/// on `period_start`, unless `last` already covers it.
+fn threshold_to_alert(
+    thresholds: &[f64],
+    percent_used: f64,
+    period_start: NaiveDate,
+    last: Option<&LastAlert>,
+) -> Option<f64> {
+    let crossed = thresholds
+        .iter()
+        .copied()
+        .filter(|threshold| percent_used >= *threshold)
+        .reduce(f64::max)?;
+    match last {
+        Some(last) if last.period_start == period_start && last.threshold_percent >= crossed => {
+            None
+        }
+        _ => Some(crossed),
+    }
+}
+
+/// Sends the alerts of `[budget_alerts]`.
+pub struct BudgetAlerter {
+    config: BudgetAlertsConfig,
+    webhook_url: String,
+    http: reqwest::Client,
+    /// Last alert per key hash and period, as saved to `state_file`
+    sent: Mutex<HashMap<String, LastAlert>>,
+}
+
+impl BudgetAlerter {
+    /// The alerter for `config`, if it has a webhook. Alerts recorded in its
+    /// `state_file` are loaded; an unreadable file is logged and ignored.
+    pub fn from_config(config: &BudgetAlertsConfig) -> Option<Self> {
+        let webhook_url = config.webhook_url.clone()?;
+        let sent = config
+            .state_file
+            .as_deref()
+            .and_then(|path| match std::fs::read_to_string(path) {
+                Ok(contents) => serde_json::from_str(&contents)
+                    .map_err(|e| tracing::warn!("Ignoring budget alert state {}: {}", path, e))
+                    .ok(),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    tracing::warn!("Failed to read budget alert state {}: {}", path, e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Some(Self {
+            config: config.clone(),
+            webhook_url,
+            http: reqwest::Client::new(),
+            sent: Mutex::new(sent),
+        })
+    }
+
+    /// Alert for any threshold `api_key` has newly crossed in the spend
+    /// recorded so far. Alerts are recorded before this returns and sent in
+    /// the background.
+    pub async fn check(&self, api_key: &str, budget: &BudgetConfig) {
+        let spend = crate::metrics::get_spend(api_key).await;
+        let key_hash = crate::api_key_store::hash_api_key(api_key);
+        let mut sent = self.sent.lock().await;
+        let mut alerts = Vec::new();
+        for (period, limit, spent, period_start) in [
+            ("daily", budget.daily_usd, spend.daily_usd, spend.day),
+            (
+                "monthly",
+                budget.monthly_usd,
+                spend.monthly_usd,
+                spend.day.with_day(1).unwrap_or(spend.day),
+            ),
+        ] {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let state_key = format!("{key_hash}/{period}");
+            let Some(threshold_percent) = threshold_to_alert(
+                &self.config.thresholds,
+                spent / limit * 100.0,
+                period_start,
+                sent.get(&state_key),
+            ) else {
+                continue;
+            };
+            sent.insert(
+                state_key,
+                LastAlert {
+                    period_start,
+                    threshold_percent,
+                },
+            );
+            alerts.push(serde_json::json!({
+                "key": format!("{}...", &api_key[..api_key.len().min(8)]),
+                "period": period,
+                "threshold_percent": threshold_percent,
+                "spent_usd": spent,
+                "limit_usd": limit,
+            }));
+        }
+        if alerts.is_empty() {
+            return;
+        }
+        if let Some(path) = &self.config.state_file {
+            if let Err(e) = serde_json::to_vec(&*sent)
+                .map_err(std::io::Error::other)
+                .and_then(|state| std::fs::write(path, state))
+            {
+                tracing::warn!("Failed to save budget alert state {}: {}", path, e);
+            }
+        }
+        drop(sent);
+
+        for alert in alerts {
+            tracing::warn!("Budget alert: {}", alert);
+            let request = self.http.post(&self.webhook_url).json(&alert);
+            tokio::spawn(async move {
+                if let Err(e) = request.send().await.and_then(|r| r.error_for_status()) {
+                    tracing::warn!("Failed to send budget alert: {}", e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::MetricsCollector;
+    use chrono::NaiveDate;
+
+    fn day(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_spend_crossing_soft_then_hard_limit() {
+        let budget = BudgetConfig {
+            daily_usd: Some(10.0),
+            monthly_usd: Some(100.0),
+            soft_limit_percent: 80.0,
+        };
+        let mut metrics = MetricsCollector::new();
+        let today = day(3, 14);
+
+        metrics.record_spend("sk-team", 7.5, today);
+        assert_eq!(
+            evaluate(&budget, &metrics.spend("sk-team", today)),
+            BudgetStatus::Within
+        );
+
+        metrics.record_spend("sk-team", 1.0, today);
+        assert_eq!(
+            evaluate(&budget, &metrics.spend("sk-team", today)),
+            BudgetStatus::Warning("Daily budget 85% used ($8.50 of $10.00)".to_string())
+        );
+
+        metrics.record_spend("sk-team", 1.5, today);
+        assert_eq!(
+            evaluate(&budget, &metrics.spend("sk-team", today)),
+            BudgetStatus::Exceeded("Daily budget of $10.00 exceeded ($10.00 spent)".to_string())
+        );
+
+        // Other keys are tracked separately
+        assert_eq!(
+            metrics.spend("sk-other", today),
+            KeySpend::default().rolled_over(today)
+        );
+    }
+
+    #[test]
+    fn test_alert_sent_once_per_threshold_and_period() {
+        let thresholds = [80.0, 100.0];
+        let last = |period_start, threshold_percent| LastAlert {
+            period_start,
+            threshold_percent,
+        };
+
+        assert_eq!(
+            threshold_to_alert(&thresholds, 50.0, day(3, 14), None),
+            None
+        );
+        assert_eq!(
+            threshold_to_alert(&thresholds, 85.0, day(3, 14), None),
+            Some(80.0)
+        );
+        // Crossing 80% again in the same period does not alert twice
+        let sent = last(day(3, 14), 80.0);
+        assert_eq!(
+            threshold_to_alert(&thresholds, 95.0, day(3, 14), Some(&sent)),
+            None
+        );
+        assert_eq!(
+            threshold_to_alert(&thresholds, 120.0, day(3, 14), Some(&sent)),
+            Some(100.0)
+        );
+        // A new period alerts again
+        assert_eq!(
+            threshold_to_alert(&thresholds, 85.0, day(3, 15), Some(&sent)),
+            Some(80.0)
+        );
+    }
+
+    #[test]
+    fn test_spend_rolls_over_by_day_and_month() {
+        let budget = BudgetConfig {
+            daily_usd: Some(10.0),
+            monthly_usd: Some(25.0),
+            soft_limit_percent: 80.0,
+        };
+        let mut metrics = MetricsCollector::new();
+
+        metrics.record_spend("sk-team", 12.0, day(3, 14));
+        metrics.record_spend("sk-team", 9.0, day(3, 15));
+        let spend = metrics.spend("sk-team", day(3, 15));
+        assert_eq!((spend.daily_usd, spend.monthly_usd), (9.0, 21.0));
+        assert_eq!(
+            evaluate(&budget, &spend),
+            BudgetStatus::Warning("Daily budget 90% used ($9.00 of $10.00)".to_string())
+        );
+
+        metrics.record_spend("sk-team", 5.0, day(3, 16));
+        assert_eq!(
+            evaluate(&budget, &metrics.spend("sk-team", day(3, 16))),
+            BudgetStatus::Exceeded("Monthly budget of $25.00 exceeded ($26.00 spent)".to_string())
+        );
+
+        // A new month starts from zero
+        let spend = metrics.spend("sk-team", day(4, 1));
+        assert_eq!((spend.daily_usd, spend.monthly_usd), (0.0, 0.0));
+        assert_eq!(evaluate(&budget, &spend), BudgetStatus::Within);
+    }
+}
@@ -98,6 +98,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env;
 use std::time::Duration;
+use ultrafast_models_sdk::circuit_breaker::CircuitBreakerConfig;
+use ultrafast_models_sdk::client::EmbeddingBatchConfig;
 use ultrafast_models_sdk::providers::ProviderConfig;
 use ultrafast_models_sdk::routing::RoutingStrategy;
 
@@ -117,6 +119,10 @@ use ultrafast_models_sdk::routing::RoutingStrategy;
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Schema version of the configuration file; older files are upgraded
+    /// by [`Config::load`]
+    #[serde(default = "default_config_version")]
+    pub config_version: u32,
     /// HTTP server configuration settings
     pub server: ServerConfig,
     /// LLM provider configurations mapped by provider name
@@ -133,6 +139,429 @@ pub struct Config {
     pub metrics: MetricsConfig,
     /// Plugin system configuration
     pub plugins: Vec<PluginConfig>,
+    /// Token pricing keyed by provider (`"openai"`) or provider and model
+    /// (`"openai/gpt-4o"`), used for cost reporting
+    #[serde(default = "default_pricing")]
+    pub pricing: HashMap<String, ModelPricing>,
+    /// Feature flags keyed by name, evaluated per request
+    #[serde(default)]
+    pub feature_flags: HashMap<String, FeatureFlag>,
+    /// How `/v1/models` is built and cached
+    #[serde(default)]
+    pub models: ModelsConfig,
+    /// Virtual model names resolved per request to a weighted choice of
+    /// concrete models
+    #[serde(default)]
+    pub model_ab_tests: HashMap<String, ModelAbTest>,
+    /// Circuit breaker for providers without their own
+    /// `[providers.<name>.circuit_breaker]` table (none when both are unset)
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// OpenTelemetry span export (needs the `otel` feature)
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    /// Spending limits keyed by API key
+    #[serde(default)]
+    pub budgets: HashMap<String, BudgetConfig>,
+    /// Webhook notified as API keys approach their budgets
+    #[serde(default)]
+    pub budget_alerts: BudgetAlertsConfig,
+    /// Limits on streamed chat completions
+    #[serde(default)]
+    pub stream: StreamConfig,
+    /// Batching of `/v1/embeddings/stream` requests
+    #[serde(default)]
+    pub embeddings: EmbeddingBatchConfig,
+    /// Rewrites applied to incoming chat requests
+    #[serde(default)]
+    pub request: RequestConfig,
+}
+
+/// `[request]` configuration for incoming chat requests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestConfig {
+    #[serde(default)]
+    pub normalization: NormalizationConfig,
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    /// Top-level fields required, by endpoint path, on top of the ones
+    /// each endpoint needs (see [`crate::request_validation`])
+    #[serde(default)]
+    pub required_fields: HashMap<String, Vec<String>>,
+}
+
+/// `[request.moderation]` check of chat requests before dispatch; see
+/// [`crate::moderation`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationConfig {
+    /// Moderate chat requests with OpenAI's moderation endpoint
+    #[serde(default)]
+    pub enabled: bool,
+    /// Base URL of the moderation API
+    #[serde(default = "default_moderation_base_url")]
+    pub base_url: String,
+    /// API key for the moderation API, defaulting to the openai provider's
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Moderation model, or the API's default
+    #[serde(default)]
+    pub model: Option<String>,
+    /// How long to wait for a moderation decision
+    #[serde(
+        default = "default_moderation_timeout",
+        with = "ultrafast_models_sdk::common::duration_serde"
+    )]
+    pub timeout: Duration,
+    /// Block requests when the moderation call fails instead of allowing them
+    #[serde(default)]
+    pub block_on_error: bool,
+}
+
+fn default_moderation_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_moderation_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: default_moderation_base_url(),
+            api_key: None,
+            model: None,
+            timeout: default_moderation_timeout(),
+            block_on_error: false,
+        }
+    }
+}
+
+/// `[request.normalization]` rewrites, all off by default, applied before
+/// routing and caching so near-identical requests share a cache entry.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NormalizationConfig {
+    /// Strip trailing whitespace from message content
+    #[serde(default)]
+    pub trim_trailing_whitespace: bool,
+    /// Drop system messages identical to an earlier system message
+    #[serde(default)]
+    pub merge_duplicate_system_prompts: bool,
+}
+
+/// `[stream]` configuration for streamed chat completions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StreamConfig {
+    /// Longest a stream may run, from the start of the request, before it
+    /// is cut off. `0s` (the default) leaves streams uncapped.
+    #[serde(default, with = "ultrafast_models_sdk::common::duration_serde")]
+    pub max_duration: Duration,
+    /// End a stream cut off by `max_duration` with a `finish_reason:
+    /// "timeout"` chunk, so clients keep the partial output, instead of an
+    /// error event
+    #[serde(default)]
+    pub return_partial_on_timeout: bool,
+    /// Gzip streams for clients that send `Accept-Encoding: gzip`, flushing
+    /// after every event so tokens are not held back by the compressor
+    #[serde(default)]
+    pub compression: bool,
+}
+
+/// Daily and monthly spending limits for one API key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    /// Hard limit on spend per UTC day
+    #[serde(default)]
+    pub daily_usd: Option<f64>,
+    /// Hard limit on spend per UTC calendar month
+    #[serde(default)]
+    pub monthly_usd: Option<f64>,
+    /// Share of a limit, in percent, past which responses carry a warning
+    #[serde(default = "default_soft_limit_percent")]
+    pub soft_limit_percent: f64,
+}
+
+fn default_soft_limit_percent() -> f64 {
+    80.0
+}
+
+/// `[budget_alerts]` configuration: a webhook called once per API key, limit
+/// period and threshold as spend crosses each threshold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BudgetAlertsConfig {
+    /// URL each alert is POSTed to as JSON; alerts are off when unset
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    /// Shares of a limit, in percent, that trigger an alert
+    #[serde(default = "default_budget_alert_thresholds")]
+    pub thresholds: Vec<f64>,
+    /// File recording the alerts already sent, so a restart does not send
+    /// them again (kept in memory only when unset)
+    #[serde(default)]
+    pub state_file: Option<String>,
+}
+
+impl Default for BudgetAlertsConfig {
+    fn default() -> Self {
+        Self {
+            webhook_url: None,
+            thresholds: default_budget_alert_thresholds(),
+            state_file: None,
+        }
+    }
+}
+
+fn default_budget_alert_thresholds() -> Vec<f64> {
+    vec![80.0, 100.0]
+}
+
+/// `[telemetry]` configuration for exporting request spans over OTLP.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// Export spans; ignored unless the gateway is built with `otel`
+    #[serde(default)]
+    pub enabled: bool,
+    /// OTLP/HTTP collector endpoint spans are sent to
+    #[serde(default = "default_otlp_endpoint")]
+    pub otlp_endpoint: String,
+    /// `service.name` resource attribute reported with every span
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4318/v1/traces".to_string()
+}
+
+fn default_service_name() -> String {
+    "ultrafast-gateway".to_string()
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            otlp_endpoint: default_otlp_endpoint(),
+            service_name: default_service_name(),
+        }
+    }
+}
+
+/// Where the `/v1/models` list comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelsSource {
+    /// Built-in list of well-known models per provider
+    #[default]
+    Static,
+    /// Ask each provider for its models, using the built-in list for
+    /// providers that cannot list them
+    Live,
+    /// Built-in list plus the models each provider reports
+    Merge,
+}
+
+/// `/v1/models` configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelsConfig {
+    /// Where the model list comes from
+    #[serde(default)]
+    pub source: ModelsSource,
+    /// How long the built list is served before it is rebuilt (0 disables
+    /// caching)
+    #[serde(
+        default = "default_models_cache_ttl",
+        with = "ultrafast_models_sdk::common::duration_serde"
+    )]
+    pub cache_ttl: Duration,
+    /// What to do with chat requests for models no provider lists
+    #[serde(default)]
+    pub unknown_model: UnknownModelPolicy,
+    /// Token limits keyed by model name or model-name prefix
+    /// (`[models."gpt-4o"]`), overriding the built-in limits
+    #[serde(flatten)]
+    pub limits: HashMap<String, ModelLimits>,
+}
+
+fn default_models_cache_ttl() -> Duration {
+    Duration::from_secs(300)
+}
+
+impl Default for ModelsConfig {
+    fn default() -> Self {
+        Self {
+            source: ModelsSource::default(),
+            cache_ttl: default_models_cache_ttl(),
+            unknown_model: UnknownModelPolicy::default(),
+            limits: HashMap::new(),
+        }
+    }
+}
+
+impl ModelsConfig {
+    /// Token limits of `model`, from the configured entry with the longest
+    /// matching prefix and otherwise the built-in limits, field by field.
+    pub fn limits_for(&self, model: &str) -> ModelLimits {
+        let configured = longest_prefix_match(model, &self.limits)
+            .copied()
+            .unwrap_or_default();
+        let builtin = longest_prefix_match(model, &default_model_limits())
+            .copied()
+            .unwrap_or_default();
+        ModelLimits {
+            context_window: configured.context_window.or(builtin.context_window),
+            max_output_tokens: configured.max_output_tokens.or(builtin.max_output_tokens),
+        }
+    }
+}
+
+/// Handling of chat requests for a model missing from the `/v1/models` list
+/// and from every provider's `model_mapping`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnknownModelPolicy {
+    /// Send the request to the providers anyway; if they report the model
+    /// does not exist, answer 404 listing the available models
+    #[default]
+    Forward,
+    /// Answer 404 listing the available models without calling a provider
+    Reject,
+}
+
+/// Token limits of a model, used by the request token guards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelLimits {
+    /// Combined prompt + completion tokens the model accepts
+    #[serde(default)]
+    pub context_window: Option<u32>,
+    /// Most completion tokens the model generates per request
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+}
+
+impl ModelLimits {
+    const fn new(context_window: u32, max_output_tokens: u32) -> Self {
+        Self {
+            context_window: Some(context_window),
+            max_output_tokens: Some(max_output_tokens),
+        }
+    }
+}
+
+/// Limits of well-known models, used for models without a `[models]` entry.
+///
+/// Only the original `gpt-4` snapshots have an 8k context window: other
+/// `gpt-4-` and `gpt-4.` models (turbo, the previews, 4.5) fall under
+/// 128k entries rather than the `gpt-4` prefix.
+pub fn default_model_limits() -> HashMap<String, ModelLimits> {
+    let limits = [
+        ("gpt-4", ModelLimits::new(8_192, 8_192)),
+        ("gpt-4-0314", ModelLimits::new(8_192, 8_192)),
+        ("gpt-4-0613", ModelLimits::new(8_192, 8_192)),
+        ("gpt-4-32k", ModelLimits::new(32_768, 32_768)),
+        ("gpt-4-", ModelLimits::new(128_000, 4_096)),
+        ("gpt-4.", ModelLimits::new(128_000, 16_384)),
+        ("gpt-4o", ModelLimits::new(128_000, 16_384)),
+        ("gpt-4.1", ModelLimits::new(1_047_576, 32_768)),
+        ("gpt-3.5-turbo", ModelLimits::new(16_385, 4_096)),
+        ("claude-3-opus", ModelLimits::new(200_000, 4_096)),
+        ("claude-3-sonnet", ModelLimits::new(200_000, 4_096)),
+        ("claude-3-haiku", ModelLimits::new(200_000, 4_096)),
+        ("claude-3-5-sonnet", ModelLimits::new(200_000, 8_192)),
+        ("claude-3-5-haiku", ModelLimits::new(200_000, 8_192)),
+        ("gemini-1.5-pro", ModelLimits::new(2_097_152, 8_192)),
+        ("gemini-1.5-flash", ModelLimits::new(1_048_576, 8_192)),
+        ("gemini-2.0-flash", ModelLimits::new(1_048_576, 8_192)),
+    ];
+    limits
+        .into_iter()
+        .map(|(model, limits)| (model.to_string(), limits))
+        .collect()
+}
+
+/// The value for `model`, preferring an exact key and otherwise the longest
+/// key that is a prefix of it.
+fn longest_prefix_match<'a, T>(model: &str, values: &'a HashMap<String, T>) -> Option<&'a T> {
+    values.get(model).or_else(|| {
+        values
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, value)| value)
+    })
+}
+
+/// Percentage rollout of a feature flag.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureFlag {
+    /// Disabled flags are off for everyone
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Share of users, from 0 to 100, the flag is on for
+    #[serde(default = "default_flag_percentage")]
+    pub percentage: f64,
+    /// Users the flag is always on for
+    #[serde(default)]
+    pub users: Vec<String>,
+}
+
+fn default_flag_percentage() -> f64 {
+    100.0
+}
+
+/// Weighted split of a virtual model name across concrete models.
+///
+/// ```toml
+/// [model_ab_tests.smart]
+/// variants = [
+///     { model = "gpt-4", weight = 70.0 },
+///     { model = "gpt-4o", weight = 30.0 },
+/// ]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelAbTest {
+    /// Concrete models and their relative weights
+    pub variants: Vec<ModelVariant>,
+}
+
+/// One concrete model of a [`ModelAbTest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelVariant {
+    /// Model name sent to the provider
+    pub model: String,
+    /// Relative share of requests, need not sum to 100 across variants
+    pub weight: f64,
+}
+
+/// Token pricing for a provider or model, in USD per 1K tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Cost per 1K prompt tokens
+    pub input_per_1k: f64,
+    /// Cost per 1K completion tokens
+    pub output_per_1k: f64,
+}
+
+/// Pricing used when the configuration has no `[pricing]` section.
+pub fn default_pricing() -> HashMap<String, ModelPricing> {
+    let rates = [
+        ("anthropic", 0.015, 0.075),
+        ("openai", 0.03, 0.06),
+        ("google-vertex-ai", 0.0005, 0.0015),
+        ("gemini", 0.0000035, 0.0000105),
+        ("ollama", 0.0, 0.0),
+    ];
+    rates
+        .into_iter()
+        .map(|(provider, input_per_1k, output_per_1k)| {
+            (
+                provider.to_string(),
+                ModelPricing {
+                    input_per_1k,
+                    output_per_1k,
+                },
+            )
+        })
+        .collect()
 }
 
 /// Configuration for metrics collection and monitoring.
@@ -151,6 +580,17 @@ pub struct MetricsConfig {
     /// How often to clean up old metrics data
     #[serde(with = "ultrafast_models_sdk::common::duration_serde")]
     pub cleanup_interval: Duration,
+    /// Upper bounds in seconds of the `ultrafast_request_duration_seconds`
+    /// histogram buckets, in increasing order
+    #[serde(default = "default_latency_buckets")]
+    pub latency_buckets: Vec<f64>,
+    /// Weights and bounds of the per-provider health score
+    #[serde(default)]
+    pub health_score: crate::metrics::HealthScoreConfig,
+}
+
+fn default_latency_buckets() -> Vec<f64> {
+    crate::metrics::DEFAULT_LATENCY_BUCKETS.to_vec()
 }
 
 /// HTTP server configuration settings.
@@ -169,24 +609,94 @@ pub struct ServerConfig {
     /// Maximum request body size in bytes
     pub max_body_size: usize,
     /// CORS (Cross-Origin Resource Sharing) configuration
+    #[serde(default)]
     pub cors: CorsConfig,
+    /// Maximum characters of generated content returned per choice
+    /// (requests may lower this with `max_response_chars`)
+    #[serde(default)]
+    pub max_response_chars: Option<usize>,
+    /// Combined prompt + completion token budget per model, normally the
+    /// model's context window (keys match exactly or as a model-name prefix)
+    #[serde(default)]
+    pub max_total_tokens: HashMap<String, u32>,
+    /// Maximum number of messages in a chat request
+    #[serde(default)]
+    pub max_messages_per_request: Option<u32>,
+    /// Per-model message limits overriding `max_messages_per_request`
+    /// (keys match exactly or as a model-name prefix)
+    #[serde(default)]
+    pub max_messages_per_model: HashMap<String, u32>,
+    /// Send an SSE comment as soon as a stream starts, so proxies that buffer
+    /// until the first body frame pass the response headers on immediately
+    #[serde(default = "default_true")]
+    pub stream_initial_flush: bool,
+    /// Send an SSE comment on streams that have been idle this long, so
+    /// proxies do not close them as dead ("0s" disables)
+    #[serde(
+        default = "default_sse_keepalive_interval",
+        with = "ultrafast_models_sdk::common::duration_serde"
+    )]
+    pub sse_keepalive_interval: Duration,
+    /// HTTP status returned when the routed provider does not support the
+    /// requested endpoint (501 or 422)
+    #[serde(default = "default_unsupported_capability_status")]
+    pub unsupported_capability_status: u16,
+    /// How long in-flight requests may run after SIGTERM/SIGINT before the
+    /// server exits anyway
+    #[serde(
+        default = "default_shutdown_timeout",
+        with = "ultrafast_models_sdk::common::duration_serde"
+    )]
+    pub shutdown_timeout: Duration,
+    /// Chat completion fields removed before responses reach clients, as
+    /// dotted paths (`"system_fingerprint"`, `"choices.logprobs"`); metrics
+    /// and the cache still see the full response
+    #[serde(default)]
+    pub strip_response_fields: Vec<String>,
+    /// Add an `x-content-sha256` header with the SHA-256 of each buffered
+    /// response body
+    #[serde(default)]
+    pub response_checksum: bool,
+}
+
+fn default_unsupported_capability_status() -> u16 {
+    501
+}
+
+fn default_shutdown_timeout() -> Duration {
+    Duration::from_secs(30)
 }
 
-/// CORS (Cross-Origin Resource Sharing) configuration.
+fn default_sse_keepalive_interval() -> Duration {
+    Duration::from_secs(15)
+}
+
+/// CORS (Cross-Origin Resource Sharing) configuration for the `/v1/*`
+/// endpoints.
 ///
 /// Controls which origins, methods, and headers are allowed
-/// in cross-origin requests.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// in cross-origin requests. The default allows no origins, so browsers
+/// block cross-origin calls until origins are listed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CorsConfig {
     /// Whether CORS is enabled
+    #[serde(default)]
     pub enabled: bool,
-    /// List of allowed origin domains
+    /// Allowed origins (`"https://app.example.com"`), or `"*"` for any
+    #[serde(default)]
     pub allowed_origins: Vec<String>,
-    /// List of allowed HTTP methods
+    /// Allowed HTTP methods, or `"*"` for any
+    #[serde(default)]
     pub allowed_methods: Vec<String>,
-    /// List of allowed HTTP headers
+    /// Allowed request headers, or `"*"` for any
+    #[serde(default)]
     pub allowed_headers: Vec<String>,
+    /// Let browsers send cookies and `Authorization` headers; requires
+    /// explicit origins, methods and headers
+    #[serde(default)]
+    pub allow_credentials: bool,
     /// Maximum age for CORS preflight responses
+    #[serde(default)]
     pub max_age: Option<Duration>,
 }
 
@@ -203,6 +713,136 @@ pub struct RoutingConfig {
     pub health_check_interval: Duration,
     /// Threshold for marking a provider as failed
     pub failover_threshold: f64,
+    /// Traffic ramp-up for providers activated at runtime
+    #[serde(default)]
+    pub slow_start: SlowStartConfig,
+    /// Automatic disabling of providers that keep failing
+    #[serde(default)]
+    pub error_budget: ErrorBudgetConfig,
+    /// Log every provider selection decision at debug level
+    #[serde(default)]
+    pub debug: bool,
+    /// Automatic load-balance weight tuning
+    #[serde(default)]
+    pub auto_tune: AutoTuneConfig,
+    /// Canned completion returned when every provider fails
+    #[serde(default)]
+    pub fallback_response: FallbackResponseConfig,
+    /// Alternate models, in order, retried on the same provider when a
+    /// model fails, before failing over to another provider
+    #[serde(default)]
+    pub model_retry_alternates: HashMap<String, Vec<String>>,
+    /// Reject chat requests whose estimated prompt, over all messages, is
+    /// longer than this many tokens. API keys can set their own limit.
+    #[serde(default)]
+    pub max_prompt_tokens: Option<u32>,
+}
+
+/// Slow-start configuration for newly activated providers.
+///
+/// A provider that is re-enabled, after its circuit breaker closes again or
+/// its error budget cooldown ends, receives a share of traffic that grows
+/// linearly over `window`, limiting the blast radius of a provider that is
+/// still unstable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowStartConfig {
+    /// Whether slow-start is enabled
+    pub enabled: bool,
+    /// Time taken to ramp a new provider up to its full share
+    #[serde(with = "ultrafast_models_sdk::common::duration_serde")]
+    pub window: Duration,
+}
+
+impl Default for SlowStartConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Error budget configuration for automatic provider disabling.
+///
+/// A provider that records more than `max_errors` failures within `window`
+/// is taken out of rotation for `cooldown` and an alert is logged. This is
+/// independent of the per-request circuit breaker.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorBudgetConfig {
+    /// Whether the error budget is enforced
+    pub enabled: bool,
+    /// Errors tolerated within `window` before the provider is disabled
+    pub max_errors: u32,
+    /// Sliding window over which errors are counted
+    #[serde(with = "ultrafast_models_sdk::common::duration_serde")]
+    pub window: Duration,
+    /// How long a disabled provider stays out of rotation
+    #[serde(with = "ultrafast_models_sdk::common::duration_serde")]
+    pub cooldown: Duration,
+}
+
+impl Default for ErrorBudgetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_errors: 100,
+            window: Duration::from_secs(300),
+            cooldown: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Automatic load-balance weight tuning configuration.
+///
+/// When enabled with the `LoadBalance` strategy, weights are recomputed
+/// every `interval` from observed latency and error rates: halved for
+/// degraded providers and raised gradually for healthy ones, always within
+/// `[min_weight, max_weight]`. Tuned weights replace the static ones.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoTuneConfig {
+    /// Whether weights are tuned automatically
+    pub enabled: bool,
+    /// Lowest weight a provider can be tuned down to
+    pub min_weight: f32,
+    /// Highest weight a provider can be tuned up to
+    pub max_weight: f32,
+    /// How often weights are recomputed
+    #[serde(with = "ultrafast_models_sdk::common::duration_serde")]
+    pub interval: Duration,
+}
+
+impl Default for AutoTuneConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_weight: 0.05,
+            max_weight: 1.0,
+            interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Canned completion returned when every provider fails.
+///
+/// When enabled, a non-streaming chat completion whose providers all fail
+/// is answered with a `ChatResponse` holding `content` and
+/// `finish_reason: "fallback"` instead of a 503.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallbackResponseConfig {
+    /// Whether the fallback completion is returned
+    pub enabled: bool,
+    /// Content of the fallback completion
+    pub content: String,
+}
+
+impl Default for FallbackResponseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            content: "Service temporarily unavailable, please retry.".to_string(),
+        }
+    }
 }
 
 /// Authentication and authorization configuration.
@@ -214,8 +854,145 @@ pub struct AuthConfig {
     pub enabled: bool,
     /// List of configured API keys
     pub api_keys: Vec<ApiKeyConfig>,
+    /// TOML or JSON file of hashed API keys, checked after `api_keys`; see
+    /// [`crate::api_key_store`]
+    #[serde(default)]
+    pub api_key_store: Option<String>,
     /// Global rate limiting settings
     pub rate_limiting: RateLimitConfig,
+    /// How the caller identity is resolved for rate limiting and metrics
+    #[serde(default)]
+    pub identity: IdentityConfig,
+    /// JWT validation settings
+    #[serde(default)]
+    pub jwt: JwtConfig,
+    /// Separate credentials for the admin and metrics endpoints
+    #[serde(default)]
+    pub admin: AdminAuthConfig,
+}
+
+/// Admin authentication configuration.
+///
+/// When enabled, `/admin/*` and `/metrics*` accept only admin keys (or API
+/// keys with `allow_admin_access`), independently of `auth.enabled`. Admin
+/// keys are not valid for the API endpoints unless `allow_api_access` is set.
+///
+/// ```toml
+/// [auth.admin]
+/// enabled = true
+/// api_keys = [{ key = "admin-0123456789abcdef", name = "ops" }]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdminAuthConfig {
+    /// Whether admin endpoints require an admin credential
+    #[serde(default)]
+    pub enabled: bool,
+    /// Keys accepted on admin endpoints
+    #[serde(default)]
+    pub api_keys: Vec<AdminKeyConfig>,
+}
+
+/// Configuration for an individual admin key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdminKeyConfig {
+    /// The admin key value
+    pub key: String,
+    /// Human-readable name for the admin key
+    pub name: String,
+    /// Whether this admin key is enabled
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Also accept this key on the API endpoints
+    #[serde(default)]
+    pub allow_api_access: bool,
+}
+
+/// JWT validation configuration.
+///
+/// HMAC algorithms (`HS256`) verify signatures with `secret`; RSA and EC
+/// algorithms (`RS256`, `ES256`) with the keys published at `jwks_url`.
+///
+/// ```toml
+/// [auth.jwt]
+/// leeway_seconds = 30
+/// algorithm = "RS256"
+/// jwks_url = "https://issuer.example.com/.well-known/jwks.json"
+/// jwks_cache_ttl = "10m"
+/// issuer = "https://issuer.example.com/"
+/// audience = "ultrafast-gateway"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtConfig {
+    /// Clock-skew tolerance in seconds applied to the `exp` and `nbf` checks
+    #[serde(default)]
+    pub leeway_seconds: u64,
+    /// The only signing algorithm accepted
+    #[serde(default)]
+    pub algorithm: jsonwebtoken::Algorithm,
+    /// HMAC signing secret, defaulting to the `GATEWAY_JWT_SECRET` variable
+    #[serde(default)]
+    pub secret: Option<String>,
+    /// JSON Web Key Set with the public keys of RSA and EC algorithms
+    #[serde(default)]
+    pub jwks_url: Option<String>,
+    /// How long fetched keys are used before the key set is fetched again
+    #[serde(
+        default = "default_jwks_cache_ttl",
+        with = "ultrafast_models_sdk::common::duration_serde"
+    )]
+    pub jwks_cache_ttl: Duration,
+    /// Required `iss` claim
+    #[serde(default)]
+    pub issuer: Option<String>,
+    /// Required `aud` claim
+    #[serde(default)]
+    pub audience: Option<String>,
+}
+
+fn default_jwks_cache_ttl() -> Duration {
+    Duration::from_secs(600)
+}
+
+impl Default for JwtConfig {
+    fn default() -> Self {
+        Self {
+            leeway_seconds: 0,
+            algorithm: jsonwebtoken::Algorithm::default(),
+            secret: None,
+            jwks_url: None,
+            jwks_cache_ttl: default_jwks_cache_ttl(),
+            issuer: None,
+            audience: None,
+        }
+    }
+}
+
+/// Identity resolution configuration.
+///
+/// Determines which part of an authenticated request becomes the `user_id`
+/// used for rate limiting and metrics.
+///
+/// ```toml
+/// [auth.identity]
+/// source = { Header = { name = "x-user-id" } }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdentityConfig {
+    /// Where the identity is read from
+    #[serde(default)]
+    pub source: IdentitySource,
+}
+
+/// Sources the gateway can derive a caller identity from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum IdentitySource {
+    /// The user bound to the `Authorization` bearer credential (API key or JWT subject)
+    #[default]
+    Bearer,
+    /// The value of a named request header
+    Header { name: String },
+    /// A dot-separated claim path inside the bearer JWT (e.g. `org.user_id`)
+    JwtClaim { path: String },
 }
 
 /// Configuration for an individual API key.
@@ -235,6 +1012,12 @@ pub struct ApiKeyConfig {
     pub allowed_models: Option<Vec<String>>,
     /// Additional metadata for the API key
     pub metadata: HashMap<String, String>,
+    /// Also accept this key on admin endpoints when `[auth.admin]` is enabled
+    #[serde(default)]
+    pub allow_admin_access: bool,
+    /// Prompt limit for this key, overriding `routing.max_prompt_tokens`
+    #[serde(default)]
+    pub max_prompt_tokens: Option<u32>,
 }
 
 /// Rate limiting configuration.
@@ -248,6 +1031,25 @@ pub struct RateLimitConfig {
     pub requests_per_hour: u32,
     /// Maximum tokens per minute
     pub tokens_per_minute: u32,
+    /// Where counters are kept (only read from `[auth.rate_limiting]`)
+    #[serde(default)]
+    pub backend: RateLimitBackend,
+}
+
+/// Available rate limiting backends.
+///
+/// ```toml
+/// [auth.rate_limiting]
+/// backend = { Redis = { url = "redis://127.0.0.1:6379" } }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum RateLimitBackend {
+    /// Per-instance counters (limits are not shared across instances)
+    #[default]
+    Memory,
+    /// Redis counters shared by every instance, falling back to memory while
+    /// Redis is unreachable
+    Redis { url: String },
 }
 
 /// Caching configuration.
@@ -263,8 +1065,157 @@ pub struct CacheConfig {
     /// Time-to-live for cached responses
     #[serde(with = "ultrafast_models_sdk::common::duration_serde")]
     pub ttl: Duration,
+    /// Time-to-live for cached embeddings, which are deterministic and so
+    /// kept far longer than other responses
+    #[serde(
+        default = "default_embedding_ttl",
+        with = "ultrafast_models_sdk::common::duration_serde"
+    )]
+    pub embedding_ttl: Duration,
     /// Maximum number of cached items
     pub max_size: usize,
+    /// Number of lock-striped segments for the in-memory store
+    #[serde(default = "default_cache_shards")]
+    pub shards: usize,
+    /// Share one upstream call between identical concurrent requests
+    #[serde(default)]
+    pub coalesce_requests: bool,
+    /// Which endpoint types are cached
+    #[serde(default)]
+    pub endpoints: CacheEndpointsConfig,
+    /// Chat request fields that make up the cache key (empty: all fields
+    /// except `user`, `stream`, `stream_options` and `request_metadata`)
+    #[serde(default)]
+    pub key_fields: Vec<String>,
+    /// Decimal places `temperature`, `top_p` and the penalties are rounded
+    /// to in chat cache keys
+    #[serde(default = "default_cache_key_precision")]
+    pub key_precision: u32,
+    /// Whether chat lookups only match identical requests
+    #[serde(default)]
+    pub mode: CacheMode,
+    /// Similar-prompt lookups used in `Semantic` mode
+    #[serde(default)]
+    pub semantic: SemanticCacheConfig,
+    /// JSON file of chat request/response pairs loaded into the cache at
+    /// startup
+    #[serde(default)]
+    pub preload_file: Option<String>,
+    /// Time-to-live for preloaded responses
+    #[serde(
+        default = "default_preload_ttl",
+        with = "ultrafast_models_sdk::common::duration_serde"
+    )]
+    pub preload_ttl: Duration,
+}
+
+/// How chat completions are looked up in the cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheMode {
+    /// Only identical requests (after key normalization) hit
+    #[default]
+    Exact,
+    /// On an exact miss, serve the response cached for the most similar
+    /// earlier prompt, if it is similar enough
+    Semantic,
+}
+
+/// Semantic cache configuration.
+///
+/// Prompts are embedded with `embedding_model` and compared by cosine
+/// similarity against earlier prompts for the same model and parameters.
+///
+/// ```toml
+/// [cache]
+/// mode = "Semantic"
+///
+/// [cache.semantic]
+/// embedding_model = "text-embedding-3-small"
+/// embedding_provider = "openai"
+/// similarity_threshold = 0.95
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticCacheConfig {
+    /// Model used to embed prompts
+    #[serde(default = "default_semantic_embedding_model")]
+    pub embedding_model: String,
+    /// Provider the embedding requests are pinned to (routed like any
+    /// other embedding request when unset)
+    #[serde(default)]
+    pub embedding_provider: Option<String>,
+    /// Minimum cosine similarity for a cached prompt to be served
+    #[serde(default = "default_semantic_similarity_threshold")]
+    pub similarity_threshold: f32,
+}
+
+impl Default for SemanticCacheConfig {
+    fn default() -> Self {
+        Self {
+            embedding_model: default_semantic_embedding_model(),
+            embedding_provider: None,
+            similarity_threshold: default_semantic_similarity_threshold(),
+        }
+    }
+}
+
+fn default_semantic_embedding_model() -> String {
+    "text-embedding-3-small".to_string()
+}
+
+fn default_semantic_similarity_threshold() -> f32 {
+    0.95
+}
+
+/// Per-endpoint caching toggles.
+///
+/// Chat and embedding responses are deterministic enough to cache; image
+/// generation and audio requests are usually unique, so they default to off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEndpointsConfig {
+    /// Cache `/v1/chat/completions` responses
+    #[serde(default = "default_true")]
+    pub chat: bool,
+    /// Cache `/v1/embeddings` responses
+    #[serde(default = "default_true")]
+    pub embeddings: bool,
+    /// Cache `/v1/images/generations` responses
+    #[serde(default)]
+    pub images: bool,
+    /// Cache `/v1/audio/*` responses (reserved: audio responses are not
+    /// cached yet, so enabling this has no effect)
+    #[serde(default)]
+    pub audio: bool,
+}
+
+impl Default for CacheEndpointsConfig {
+    fn default() -> Self {
+        Self {
+            chat: true,
+            embeddings: true,
+            images: false,
+            audio: false,
+        }
+    }
+}
+
+fn default_cache_shards() -> usize {
+    16
+}
+
+fn default_embedding_ttl() -> Duration {
+    Duration::from_secs(30 * 24 * 60 * 60)
+}
+
+fn default_preload_ttl() -> Duration {
+    Duration::from_secs(24 * 60 * 60)
+}
+
+fn default_cache_key_precision() -> u32 {
+    ultrafast_models_sdk::cache::DEFAULT_KEY_PRECISION
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// Available caching backends.
@@ -289,16 +1240,63 @@ pub struct LoggingConfig {
     pub format: LogFormat,
     /// Log output destination
     pub output: LogOutput,
+    /// Debug logging of request and response bodies
+    #[serde(default)]
+    pub body_logging: BodyLoggingConfig,
+}
+
+/// Request/response body logging configuration.
+///
+/// Bodies are logged at debug level with `Authorization` headers, JSON
+/// `api_key` fields and every `redact_patterns` match replaced by `***`.
+///
+/// ```toml
+/// [logging.body_logging]
+/// enabled = true
+/// max_bytes = 4096
+/// redact_patterns = ["sk-[A-Za-z0-9]+"]
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyLoggingConfig {
+    /// Whether bodies are logged
+    #[serde(default)]
+    pub enabled: bool,
+    /// Bodies longer than this are truncated in the log
+    #[serde(default = "default_body_logging_max_bytes")]
+    pub max_bytes: usize,
+    /// Regular expressions whose matches are redacted
+    #[serde(default)]
+    pub redact_patterns: Vec<String>,
+}
+
+impl Default for BodyLoggingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: default_body_logging_max_bytes(),
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+fn default_body_logging_max_bytes() -> usize {
+    4096
 }
 
 /// Available log output formats.
+///
+/// Also selects the format of the access log: one JSON object per request
+/// with `Json`, tracing fields otherwise.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum LogFormat {
     /// JSON format for structured logging
+    #[serde(alias = "json")]
     Json,
     /// Human-readable pretty format
+    #[serde(alias = "pretty")]
     Pretty,
     /// Compact single-line format
+    #[serde(alias = "compact")]
     Compact,
 }
 
@@ -327,10 +1325,85 @@ pub struct PluginConfig {
 
 // Duration handling moved to shared module
 
+/// Schema version written by this gateway.
+///
+/// - Version 1: files without `config_version`. Every top-level section was
+///   required, as were most settings inside them.
+/// - Version 2: `config_version = 2`. Sections and settings left out take
+///   their defaults.
+pub const CURRENT_CONFIG_VERSION: u32 = 2;
+
+fn default_config_version() -> u32 {
+    CURRENT_CONFIG_VERSION
+}
+
+/// Sections whose missing settings are filled in when upgrading a version 1
+/// file.
+const V1_SECTIONS: [&str; 6] = ["routing", "auth", "cache", "logging", "metrics", "plugins"];
+
+/// Upgrade a parsed configuration file to [`CURRENT_CONFIG_VERSION`].
+///
+/// Files without `config_version` are version 1. Files newer than this
+/// gateway are rejected rather than loaded with settings it does not know.
+pub fn migrate_config(table: &mut toml::Table) -> anyhow::Result<()> {
+    let version = match table.get("config_version") {
+        None => 1,
+        Some(toml::Value::Integer(version)) if *version >= 1 => *version as u32,
+        Some(version) => return Err(anyhow::anyhow!("Invalid config_version: {}", version)),
+    };
+    if version > CURRENT_CONFIG_VERSION {
+        return Err(anyhow::anyhow!(
+            "config_version {} is newer than this gateway supports ({})",
+            version,
+            CURRENT_CONFIG_VERSION
+        ));
+    }
+
+    if version < 2 {
+        migrate_v1_to_v2(table)?;
+        tracing::warn!(
+            "Upgraded configuration from version {} to {}; add config_version = {} once the file is updated",
+            version,
+            CURRENT_CONFIG_VERSION,
+            CURRENT_CONFIG_VERSION
+        );
+    }
+    table.insert(
+        "config_version".to_string(),
+        toml::Value::Integer(CURRENT_CONFIG_VERSION.into()),
+    );
+    Ok(())
+}
+
+/// Fill in sections missing from a version 1 file, and settings missing
+/// from the sections it has, with the defaults of [`Config::default`].
+fn migrate_v1_to_v2(table: &mut toml::Table) -> anyhow::Result<()> {
+    let toml::Value::Table(defaults) = toml::Value::try_from(Config::default())? else {
+        return Err(anyhow::anyhow!("Default configuration is not a table"));
+    };
+    for section in V1_SECTIONS {
+        let Some(default) = defaults.get(section) else {
+            continue;
+        };
+        match (table.get_mut(section), default) {
+            (None, default) => {
+                table.insert(section.to_string(), default.clone());
+            }
+            (Some(toml::Value::Table(current)), toml::Value::Table(default)) => {
+                for (key, value) in default {
+                    current.entry(key).or_insert_with(|| value.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
 impl Config {
     pub fn load(path: &str) -> anyhow::Result<Self> {
         let content = std::fs::read_to_string(path)?;
-        let mut config: Config = toml::from_str(&content)?;
+        let mut config = Self::from_toml(&content)?;
 
         // Apply environment variable overrides
         config.apply_env_overrides()?;
@@ -341,6 +1414,23 @@ impl Config {
         Ok(config)
     }
 
+    /// Parse a configuration file, upgrading older versions with
+    /// [`migrate_config`].
+    pub fn from_toml(content: &str) -> anyhow::Result<Self> {
+        let mut table: toml::Table = toml::from_str(content)?;
+        migrate_config(&mut table)?;
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+
+    /// Circuit breaker for `provider`: its own table, else the global
+    /// `[circuit_breaker]` default.
+    pub fn circuit_breaker_for(&self, provider: &str) -> Option<&CircuitBreakerConfig> {
+        self.providers
+            .get(provider)
+            .and_then(|provider| provider.circuit_breaker.as_ref())
+            .or(self.circuit_breaker.as_ref())
+    }
+
     /// Validate configuration schema and constraints
     pub fn validate_schema(&self) -> anyhow::Result<()> {
         // Validate server configuration
@@ -361,7 +1451,7 @@ impl Config {
                 ));
             }
 
-            if provider.timeout.as_secs() == 0 {
+            if provider.total_timeout.as_secs() == 0 {
                 return Err(anyhow::anyhow!(
                     "Provider timeout cannot be 0 for provider: {}",
                     name
@@ -489,14 +1579,13 @@ impl Config {
                     parse_duration(&recovery_timeout),
                     parse_duration(&request_timeout),
                 ) {
-                    provider_config.circuit_breaker = Some(
-                        ultrafast_models_sdk::circuit_breaker::CircuitBreakerConfig {
-                            failure_threshold,
-                            recovery_timeout,
-                            request_timeout,
-                            half_open_max_calls: 3, // Default value
-                        },
-                    );
+                    provider_config.circuit_breaker = Some(CircuitBreakerConfig {
+                        failure_threshold,
+                        recovery_timeout,
+                        request_timeout,
+                        half_open_max_calls: 3, // Default value
+                        ..Default::default()
+                    });
                     tracing::debug!(
                         "Loaded circuit breaker config for provider: {}",
                         provider_name
@@ -581,11 +1670,19 @@ impl Config {
             || {
                 self.validate_server()?;
                 self.validate_providers()?;
+                self.validate_routing()?;
                 self.validate_auth()?;
                 self.validate_cache()?;
                 self.validate_metrics()?;
                 self.validate_logging()?;
                 self.validate_plugins()?;
+                self.validate_pricing()?;
+                self.validate_feature_flags()?;
+                self.validate_model_ab_tests()?;
+                self.validate_models()?;
+                self.validate_telemetry()?;
+                self.validate_budgets()?;
+                self.validate_embeddings()?;
                 Ok(())
             },
             "Configuration validation",
@@ -595,54 +1692,360 @@ impl Config {
         Ok(())
     }
 
-    fn validate_server(&self) -> anyhow::Result<()> {
-        if self.server.port == 0 {
+    fn validate_server(&self) -> anyhow::Result<()> {
+        if self.server.port == 0 {
+            return Err(anyhow::anyhow!(
+                "Server port must be between 1 and 65535, got {}",
+                self.server.port
+            ));
+        }
+
+        if self.server.max_body_size == 0 {
+            return Err(anyhow::anyhow!("Max body size cannot be 0"));
+        }
+
+        if self.server.max_body_size > 100 * 1024 * 1024 {
+            // 100MB
+            return Err(anyhow::anyhow!(
+                "Max body size cannot exceed 100MB, got {} bytes",
+                self.server.max_body_size
+            ));
+        }
+
+        if !matches!(self.server.unsupported_capability_status, 422 | 501) {
+            return Err(anyhow::anyhow!(
+                "Unsupported capability status must be 422 or 501, got {}",
+                self.server.unsupported_capability_status
+            ));
+        }
+
+        if self.server.timeout.as_secs() == 0 {
+            return Err(anyhow::anyhow!("Server timeout cannot be 0"));
+        }
+
+        if self.server.timeout.as_secs() > 300 {
+            // 5 minutes
+            return Err(anyhow::anyhow!(
+                "Server timeout cannot exceed 5 minutes, got {} seconds",
+                self.server.timeout.as_secs()
+            ));
+        }
+
+        // Validate host format
+        if self.server.host.is_empty() {
+            return Err(anyhow::anyhow!("Server host cannot be empty"));
+        }
+
+        if self.server.max_response_chars == Some(0) {
+            return Err(anyhow::anyhow!(
+                "Server max_response_chars must be greater than 0"
+            ));
+        }
+
+        if self.server.max_messages_per_request == Some(0) {
+            return Err(anyhow::anyhow!(
+                "Server max_messages_per_request must be greater than 0"
+            ));
+        }
+
+        if let Some((model, _)) = self
+            .server
+            .max_messages_per_model
+            .iter()
+            .find(|(_, limit)| **limit == 0)
+        {
+            return Err(anyhow::anyhow!(
+                "Server max_messages_per_model for model {} must be greater than 0",
+                model
+            ));
+        }
+
+        if let Some((model, _)) = self
+            .server
+            .max_total_tokens
+            .iter()
+            .find(|(_, limit)| **limit == 0)
+        {
+            return Err(anyhow::anyhow!(
+                "Server max_total_tokens for model {} must be greater than 0",
+                model
+            ));
+        }
+
+        validate_cors(&self.server.cors)?;
+
+        Ok(())
+    }
+
+    fn validate_providers(&self) -> anyhow::Result<()> {
+        if self.providers.is_empty() {
+            return Err(anyhow::anyhow!("At least one provider must be configured"));
+        }
+
+        if let Some(circuit_breaker) = &self.circuit_breaker {
+            validate_circuit_breaker("circuit_breaker", circuit_breaker)?;
+        }
+
+        // Check if at least one provider is enabled
+        let enabled_providers: Vec<_> = self
+            .providers
+            .iter()
+            .filter(|(_, provider)| provider.enabled)
+            .collect();
+        if enabled_providers.is_empty() {
+            return Err(anyhow::anyhow!("At least one provider must be enabled"));
+        }
+
+        for (name, provider) in &self.providers {
+            // Only validate enabled providers
+            if !provider.enabled {
+                continue;
+            }
+
+            // Validate provider name
+            if name.is_empty() {
+                return Err(anyhow::anyhow!("Provider name cannot be empty"));
+            }
+
+            if let Some(provider_type) = &provider.provider_type {
+                if !ultrafast_models_sdk::providers::BUILTIN_PROVIDERS
+                    .contains(&provider_type.as_str())
+                {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} has unknown type {}",
+                        name,
+                        provider_type
+                    ));
+                }
+                if provider_type == "openai_compatible" && provider.base_url.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} of type openai_compatible needs a base_url",
+                        name
+                    ));
+                }
+            }
+
+            // Allow empty API keys for local providers like Ollama
+            if provider.api_key.is_empty() && !name.to_lowercase().contains("ollama") {
+                return Err(anyhow::anyhow!("Provider {} has empty API key", name));
+            }
+
+            if provider.total_timeout.as_secs() == 0 {
+                return Err(anyhow::anyhow!("Provider {} has zero timeout", name));
+            }
+
+            if provider.total_timeout.as_secs() > 300 {
+                // 5 minutes
+                return Err(anyhow::anyhow!(
+                    "Provider {} timeout cannot exceed 5 minutes, got {} seconds",
+                    name,
+                    provider.total_timeout.as_secs()
+                ));
+            }
+
+            for (field, timeout) in [
+                ("connect_timeout", provider.connect_timeout),
+                ("first_byte_timeout", provider.first_byte_timeout),
+            ] {
+                if timeout > provider.total_timeout {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} {} cannot exceed total_timeout, got {:?} > {:?}",
+                        name,
+                        field,
+                        timeout,
+                        provider.total_timeout
+                    ));
+                }
+            }
+
+            if provider.max_retries > 10 {
+                return Err(anyhow::anyhow!(
+                    "Provider {} max_retries cannot exceed 10, got {}",
+                    name,
+                    provider.max_retries
+                ));
+            }
+
+            // Validate base URL if provided
+            if let Some(base_url) = &provider.base_url {
+                if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} base_url must start with http:// or https://",
+                        name
+                    ));
+                }
+            }
+
+            // Validate rate limits if provided
+            if let Some(rate_limit) = &provider.rate_limit {
+                if rate_limit.requests_per_minute == 0 && rate_limit.tokens_per_minute == 0 {
+                    return Err(anyhow::anyhow!("Provider {} rate limit must have non-zero requests_per_minute or tokens_per_minute", name));
+                }
+            }
+
+            if let Some(circuit_breaker) = &provider.circuit_breaker {
+                validate_circuit_breaker(
+                    &format!("Provider {name} circuit_breaker"),
+                    circuit_breaker,
+                )?;
+            }
+
+            if let Some(fairness) = &provider.fairness {
+                if fairness.max_concurrent_requests == 0 {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} fairness max_concurrent_requests must be greater than 0",
+                        name
+                    ));
+                }
+                if let Some((model, _)) = fairness
+                    .model_weights
+                    .iter()
+                    .find(|(_, weight)| **weight == 0)
+                {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} fairness weight for model {} must be greater than 0",
+                        name,
+                        model
+                    ));
+                }
+            }
+
+            ultrafast_models_sdk::providers::header_template::validate_header_templates(
+                &provider.headers,
+            )
+            .map_err(|e| anyhow::anyhow!("Provider {} {}", name, e))?;
+
+            if let Some(template) = &provider.request_template {
+                ultrafast_models_sdk::providers::request_template::validate_request_template(
+                    template,
+                )
+                .map_err(|e| anyhow::anyhow!("Provider {} {}", name, e))?;
+            }
+
+            if let Some(azure) = &provider.azure {
+                if let Some((model, _)) = azure
+                    .deployments
+                    .iter()
+                    .find(|(_, deployment)| deployment.deployment.trim().is_empty())
+                {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} azure deployment for model {} cannot be empty",
+                        name,
+                        model
+                    ));
+                }
+            }
+
+            if let Some(keepalive) = &provider.stream_keepalive {
+                if keepalive.idle_interval.is_zero() {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} stream_keepalive idle_interval must be greater than 0",
+                        name
+                    ));
+                }
+                if !keepalive.ping_path.starts_with('/') {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} stream_keepalive ping_path must start with '/', got {}",
+                        name,
+                        keepalive.ping_path
+                    ));
+                }
+            }
+
+            if let Some(range) = &provider.temperature_range {
+                if !range.min.is_finite()
+                    || !range.max.is_finite()
+                    || range.min < 0.0
+                    || range.min > range.max
+                {
+                    return Err(anyhow::anyhow!(
+                        "Provider {} temperature_range must satisfy 0 <= min <= max, got {}..={}",
+                        name,
+                        range.min,
+                        range.max
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_routing(&self) -> anyhow::Result<()> {
+        if self.routing.slow_start.enabled && self.routing.slow_start.window.is_zero() {
             return Err(anyhow::anyhow!(
-                "Server port must be between 1 and 65535, got {}",
-                self.server.port
+                "Routing slow_start window must be greater than 0"
             ));
         }
 
-        if self.server.max_body_size == 0 {
-            return Err(anyhow::anyhow!("Max body size cannot be 0"));
-        }
-
-        if self.server.max_body_size > 100 * 1024 * 1024 {
-            // 100MB
-            return Err(anyhow::anyhow!(
-                "Max body size cannot exceed 100MB, got {} bytes",
-                self.server.max_body_size
-            ));
+        let budget = &self.routing.error_budget;
+        if budget.enabled {
+            if budget.max_errors == 0 {
+                return Err(anyhow::anyhow!(
+                    "Routing error_budget max_errors must be greater than 0"
+                ));
+            }
+            if budget.window.is_zero() || budget.cooldown.is_zero() {
+                return Err(anyhow::anyhow!(
+                    "Routing error_budget window and cooldown must be greater than 0"
+                ));
+            }
         }
 
-        if self.server.timeout.as_secs() == 0 {
-            return Err(anyhow::anyhow!("Server timeout cannot be 0"));
+        if let Some(name) = self.routing.strategy.sticky_header() {
+            if axum::http::HeaderName::from_bytes(name.as_bytes()).is_err() {
+                return Err(anyhow::anyhow!(
+                    "Sticky routing header '{}' is not a valid header name",
+                    name
+                ));
+            }
         }
 
-        if self.server.timeout.as_secs() > 300 {
-            // 5 minutes
-            return Err(anyhow::anyhow!(
-                "Server timeout cannot exceed 5 minutes, got {} seconds",
-                self.server.timeout.as_secs()
-            ));
+        let mut strategy = &self.routing.strategy;
+        while let RoutingStrategy::Sticky { inner, .. } | RoutingStrategy::ModelAffinity { inner } =
+            strategy
+        {
+            strategy = inner;
         }
-
-        // Validate host format
-        if self.server.host.is_empty() {
-            return Err(anyhow::anyhow!("Server host cannot be empty"));
+        if let RoutingStrategy::LatencyWeighted { window, smoothing } = strategy {
+            if window.is_zero() {
+                return Err(anyhow::anyhow!(
+                    "LatencyWeighted window must be greater than 0"
+                ));
+            }
+            if !(*smoothing > 0.0 && *smoothing <= 1.0) {
+                return Err(anyhow::anyhow!(
+                    "LatencyWeighted smoothing must be in (0, 1], got {}",
+                    smoothing
+                ));
+            }
         }
 
-        // Validate CORS config
-        if self.server.cors.enabled {
-            if self.server.cors.allowed_origins.is_empty() {
+        let auto_tune = &self.routing.auto_tune;
+        if auto_tune.enabled {
+            if !(auto_tune.min_weight > 0.0 && auto_tune.min_weight <= auto_tune.max_weight) {
+                return Err(anyhow::anyhow!(
+                    "Routing auto_tune requires 0 < min_weight <= max_weight, got {}..={}",
+                    auto_tune.min_weight,
+                    auto_tune.max_weight
+                ));
+            }
+            if auto_tune.interval.is_zero() {
                 return Err(anyhow::anyhow!(
-                    "CORS enabled but no allowed origins specified"
+                    "Routing auto_tune interval must be greater than 0"
                 ));
             }
+        }
 
-            if self.server.cors.allowed_methods.is_empty() {
+        for (model, alternates) in &self.routing.model_retry_alternates {
+            if let Some(alternate) = alternates
+                .iter()
+                .find(|alternate| alternate.trim().is_empty() || *alternate == model)
+            {
                 return Err(anyhow::anyhow!(
-                    "CORS enabled but no allowed methods specified"
+                    "Routing model_retry_alternates for {} must name other models, got '{}'",
+                    model,
+                    alternate
                 ));
             }
         }
@@ -650,72 +2053,149 @@ impl Config {
         Ok(())
     }
 
-    fn validate_providers(&self) -> anyhow::Result<()> {
-        if self.providers.is_empty() {
-            return Err(anyhow::anyhow!("At least one provider must be configured"));
+    fn validate_pricing(&self) -> anyhow::Result<()> {
+        for (key, pricing) in &self.pricing {
+            if !(pricing.input_per_1k >= 0.0 && pricing.output_per_1k >= 0.0) {
+                return Err(anyhow::anyhow!(
+                    "Pricing for {} must be non-negative, got input_per_1k={} output_per_1k={}",
+                    key,
+                    pricing.input_per_1k,
+                    pricing.output_per_1k
+                ));
+            }
         }
 
-        // Check if at least one provider is enabled
-        let enabled_providers: Vec<_> = self
-            .providers
-            .iter()
-            .filter(|(_, provider)| provider.enabled)
-            .collect();
-        if enabled_providers.is_empty() {
-            return Err(anyhow::anyhow!("At least one provider must be enabled"));
-        }
+        Ok(())
+    }
 
-        for (name, provider) in &self.providers {
-            // Only validate enabled providers
-            if !provider.enabled {
-                continue;
+    fn validate_feature_flags(&self) -> anyhow::Result<()> {
+        for (name, flag) in &self.feature_flags {
+            if !(0.0..=100.0).contains(&flag.percentage) {
+                return Err(anyhow::anyhow!(
+                    "Feature flag {} percentage must be between 0 and 100, got {}",
+                    name,
+                    flag.percentage
+                ));
             }
+        }
 
-            // Validate provider name
-            if name.is_empty() {
-                return Err(anyhow::anyhow!("Provider name cannot be empty"));
-            }
+        Ok(())
+    }
 
-            // Allow empty API keys for local providers like Ollama
-            if provider.api_key.is_empty() && !name.to_lowercase().contains("ollama") {
-                return Err(anyhow::anyhow!("Provider {} has empty API key", name));
-            }
+    fn validate_embeddings(&self) -> anyhow::Result<()> {
+        if self.embeddings.batch_size == 0 {
+            return Err(anyhow::anyhow!(
+                "Embeddings batch_size must be greater than 0"
+            ));
+        }
+        if self.embeddings.max_concurrent_batches == 0 {
+            return Err(anyhow::anyhow!(
+                "Embeddings max_concurrent_batches must be greater than 0"
+            ));
+        }
+        Ok(())
+    }
 
-            if provider.timeout.as_secs() == 0 {
-                return Err(anyhow::anyhow!("Provider {} has zero timeout", name));
+    fn validate_budgets(&self) -> anyhow::Result<()> {
+        for (api_key, budget) in &self.budgets {
+            let name = &api_key[..api_key.len().min(8)];
+            if budget.daily_usd.is_none() && budget.monthly_usd.is_none() {
+                return Err(anyhow::anyhow!(
+                    "Budget for key {}... must set daily_usd or monthly_usd",
+                    name
+                ));
             }
-
-            if provider.timeout.as_secs() > 300 {
-                // 5 minutes
+            if [budget.daily_usd, budget.monthly_usd]
+                .into_iter()
+                .flatten()
+                .any(|limit| limit <= 0.0)
+            {
                 return Err(anyhow::anyhow!(
-                    "Provider {} timeout cannot exceed 5 minutes, got {} seconds",
+                    "Budget limits for key {}... must be greater than 0",
+                    name
+                ));
+            }
+            if !(budget.soft_limit_percent > 0.0 && budget.soft_limit_percent <= 100.0) {
+                return Err(anyhow::anyhow!(
+                    "Budget soft_limit_percent for key {}... must be between 0 and 100, got {}",
                     name,
-                    provider.timeout.as_secs()
+                    budget.soft_limit_percent
                 ));
             }
+        }
 
-            if provider.max_retries > 10 {
+        let alerts = &self.budget_alerts;
+        if let Some(url) = &alerts.webhook_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
                 return Err(anyhow::anyhow!(
-                    "Provider {} max_retries cannot exceed 10, got {}",
-                    name,
-                    provider.max_retries
+                    "budget_alerts.webhook_url must be an http(s) URL, got {}",
+                    url
                 ));
             }
+        }
+        if let Some(threshold) = alerts
+            .thresholds
+            .iter()
+            .find(|threshold| !(**threshold > 0.0 && **threshold <= 100.0))
+        {
+            return Err(anyhow::anyhow!(
+                "budget_alerts thresholds must be between 0 and 100, got {}",
+                threshold
+            ));
+        }
+        Ok(())
+    }
 
-            // Validate base URL if provided
-            if let Some(base_url) = &provider.base_url {
-                if !base_url.starts_with("http://") && !base_url.starts_with("https://") {
+    fn validate_telemetry(&self) -> anyhow::Result<()> {
+        if self.telemetry.enabled && self.telemetry.otlp_endpoint.trim().is_empty() {
+            return Err(anyhow::anyhow!(
+                "telemetry.otlp_endpoint is required when telemetry is enabled"
+            ));
+        }
+        if self.telemetry.service_name.trim().is_empty() {
+            return Err(anyhow::anyhow!("telemetry.service_name cannot be empty"));
+        }
+        Ok(())
+    }
+
+    fn validate_model_ab_tests(&self) -> anyhow::Result<()> {
+        for (name, test) in &self.model_ab_tests {
+            if test.variants.is_empty() {
+                return Err(anyhow::anyhow!("Model A/B test {} has no variants", name));
+            }
+            for variant in &test.variants {
+                if !variant.weight.is_finite() || variant.weight <= 0.0 {
                     return Err(anyhow::anyhow!(
-                        "Provider {} base_url must start with http:// or https://",
-                        name
+                        "Model A/B test {} weight for {} must be greater than 0, got {}",
+                        name,
+                        variant.model,
+                        variant.weight
                     ));
                 }
             }
+        }
 
-            // Validate rate limits if provided
-            if let Some(rate_limit) = &provider.rate_limit {
-                if rate_limit.requests_per_minute == 0 && rate_limit.tokens_per_minute == 0 {
-                    return Err(anyhow::anyhow!("Provider {} rate limit must have non-zero requests_per_minute or tokens_per_minute", name));
+        Ok(())
+    }
+
+    fn validate_models(&self) -> anyhow::Result<()> {
+        for (model, limits) in &self.models.limits {
+            if limits.context_window == Some(0) || limits.max_output_tokens == Some(0) {
+                return Err(anyhow::anyhow!(
+                    "Model {} token limits must be greater than 0",
+                    model
+                ));
+            }
+            if let (Some(context_window), Some(max_output_tokens)) =
+                (limits.context_window, limits.max_output_tokens)
+            {
+                if max_output_tokens > context_window {
+                    return Err(anyhow::anyhow!(
+                        "Model {} max_output_tokens ({}) exceeds its context_window ({})",
+                        model,
+                        max_output_tokens,
+                        context_window
+                    ));
                 }
             }
         }
@@ -787,6 +2267,73 @@ impl Config {
                     ));
                 }
             }
+
+            match &self.auth.identity.source {
+                IdentitySource::Bearer => {}
+                IdentitySource::Header { name } => {
+                    if name.parse::<axum::http::HeaderName>().is_err() {
+                        return Err(anyhow::anyhow!("Invalid identity header name: '{}'", name));
+                    }
+                }
+                IdentitySource::JwtClaim { path } => {
+                    if path.is_empty() || path.split('.').any(|segment| segment.is_empty()) {
+                        return Err(anyhow::anyhow!(
+                            "Invalid identity JWT claim path: '{}'",
+                            path
+                        ));
+                    }
+                }
+            }
+        }
+
+        let jwt = &self.auth.jwt;
+        let hmac = matches!(
+            jwt.algorithm,
+            jsonwebtoken::Algorithm::HS256
+                | jsonwebtoken::Algorithm::HS384
+                | jsonwebtoken::Algorithm::HS512
+        );
+        match (&jwt.jwks_url, hmac) {
+            (Some(_), true) => {
+                return Err(anyhow::anyhow!(
+                    "Auth jwt jwks_url requires an RSA or EC algorithm, not {:?}",
+                    jwt.algorithm
+                ));
+            }
+            (None, false) => {
+                return Err(anyhow::anyhow!(
+                    "Auth jwt algorithm {:?} requires a jwks_url",
+                    jwt.algorithm
+                ));
+            }
+            _ => {}
+        }
+        if jwt.secret.as_deref() == Some("") {
+            return Err(anyhow::anyhow!("Auth jwt secret cannot be empty"));
+        }
+
+        let admin = &self.auth.admin;
+        if admin.enabled {
+            if admin.api_keys.is_empty() && !self.auth.api_keys.iter().any(|k| k.allow_admin_access)
+            {
+                return Err(anyhow::anyhow!(
+                    "Admin auth enabled but no admin keys configured"
+                ));
+            }
+            for admin_key in &admin.api_keys {
+                if admin_key.key.len() < 16 {
+                    return Err(anyhow::anyhow!(
+                        "Admin key {} is too short (minimum 16 characters)",
+                        admin_key.name
+                    ));
+                }
+                if self.auth.api_keys.iter().any(|k| k.key == admin_key.key) {
+                    return Err(anyhow::anyhow!(
+                        "Admin key {} is also configured as an API key; use allow_admin_access instead",
+                        admin_key.name
+                    ));
+                }
+            }
         }
 
         // Validate global rate limiting
@@ -805,6 +2352,13 @@ impl Config {
                 "Global rate limiting tokens_per_minute cannot be 0"
             ));
         }
+        if let RateLimitBackend::Redis { url } = &self.auth.rate_limiting.backend {
+            if url.trim().is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Rate limiting Redis backend requires a url"
+                ));
+            }
+        }
 
         Ok(())
     }
@@ -827,6 +2381,47 @@ impl Config {
                 return Err(anyhow::anyhow!("Cache TTL cannot be 0"));
             }
 
+            if self.cache.embedding_ttl.as_secs() == 0 {
+                return Err(anyhow::anyhow!("Cache embedding_ttl cannot be 0"));
+            }
+
+            if self.cache.shards == 0 || self.cache.shards > 1024 {
+                return Err(anyhow::anyhow!(
+                    "Cache shards must be between 1 and 1024, got {}",
+                    self.cache.shards
+                ));
+            }
+
+            if self.cache.key_precision > 6 {
+                return Err(anyhow::anyhow!(
+                    "Cache key_precision must be at most 6, got {}",
+                    self.cache.key_precision
+                ));
+            }
+
+            if self.cache.mode == CacheMode::Semantic {
+                let semantic = &self.cache.semantic;
+                if semantic.embedding_model.is_empty() {
+                    return Err(anyhow::anyhow!(
+                        "Semantic cache requires an embedding_model"
+                    ));
+                }
+                if !(semantic.similarity_threshold > 0.0 && semantic.similarity_threshold <= 1.0) {
+                    return Err(anyhow::anyhow!(
+                        "Semantic cache similarity_threshold must be in (0, 1], got {}",
+                        semantic.similarity_threshold
+                    ));
+                }
+                if let Some(provider) = &semantic.embedding_provider {
+                    if !self.providers.contains_key(provider) {
+                        return Err(anyhow::anyhow!(
+                            "Semantic cache embedding_provider '{}' is not configured",
+                            provider
+                        ));
+                    }
+                }
+            }
+
             // Validate Redis URL if Redis backend is used
             if let CacheBackend::Redis { url } = &self.cache.backend {
                 if !url.starts_with("redis://") && !url.starts_with("rediss://") {
@@ -867,6 +2462,53 @@ impl Config {
                     "Metrics cleanup_interval cannot be longer than retention_duration"
                 ));
             }
+
+            let buckets = &self.metrics.latency_buckets;
+            if buckets.is_empty() {
+                return Err(anyhow::anyhow!("Metrics latency_buckets cannot be empty"));
+            }
+            if buckets
+                .iter()
+                .any(|bound| !bound.is_finite() || *bound <= 0.0)
+            {
+                return Err(anyhow::anyhow!(
+                    "Metrics latency_buckets must be positive numbers of seconds"
+                ));
+            }
+            if buckets.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return Err(anyhow::anyhow!(
+                    "Metrics latency_buckets must be in increasing order"
+                ));
+            }
+
+            let health = &self.metrics.health_score;
+            let weights = [
+                health.latency_weight,
+                health.error_weight,
+                health.circuit_weight,
+            ];
+            if weights
+                .iter()
+                .any(|weight| !weight.is_finite() || *weight < 0.0)
+                || weights.iter().sum::<f64>() <= 0.0
+            {
+                return Err(anyhow::anyhow!(
+                    "Metrics health_score weights must be non-negative with a positive sum"
+                ));
+            }
+            if !(health.latency_target_ms >= 0.0
+                && health.latency_target_ms < health.latency_max_ms)
+            {
+                return Err(anyhow::anyhow!(
+                    "Metrics health_score requires 0 <= latency_target_ms < latency_max_ms"
+                ));
+            }
+            if !(health.smoothing > 0.0 && health.smoothing <= 1.0) {
+                return Err(anyhow::anyhow!(
+                    "Metrics health_score smoothing must be in (0, 1], got {}",
+                    health.smoothing
+                ));
+            }
         }
 
         Ok(())
@@ -900,6 +2542,22 @@ impl Config {
             }
         }
 
+        let body_logging = &self.logging.body_logging;
+        if body_logging.enabled && body_logging.max_bytes == 0 {
+            return Err(anyhow::anyhow!(
+                "logging.body_logging.max_bytes must be greater than 0"
+            ));
+        }
+        for pattern in &body_logging.redact_patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(anyhow::anyhow!(
+                    "Invalid logging.body_logging redact pattern '{}': {}",
+                    pattern,
+                    e
+                ));
+            }
+        }
+
         Ok(())
     }
 
@@ -957,54 +2615,180 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
+            config_version: CURRENT_CONFIG_VERSION,
             server: ServerConfig {
                 host: "127.0.0.1".to_string(),
                 port: 3000,
                 timeout: Duration::from_secs(30),
                 max_body_size: 1024 * 1024, // 1MB
-                cors: CorsConfig {
-                    enabled: true,
-                    allowed_origins: vec!["*".to_string()],
-                    allowed_methods: vec!["GET".to_string(), "POST".to_string()],
-                    allowed_headers: vec!["*".to_string()],
-                    max_age: Some(Duration::from_secs(3600)),
-                },
+                cors: CorsConfig::default(),
+                max_response_chars: None,
+                max_total_tokens: HashMap::new(),
+                max_messages_per_request: None,
+                max_messages_per_model: HashMap::new(),
+                stream_initial_flush: true,
+                sse_keepalive_interval: default_sse_keepalive_interval(),
+                unsupported_capability_status: default_unsupported_capability_status(),
+                shutdown_timeout: default_shutdown_timeout(),
+                strip_response_fields: Vec::new(),
+                response_checksum: false,
             },
             providers: HashMap::new(),
             routing: RoutingConfig {
                 strategy: RoutingStrategy::Single,
                 health_check_interval: Duration::from_secs(30),
                 failover_threshold: 0.8,
+                slow_start: SlowStartConfig::default(),
+                error_budget: ErrorBudgetConfig::default(),
+                debug: false,
+                auto_tune: AutoTuneConfig::default(),
+                fallback_response: FallbackResponseConfig::default(),
+                model_retry_alternates: HashMap::new(),
+                max_prompt_tokens: None,
             },
             auth: AuthConfig {
                 enabled: false,
                 api_keys: vec![],
+                api_key_store: None,
                 rate_limiting: RateLimitConfig {
                     requests_per_minute: 60,
                     requests_per_hour: 1000,
                     tokens_per_minute: 10000,
+                    backend: RateLimitBackend::Memory,
                 },
+                identity: IdentityConfig::default(),
+                jwt: JwtConfig::default(),
+                admin: AdminAuthConfig::default(),
             },
             cache: CacheConfig {
                 enabled: true,
                 backend: CacheBackend::Memory,
                 ttl: Duration::from_secs(300),
+                embedding_ttl: default_embedding_ttl(),
                 max_size: 1000,
+                shards: default_cache_shards(),
+                coalesce_requests: false,
+                endpoints: CacheEndpointsConfig::default(),
+                key_fields: Vec::new(),
+                key_precision: default_cache_key_precision(),
+                mode: CacheMode::Exact,
+                semantic: SemanticCacheConfig::default(),
+                preload_file: None,
+                preload_ttl: default_preload_ttl(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
                 format: LogFormat::Pretty,
                 output: LogOutput::Stdout,
+                body_logging: BodyLoggingConfig::default(),
             },
             metrics: MetricsConfig {
                 enabled: true,
                 max_requests: 10000,
                 retention_duration: Duration::from_secs(3600), // 1 hour
                 cleanup_interval: Duration::from_secs(300),    // 5 minutes
+                latency_buckets: default_latency_buckets(),
+                health_score: Default::default(),
             },
             plugins: vec![],
+            pricing: default_pricing(),
+            feature_flags: HashMap::new(),
+            models: ModelsConfig::default(),
+            model_ab_tests: HashMap::new(),
+            circuit_breaker: None,
+            telemetry: TelemetryConfig::default(),
+            budgets: HashMap::new(),
+            budget_alerts: BudgetAlertsConfig::default(),
+            stream: StreamConfig::default(),
+            embeddings: EmbeddingBatchConfig::default(),
+            request: RequestConfig::default(),
+        }
+    }
+}
+
+/// Reject CORS settings that browsers could not use or that would let any
+/// site make credentialed requests.
+fn validate_cors(cors: &CorsConfig) -> anyhow::Result<()> {
+    if !cors.enabled {
+        return Ok(());
+    }
+    if cors.allowed_origins.is_empty() {
+        return Err(anyhow::anyhow!(
+            "CORS enabled but no allowed origins specified"
+        ));
+    }
+    if cors.allowed_methods.is_empty() {
+        return Err(anyhow::anyhow!(
+            "CORS enabled but no allowed methods specified"
+        ));
+    }
+
+    for origin in cors.allowed_origins.iter().filter(|o| *o != "*") {
+        let host = origin
+            .strip_prefix("https://")
+            .or_else(|| origin.strip_prefix("http://"));
+        if host.is_none_or(|host| host.is_empty() || host.contains('/')) {
+            return Err(anyhow::anyhow!(
+                "Invalid CORS origin {}: expected scheme://host[:port]",
+                origin
+            ));
+        }
+    }
+    for method in cors.allowed_methods.iter().filter(|m| *m != "*") {
+        if axum::http::Method::from_bytes(method.as_bytes()).is_err() {
+            return Err(anyhow::anyhow!("Invalid CORS method: {}", method));
+        }
+    }
+    for header in cors.allowed_headers.iter().filter(|h| *h != "*") {
+        if axum::http::HeaderName::from_bytes(header.as_bytes()).is_err() {
+            return Err(anyhow::anyhow!("Invalid CORS header: {}", header));
+        }
+    }
+
+    if cors.allow_credentials {
+        let wildcard = [
+            &cors.allowed_origins,
+            &cors.allowed_methods,
+            &cors.allowed_headers,
+        ]
+        .iter()
+        .any(|values| values.iter().any(|value| value == "*"));
+        if wildcard {
+            return Err(anyhow::anyhow!(
+                "CORS allow_credentials cannot be combined with \"*\" origins, methods or headers"
+            ));
         }
     }
+    Ok(())
+}
+
+/// Reject circuit breaker settings that would never close or never open.
+fn validate_circuit_breaker(scope: &str, config: &CircuitBreakerConfig) -> anyhow::Result<()> {
+    if config.failure_threshold == 0 {
+        return Err(anyhow::anyhow!(
+            "{} failure_threshold must be greater than 0",
+            scope
+        ));
+    }
+    if config.half_open_max_calls == 0 {
+        return Err(anyhow::anyhow!(
+            "{} half_open_max_calls must be greater than 0",
+            scope
+        ));
+    }
+    if config.recovery_timeout.is_zero() || config.request_timeout.is_zero() {
+        return Err(anyhow::anyhow!(
+            "{} recovery_timeout and request_timeout must be greater than 0",
+            scope
+        ));
+    }
+    if config.latency_threshold_ms == Some(0) || config.latency_window == 0 {
+        return Err(anyhow::anyhow!(
+            "{} latency_threshold_ms and latency_window must be greater than 0",
+            scope
+        ));
+    }
+    Ok(())
 }
 
 pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
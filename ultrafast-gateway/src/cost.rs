@@ -0,0 +1,132 @@
+//! # Cost Estimation Module
+//!
+//! This module estimates the USD cost of provider requests from token usage
+//! and the `[pricing]` section of the gateway configuration.
+//!
+//! ## Overview
+//!
+//! Pricing entries are keyed by provider name (`"openai"`) or by provider and
+//! model (`"openai/gpt-4o"`). A model-specific entry takes precedence over the
+//! provider default, so operators can keep a provider-wide rate and override
+//! it for individual models.
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [pricing.openai]
+//! input_per_1k = 0.03
+//! output_per_1k = 0.06
+//!
+//! [pricing."openai/gpt-4o-mini"]
+//! input_per_1k = 0.00015
+//! output_per_1k = 0.0006
+//! ```
+//!
+//! Requests to a provider without a pricing entry are reported at a cost of
+//! 0.0 and a warning is logged once per provider/model pair.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use ultrafast_gateway::config::Config;
+//! use ultrafast_gateway::cost::{estimate_cost, initialize_pricing};
+//!
+//! # let config = Config::default();
+//! initialize_pricing(config.pricing.clone());
+//! let cost = estimate_cost("openai", "gpt-4", 1200, 300);
+//! ```
+
+use crate::config::ModelPricing;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock, RwLock};
+
+static PRICING: OnceLock<RwLock<HashMap<String, ModelPricing>>> = OnceLock::new();
+static WARNED_MISSING: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn get_pricing() -> &'static RwLock<HashMap<String, ModelPricing>> {
+    PRICING.get_or_init(|| RwLock::new(crate::config::default_pricing()))
+}
+
+/// Install the pricing table used by [`estimate_cost`].
+pub fn initialize_pricing(pricing: HashMap<String, ModelPricing>) {
+    *get_pricing().write().unwrap() = pricing;
+}
+
+/// Estimate the cost in USD of a request using the global pricing table.
+pub fn estimate_cost(provider: &str, model: &str, input_tokens: u32, output_tokens: u32) -> f64 {
+    let pricing = get_pricing().read().unwrap();
+    estimate_cost_with(&pricing, provider, model, input_tokens, output_tokens)
+}
+
+/// Estimate the cost in USD of a request using an explicit pricing table.
+///
+/// Looks up `"{provider}/{model}"` first and falls back to `provider`.
+pub fn estimate_cost_with(
+    pricing: &HashMap<String, ModelPricing>,
+    provider: &str,
+    model: &str,
+    input_tokens: u32,
+    output_tokens: u32,
+) -> f64 {
+    let model_key = format!("{provider}/{model}");
+    let Some(rates) = pricing.get(&model_key).or_else(|| pricing.get(provider)) else {
+        warn_missing_pricing(&model_key);
+        return 0.0;
+    };
+
+    (input_tokens as f64 / 1000.0) * rates.input_per_1k
+        + (output_tokens as f64 / 1000.0) * rates.output_per_1k
+}
+
+fn warn_missing_pricing(model_key: &str) {
+    let mut warned = WARNED_MISSING
+        .get_or_init(|| Mutex::new(HashSet::new()))
+        .lock()
+        .unwrap();
+    if warned.insert(model_key.to_string()) {
+        tracing::warn!(
+            "No pricing configured for {}; reporting cost as 0.0. Add a [pricing] entry to track costs.",
+            model_key
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pricing() -> HashMap<String, ModelPricing> {
+        HashMap::from([
+            (
+                "openai".to_string(),
+                ModelPricing {
+                    input_per_1k: 0.03,
+                    output_per_1k: 0.06,
+                },
+            ),
+            (
+                "openai/gpt-4o-mini".to_string(),
+                ModelPricing {
+                    input_per_1k: 0.001,
+                    output_per_1k: 0.002,
+                },
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_model_pricing_overrides_provider_default() {
+        let pricing = pricing();
+        let cost = estimate_cost_with(&pricing, "openai", "gpt-4o-mini", 1000, 1000);
+        assert!((cost - 0.003).abs() < 1e-9, "{cost}");
+
+        let cost = estimate_cost_with(&pricing, "openai", "gpt-4", 1000, 1000);
+        assert!((cost - 0.09).abs() < 1e-9, "{cost}");
+    }
+
+    #[test]
+    fn test_missing_pricing_costs_nothing() {
+        let cost = estimate_cost_with(&pricing(), "mistral", "mistral-large", 1000, 1000);
+        assert_eq!(cost, 0.0);
+    }
+}
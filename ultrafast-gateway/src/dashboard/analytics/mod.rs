@@ -4,6 +4,7 @@
 use crate::gateway_error::GatewayError;
 use crate::dashboard::architecture::{DashboardContext, TimeRange};
 use serde::{Deserialize, Serialize};
+use crate::metrics::{AggregatedMetrics, Aggregation};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -503,37 +504,33 @@ impl AnalyticsEngine {
     }
     
     async fn execute_aggregation_query(&self, query: CustomAnalyticsQuery) -> Result<QueryResult, GatewayError> {
-        // Basic aggregation query execution
-        // In production, this would execute against a real database
+        // Aggregates the gateway's recorded metrics, grouped by provider
         let start_time = std::time::Instant::now();
         
         // Parse the query to extract aggregation parameters
         let query_lower = query.query.to_lowercase();
-        let aggregation_type = if query_lower.contains("count") {
-            "count"
+        let aggregation = if query_lower.contains("count") {
+            Aggregation::Count
         } else if query_lower.contains("sum") {
-            "sum"
+            Aggregation::Sum
         } else if query_lower.contains("avg") {
-            "average"
+            Aggregation::Average
         } else if query_lower.contains("min") {
-            "minimum"
+            Aggregation::Minimum
         } else if query_lower.contains("max") {
-            "maximum"
+            Aggregation::Maximum
         } else {
-            "count"
+            Aggregation::Count
         };
         
-        // Simulate aggregation result
-        let result_data = json!({
-            "aggregation_type": aggregation_type,
-            "value": 1234,
-            "group_by": "provider",
-            "groups": [
-                {"provider": "openai", "value": 567},
-                {"provider": "anthropic", "value": 432},
-                {"provider": "google", "value": 235}
-            ]
-        });
+        let result_data = match self.config.data_source {
+            AnalyticsDataSource::Metrics => {
+                let metrics = crate::metrics::get_aggregated_metrics().await;
+                metrics_aggregation(aggregation, &metrics)
+            }
+            AnalyticsDataSource::Demo => demo_aggregation(aggregation),
+        };
+        let row_count = result_data["groups"].as_array().map_or(0, Vec::len);
         
         let execution_time = start_time.elapsed();
         
@@ -542,7 +539,7 @@ impl AnalyticsEngine {
             result_type: ResultType::Aggregation,
             data: result_data,
             execution_time_ms: execution_time.as_millis() as u64,
-            row_count: 3,
+            row_count,
         })
     }
 
@@ -1387,6 +1384,46 @@ pub struct AnalyticsMetadata {
     pub cache_hit: bool,
 }
 
+/// Aggregation of the recorded gateway metrics for a custom query, grouped
+/// by provider.
+fn metrics_aggregation(aggregation: Aggregation, metrics: &AggregatedMetrics) -> Value {
+    let (value, groups) = metrics.by_provider(aggregation);
+    json!({
+        "aggregation_type": aggregation,
+        "value": value,
+        "group_by": "provider",
+        "groups": groups
+            .into_iter()
+            .map(|(provider, value)| json!({"provider": provider, "value": value}))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Fixed aggregation shown with [`AnalyticsDataSource::Demo`].
+fn demo_aggregation(aggregation: Aggregation) -> Value {
+    json!({
+        "aggregation_type": aggregation,
+        "value": 1234,
+        "group_by": "provider",
+        "groups": [
+            {"provider": "openai", "value": 567},
+            {"provider": "anthropic", "value": 432},
+            {"provider": "google", "value": 235}
+        ]
+    })
+}
+
+/// Where custom analytics queries read their data from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalyticsDataSource {
+    /// The gateway's recorded request metrics
+    #[default]
+    Metrics,
+    /// Fixed demo data, for screenshots and demos
+    Demo,
+}
+
 // Configuration and supporting structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalyticsConfig {
@@ -1398,6 +1435,8 @@ pub struct AnalyticsConfig {
     pub cache_ttl_seconds: u64,
     pub max_data_points: usize,
     pub retention_days: u32,
+    #[serde(default)]
+    pub data_source: AnalyticsDataSource,
 }
 
 impl Default for AnalyticsConfig {
@@ -1411,6 +1450,7 @@ impl Default for AnalyticsConfig {
             cache_ttl_seconds: 300, // 5 minutes
             max_data_points: 10000,
             retention_days: 90,
+            data_source: AnalyticsDataSource::Metrics,
         }
     }
 }
@@ -1653,4 +1693,5 @@ pub struct UsageData {
     pub usage_by_model: HashMap<String, ModelUsage>,
     pub usage_by_user: HashMap<String, UserUsage>,
     pub usage_trends: Vec<UsageTrendPoint>,
-}
\ No newline at end of file
+}
+
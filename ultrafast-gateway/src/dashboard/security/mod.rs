@@ -685,37 +685,17 @@ pub mod authentication {
         }
 
         async fn authenticate_jwt(&self, token: &str) -> Result<UserContext, GatewayError> {
-            // In a real implementation, you would validate the JWT token
-            // For now, we'll implement a basic JWT validation
             if token.is_empty() {
                 return Err(GatewayError::Authentication {
                     message: "Empty JWT token".to_string(),
                 });
             }
 
-            // Basic JWT structure validation (header.payload.signature)
-            let parts: Vec<&str> = token.split('.').collect();
-            if parts.len() != 3 {
-                return Err(GatewayError::Authentication {
-                    message: "Invalid JWT format".to_string(),
-                });
-            }
-
-            // Decode payload (in production, you'd verify the signature)
-            let payload = parts[1];
-            let decoded = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
-                .map_err(|_| GatewayError::Authentication {
-                    message: "Invalid JWT payload".to_string(),
-                })?;
-
-            let payload_str = String::from_utf8(decoded)
-                .map_err(|_| GatewayError::Authentication {
-                    message: "Invalid JWT payload encoding".to_string(),
-                })?;
-
-            let payload_json: serde_json::Value = serde_json::from_str(&payload_str)
-                .map_err(|_| GatewayError::Authentication {
-                    message: "Invalid JWT payload format".to_string(),
+            // Signature, expiry, issuer and audience are checked against [auth.jwt]
+            let payload_json = crate::auth::verify_jwt_claims_global(token)
+                .await
+                .map_err(|e| GatewayError::Authentication {
+                    message: format!("Invalid JWT: {e}"),
                 })?;
 
             // Extract user information from JWT payload
@@ -763,35 +743,31 @@ pub mod authentication {
         }
 
         async fn authenticate_api_key(&self, api_key: &str) -> Result<UserContext, GatewayError> {
-            // In a real implementation, you would validate the API key against a database
-            // For now, we'll implement a basic API key validation
             if api_key.is_empty() {
                 return Err(GatewayError::Authentication {
                     message: "Empty API key".to_string(),
                 });
             }
 
-            // Basic API key format validation (should be at least 32 characters)
-            if api_key.len() < 32 {
-                return Err(GatewayError::Authentication {
-                    message: "Invalid API key format".to_string(),
-                });
-            }
-
-            // In production, you would:
-            // 1. Hash the API key and look it up in a database
-            // 2. Check if the API key is active and not expired
-            // 3. Retrieve user information associated with the API key
-
-            // For now, we'll create a user context based on the API key
-            let user_id = format!("api_user_{}", api_key[..8].to_string());
-            let username = format!("API User ({})", api_key[..8].to_string());
+            // Configured keys and the hashed key store of [auth]
+            let context = crate::auth::validate_api_key_global(api_key)
+                .await
+                .map_err(|e| GatewayError::Authentication {
+                    message: e.to_string(),
+                })?;
+            let user_id = context.user_id;
+            let username = format!("API User ({})", user_id);
+            let roles = context
+                .metadata
+                .get("roles")
+                .map(|roles| roles.split(',').map(|role| role.to_string()).collect())
+                .unwrap_or_else(|| vec!["api_user".to_string()]);
 
             Ok(UserContext {
                 user_id,
                 username,
                 email: None,
-                roles: vec!["api_user".to_string()],
+                roles,
                 permissions: vec!["dashboard:read".to_string(), "api:access".to_string()],
                 session_id: format!("api_{}", uuid::Uuid::new_v4()),
                 login_time: chrono::Utc::now().timestamp(),
@@ -611,9 +611,9 @@ class ModernDashboard {
                 const errorRate = stats.error_rate || 0;
                 const latency = stats.average_latency_ms || 0;
                 const uptime = stats.uptime_percentage || 0;
+                const healthScore = stats.health_score ?? 100;
                 
-                const slaMetrics = this.calculateSLA(errorRate, latency, uptime);
-                const statusInfo = this.getProviderStatusInfo(slaMetrics);
+                const statusInfo = this.getProviderStatusInfo(healthScore);
                 
                 const div = document.createElement('div');
                 div.className = `provider-card border-l-4 border-${statusInfo.color}-500 bg-white dark:bg-gray-800 rounded-lg p-6 shadow-sm border border-gray-200 dark:border-gray-700 mb-4`;
@@ -631,7 +631,7 @@ class ModernDashboard {
                                         ${provider.charAt(0).toUpperCase() + provider.slice(1)}
                                     </div>
                                     <div class="text-sm text-${statusInfo.color}-600 dark:text-${statusInfo.color}-400">
-                                        ${statusInfo.status} • SLA Score: ${slaMetrics.score.toFixed(1)}%
+                                        ${statusInfo.status} • Health Score: ${healthScore.toFixed(0)}/100
                                     </div>
                                 </div>
                             </div>
@@ -702,24 +702,8 @@ class ModernDashboard {
         }
     }
     
-    calculateSLA(errorRate, latency, uptime) {
-        const reliabilityScore = Math.max(0, 100 - (errorRate * 100 * 10));
-        const performanceScore = Math.max(0, 100 - Math.max(0, (latency - 1000) / 50));
-        const uptimeScore = uptime;
-        
-        const score = (reliabilityScore * 0.4) + (performanceScore * 0.3) + (uptimeScore * 0.3);
-        
-        return {
-            score,
-            reliability: reliabilityScore,
-            performance: performanceScore,
-            uptime: uptimeScore
-        };
-    }
-    
-    getProviderStatusInfo(slaMetrics) {
-        const score = slaMetrics.score;
-        
+    // Scores are computed by the gateway (`health_score` in /metrics)
+    getProviderStatusInfo(score) {
         if (score >= 95) {
             return { 
                 status: 'Excellent', 
@@ -14,6 +14,8 @@
 //! - **Cache Statistics**: Hit rates, memory usage, and performance metrics
 //! - **Atomic Operations**: Thread-safe cache operations
 //! - **Key Management**: Structured cache key generation
+//! - **Preloading**: Chat request/response pairs from `cache.preload_file` are
+//!   cached at startup
 //!
 //! ## Cache Backends
 //!
@@ -24,6 +26,12 @@
 //! - **Memory Efficient**: Configurable size limits
 //! - **Automatic Cleanup**: Expired entries removed automatically
 //! - **Thread Safe**: Concurrent access support
+//! - **Sharded Locks**: Keys are spread across `cache.shards` segments to reduce contention
+//! - **Request Coalescing**: With `cache.coalesce_requests`, identical concurrent
+//!   requests share a single upstream call
+//! - **Semantic Lookups**: With `cache.mode = "Semantic"`, a chat request that
+//!   misses is served the response cached for the most similar earlier prompt
+//!   (by cosine similarity of prompt embeddings) above `similarity_threshold`
 //!
 //! ### Redis Caching
 //!
@@ -52,7 +60,17 @@
 //!     enabled: true,
 //!     backend: CacheBackend::Redis { url: "redis://localhost:6379".to_string() },
 //!     ttl: Duration::from_secs(3600),
+//!     embedding_ttl: Duration::from_secs(30 * 24 * 3600),
 //!     max_size: 1000,
+//!     shards: 16,
+//!     coalesce_requests: false,
+//!     endpoints: CacheEndpointsConfig::default(),
+//!     key_fields: vec![],
+//!     key_precision: 2,
+//!     mode: CacheMode::Exact,
+//!     semantic: SemanticCacheConfig::default(),
+//!     preload_file: None,
+//!     preload_ttl: Duration::from_secs(24 * 3600),
 //! };
 //!
 //! let cache_manager = CacheManager::new(config).await?;
@@ -77,6 +95,19 @@
 //! backend = "redis"  # or "memory"
 //! ttl = "1h"
 //! max_size = 1000
+//! shards = 16  # lock-striped segments for the in-memory store
+//! coalesce_requests = false  # share one upstream call between identical in-flight requests
+//! mode = "Exact"  # or "Semantic" to also match similar prompts
+//!
+//! [cache.semantic]  # used in Semantic mode
+//! embedding_model = "text-embedding-3-small"
+//! similarity_threshold = 0.95
+//!
+//! [cache.endpoints]  # per-endpoint toggles
+//! chat = true
+//! embeddings = true
+//! images = false
+//! audio = false
 //! ```
 //!
 //! ## Performance Benefits
@@ -107,27 +138,250 @@
 //! - **Error Rates**: Cache operation failures
 
 // Caching module with Redis integration and cache invalidation
-use crate::config::{CacheBackend, CacheConfig};
+use crate::config::{CacheBackend, CacheConfig, CacheMode, ModelAbTest, NormalizationConfig};
 use crate::gateway_error::GatewayError;
 use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, OnceLock};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+use ultrafast_models_sdk::models::{ChatRequest, ChatResponse};
+
+/// A single lock-striped segment of the in-memory cache.
+type CacheShard = RwLock<HashMap<String, CacheEntry>>;
+
+/// In-memory cache store split into independently locked shards.
+///
+/// Keys are assigned to a shard by hash so that concurrent requests for
+/// different keys rarely contend on the same lock. Each shard enforces
+/// its own slice of the overall `max_size` budget.
+#[derive(Debug)]
+struct ShardedStore {
+    shards: Vec<CacheShard>,
+    max_entries_per_shard: usize,
+}
+
+impl ShardedStore {
+    fn new(shard_count: usize, max_size: usize) -> Self {
+        let shard_count = shard_count.max(1);
+        let shards = (0..shard_count)
+            .map(|_| RwLock::new(HashMap::new()))
+            .collect();
+        Self {
+            shards,
+            max_entries_per_shard: max_size.div_ceil(shard_count).max(1),
+        }
+    }
+
+    fn shard_for(&self, key: &str) -> &CacheShard {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let index = (hasher.finish() as usize) % self.shards.len();
+        &self.shards[index]
+    }
+
+    async fn get(&self, key: &str) -> Option<serde_json::Value> {
+        let shard = self.shard_for(key);
+        {
+            let cache = shard.read().await;
+            match cache.get(key) {
+                Some(entry) if !entry.is_expired() => return Some(entry.data.clone()),
+                Some(_) => {}
+                None => return None,
+            }
+        }
+
+        // Entry expired; take the write lock to drop it
+        let mut cache = shard.write().await;
+        if cache.get(key).is_some_and(|entry| entry.is_expired()) {
+            cache.remove(key);
+        }
+        None
+    }
+
+    async fn insert(&self, key: &str, entry: CacheEntry) {
+        let mut cache = self.shard_for(key).write().await;
+
+        // Implement simple LRU eviction if the shard is full
+        if cache.len() >= self.max_entries_per_shard && !cache.contains_key(key) {
+            // Remove oldest entries (simple implementation)
+            let mut entries: Vec<_> = cache
+                .iter()
+                .map(|(k, v)| (k.clone(), v.created_at))
+                .collect();
+            entries.sort_by_key(|(_, created_at)| *created_at);
+
+            let remove_count = cache.len() - self.max_entries_per_shard + 1;
+            for (key_to_remove, _) in entries.into_iter().take(remove_count) {
+                cache.remove(&key_to_remove);
+            }
+        }
+
+        cache.insert(key.to_string(), entry);
+    }
+
+    async fn remove(&self, key: &str) {
+        self.shard_for(key).write().await.remove(key);
+    }
+
+    async fn clear(&self) {
+        for shard in &self.shards {
+            shard.write().await.clear();
+        }
+    }
+
+    async fn stats(&self) -> CacheStats {
+        let mut total_entries = 0;
+        let mut expired_entries = 0;
+        for shard in &self.shards {
+            let cache = shard.read().await;
+            total_entries += cache.len();
+            expired_entries += cache.values().filter(|entry| entry.is_expired()).count();
+        }
+        CacheStats {
+            total_entries,
+            expired_entries,
+            memory_usage_bytes: total_entries * std::mem::size_of::<CacheEntry>(),
+        }
+    }
+}
+
+/// A prompt embedding stored alongside the cache entry it was answered with.
+#[derive(Debug)]
+struct SemanticEntry {
+    /// Exact cache key of the request without its messages, so only prompts
+    /// sent with the same model and parameters are compared
+    scope: String,
+    embedding: Vec<f32>,
+    /// Key of the cached response
+    key: String,
+}
 
-/// Global in-memory cache storage for fallback operations.
+/// Prompt embeddings of cached chat responses, oldest first.
 ///
-/// Uses `OnceLock` to ensure the cache store is initialized only once
-/// and shared across all threads.
-static CACHE: OnceLock<Arc<RwLock<HashMap<String, CacheEntry>>>> = OnceLock::new();
+/// Lookups are a linear scan; the index holds at most `max_size` entries,
+/// like the cache itself. Entries whose response has expired or been
+/// evicted are dropped when they are matched.
+#[derive(Debug)]
+struct SemanticIndex {
+    entries: RwLock<VecDeque<SemanticEntry>>,
+    max_entries: usize,
+}
+
+impl SemanticIndex {
+    fn new(max_entries: usize) -> Self {
+        Self {
+            entries: RwLock::new(VecDeque::new()),
+            max_entries,
+        }
+    }
+
+    /// Cache key of the most similar prompt in `scope` at or above
+    /// `threshold`.
+    async fn nearest(&self, scope: &str, embedding: &[f32], threshold: f32) -> Option<String> {
+        let entries = self.entries.read().await;
+        entries
+            .iter()
+            .filter(|entry| entry.scope == scope)
+            .map(|entry| (cosine_similarity(&entry.embedding, embedding), entry))
+            .filter(|(similarity, _)| *similarity >= threshold)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, entry)| entry.key.clone())
+    }
+
+    async fn insert(&self, scope: &str, embedding: Vec<f32>, key: &str) {
+        let mut entries = self.entries.write().await;
+        entries.retain(|entry| entry.key != key);
+        while entries.len() >= self.max_entries {
+            entries.pop_front();
+        }
+        entries.push_back(SemanticEntry {
+            scope: scope.to_string(),
+            embedding,
+            key: key.to_string(),
+        });
+    }
+
+    async fn remove(&self, key: &str) {
+        self.entries.write().await.retain(|entry| entry.key != key);
+    }
+
+    async fn clear(&self) {
+        self.entries.write().await.clear();
+    }
+}
+
+/// Cosine similarity of two embeddings (0 for mismatched or zero vectors).
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Endpoint types whose responses can be cached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheEndpoint {
+    Chat,
+    Embeddings,
+    Images,
+    Audio,
+}
+
+/// Result of an upstream call shared with coalesced requests (`None` on failure).
+type FlightResult = Option<serde_json::Value>;
+
+/// Upstream calls currently in flight, keyed by cache key.
+type InFlightMap = Arc<Mutex<HashMap<String, broadcast::Sender<FlightResult>>>>;
 
-/// Get the global cache store instance.
+/// Role of a request in single-flight request coalescing.
+pub enum Flight {
+    /// No identical request is in flight: call upstream and publish the
+    /// result through the guard.
+    Leader(FlightGuard),
+    /// An identical request is already in flight: wait for its result.
+    Follower(broadcast::Receiver<FlightResult>),
+}
+
+/// Handle held by the request performing a coalesced upstream call.
 ///
-/// Returns a reference to the global cache store, initializing it
-/// if it hasn't been initialized yet.
-fn get_cache_store() -> &'static Arc<RwLock<HashMap<String, CacheEntry>>> {
-    CACHE.get_or_init(|| Arc::new(RwLock::new(HashMap::new())))
+/// Dropping the guard without calling [`FlightGuard::complete`] releases
+/// waiting requests without a result, so they fall back to calling upstream
+/// themselves.
+pub struct FlightGuard {
+    key: String,
+    in_flight: InFlightMap,
+    completed: bool,
+}
+
+impl FlightGuard {
+    /// Publish the upstream response to every request waiting on this flight.
+    pub fn complete(mut self, response: serde_json::Value) {
+        self.completed = true;
+        let sender = self.in_flight.lock().unwrap().remove(&self.key);
+        if let Some(sender) = sender {
+            // No receivers simply means nobody joined this flight
+            let _ = sender.send(Some(response));
+        }
+    }
+}
+
+impl Drop for FlightGuard {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.in_flight.lock().unwrap().remove(&self.key);
+        }
+    }
 }
 
 /// A cache entry containing data and metadata.
@@ -186,6 +440,24 @@ impl CacheEntry {
     }
 }
 
+/// Optimize the payload of a chat request, keeping the fields that are not
+/// serialized. The chat handler sends and caches requests in this form.
+pub fn optimize_chat_request(request: &ChatRequest) -> Result<ChatRequest, serde_json::Error> {
+    let request_json = serde_json::to_value(request)?;
+    let optimized_request_json =
+        crate::json_optimization::JsonOptimizer::optimize_request_payload(&request_json);
+    let mut optimized_request: ChatRequest = serde_json::from_value(optimized_request_json)?;
+    optimized_request.routing_metadata = request.routing_metadata.clone();
+    Ok(optimized_request)
+}
+
+/// One request/response pair of `cache.preload_file`.
+#[derive(Deserialize)]
+struct PreloadEntry {
+    request: serde_json::Value,
+    response: serde_json::Value,
+}
+
 /// Cache manager for handling both Redis and in-memory caching.
 ///
 /// Provides a unified interface for caching operations with automatic
@@ -202,7 +474,17 @@ impl CacheEntry {
 ///     enabled: true,
 ///     backend: CacheBackend::Redis { url: "redis://localhost:6379".to_string() },
 ///     ttl: Duration::from_secs(3600),
+///     embedding_ttl: Duration::from_secs(30 * 24 * 3600),
 ///     max_size: 1000,
+///     shards: 16,
+///     coalesce_requests: false,
+///     endpoints: CacheEndpointsConfig::default(),
+///     key_fields: vec![],
+///     key_precision: 2,
+///     mode: CacheMode::Exact,
+///     semantic: SemanticCacheConfig::default(),
+///     preload_file: None,
+///     preload_ttl: Duration::from_secs(24 * 3600),
 /// };
 ///
 /// let cache_manager = CacheManager::new(config).await?;
@@ -213,6 +495,12 @@ pub struct CacheManager {
     config: CacheConfig,
     /// Optional Redis client for distributed caching
     redis_client: Option<redis::Client>,
+    /// Sharded in-memory store (primary for memory backend, fallback for Redis)
+    memory: ShardedStore,
+    /// Upstream calls that identical requests can join
+    in_flight: InFlightMap,
+    /// Prompt embeddings for semantic chat lookups
+    semantic: SemanticIndex,
 }
 
 impl CacheManager {
@@ -254,12 +542,182 @@ impl CacheManager {
             }
         };
 
+        let memory = ShardedStore::new(config.shards, config.max_size);
+        let semantic = SemanticIndex::new(config.max_size);
+
         Ok(Self {
             config,
             redis_client,
+            memory,
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            semantic,
         })
     }
 
+    /// Whether responses for `endpoint` should be cached.
+    pub fn caches(&self, endpoint: CacheEndpoint) -> bool {
+        let endpoints = &self.config.endpoints;
+        self.config.enabled
+            && match endpoint {
+                CacheEndpoint::Chat => endpoints.chat,
+                CacheEndpoint::Embeddings => endpoints.embeddings,
+                CacheEndpoint::Images => endpoints.images,
+                CacheEndpoint::Audio => endpoints.audio,
+            }
+    }
+
+    /// Whether chat misses fall back to semantic lookups.
+    pub fn semantic(&self) -> bool {
+        self.caches(CacheEndpoint::Chat) && self.config.mode == CacheMode::Semantic
+    }
+
+    /// Retrieve the response cached for the prompt most similar to
+    /// `embedding` within `scope`, if any is above the similarity threshold.
+    pub async fn semantic_get(&self, scope: &str, embedding: &[f32]) -> Option<serde_json::Value> {
+        if !self.semantic() {
+            return None;
+        }
+
+        let threshold = self.config.semantic.similarity_threshold;
+        let key = self.semantic.nearest(scope, embedding, threshold).await?;
+        let value = self.get(&key).await;
+        if value.is_none() {
+            // The response expired or was evicted; forget its prompt
+            self.semantic.remove(&key).await;
+        }
+        value
+    }
+
+    /// Record the prompt embedding of the response cached under `key`.
+    pub async fn semantic_set(&self, scope: &str, embedding: Vec<f32>, key: &str) {
+        if !self.semantic() {
+            return;
+        }
+        self.semantic.insert(scope, embedding, key).await;
+    }
+
+    /// Join the in-flight upstream call for `key`, or start a new one.
+    ///
+    /// Returns `None` when request coalescing is disabled.
+    pub fn join_flight(&self, key: &str) -> Option<Flight> {
+        if !self.config.coalesce_requests {
+            return None;
+        }
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(sender) = in_flight.get(key) {
+            return Some(Flight::Follower(sender.subscribe()));
+        }
+
+        let (sender, _) = broadcast::channel(1);
+        in_flight.insert(key.to_string(), sender);
+        Some(Flight::Leader(FlightGuard {
+            key: key.to_string(),
+            in_flight: self.in_flight.clone(),
+            completed: false,
+        }))
+    }
+
+    /// Load the chat request/response pairs of `cache.preload_file` into
+    /// the cache, returning how many were loaded.
+    ///
+    /// The file holds a JSON array of `{"request": ..., "response": ...}`
+    /// objects. Each request is keyed as the chat handler keys it, after
+    /// `normalization` and payload optimization, so a matching request is
+    /// answered from the cache. A request for a model A/B test is cached for
+    /// each of the test's variants. Entries that do not parse are skipped
+    /// with a warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a JSON array.
+    pub async fn preload(
+        &self,
+        normalization: &NormalizationConfig,
+        model_ab_tests: &HashMap<String, ModelAbTest>,
+    ) -> Result<usize, GatewayError> {
+        let Some(path) = &self.config.preload_file else {
+            return Ok(0);
+        };
+        if !self.caches(CacheEndpoint::Chat) {
+            tracing::warn!("Chat caching is disabled, not preloading {}", path);
+            return Ok(0);
+        }
+
+        let contents = std::fs::read_to_string(path).map_err(|e| GatewayError::Config {
+            message: format!("Failed to read cache preload file {path}: {e}"),
+        })?;
+        let entries: Vec<serde_json::Value> =
+            serde_json::from_str(&contents).map_err(|e| GatewayError::Config {
+                message: format!("Cache preload file {path} is not a JSON array: {e}"),
+            })?;
+
+        let mut loaded = 0;
+        for (index, entry) in entries.into_iter().enumerate() {
+            match self.preload_entry(entry, normalization, model_ab_tests) {
+                Ok((keys, response)) => {
+                    for key in keys {
+                        self.set(&key, response.clone(), Some(self.config.preload_ttl))
+                            .await;
+                    }
+                    loaded += 1;
+                }
+                Err(e) => tracing::warn!(
+                    "Skipping malformed cache preload entry {} in {}: {}",
+                    index,
+                    path,
+                    e
+                ),
+            }
+        }
+        tracing::info!("Preloaded {} cache entries from {}", loaded, path);
+        Ok(loaded)
+    }
+
+    /// Cache keys and response of one preload entry.
+    fn preload_entry(
+        &self,
+        entry: serde_json::Value,
+        normalization: &NormalizationConfig,
+        model_ab_tests: &HashMap<String, ModelAbTest>,
+    ) -> Result<(Vec<String>, serde_json::Value), serde_json::Error> {
+        let entry: PreloadEntry = serde_json::from_value(entry)?;
+        let request: ChatRequest = serde_json::from_value(entry.request)?;
+        let response: ChatResponse = serde_json::from_value(entry.response)?;
+
+        // The handler resolves an A/B test to one variant per request
+        let models = match model_ab_tests.get(&request.model) {
+            Some(test) => test
+                .variants
+                .iter()
+                .map(|variant| variant.model.clone())
+                .collect(),
+            None => vec![request.model.clone()],
+        };
+        let keys = models
+            .into_iter()
+            .map(|model| {
+                let mut request = ChatRequest {
+                    model,
+                    ..request.clone()
+                };
+                crate::request_normalization::normalize(&mut request, normalization);
+                Ok(self.chat_key(&optimize_chat_request(&request)?))
+            })
+            .collect::<Result<_, serde_json::Error>>()?;
+        Ok((keys, serde_json::to_value(response)?))
+    }
+
+    /// Key a chat completion for `optimized_request` is cached under; see
+    /// [`optimize_chat_request`].
+    pub fn chat_key(&self, optimized_request: &ChatRequest) -> String {
+        ultrafast_models_sdk::cache::CacheKeyBuilder::build_chat_key_with(
+            optimized_request,
+            &self.config.key_fields,
+            self.config.key_precision,
+        )
+    }
+
     /// Retrieve a value from the cache.
     ///
     /// Attempts to retrieve a value from Redis first, then falls back
@@ -291,15 +749,7 @@ impl CacheManager {
         }
 
         // Memory cache (fallback or primary)
-        let mut cache = get_cache_store().write().await;
-        if let Some(entry) = cache.get(key) {
-            if !entry.is_expired() {
-                return Some(entry.data.clone());
-            } else {
-                cache.remove(key);
-            }
-        }
-        None
+        self.memory.get(key).await
     }
 
     /// Cache a value in the cache.
@@ -339,24 +789,7 @@ impl CacheManager {
         }
 
         // Memory cache (fallback or primary)
-        let mut cache = get_cache_store().write().await;
-
-        // Implement simple LRU eviction if cache is full
-        if cache.len() >= self.config.max_size {
-            // Remove oldest entries (simple implementation)
-            let mut entries: Vec<_> = cache
-                .iter()
-                .map(|(k, v)| (k.clone(), v.created_at))
-                .collect();
-            entries.sort_by_key(|(_, created_at)| *created_at);
-
-            let remove_count = cache.len() - self.config.max_size + 1;
-            for (key_to_remove, _) in entries.into_iter().take(remove_count) {
-                cache.remove(&key_to_remove);
-            }
-        }
-
-        cache.insert(key.to_string(), entry);
+        self.memory.insert(key, entry).await;
     }
 
     /// Invalidate a single cache entry.
@@ -391,8 +824,7 @@ impl CacheManager {
         }
 
         // Memory cache (fallback or primary)
-        let mut cache = get_cache_store().write().await;
-        cache.remove(key);
+        self.memory.remove(key).await;
     }
 
     /// Clear the entire cache.
@@ -410,6 +842,8 @@ impl CacheManager {
             return;
         }
 
+        self.semantic.clear().await;
+
         // Try Redis first if available, fallback to memory
         if self.redis_client.is_some() {
             match self.redis_clear().await {
@@ -422,8 +856,7 @@ impl CacheManager {
         }
 
         // Memory cache (fallback or primary)
-        let mut cache = get_cache_store().write().await;
-        cache.clear();
+        self.memory.clear().await;
     }
 
     /// Get current cache statistics.
@@ -436,14 +869,7 @@ impl CacheManager {
     /// Returns a `CacheStats` struct containing the statistics.
     pub async fn stats(&self) -> CacheStats {
         match &self.config.backend {
-            CacheBackend::Memory => {
-                let cache = get_cache_store().read().await;
-                CacheStats {
-                    total_entries: cache.len(),
-                    expired_entries: cache.values().filter(|entry| entry.is_expired()).count(),
-                    memory_usage_bytes: cache.len() * std::mem::size_of::<CacheEntry>(),
-                }
-            }
+            CacheBackend::Memory => self.memory.stats().await,
             CacheBackend::Redis { url: _ } => CacheStats {
                 total_entries: 0,
                 expired_entries: 0,
@@ -452,38 +878,6 @@ impl CacheManager {
         }
     }
 
-    // Helper methods for memory cache
-    #[allow(dead_code)]
-    async fn get_from_memory(&self, key: &str) -> Option<serde_json::Value> {
-        let mut cache = get_cache_store().write().await;
-        if let Some(entry) = cache.get(key) {
-            if !entry.is_expired() {
-                return Some(entry.data.clone());
-            } else {
-                cache.remove(key);
-            }
-        }
-        None
-    }
-
-    #[allow(dead_code)]
-    async fn set_in_memory(&self, key: &str, entry: CacheEntry) {
-        let mut cache = get_cache_store().write().await;
-        cache.insert(key.to_string(), entry);
-    }
-
-    #[allow(dead_code)]
-    async fn invalidate_memory(&self, key: &str) {
-        let mut cache = get_cache_store().write().await;
-        cache.remove(key);
-    }
-
-    #[allow(dead_code)]
-    async fn clear_memory(&self) {
-        let mut cache = get_cache_store().write().await;
-        cache.clear();
-    }
-
     // Redis implementation methods using async connections
     async fn redis_get(
         &self,
@@ -727,3 +1121,139 @@ impl CacheKeyBuilder {
         format!("{:x}", hasher.finish())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn memory_config(shards: usize, max_size: usize) -> CacheConfig {
+        CacheConfig {
+            enabled: true,
+            backend: CacheBackend::Memory,
+            ttl: Duration::from_secs(60),
+            embedding_ttl: Duration::from_secs(60),
+            max_size,
+            shards,
+            coalesce_requests: false,
+            endpoints: Default::default(),
+            key_fields: Vec::new(),
+            key_precision: 2,
+            mode: CacheMode::Exact,
+            semantic: Default::default(),
+            preload_file: None,
+            preload_ttl: Duration::from_secs(60),
+        }
+    }
+
+    /// Run 32 concurrent workers that each write and read back 250 keys,
+    /// returning the number of reads that hit.
+    async fn run_concurrent_workload(cache: Arc<CacheManager>) -> usize {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..32)
+            .map(|worker| {
+                let cache = cache.clone();
+                let hits = hits.clone();
+                tokio::spawn(async move {
+                    for i in 0..250 {
+                        let key = format!("worker{worker}:key{i}");
+                        cache.set(&key, serde_json::json!(i), None).await;
+                        if cache.get(&key).await == Some(serde_json::json!(i)) {
+                            hits.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        hits.load(Ordering::Relaxed)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_sharded_cache_concurrent_access() {
+        for shards in [1, 16] {
+            let cache = Arc::new(
+                CacheManager::new(memory_config(shards, 100_000))
+                    .await
+                    .unwrap(),
+            );
+
+            assert_eq!(run_concurrent_workload(cache.clone()).await, 32 * 250);
+            assert_eq!(cache.stats().await.total_entries, 32 * 250);
+            for worker in 0..32 {
+                for i in [0, 124, 249] {
+                    let key = format!("worker{worker}:key{i}");
+                    assert_eq!(cache.get(&key).await, Some(serde_json::json!(i)));
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sharded_cache_respects_max_size() {
+        let cache = CacheManager::new(memory_config(4, 40)).await.unwrap();
+        for i in 0..200 {
+            cache
+                .set(&format!("key{i}"), serde_json::json!(i), None)
+                .await;
+        }
+
+        assert!(cache.stats().await.total_entries <= 40);
+        assert_eq!(cache.get("key199").await, Some(serde_json::json!(199)));
+
+        cache.invalidate("key199").await;
+        assert_eq!(cache.get("key199").await, None);
+
+        cache.clear().await;
+        assert_eq!(cache.stats().await.total_entries, 0);
+    }
+
+    /// Stub embedding: word counts hashed into a small fixed-size vector.
+    fn embed(prompt: &str) -> Vec<f32> {
+        let mut embedding = vec![0.0; 64];
+        for word in prompt
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let bucket = CacheKeyBuilder::hash_content(word);
+            let bucket = u64::from_str_radix(&bucket, 16).unwrap() as usize % embedding.len();
+            embedding[bucket] += 1.0;
+        }
+        embedding
+    }
+
+    #[tokio::test]
+    async fn test_semantic_lookup_matches_similar_prompts() {
+        let mut config = memory_config(4, 100);
+        config.mode = CacheMode::Semantic;
+        config.semantic.similarity_threshold = 0.9;
+        let cache = CacheManager::new(config).await.unwrap();
+
+        let prompt = "What is the capital city of France?";
+        let response = serde_json::json!({"answer": "Paris"});
+        cache.set("chat:france", response.clone(), None).await;
+        cache
+            .semantic_set("gpt-4", embed(prompt), "chat:france")
+            .await;
+
+        let near_duplicate = embed("What is the capital city of France, please?");
+        assert_eq!(
+            cache.semantic_get("gpt-4", &near_duplicate).await,
+            Some(response)
+        );
+
+        let unrelated = embed("How do I bake sourdough bread at home?");
+        assert_eq!(cache.semantic_get("gpt-4", &unrelated).await, None);
+
+        // Same prompt, different model or parameters
+        assert_eq!(cache.semantic_get("gpt-4o", &near_duplicate).await, None);
+
+        // Entries whose response is gone no longer match
+        cache.invalidate("chat:france").await;
+        assert_eq!(cache.semantic_get("gpt-4", &near_duplicate).await, None);
+    }
+}
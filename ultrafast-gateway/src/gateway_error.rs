@@ -130,10 +130,44 @@ pub enum GatewayError {
     #[error("Invalid request: {message}")]
     InvalidRequest { message: String },
 
+    /// Request fields with values no provider accepts, one entry per field
+    #[error(
+        "Request validation failed: {}",
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+    )]
+    Validation {
+        errors: Vec<crate::request_validation::FieldError>,
+    },
+
+    /// Request body without fields its endpoint requires
+    #[error("Missing required field{}: {}", if fields.len() == 1 { "" } else { "s" }, fields.join(", "))]
+    MissingFields { fields: Vec<String> },
+
+    /// Request body larger than `max_body_size`
+    #[error("Payload too large: {message}")]
+    PayloadTooLarge { message: String },
+
+    /// Estimated prompt longer than the `max_prompt_tokens` allowed
+    #[error(
+        "Estimated prompt of {estimated_tokens} tokens exceeds the {max_prompt_tokens}-token limit"
+    )]
+    PromptTooLong {
+        estimated_tokens: u32,
+        max_prompt_tokens: u32,
+    },
+
+    /// Uploaded content of a type the endpoint does not accept
+    #[error("Unsupported media type: {message}")]
+    UnsupportedMediaType { message: String },
+
     /// Rate limiting and quota violation errors
     #[error("Rate limit exceeded: {message}")]
     RateLimit { message: String },
 
+    /// Projected cost above the request's `x-max-cost-usd` cap
+    #[error("Cost limit exceeded: {message}")]
+    CostLimitExceeded { message: String },
+
     /// Content filtering and moderation errors
     #[error("Content filtered: {message}")]
     ContentFiltered { message: String },
@@ -142,6 +176,25 @@ pub enum GatewayError {
     #[error("Internal server error: {message}")]
     Internal { message: String },
 
+    /// Requested resource does not exist
+    #[error("Not found: {message}")]
+    NotFound { message: String },
+
+    /// No provider serves the requested model
+    #[error("Model {model} is not available; available models: {}", available.join(", "))]
+    ModelNotFound {
+        model: String,
+        available: Vec<String>,
+    },
+
+    /// The routed provider does not implement the requested endpoint
+    #[error("Provider {provider} does not support {capability}")]
+    UnsupportedCapability {
+        provider: String,
+        capability: String,
+        status: StatusCode,
+    },
+
     /// Service unavailability errors
     #[error("Service unavailable")]
     ServiceUnavailable,
@@ -192,7 +245,7 @@ impl IntoResponse for GatewayError {
                     self.to_string(),
                     "invalid_api_key",
                 ),
-                ProviderError::RateLimit => (
+                ProviderError::RateLimit { .. } => (
                     StatusCode::TOO_MANY_REQUESTS,
                     self.to_string(),
                     "provider_rate_limit",
@@ -229,11 +282,46 @@ impl IntoResponse for GatewayError {
             GatewayError::InvalidRequest { .. } => {
                 (StatusCode::BAD_REQUEST, self.to_string(), "invalid_request")
             }
+            GatewayError::MissingFields { .. } => {
+                (StatusCode::BAD_REQUEST, self.to_string(), "invalid_request")
+            }
+            GatewayError::Validation { .. } => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                self.to_string(),
+                "validation_error",
+            ),
+            GatewayError::PayloadTooLarge { .. } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                self.to_string(),
+                "payload_too_large",
+            ),
+            GatewayError::PromptTooLong { .. } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                self.to_string(),
+                "prompt_too_long",
+            ),
+            GatewayError::UnsupportedMediaType { .. } => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                self.to_string(),
+                "unsupported_media_type",
+            ),
+            GatewayError::CostLimitExceeded { .. } => (
+                StatusCode::PAYMENT_REQUIRED,
+                self.to_string(),
+                "cost_limit_exceeded",
+            ),
             GatewayError::ContentFiltered { .. } => (
                 StatusCode::BAD_REQUEST,
                 self.to_string(),
                 "content_filtered",
             ),
+            GatewayError::NotFound { .. } => (StatusCode::NOT_FOUND, self.to_string(), "not_found"),
+            GatewayError::ModelNotFound { .. } => {
+                (StatusCode::NOT_FOUND, self.to_string(), "model_not_found")
+            }
+            GatewayError::UnsupportedCapability { status, .. } => {
+                (*status, self.to_string(), "unsupported_capability")
+            }
             GatewayError::ServiceUnavailable => (
                 StatusCode::SERVICE_UNAVAILABLE,
                 self.to_string(),
@@ -261,14 +349,37 @@ impl IntoResponse for GatewayError {
             ),
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": {
                 "message": error_message,
                 "type": error_type,
                 "code": status.as_u16()
             }
-        }));
+        });
+        match &self {
+            GatewayError::Validation { errors } => {
+                body["error"]["errors"] = json!(errors);
+            }
+            GatewayError::MissingFields { fields } => {
+                let errors: Vec<_> = fields
+                    .iter()
+                    .map(|field| crate::request_validation::FieldError {
+                        field: field.clone(),
+                        message: "is required".to_string(),
+                    })
+                    .collect();
+                body["error"]["errors"] = json!(errors);
+            }
+            GatewayError::PromptTooLong {
+                estimated_tokens,
+                max_prompt_tokens,
+            } => {
+                body["error"]["estimated_tokens"] = json!(estimated_tokens);
+                body["error"]["max_prompt_tokens"] = json!(max_prompt_tokens);
+            }
+            _ => {}
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
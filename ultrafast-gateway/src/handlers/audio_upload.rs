@@ -0,0 +1,178 @@
+//! Request bodies for `POST /v1/audio/transcriptions`.
+//!
+//! The endpoint accepts the `multipart/form-data` uploads sent by the OpenAI
+//! Whisper API clients, with the audio in a `file` part and the `model`,
+//! `language`, `prompt`, `response_format` and `temperature` options as text
+//! fields. JSON bodies with the file as a byte array are still accepted.
+//!
+//! Uploads are limited to `server.max_body_size` bytes (`413 Payload Too
+//! Large` past it), and files that are not a supported audio format are
+//! rejected with `415 Unsupported Media Type`.
+
+use crate::gateway_error::GatewayError;
+use crate::server::AppState;
+use axum::body::Body;
+use axum::extract::multipart::{Field, MultipartError};
+use axum::extract::{FromRequest, Multipart};
+use axum::http::{header, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use ultrafast_models_sdk::models::AudioRequest;
+
+/// Audio mime types providers accept for transcription.
+const AUDIO_MIME_TYPES: &[&str] = &[
+    "audio/flac",
+    "audio/x-flac",
+    "audio/m4a",
+    "audio/x-m4a",
+    "audio/mp3",
+    "audio/mp4",
+    "audio/mpeg",
+    "audio/mpga",
+    "audio/ogg",
+    "audio/wav",
+    "audio/wave",
+    "audio/x-wav",
+    "audio/vnd.wave",
+    "audio/webm",
+    "video/mp4",
+    "video/mpeg",
+    "video/webm",
+];
+
+/// Transcription request read from either a multipart or a JSON body.
+pub struct AudioUpload(pub AudioRequest);
+
+impl FromRequest<AppState> for AudioUpload {
+    type Rejection = Response;
+
+    async fn from_request(req: Request<Body>, state: &AppState) -> Result<Self, Self::Rejection> {
+        let is_multipart = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.starts_with("multipart/form-data"));
+        if !is_multipart {
+            let Json(request) = Json::<AudioRequest>::from_request(req, state)
+                .await
+                .map_err(IntoResponse::into_response)?;
+            return Ok(Self(request));
+        }
+
+        let multipart = Multipart::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+        read_multipart(multipart)
+            .await
+            .map(Self)
+            .map_err(IntoResponse::into_response)
+    }
+}
+
+async fn read_multipart(mut multipart: Multipart) -> Result<AudioRequest, GatewayError> {
+    let mut request = AudioRequest {
+        file: Vec::new(),
+        file_name: None,
+        mime_type: None,
+        model: String::new(),
+        language: None,
+        prompt: None,
+        response_format: None,
+        temperature: None,
+    };
+    let mut has_file = false;
+
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        match field.name().unwrap_or_default() {
+            "file" => {
+                let file_name = field.file_name().map(str::to_string);
+                let mime_type = audio_mime_type(&field)?;
+                request.file = field.bytes().await.map_err(multipart_error)?.to_vec();
+                request.file_name = file_name;
+                request.mime_type = Some(mime_type);
+                has_file = true;
+            }
+            "model" => request.model = text(field).await?,
+            "language" => request.language = Some(text(field).await?),
+            "prompt" => request.prompt = Some(text(field).await?),
+            "response_format" => request.response_format = Some(text(field).await?),
+            "temperature" => {
+                let value = text(field).await?;
+                request.temperature =
+                    Some(value.parse().map_err(|_| GatewayError::InvalidRequest {
+                        message: format!("Invalid temperature: {value}"),
+                    })?);
+            }
+            // Options this gateway does not forward yet
+            _ => {}
+        }
+    }
+
+    if !has_file {
+        return Err(GatewayError::InvalidRequest {
+            message: "Missing 'file' field".to_string(),
+        });
+    }
+    if request.model.is_empty() {
+        return Err(GatewayError::InvalidRequest {
+            message: "Missing 'model' field".to_string(),
+        });
+    }
+    Ok(request)
+}
+
+/// Mime type of the uploaded file, guessed from its extension when the
+/// client sent a generic one (as `curl -F file=@...` does).
+fn audio_mime_type(field: &Field<'_>) -> Result<String, GatewayError> {
+    let declared = field
+        .content_type()
+        .map(|ct| {
+            ct.split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase()
+        })
+        .filter(|ct| ct != "application/octet-stream");
+    let mime_type = declared.or_else(|| {
+        let extension = field.file_name()?.rsplit_once('.')?.1.to_lowercase();
+        let guessed = match extension.as_str() {
+            "flac" => "audio/flac",
+            "m4a" => "audio/m4a",
+            "mp3" | "mpga" => "audio/mpeg",
+            "mp4" => "audio/mp4",
+            "mpeg" => "video/mpeg",
+            "oga" | "ogg" => "audio/ogg",
+            "wav" => "audio/wav",
+            "webm" => "audio/webm",
+            _ => return None,
+        };
+        Some(guessed.to_string())
+    });
+
+    match mime_type {
+        Some(mime_type) if AUDIO_MIME_TYPES.contains(&mime_type.as_str()) => Ok(mime_type),
+        other => Err(GatewayError::UnsupportedMediaType {
+            message: format!(
+                "Unsupported audio type '{}'",
+                other.as_deref().unwrap_or("application/octet-stream")
+            ),
+        }),
+    }
+}
+
+async fn text(field: Field<'_>) -> Result<String, GatewayError> {
+    field.text().await.map_err(multipart_error)
+}
+
+fn multipart_error(e: MultipartError) -> GatewayError {
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        GatewayError::PayloadTooLarge {
+            message: e.body_text(),
+        }
+    } else {
+        GatewayError::InvalidRequest {
+            message: e.body_text(),
+        }
+    }
+}
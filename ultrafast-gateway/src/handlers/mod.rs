@@ -20,8 +20,9 @@
 //!
 //! - `POST /v1/chat/completions` - Chat completion API with streaming support
 //! - `POST /v1/embeddings` - Text embedding generation
+//! - `POST /v1/embeddings/stream` - Batched embeddings streamed as SSE
 //! - `POST /v1/images/generations` - Image generation from text prompts
-//! - `POST /v1/audio/transcriptions` - Audio transcription
+//! - `POST /v1/audio/transcriptions` - Audio transcription (multipart or JSON upload)
 //! - `POST /v1/audio/speech` - Text-to-speech conversion
 //! - `GET /v1/models` - List available models
 //!
@@ -33,6 +34,9 @@
 //! - `GET /admin/providers` - Provider status and health
 //! - `GET /admin/config` - Current configuration status
 //! - `GET /admin/circuit-breaker` - Circuit breaker metrics
+//! - `POST /admin/circuit-breaker/{provider}/{action}` - Open, close or reset
+//!   a provider's circuit breaker
+//! - `POST /admin/compare` - Send one chat request to several providers
 //!
 //! ### Dashboard Endpoints
 //!
@@ -100,26 +104,42 @@
 //! - **Memory Management**: Efficient memory usage
 //! - **Concurrent Processing**: Async request handling
 
+pub mod audio_upload;
+pub mod stream_compression;
+
+use crate::api_key_store::KeyUsage;
+use crate::config::{Config, ModelsSource, ServerConfig, UnknownModelPolicy};
 use crate::dashboard;
 use crate::dashboard::websocket::{DashboardWebSocketQuery, WebSocketManager};
+use crate::gateway_caching::{optimize_chat_request, CacheEndpoint, CacheKeyBuilder, Flight};
 use crate::gateway_error::GatewayError;
+use crate::middleware::access_log::{with_access_log, AccessLogFields};
+use crate::moderation::ModerationDecision;
+use crate::request_context::RequestContext;
+use crate::request_validation::{RequiredFields, ValidatedJson};
 use crate::server::AppState;
+use audio_upload::AudioUpload;
 use axum::response::sse::{Event, Sse};
 use axum::{
     body::Body,
-    extract::{Query, State, WebSocketUpgrade},
-    http::{Response, StatusCode},
-    response::{Html, Json},
+    extract::{Extension, Path, Query, State, WebSocketUpgrade},
+    http::{HeaderMap, Response, StatusCode},
+    response::{Html, IntoResponse, Json},
 };
 use futures::StreamExt;
+use serde::Deserialize;
 use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use tokio_stream::wrappers::ReceiverStream;
-use ultrafast_models_sdk::error::ProviderError;
+use tracing::Instrument;
+use ultrafast_models_sdk::error::{ClientError, ProviderError};
 use ultrafast_models_sdk::models::{
-    AudioRequest, AudioResponse, ChatRequest, EmbeddingRequest, EmbeddingResponse, ImageRequest,
-    ImageResponse, SpeechRequest, SpeechResponse,
+    AudioResponse, ChatRequest, ChatResponse, Choice, Delta, EmbeddingInput, EmbeddingRequest,
+    EmbeddingResponse, ImageRequest, ImageResponse, Message, Role, SpeechRequest, SpeechResponse,
+    StreamChoice, StreamChunk, Usage,
 };
+use ultrafast_models_sdk::providers::HealthStatus;
 
 /// Handle chat completion requests with caching and streaming support.
 ///
@@ -157,99 +177,192 @@ use ultrafast_models_sdk::models::{
 /// ```
 pub async fn chat_completions(
     State(state): State<AppState>,
-    Json(request): Json<ChatRequest>,
+    context: Option<Extension<RequestContext>>,
+    headers: HeaderMap,
+    ValidatedJson(mut request): ValidatedJson<ChatRequest>,
 ) -> Result<Response<Body>, GatewayError> {
+    crate::request_validation::validate_chat(&request)?;
+    let user_id = request_user_id(context.as_deref(), &request);
+    let api_key = context.as_deref().and_then(|ctx| ctx.api_key.clone());
+    let provider_override = provider_override(&state, &headers)?;
+    add_sticky_session(&state, &headers, &mut request);
+    resolve_model_ab_test(&state, &mut request);
+    crate::request_normalization::normalize(&mut request, &state.config.request.normalization);
+    let max_response_chars =
+        take_max_response_chars(&mut request, state.config.server.max_response_chars);
+    check_message_limit(&request, &state.config.server)?;
+    check_total_token_budget(&request, &state.config)?;
+    check_prompt_tokens(
+        &request,
+        &state.config,
+        context.as_deref().and_then(|ctx| ctx.max_prompt_tokens),
+    )?;
+    let max_cost_usd = max_cost_usd(&headers)?;
+    if let Some(max_cost_usd) = max_cost_usd {
+        check_cost_ceiling(
+            &state,
+            &mut request,
+            provider_override.as_deref(),
+            max_cost_usd,
+        )?;
+    }
+    check_model_available(&state, &request).await?;
+    if let Some(moderator) = &state.moderator {
+        if let ModerationDecision::Block { reason } = moderator.check(&request).await {
+            return Err(GatewayError::ContentFiltered { message: reason });
+        }
+    }
+
     // Check if this is a streaming request
     if request.stream.unwrap_or(false) {
+        let compress =
+            state.config.stream.compression && stream_compression::accepts_gzip(&headers);
         // Handle streaming requests with SSE
-        return handle_streaming_chat_completions(State(state), Json(request)).await;
+        let fields = AccessLogFields {
+            model: Some(request.model.clone()),
+            ..Default::default()
+        };
+        return handle_streaming_chat_completions(
+            State(state),
+            Json(request),
+            user_id,
+            api_key,
+            provider_override,
+            max_cost_usd,
+            compress,
+        )
+        .await
+        .map(|response| with_access_log(response, fields));
     }
 
     // Phase 4: Optimize request payload (request-side only); keep responses intact for compatibility
-    let request_json = serde_json::to_value(&request)?;
-    let optimized_request_json =
-        crate::json_optimization::JsonOptimizer::optimize_request_payload(&request_json);
-    let optimized_request: ChatRequest = serde_json::from_value(optimized_request_json)?;
-
-    // Check cache first
-    let cache_key = if !optimized_request.stream.unwrap_or(false) {
-        Some(ultrafast_models_sdk::cache::CacheKeyBuilder::build_chat_key(&optimized_request))
+    let optimized_request = optimize_chat_request(&request)?;
+
+    // Check cache first; pinned requests always go to their provider
+    let cache_key = if provider_override.is_none()
+        && state.cache_manager.caches(CacheEndpoint::Chat)
+        && !optimized_request.stream.unwrap_or(false)
+    {
+        Some(state.cache_manager.chat_key(&optimized_request))
     } else {
         None
     };
 
-    if let Some(cache_key) = &cache_key {
+    let cache_span = match &cache_key {
+        Some(_) => tracing::info_span!("gateway.cache", cache.hit = tracing::field::Empty),
+        None => tracing::Span::none(),
+    };
+    let (cached_response, semantic_key) = async {
+        let Some(cache_key) = &cache_key else {
+            return (None, None);
+        };
         if let Some(cached_response) = state.cache_manager.get(cache_key).await {
             tracing::debug!("Cache hit for chat completion");
-            return Ok(Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&cached_response)?))
-                .unwrap());
+            return (Some(cached_response), None);
+        }
+
+        // On an exact miss, look for the response to a similar earlier prompt
+        let semantic_key = if state.cache_manager.semantic() {
+            semantic_cache_key(&state, &optimized_request).await
+        } else {
+            None
+        };
+        if let Some((scope, embedding)) = &semantic_key {
+            if let Some(cached_response) = state.cache_manager.semantic_get(scope, embedding).await
+            {
+                tracing::debug!("Semantic cache hit for chat completion");
+                return (Some(cached_response), semantic_key);
+            }
+        }
+        (None, semantic_key)
+    }
+    .instrument(cache_span.clone())
+    .await;
+    cache_span.record("cache.hit", cached_response.is_some());
+    if let Some(cached_response) = cached_response {
+        let fields = AccessLogFields {
+            model: Some(optimized_request.model.clone()),
+            cache_hit: true,
+            ..Default::default()
+        };
+        return shared_chat_completion_response(
+            cached_response,
+            max_response_chars,
+            &state.config.server.strip_response_fields,
+        )
+        .map(|response| with_access_log(response, fields));
+    }
+
+    // Coalesce identical concurrent requests into a single upstream call
+    let mut flight_guard = None;
+    let flight = cache_key
+        .as_deref()
+        .and_then(|cache_key| state.cache_manager.join_flight(cache_key));
+    let coalescing = flight.is_some();
+    match flight {
+        Some(Flight::Follower(mut receiver)) => {
+            if let Ok(Some(shared_response)) = receiver.recv().await {
+                tracing::debug!("Coalesced chat completion with in-flight request");
+                crate::metrics::record_coalescing(true).await;
+                return shared_chat_completion_response(
+                    shared_response,
+                    max_response_chars,
+                    &state.config.server.strip_response_fields,
+                );
+            }
+            // The in-flight request failed; call upstream ourselves
         }
+        Some(Flight::Leader(guard)) => flight_guard = Some(guard),
+        None => {}
+    }
+    if coalescing {
+        crate::metrics::record_coalescing(false).await;
     }
 
     // Route to appropriate provider using the client
+    let provider_span = provider_span(&optimized_request.model);
     let start_time = std::time::Instant::now();
-    let result = state
-        .client
-        .chat_completion(optimized_request.clone())
-        .await;
+    let result = match &provider_override {
+        Some(provider_id) => state
+            .client
+            .chat_completion_with_provider(provider_id, optimized_request.clone())
+            .instrument(provider_span.clone())
+            .await
+            .map(|response| (response, 1)),
+        None => {
+            state
+                .client
+                .chat_completion_with_attempts(optimized_request.clone())
+                .instrument(provider_span.clone())
+                .await
+        }
+    };
     let latency = start_time.elapsed();
+    let (result, attempts) = match result {
+        Ok((response, attempts)) => (Ok(response), attempts),
+        Err(e) => (Err(e), 0),
+    };
 
     // Extract provider and token information from response
     let (provider_name, input_tokens, output_tokens, cost_usd) = match &result {
         Ok(response) => {
-            let provider = state.client.get_last_used_provider().await;
+            let provider = match &provider_override {
+                Some(provider_id) => Some(provider_id.clone()),
+                None => state.client.get_last_used_provider().await,
+            };
             let input_tokens = response.usage.as_ref().map(|u| u.prompt_tokens);
             let output_tokens = response.usage.as_ref().map(|u| u.completion_tokens);
 
-            // Calculate cost based on provider and token counts
+            // Calculate cost from the configured pricing table
             let cost = if let (Some(provider_name), Some(input), Some(output)) =
                 (provider.as_ref(), input_tokens, output_tokens)
             {
-                // Simple cost calculation based on provider
-                let cost = match provider_name.as_str() {
-                    "anthropic" => {
-                        // Claude-3 pricing: $0.015 per 1K input tokens, $0.075 per 1K output tokens
-                        let input_cost = (input as f64 / 1000.0) * 0.015;
-                        let output_cost = (output as f64 / 1000.0) * 0.075;
-                        input_cost + output_cost
-                    }
-                    "openai" => {
-                        // GPT-4 pricing: $0.03 per 1K input tokens, $0.06 per 1K output tokens
-                        let input_cost = (input as f64 / 1000.0) * 0.03;
-                        let output_cost = (output as f64 / 1000.0) * 0.06;
-                        input_cost + output_cost
-                    }
-                    "google-vertex-ai" => {
-                        // Google Vertex AI pricing (includes Gemini models)
-                        // Gemini Pro: $0.0005 per 1K input tokens, $0.0015 per 1K output tokens
-                        // Gemini Pro Vision: $0.0025 per 1K input tokens, $0.0075 per 1K output tokens
-                        // Using Gemini Pro pricing as default
-                        let input_cost = (input as f64 / 1000.0) * 0.0005;
-                        let output_cost = (output as f64 / 1000.0) * 0.0015;
-                        input_cost + output_cost
-                    }
-                    "gemini" => {
-                        // Gemini API pricing (direct API, not Vertex AI)
-                        // Gemini 1.5 Pro: $0.0035 per 1M input tokens, $0.0105 per 1M output tokens
-                        // Gemini 1.5 Flash: $0.000075 per 1M input tokens, $0.0003 per 1M output tokens
-                        // Using Gemini 1.5 Pro pricing as default
-                        let input_cost = (input as f64 / 1_000_000.0) * 0.0035;
-                        let output_cost = (output as f64 / 1_000_000.0) * 0.0105;
-                        input_cost + output_cost
-                    }
-                    "ollama" => {
-                        // Ollama is free
-                        0.0
-                    }
-                    _ => {
-                        // Default cost for unknown providers
-                        0.0
-                    }
-                };
-                Some(cost)
+                Some(crate::cost::estimate_cost(
+                    provider_name,
+                    &optimized_request.model,
+                    input,
+                    output,
+                ))
             } else {
                 None
             };
@@ -258,6 +371,13 @@ pub async fn chat_completions(
         }
         Err(_) => (None, None, None, None),
     };
+    record_provider_span(
+        &provider_span,
+        provider_name.as_deref(),
+        input_tokens,
+        output_tokens,
+        result.is_err(),
+    );
 
     // Update metrics with real data
     crate::metrics::record_request(
@@ -267,35 +387,96 @@ pub async fn chat_completions(
             if result.is_ok() { 200 } else { 500 },
             latency,
         )
-        .provider(provider_name.unwrap_or_default())
+        .provider(provider_name.clone().unwrap_or_default())
         .model(optimized_request.model.clone())
+        .metadata(
+            optimized_request
+                .request_metadata
+                .clone()
+                .unwrap_or_default(),
+        )
         .input_tokens(input_tokens.unwrap_or_default())
         .output_tokens(output_tokens.unwrap_or_default())
         .cost_usd(cost_usd.unwrap_or_default())
-        .user_id(optimized_request.user.clone().unwrap_or_default())
+        .user_id(user_id.clone())
+        .provider_override(provider_override.is_some())
         .build(),
     )
     .await;
+    if let (Some(api_key), Some(cost_usd)) = (&api_key, cost_usd) {
+        crate::metrics::record_spend(api_key, cost_usd).await;
+        check_budget_alerts(&state, api_key).await;
+    }
+    if let Some(api_key) = &api_key {
+        let tokens = input_tokens.unwrap_or_default() + output_tokens.unwrap_or_default();
+        tokio::spawn(crate::auth::record_key_usage(
+            api_key.clone(),
+            KeyUsage {
+                requests: 0,
+                tokens: u64::from(tokens),
+            },
+        ));
+    }
 
     match result {
-        Ok(response) => {
-            // Cache successful response
+        Ok(mut response) => {
+            // Cache and share the untruncated response, so other limits still apply
             if let Some(cache_key) = &cache_key {
+                let response_json = serde_json::to_value(&response)?;
                 let ttl = determine_cache_ttl(&optimized_request, latency);
                 state
                     .cache_manager
-                    .set(cache_key, serde_json::to_value(&response)?, Some(ttl))
+                    .set(cache_key, response_json.clone(), Some(ttl))
                     .await;
+                if let Some((scope, embedding)) = semantic_key {
+                    state
+                        .cache_manager
+                        .semantic_set(&scope, embedding, cache_key)
+                        .await;
+                }
+                if let Some(guard) = flight_guard {
+                    guard.complete(response_json);
+                }
             }
 
-            Ok(Response::builder()
-                .status(StatusCode::OK)
-                .header("content-type", "application/json")
-                .body(Body::from(serde_json::to_string(&response)?))
-                .unwrap())
+            let truncated = max_response_chars
+                .is_some_and(|max_chars| truncate_chat_response(&mut response, max_chars));
+            let mut http_response = chat_completion_response(
+                &response,
+                truncated,
+                &state.config.server.strip_response_fields,
+            )?;
+            http_response
+                .headers_mut()
+                .insert(ATTEMPTS_HEADER, attempts.into());
+            Ok(with_access_log(
+                http_response,
+                AccessLogFields {
+                    provider: provider_name,
+                    model: Some(optimized_request.model.clone()),
+                    input_tokens,
+                    output_tokens,
+                    cost_usd,
+                    cache_hit: false,
+                },
+            ))
+        }
+        Err(e) if is_rejected_chat_request(&e) => Err(rejected_chat_request(&state, e)),
+        Err(ClientError::Provider(ProviderError::ModelNotFound { .. })) => {
+            Err(model_not_found(&state, &optimized_request.model).await)
         }
         Err(e) => {
             tracing::error!("Provider error: {}", e);
+            let fallback = &state.config.routing.fallback_response;
+            if fallback.enabled {
+                crate::metrics::record_fallback_response().await;
+                let response = fallback_chat_response(&optimized_request.model, &fallback.content);
+                return chat_completion_response(
+                    &response,
+                    false,
+                    &state.config.server.strip_response_fields,
+                );
+            }
             Err(GatewayError::Provider(
                 ultrafast_models_sdk::error::ProviderError::ServiceUnavailable,
             ))
@@ -303,59 +484,257 @@ pub async fn chat_completions(
     }
 }
 
+/// Completion answering a request for `model` whose providers all failed;
+/// see `routing.fallback_response`.
+fn fallback_chat_response(model: &str, content: &str) -> ChatResponse {
+    ChatResponse {
+        id: format!("chatcmpl-fallback-{}", uuid::Uuid::new_v4()),
+        object: "chat.completion".to_string(),
+        created: chrono::Utc::now().timestamp() as u64,
+        model: model.to_string(),
+        choices: vec![Choice {
+            index: 0,
+            message: Message::assistant(content),
+            finish_reason: Some("fallback".to_string()),
+            logprobs: None,
+        }],
+        usage: Some(Usage::default()),
+        system_fingerprint: None,
+    }
+}
+
+/// Span covering the upstream call for `model`; see [`record_provider_span`].
+fn provider_span(model: &str) -> tracing::Span {
+    tracing::info_span!(
+        "gateway.provider",
+        model,
+        provider = tracing::field::Empty,
+        input_tokens = tracing::field::Empty,
+        output_tokens = tracing::field::Empty,
+        otel.status_code = tracing::field::Empty,
+    )
+}
+
+/// Tag the provider span with the outcome of the upstream call.
+fn record_provider_span(
+    span: &tracing::Span,
+    provider: Option<&str>,
+    input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+    failed: bool,
+) {
+    if let Some(provider) = provider {
+        span.record("provider", provider);
+    }
+    if let Some(input_tokens) = input_tokens {
+        span.record("input_tokens", input_tokens);
+    }
+    if let Some(output_tokens) = output_tokens {
+        span.record("output_tokens", output_tokens);
+    }
+    if failed {
+        span.record("otel.status_code", "ERROR");
+    }
+}
+
+/// Normalize the `role` field of streamed deltas to OpenAI's format.
+///
+/// Providers disagree on where the role appears: Anthropic never sends it,
+/// Gemini repeats it on every chunk. Clients expect `role: assistant` on the
+/// first delta of each choice and content only afterwards, so the role is
+/// added to the first delta and stripped from the rest.
+fn normalize_delta_roles(chunk: &mut StreamChunk, role_sent: &mut HashSet<u32>) {
+    for choice in &mut chunk.choices {
+        if role_sent.insert(choice.index) {
+            choice.delta.role.get_or_insert(Role::Assistant);
+        } else {
+            choice.delta.role = None;
+        }
+    }
+}
+
+/// Whether `chunk` is the usage-only chunk OpenAI sends last when
+/// `stream_options.include_usage` is set, which the provider asks for to
+/// meter every stream. It is only forwarded to clients that asked for it.
+fn is_unrequested_usage_chunk(chunk: &StreamChunk, request: &ChatRequest) -> bool {
+    let requested = request
+        .stream_options
+        .as_ref()
+        .is_some_and(|options| options.include_usage);
+    !requested && chunk.choices.is_empty() && chunk.usage.is_some()
+}
+
+/// Final SSE event for a stream cut off by `stream.max_duration`: a chunk
+/// with `finish_reason: "timeout"` when partial results are returned,
+/// otherwise an error event.
+fn stream_timeout_event(
+    last_chunk: Option<&StreamChunk>,
+    model: &str,
+    config: &crate::config::StreamConfig,
+    strip_fields: &[String],
+) -> String {
+    tracing::warn!(
+        "Stream for {} exceeded max_duration of {:?}",
+        model,
+        config.max_duration
+    );
+    if !config.return_partial_on_timeout {
+        return format!(
+            "data: {{\"error\": \"Stream exceeded max_duration of {:?}\"}}\n\n",
+            config.max_duration
+        );
+    }
+
+    let chunk = StreamChunk {
+        id: last_chunk.map(|c| c.id.clone()).unwrap_or_default(),
+        object: "chat.completion.chunk".to_string(),
+        created: last_chunk.map_or_else(|| chrono::Utc::now().timestamp() as u64, |c| c.created),
+        model: last_chunk.map_or_else(|| model.to_string(), |c| c.model.clone()),
+        choices: vec![StreamChoice {
+            index: 0,
+            delta: Delta {
+                role: None,
+                content: None,
+                tool_calls: None,
+            },
+            finish_reason: Some("timeout".to_string()),
+        }],
+        usage: None,
+    };
+    format!(
+        "data: {}\n\n",
+        client_json(&chunk, strip_fields).unwrap_or_default()
+    )
+}
+
 async fn handle_streaming_chat_completions(
     State(state): State<AppState>,
     Json(request): Json<ChatRequest>,
+    user_id: String,
+    api_key: Option<String>,
+    provider_override: Option<String>,
+    max_cost_usd: Option<f64>,
+    compress: bool,
 ) -> Result<Response<Body>, GatewayError> {
     // Phase 4: Optimize request payload
-    let request_json = serde_json::to_value(&request)?;
-    let optimized_request_json =
-        crate::json_optimization::JsonOptimizer::optimize_request_payload(&request_json);
-    let optimized_request: ChatRequest = serde_json::from_value(optimized_request_json)?;
+    let optimized_request = optimize_chat_request(&request)?;
 
     // Route to appropriate provider using the client
+    let provider_span = provider_span(&optimized_request.model);
     let start_time = std::time::Instant::now();
-    let stream_result = state
-        .client
-        .stream_chat_completion(optimized_request.clone())
-        .await;
+    let stream_result = match &provider_override {
+        Some(provider_id) => state
+            .client
+            .stream_chat_completion_with_provider(provider_id, optimized_request.clone())
+            .instrument(provider_span.clone())
+            .await
+            .map(|stream| (stream, Some(provider_id.clone()))),
+        None => {
+            state
+                .client
+                .stream_chat_completion_with_provider_id(optimized_request.clone())
+                .instrument(provider_span.clone())
+                .await
+        }
+    };
     let latency = start_time.elapsed();
+    if stream_result.is_err() {
+        record_provider_span(&provider_span, None, None, None, true);
+    }
 
     match stream_result {
-        Ok(stream) => {
+        Ok((stream, provider)) => {
+            let provider = provider.unwrap_or_default();
             // Create a channel for streaming events
             let (tx, rx) = tokio::sync::mpsc::channel::<String>(100);
 
             // Spawn a task to handle the stream
             let mut stream = stream;
+            let strip_fields = state.config.server.strip_response_fields.clone();
+            let stream_config = state.config.stream.clone();
+            let deadline = (!stream_config.max_duration.is_zero())
+                .then(|| tokio::time::Instant::from_std(start_time) + stream_config.max_duration);
+            let alert_state = state.clone();
             tokio::spawn(async move {
-                let mut total_tokens = 0;
+                let mut usage = None;
                 let mut content = String::new();
-
-                while let Some(chunk_result) = stream.next().await {
+                let mut role_sent = HashSet::new();
+                let mut last_chunk: Option<StreamChunk> = None;
+
+                // Priced at the provider serving the stream, with output
+                // counted as it arrives
+                let cost_ceiling = max_cost_usd.map(|max_cost_usd| StreamCostCeiling {
+                    max_cost_usd,
+                    provider: provider.clone(),
+                    prompt_tokens: estimate_tokens(&optimized_request),
+                });
+                let mut streamed_tokens = 0u32;
+
+                loop {
+                    let next = match deadline {
+                        Some(deadline) => tokio::time::timeout_at(deadline, stream.next()).await,
+                        None => Ok(stream.next().await),
+                    };
+                    let Ok(next) = next else {
+                        let event = stream_timeout_event(
+                            last_chunk.as_ref(),
+                            &optimized_request.model,
+                            &stream_config,
+                            &strip_fields,
+                        );
+                        let _ = tx.send(event).await;
+                        break;
+                    };
+                    let Some(chunk_result) = next else {
+                        break;
+                    };
                     match chunk_result {
-                        Ok(chunk) => {
+                        Ok(mut chunk) => {
+                            if chunk.usage.is_some() {
+                                usage = chunk.usage.clone();
+                            }
+                            if is_unrequested_usage_chunk(&chunk, &request) {
+                                continue;
+                            }
+                            normalize_delta_roles(&mut chunk, &mut role_sent);
+
                             // Convert StreamChunk to SSE format
-                            let event_data = serde_json::to_string(&chunk).unwrap_or_default();
+                            let event_data = client_json(&chunk, &strip_fields).unwrap_or_default();
                             let sse_event = format!("data: {event_data}\n\n");
 
                             // Track content for metrics
                             if let Some(choice) = chunk.choices.first() {
                                 if let Some(text) = &choice.delta.content {
                                     content.push_str(text);
+                                    streamed_tokens +=
+                                        ultrafast_models_sdk::tokenizer::count_tokens(
+                                            &optimized_request.model,
+                                            text,
+                                        );
                                 }
                             }
 
-                            // Track tokens (StreamChunk doesn't have usage field)
-                            total_tokens = content.len() as u32;
+                            last_chunk = Some(chunk);
 
                             if (tx.send(sse_event).await).is_err() {
                                 break;
                             }
+
+                            // Dropping the stream stops the provider generating
+                            if let Some(message) = cost_ceiling.as_ref().and_then(|ceiling| {
+                                ceiling.exceeded(&optimized_request.model, streamed_tokens)
+                            }) {
+                                tracing::warn!("Aborting stream: {}", message);
+                                let error_event = json!({
+                                    "error": {"message": message, "type": "cost_limit_exceeded"}
+                                });
+                                let _ = tx.send(format!("data: {error_event}\n\n")).await;
+                                break;
+                            }
                         }
                         Err(e) => {
                             tracing::error!("Stream error: {}", e);
-                            let error_event = format!("data: {{\"error\": \"{e}\"}}\n\n");
+                            let error_event = format!("data: {}\n\n", stream_error_event(&e));
                             let _ = tx.send(error_event).await;
                             break;
                         }
@@ -365,9 +744,25 @@ async fn handle_streaming_chat_completions(
                 // Send final event
                 let final_event = "data: [DONE]\n\n";
                 let _ = tx.send(final_event.to_string()).await;
+                // Close the stream, and its keep-alive pings, before metrics
+                drop(tx);
 
                 // Update metrics
-                let provider = state.client.get_last_used_provider().await;
+                let (input_tokens, output_tokens) =
+                    stream_token_usage(&optimized_request, usage.as_ref(), &content);
+                record_provider_span(
+                    &provider_span,
+                    Some(&provider),
+                    Some(input_tokens),
+                    Some(output_tokens),
+                    false,
+                );
+                let cost = crate::cost::estimate_cost(
+                    &provider,
+                    &optimized_request.model,
+                    input_tokens,
+                    output_tokens,
+                );
                 crate::metrics::record_request(
                     crate::metrics::RequestMetricsBuilder::new(
                         "POST".to_string(),
@@ -375,33 +770,68 @@ async fn handle_streaming_chat_completions(
                         200,
                         latency,
                     )
-                    .provider(provider.unwrap_or_default())
+                    .provider(provider)
                     .model(optimized_request.model.clone())
-                    .input_tokens(total_tokens)
-                    .output_tokens(total_tokens)
-                    .cost_usd(0.0) // Cost calculation would be done differently for streaming
-                    .user_id(optimized_request.user.clone().unwrap_or_default())
+                    .metadata(
+                        optimized_request
+                            .request_metadata
+                            .clone()
+                            .unwrap_or_default(),
+                    )
+                    .input_tokens(input_tokens)
+                    .output_tokens(output_tokens)
+                    .cost_usd(cost)
+                    .user_id(user_id.clone())
+                    .provider_override(provider_override.is_some())
                     .build(),
                 )
                 .await;
+                if let Some(api_key) = api_key {
+                    crate::metrics::record_spend(&api_key, cost).await;
+                    check_budget_alerts(&alert_state, &api_key).await;
+                    let usage = KeyUsage {
+                        requests: 0,
+                        tokens: u64::from(input_tokens + output_tokens),
+                    };
+                    crate::auth::record_key_usage(api_key, usage).await;
+                }
             });
 
             // For now, let's use a simpler approach with a custom stream
+            let initial_flush = state.config.server.stream_initial_flush;
+            let events = with_keepalive(
+                ReceiverStream::new(rx),
+                state.config.server.sse_keepalive_interval,
+                || KEEPALIVE_COMMENT.to_string(),
+            );
+            let mut encoder = stream_compression::SseEncoder::new(compress);
             let body = Body::from_stream(async_stream::stream! {
-                let mut rx = rx;
-                while let Some(event) = rx.recv().await {
-                    yield Ok::<axum::body::Bytes, std::io::Error>(event.into());
+                if initial_flush {
+                    yield encoder.event(STREAM_START_COMMENT);
+                }
+                futures::pin_mut!(events);
+                while let Some(event) = events.next().await {
+                    yield encoder.event(event);
+                }
+                if let Some(trailer) = encoder.finish() {
+                    yield trailer;
                 }
             });
 
-            Ok(Response::builder()
+            let mut response = Response::builder()
                 .status(StatusCode::OK)
                 .header("content-type", "text/event-stream")
                 .header("cache-control", "no-cache")
-                .header("connection", "keep-alive")
-                .body(body)
-                .unwrap())
+                .header("connection", "keep-alive");
+            if state.config.stream.compression {
+                response = response.header("vary", "accept-encoding");
+            }
+            if compress {
+                response = response.header("content-encoding", "gzip");
+            }
+            Ok(response.body(body).unwrap())
         }
+        Err(e) if is_rejected_chat_request(&e) => Err(rejected_chat_request(&state, e)),
         Err(e) => {
             tracing::error!("Stream initialization error: {}", e);
             Err(GatewayError::Provider(
@@ -413,38 +843,56 @@ async fn handle_streaming_chat_completions(
 
 pub async fn stream_chat_completions(
     State(state): State<AppState>,
-    Json(request): Json<ChatRequest>,
+    ValidatedJson(request): ValidatedJson<ChatRequest>,
 ) -> Result<Sse<impl futures::Stream<Item = Result<Event, Infallible>>>, GatewayError> {
+    crate::request_validation::validate_chat(&request)?;
+
     // Phase 4: Optimize request payload
-    let request_json = serde_json::to_value(&request)?;
-    let optimized_request_json =
-        crate::json_optimization::JsonOptimizer::optimize_request_payload(&request_json);
-    let optimized_request: ChatRequest = serde_json::from_value(optimized_request_json)?;
+    let optimized_request = optimize_chat_request(&request)?;
 
     // Route to appropriate provider using the client
+    let provider_span = provider_span(&optimized_request.model);
     let start_time = std::time::Instant::now();
     let stream_result = state
         .client
-        .stream_chat_completion(optimized_request.clone())
+        .stream_chat_completion_with_provider_id(optimized_request.clone())
+        .instrument(provider_span.clone())
         .await;
     let latency = start_time.elapsed();
+    if stream_result.is_err() {
+        record_provider_span(&provider_span, None, None, None, true);
+    }
 
     match stream_result {
-        Ok(stream) => {
+        Ok((stream, provider)) => {
+            let provider = provider.unwrap_or_default();
             // Create a channel for streaming events
             let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(100);
+            if state.config.server.stream_initial_flush {
+                let _ = tx.try_send(Ok(Event::default().comment("stream-start")));
+            }
 
             // Spawn a task to handle the stream
             let mut stream = stream;
+            let strip_fields = state.config.server.strip_response_fields.clone();
             tokio::spawn(async move {
-                let mut total_tokens = 0;
+                let mut usage = None;
                 let mut content = String::new();
+                let mut role_sent = HashSet::new();
 
                 while let Some(chunk_result) = stream.next().await {
                     match chunk_result {
-                        Ok(chunk) => {
+                        Ok(mut chunk) => {
+                            if chunk.usage.is_some() {
+                                usage = chunk.usage.clone();
+                            }
+                            if is_unrequested_usage_chunk(&chunk, &request) {
+                                continue;
+                            }
+                            normalize_delta_roles(&mut chunk, &mut role_sent);
+
                             // Convert StreamChunk to SSE Event
-                            let event_data = serde_json::to_string(&chunk).unwrap_or_default();
+                            let event_data = client_json(&chunk, &strip_fields).unwrap_or_default();
                             let event = Event::default().data(event_data);
 
                             // Track content for metrics
@@ -454,10 +902,6 @@ pub async fn stream_chat_completions(
                                 }
                             }
 
-                            // Track tokens (StreamChunk doesn't have usage field)
-                            // We'll track content length instead
-                            total_tokens = content.len() as u32;
-
                             if (tx.send(Ok(event)).await).is_err() {
                                 break;
                             }
@@ -465,7 +909,7 @@ pub async fn stream_chat_completions(
                         Err(e) => {
                             tracing::error!("Stream error: {}", e);
                             let error_event =
-                                Event::default().data(format!("{{\"error\": \"{e}\"}}"));
+                                Event::default().data(stream_error_event(&e).to_string());
                             let _ = tx.send(Ok(error_event)).await;
                             break;
                         }
@@ -475,9 +919,24 @@ pub async fn stream_chat_completions(
                 // Send final event
                 let final_event = Event::default().data("[DONE]");
                 let _ = tx.send(Ok(final_event)).await;
+                drop(tx);
 
                 // Update metrics
-                let provider = state.client.get_last_used_provider().await;
+                let (input_tokens, output_tokens) =
+                    stream_token_usage(&optimized_request, usage.as_ref(), &content);
+                record_provider_span(
+                    &provider_span,
+                    Some(&provider),
+                    Some(input_tokens),
+                    Some(output_tokens),
+                    false,
+                );
+                let cost = crate::cost::estimate_cost(
+                    &provider,
+                    &optimized_request.model,
+                    input_tokens,
+                    output_tokens,
+                );
                 crate::metrics::record_request(
                     crate::metrics::RequestMetricsBuilder::new(
                         "POST".to_string(),
@@ -485,19 +944,30 @@ pub async fn stream_chat_completions(
                         200,
                         latency,
                     )
-                    .provider(provider.unwrap_or_default())
+                    .provider(provider)
                     .model(optimized_request.model.clone())
-                    .input_tokens(total_tokens)
-                    .output_tokens(total_tokens)
-                    .cost_usd(0.0) // Cost calculation would be done differently for streaming
+                    .metadata(
+                        optimized_request
+                            .request_metadata
+                            .clone()
+                            .unwrap_or_default(),
+                    )
+                    .input_tokens(input_tokens)
+                    .output_tokens(output_tokens)
+                    .cost_usd(cost)
                     .user_id(optimized_request.user.clone().unwrap_or_default())
                     .build(),
                 )
                 .await;
             });
 
-            Ok(Sse::new(ReceiverStream::new(rx)))
+            Ok(Sse::new(with_keepalive(
+                ReceiverStream::new(rx),
+                state.config.server.sse_keepalive_interval,
+                || Ok(Event::default().comment("keep-alive")),
+            )))
         }
+        Err(e) if is_rejected_chat_request(&e) => Err(rejected_chat_request(&state, e)),
         Err(e) => {
             tracing::error!("Stream initialization error: {}", e);
             Err(GatewayError::Provider(
@@ -507,12 +977,22 @@ pub async fn stream_chat_completions(
     }
 }
 
+/// Body of the legacy `POST /v1/completions`.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+pub struct LegacyCompletionRequest(Value);
+
+impl RequiredFields for LegacyCompletionRequest {
+    const REQUIRED_FIELDS: &'static [&'static str] = &["model", "prompt"];
+}
+
 pub async fn completions(
     State(state): State<AppState>,
-    Json(request): Json<Value>,
+    ValidatedJson(LegacyCompletionRequest(request)): ValidatedJson<LegacyCompletionRequest>,
 ) -> Result<Json<Value>, GatewayError> {
     // Convert legacy completions format to chat completions format
     let chat_request = convert_legacy_completion_to_chat(request)?;
+    crate::request_validation::validate_chat(&chat_request)?;
 
     // Use the existing chat completions logic
     let response = state.client.chat_completion(chat_request).await?;
@@ -525,11 +1005,36 @@ pub async fn completions(
 
 pub async fn embeddings(
     State(state): State<AppState>,
-    Json(request): Json<EmbeddingRequest>,
+    headers: HeaderMap,
+    ValidatedJson(request): ValidatedJson<EmbeddingRequest>,
 ) -> Result<Json<EmbeddingResponse>, GatewayError> {
+    crate::request_validation::validate_embedding(&request)?;
+    let provider_override = provider_override(&state, &headers)?;
+    let cache_key =
+        if provider_override.is_none() && state.cache_manager.caches(CacheEndpoint::Embeddings) {
+            let request_hash = CacheKeyBuilder::hash_content(&serde_json::to_string(&request)?);
+            Some(CacheKeyBuilder::embedding_key(
+                &request.model,
+                &request_hash,
+            ))
+        } else {
+            None
+        };
+    if let Some(cached) = cached_response(&state, cache_key.as_deref()).await {
+        return Ok(Json(cached));
+    }
+
     // Route to appropriate provider using the client
     let start_time = std::time::Instant::now();
-    let result = state.client.embedding(request).await;
+    let result = match &provider_override {
+        Some(provider_id) => {
+            state
+                .client
+                .embedding_with_provider(provider_id, request)
+                .await
+        }
+        None => state.client.embedding(request).await,
+    };
     let latency = start_time.elapsed();
 
     // Record metrics
@@ -540,32 +1045,125 @@ pub async fn embeddings(
             200,
             latency,
         )
-        .provider(
-            state
+        .provider(match provider_override.clone() {
+            Some(provider_id) => provider_id,
+            None => state
                 .client
                 .get_last_used_provider()
                 .await
                 .unwrap_or_default(),
-        )
+        })
+        .provider_override(provider_override.is_some())
         .build(),
     )
     .await;
 
     match result {
-        Ok(response) => Ok(Json(response)),
-        Err(e) => Err(GatewayError::Provider(ProviderError::Configuration {
-            message: format!("Embedding request failed: {e}"),
-        })),
+        Ok(response) => {
+            // Embeddings are deterministic, so they skip the chat TTL heuristic
+            let ttl = state.config.cache.embedding_ttl;
+            cache_response(&state, cache_key.as_deref(), &response, Some(ttl)).await;
+            Ok(Json(response))
+        }
+        Err(e) => Err(capability_error(&state, e, "Embedding")),
+    }
+}
+
+/// `POST /v1/embeddings/stream`: embed a large input list in batches (see
+/// `[embeddings]` in the config), sending each batch's embeddings as an SSE
+/// event as soon as it completes. Each embedding keeps the `index` of its
+/// input, so clients can reassemble the batches in input order.
+pub async fn stream_embeddings(
+    State(state): State<AppState>,
+    ValidatedJson(request): ValidatedJson<EmbeddingRequest>,
+) -> Response<Body> {
+    if let Err(e) = crate::request_validation::validate_embedding(&request) {
+        return e.into_response();
     }
+    let body = Body::from_stream(async_stream::stream! {
+        let start_time = std::time::Instant::now();
+        let mut input_tokens = 0;
+        let mut status = 200;
+        let mut batches = state.client.stream_embeddings(request);
+        while let Some(batch) = batches.next().await {
+            match batch {
+                Ok(batch) => {
+                    input_tokens += batch.usage.prompt_tokens;
+                    let data = serde_json::to_string(&batch).unwrap_or_default();
+                    yield Ok::<axum::body::Bytes, std::io::Error>(
+                        format!("data: {data}\n\n").into(),
+                    );
+                }
+                Err(e) => {
+                    tracing::error!("Embedding batch error: {}", e);
+                    status = 500;
+                    let error = json!({"error": e.to_string()});
+                    yield Ok(format!("data: {error}\n\n").into());
+                    break;
+                }
+            }
+        }
+        drop(batches);
+        yield Ok("data: [DONE]\n\n".into());
+
+        crate::metrics::record_request(
+            crate::metrics::RequestMetricsBuilder::new(
+                "POST".to_string(),
+                "/v1/embeddings/stream".to_string(),
+                status,
+                start_time.elapsed(),
+            )
+            .provider(
+                state
+                    .client
+                    .get_last_used_provider()
+                    .await
+                    .unwrap_or_default(),
+            )
+            .input_tokens(input_tokens)
+            .build(),
+        )
+        .await;
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .body(body)
+        .unwrap()
 }
 
 pub async fn image_generations(
     State(state): State<AppState>,
-    Json(request): Json<ImageRequest>,
+    headers: HeaderMap,
+    ValidatedJson(request): ValidatedJson<ImageRequest>,
 ) -> Result<Json<ImageResponse>, GatewayError> {
+    crate::request_validation::validate_image(&request)?;
+    let provider_override = provider_override(&state, &headers)?;
+    let cache_key =
+        if provider_override.is_none() && state.cache_manager.caches(CacheEndpoint::Images) {
+            let request_hash = CacheKeyBuilder::hash_content(&serde_json::to_string(&request)?);
+            let model = request.model.as_deref().unwrap_or_default();
+            Some(CacheKeyBuilder::image_generation_key(model, &request_hash))
+        } else {
+            None
+        };
+    if let Some(cached) = cached_response(&state, cache_key.as_deref()).await {
+        return Ok(Json(cached));
+    }
+
     // Route to appropriate provider using the client
     let start_time = std::time::Instant::now();
-    let result = state.client.image_generation(request).await;
+    let result = match &provider_override {
+        Some(provider_id) => {
+            state
+                .client
+                .image_generation_with_provider(provider_id, request)
+                .await
+        }
+        None => state.client.image_generation(request).await,
+    };
     let latency = start_time.elapsed();
 
     // Record metrics
@@ -576,28 +1174,31 @@ pub async fn image_generations(
             200,
             latency,
         )
-        .provider(
-            state
+        .provider(match provider_override.clone() {
+            Some(provider_id) => provider_id,
+            None => state
                 .client
                 .get_last_used_provider()
                 .await
                 .unwrap_or_default(),
-        )
+        })
+        .provider_override(provider_override.is_some())
         .build(),
     )
     .await;
 
     match result {
-        Ok(response) => Ok(Json(response)),
-        Err(e) => Err(GatewayError::Provider(ProviderError::Configuration {
-            message: format!("Image generation request failed: {e}"),
-        })),
+        Ok(response) => {
+            cache_response(&state, cache_key.as_deref(), &response, None).await;
+            Ok(Json(response))
+        }
+        Err(e) => Err(capability_error(&state, e, "Image generation")),
     }
 }
 
 pub async fn audio_transcriptions(
     State(state): State<AppState>,
-    Json(request): Json<AudioRequest>,
+    AudioUpload(request): AudioUpload,
 ) -> Result<Json<AudioResponse>, GatewayError> {
     // Route to appropriate provider using the client
     let start_time = std::time::Instant::now();
@@ -625,15 +1226,13 @@ pub async fn audio_transcriptions(
 
     match result {
         Ok(response) => Ok(Json(response)),
-        Err(e) => Err(GatewayError::Provider(ProviderError::Configuration {
-            message: format!("Audio transcription request failed: {e}"),
-        })),
+        Err(e) => Err(capability_error(&state, e, "Audio transcription")),
     }
 }
 
 pub async fn text_to_speech(
     State(state): State<AppState>,
-    Json(request): Json<SpeechRequest>,
+    ValidatedJson(request): ValidatedJson<SpeechRequest>,
 ) -> Result<Json<SpeechResponse>, GatewayError> {
     // Route to appropriate provider using the client
     let start_time = std::time::Instant::now();
@@ -661,80 +1260,255 @@ pub async fn text_to_speech(
 
     match result {
         Ok(response) => Ok(Json(response)),
-        Err(e) => Err(GatewayError::Provider(ProviderError::Configuration {
-            message: format!("Text-to-speech request failed: {e}"),
-        })),
+        Err(e) => Err(capability_error(&state, e, "Text-to-speech")),
+    }
+}
+
+/// Whether a chat request was rejected before reaching a provider: misplaced
+/// roles the provider would reject (see `role_ordering`) or tools sent to a
+/// provider without function calling.
+fn is_rejected_chat_request(error: &ClientError) -> bool {
+    matches!(
+        error,
+        ClientError::InvalidRequest { .. }
+            | ClientError::Provider(ProviderError::Unsupported { .. })
+    )
+}
+
+/// Error for a chat request matched by [`is_rejected_chat_request`].
+fn rejected_chat_request(state: &AppState, error: ClientError) -> GatewayError {
+    match error {
+        e @ ClientError::InvalidRequest { .. } => e.into(),
+        e => capability_error(state, e, "Chat completion"),
+    }
+}
+
+/// Surface an unsupported endpoint as a precise error naming the provider and
+/// capability, with the status from `server.unsupported_capability_status`.
+/// Every other failure keeps the generic provider error.
+fn capability_error(state: &AppState, error: ClientError, context: &str) -> GatewayError {
+    match error {
+        ClientError::Provider(ProviderError::Unsupported {
+            provider,
+            capability,
+        }) => GatewayError::UnsupportedCapability {
+            provider,
+            capability: capability.to_string(),
+            status: StatusCode::from_u16(state.config.server.unsupported_capability_status)
+                .unwrap_or(StatusCode::NOT_IMPLEMENTED),
+        },
+        e => GatewayError::Provider(ProviderError::Configuration {
+            message: format!("{context} request failed: {e}"),
+        }),
     }
 }
 
+/// `GET /v1/usage`: the calling key's rate limit counters, requests in
+/// flight and remaining budget, so clients can pace themselves.
+pub async fn usage(
+    State(state): State<AppState>,
+    context: Option<Extension<RequestContext>>,
+    rate_limits: Option<Extension<crate::auth::RateLimits>>,
+) -> Result<Json<Value>, GatewayError> {
+    let Some((api_key, user_id)) = context
+        .as_deref()
+        .and_then(|ctx| Some((ctx.api_key.clone()?, ctx.user_id.clone()?)))
+    else {
+        return Err(GatewayError::Auth {
+            message: "Usage is only reported for authenticated API keys".to_string(),
+        });
+    };
+
+    let limits = rate_limits.map_or_else(crate::auth::caller_rate_limits, |limits| limits.0);
+    let limits = crate::auth::rate_limit_usage(&user_id, limits).await;
+    let window = |limit: u32, used: u32| {
+        json!({
+            "limit": limit,
+            "used": used,
+            "remaining": limit.saturating_sub(used),
+        })
+    };
+
+    let budget = match state.config.budgets.get(&api_key) {
+        Some(budget) => {
+            let spend = crate::metrics::get_spend(&api_key).await;
+            let period = |limit: Option<f64>, spent: f64| {
+                limit.map(|limit| {
+                    json!({
+                        "limit_usd": limit,
+                        "spent_usd": spent,
+                        "remaining_usd": (limit - spent).max(0.0),
+                    })
+                })
+            };
+            json!({
+                "daily": period(budget.daily_usd, spend.daily_usd),
+                "monthly": period(budget.monthly_usd, spend.monthly_usd),
+            })
+        }
+        None => Value::Null,
+    };
+
+    Ok(Json(json!({
+        "object": "usage",
+        "rate_limits": {
+            "requests_per_minute": window(limits.requests_per_minute, limits.current_minute_requests),
+            "requests_per_hour": window(limits.requests_per_hour, limits.current_hour_requests),
+            "tokens_per_minute": window(limits.tokens_per_minute, limits.current_minute_tokens),
+        },
+        "concurrent_requests": crate::metrics::key_requests_in_flight(&api_key),
+        "budget": budget,
+    })))
+}
+
 pub async fn list_models(State(state): State<AppState>) -> Result<Json<Value>, GatewayError> {
+    Ok(Json(model_list(&state).await))
+}
+
+/// The `/v1/models` response, served from cache while it is fresh.
+async fn model_list(state: &AppState) -> Value {
+    let cache_ttl = state.config.models.cache_ttl;
+    if let Some((built_at, response)) = &*state.models_cache.read().await {
+        if built_at.elapsed() < cache_ttl {
+            return response.clone();
+        }
+    }
+
+    let (response, stale) = build_model_list(state).await;
+    // A list with stale entries is rebuilt on the next request
+    if !cache_ttl.is_zero() && !stale {
+        *state.models_cache.write().await = Some((std::time::Instant::now(), response.clone()));
+    }
+
+    response
+}
+
+/// Models chat requests can name: the `/v1/models` list plus every enabled
+/// provider's `model_mapping` keys.
+async fn available_models(state: &AppState) -> Vec<String> {
+    let list = model_list(state).await;
+    let mut models: Vec<String> = Vec::new();
+    let listed = list["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry["id"].as_str());
+    let mut mapped: Vec<&str> = state
+        .config
+        .providers
+        .values()
+        .filter(|provider| provider.enabled)
+        .flat_map(|provider| provider.model_mapping.keys().map(String::as_str))
+        .collect();
+    mapped.sort_unstable();
+    for model in listed.chain(mapped) {
+        if !models.iter().any(|known| known == model) {
+            models.push(model.to_string());
+        }
+    }
+    models
+}
+
+/// Error for a chat request naming a model no provider serves.
+async fn model_not_found(state: &AppState, model: &str) -> GatewayError {
+    GatewayError::ModelNotFound {
+        model: model.to_string(),
+        available: available_models(state).await,
+    }
+}
+
+/// Reject requests for unknown models before dispatch when
+/// `models.unknown_model` is `Reject`.
+async fn check_model_available(
+    state: &AppState,
+    request: &ChatRequest,
+) -> Result<(), GatewayError> {
+    if state.config.models.unknown_model != UnknownModelPolicy::Reject {
+        return Ok(());
+    }
+    if available_models(state).await.contains(&request.model) {
+        return Ok(());
+    }
+    Err(model_not_found(state, &request.model).await)
+}
+
+/// Build the `/v1/models` response from the configured `models.source`.
+///
+/// When a provider cannot be reached, the last list it returned is served
+/// with `"stale": true` on its entries, and the built-in list if it never
+/// answered. Returns whether any entry is stale.
+async fn build_model_list(state: &AppState) -> (Value, bool) {
     let mut all_models = Vec::new();
+    let mut any_stale = false;
 
     // Get models from all configured providers
     for (provider_name, provider_config) in &state.config.providers {
-        if provider_config.enabled {
-            // Create a list of supported models for each provider
-            let models = match provider_name.as_str() {
-                "openai" => vec![
-                    "gpt-4",
-                    "gpt-4-turbo",
-                    "gpt-4-turbo-preview",
-                    "gpt-3.5-turbo",
-                    "gpt-3.5-turbo-16k",
-                    "text-embedding-ada-002",
-                    "text-embedding-3-small",
-                    "text-embedding-3-large",
-                    "dall-e-2",
-                    "dall-e-3",
-                    "whisper-1",
-                    "tts-1",
-                    "tts-1-hd",
-                ],
-                "anthropic" => vec![
-                    "claude-opus-4-20250514",
-                    "claude-sonnet-4-20250514",
-                    "claude-3-7-sonnet-20250219",
-                    "claude-3-5-sonnet-20241022",
-                    "claude-3-5-haiku-20241022",
-                    "claude-3-5-sonnet-20240620",
-                    "claude-3-haiku-20240307",
-                    "claude-3",
-                    "claude",
-                ],
-                "azure-openai" => vec![
-                    "gpt-4",
-                    "gpt-4-turbo",
-                    "gpt-35-turbo",
-                    "text-embedding-ada-002",
-                    "dall-e-3",
-                ],
-                "google-vertex-ai" => vec![
-                    "chat-bison",
-                    "text-bison",
-                    "gemini-pro",
-                    "textembedding-gecko",
-                ],
-                "gemini" => vec![
-                    "gemini-1.5-pro",
-                    "gemini-1.5-pro-latest",
-                    "gemini-1.5-flash",
-                    "gemini-1.5-flash-latest",
-                    "gemini-1.0-pro",
-                    "gemini-1.0-pro-vision",
-                    "text-embedding-004",
-                ],
-                _ => vec![],
-            };
+        if !provider_config.enabled {
+            continue;
+        }
 
-            for model in models {
-                all_models.push(json!({
-                    "id": model,
-                    "object": "model",
-                    "created": 1677610602,
-                    "owned_by": provider_name,
-                    "provider": provider_name
-                }));
+        let builtin: Vec<String> = builtin_models(provider_name)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let mut stale = false;
+        let live = match state.config.models.source {
+            ModelsSource::Static => None,
+            ModelsSource::Live | ModelsSource::Merge => {
+                match state.client.list_provider_models(provider_name).await {
+                    Ok(live) => {
+                        state
+                            .live_models
+                            .write()
+                            .await
+                            .insert(provider_name.clone(), live.clone());
+                        Some(live)
+                    }
+                    Err(e) => {
+                        let last = state.live_models.read().await.get(provider_name).cloned();
+                        tracing::warn!(
+                            "Could not list models for provider {}, using {} list: {}",
+                            provider_name,
+                            if last.is_some() {
+                                "last fetched"
+                            } else {
+                                "built-in"
+                            },
+                            e
+                        );
+                        stale = last.is_some();
+                        last
+                    }
+                }
             }
+        };
+        let models = match (state.config.models.source, live) {
+            (ModelsSource::Live, Some(live)) => live,
+            (ModelsSource::Merge, Some(live)) => {
+                let mut models = builtin;
+                for model in live {
+                    if !models.contains(&model) {
+                        models.push(model);
+                    }
+                }
+                models
+            }
+            _ => builtin,
+        };
+        any_stale |= stale;
+
+        for model in models {
+            let mut entry = json!({
+                "id": model,
+                "object": "model",
+                "created": 1677610602,
+                "owned_by": provider_name,
+                "provider": provider_name
+            });
+            if stale {
+                entry["stale"] = json!(true);
+            }
+            all_models.push(entry);
         }
     }
 
@@ -742,8 +1516,62 @@ pub async fn list_models(State(state): State<AppState>) -> Result<Json<Value>, G
         "object": "list",
         "data": all_models
     });
+    (response, any_stale)
+}
 
-    Ok(Json(response))
+/// Well-known models for each provider type.
+fn builtin_models(provider_name: &str) -> Vec<&'static str> {
+    match provider_name {
+        "openai" => vec![
+            "gpt-4",
+            "gpt-4-turbo",
+            "gpt-4-turbo-preview",
+            "gpt-3.5-turbo",
+            "gpt-3.5-turbo-16k",
+            "text-embedding-ada-002",
+            "text-embedding-3-small",
+            "text-embedding-3-large",
+            "dall-e-2",
+            "dall-e-3",
+            "whisper-1",
+            "tts-1",
+            "tts-1-hd",
+        ],
+        "anthropic" => vec![
+            "claude-opus-4-20250514",
+            "claude-sonnet-4-20250514",
+            "claude-3-7-sonnet-20250219",
+            "claude-3-5-sonnet-20241022",
+            "claude-3-5-haiku-20241022",
+            "claude-3-5-sonnet-20240620",
+            "claude-3-haiku-20240307",
+            "claude-3",
+            "claude",
+        ],
+        "azure-openai" => vec![
+            "gpt-4",
+            "gpt-4-turbo",
+            "gpt-35-turbo",
+            "text-embedding-ada-002",
+            "dall-e-3",
+        ],
+        "google-vertex-ai" => vec![
+            "chat-bison",
+            "text-bison",
+            "gemini-pro",
+            "textembedding-gecko",
+        ],
+        "gemini" => vec![
+            "gemini-1.5-pro",
+            "gemini-1.5-pro-latest",
+            "gemini-1.5-flash",
+            "gemini-1.5-flash-latest",
+            "gemini-1.0-pro",
+            "gemini-1.0-pro-vision",
+            "text-embedding-004",
+        ],
+        _ => vec![],
+    }
 }
 
 pub async fn health_check() -> Result<Json<Value>, GatewayError> {
@@ -760,7 +1588,47 @@ pub async fn health_check() -> Result<Json<Value>, GatewayError> {
     Ok(Json(response))
 }
 
+pub async fn version() -> Result<Json<Value>, GatewayError> {
+    use ultrafast_models_sdk::providers::{create_provider, ProviderConfig, BUILTIN_PROVIDERS};
+
+    // Instantiate each adapter with placeholder settings to report its capabilities
+    let providers: Vec<Value> = BUILTIN_PROVIDERS
+        .iter()
+        .filter_map(|name| {
+            let probe = ProviderConfig::new(*name, "").with_header("project-id", "version-probe");
+            let provider = create_provider(probe).ok()?;
+            Some(json!({
+                "name": name,
+                "streaming": provider.supports_streaming(),
+                "function_calling": provider.supports_function_calling(),
+            }))
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_sha": env!("GATEWAY_GIT_SHA"),
+        "providers": providers,
+    })))
+}
+
+/// Pass the current circuit breaker state of each provider to the metrics
+/// collector for health scoring, and return them.
+async fn refresh_circuit_states(
+    state: &AppState,
+) -> HashMap<String, ultrafast_models_sdk::CircuitState> {
+    let mut states = HashMap::new();
+    for provider_id in state.config.providers.keys() {
+        if let Some(circuit_breaker) = state.client.provider_circuit_breaker(provider_id) {
+            states.insert(provider_id.clone(), circuit_breaker.get_state().await);
+        }
+    }
+    crate::metrics::record_circuit_states(states.clone()).await;
+    states
+}
+
 pub async fn metrics(State(state): State<AppState>) -> Result<Json<Value>, GatewayError> {
+    let circuit_states = refresh_circuit_states(&state).await;
     let metrics = crate::metrics::get_aggregated_metrics().await;
 
     // Convert metrics to JSON for easier manipulation
@@ -775,6 +1643,10 @@ pub async fn metrics(State(state): State<AppState>) -> Result<Json<Value>, Gatew
             .providers
             .iter()
             .map(|(name, config)| {
+                let circuit = circuit_states
+                    .get(name)
+                    .copied()
+                    .unwrap_or(ultrafast_models_sdk::CircuitState::Closed);
                 (
                     name.clone(),
                     json!({
@@ -787,9 +1659,11 @@ pub async fn metrics(State(state): State<AppState>) -> Result<Json<Value>, Gatew
                         "uptime_percentage": 100.0,
                         "error_rate": 0.0,
                         "last_request": null,
+                        "latency_ewma_ms": 0.0,
+                        "health_score": state.config.metrics.health_score.score(0.0, 0.0, circuit),
                         "enabled": config.enabled,
                         "base_url": config.base_url,
-                        "timeout": config.timeout.as_secs()
+                        "timeout": config.total_timeout.as_secs()
                     }),
                 )
             })
@@ -816,6 +1690,7 @@ pub async fn metrics(State(state): State<AppState>) -> Result<Json<Value>, Gatew
             "most_common_error": metrics.error_stats.most_common_error
         },
         "cache_stats": metrics.cache_stats,
+        "metadata_stats": metrics.metadata_stats,
         "p50_latency_ms": metrics.p50_latency_ms,
         "p90_latency_ms": metrics.p90_latency_ms,
         "p95_latency_ms": metrics.p95_latency_ms,
@@ -838,8 +1713,9 @@ pub async fn metrics(State(state): State<AppState>) -> Result<Json<Value>, Gatew
 }
 
 pub async fn prometheus_metrics(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
 ) -> Result<Response<Body>, GatewayError> {
+    refresh_circuit_states(&state).await;
     let prometheus_metrics = crate::metrics::get_prometheus_metrics().await;
 
     Ok(Response::builder()
@@ -850,26 +1726,62 @@ pub async fn prometheus_metrics(
 }
 
 pub async fn list_providers(State(state): State<AppState>) -> Result<Json<Value>, GatewayError> {
-    let providers: Vec<Value> = state
-        .config
-        .providers
-        .iter()
-        .map(|(name, config)| {
-            json!({
-                "name": name,
-                "enabled": config.enabled,
-                "base_url": config.base_url,
-                "timeout": config.timeout.as_secs(),
-                "models": Vec::<String>::new() // Could be populated with actual supported models
-            })
-        })
-        .collect();
+    let mut providers = Vec::with_capacity(state.config.providers.len());
+    for (name, config) in &state.config.providers {
+        providers.push(json!({
+            "name": name,
+            "enabled": config.enabled,
+            "base_url": config.base_url,
+            "timeout": config.total_timeout.as_secs(),
+            "models": Vec::<String>::new(), // Could be populated with actual supported models
+            "slow_start_factor": state.client.slow_start_factor(name).await,
+        }));
+    }
 
     Ok(Json(json!({
         "providers": providers
     })))
 }
 
+/// Probe a provider on demand (`GET /admin/providers/{id}/health`).
+///
+/// Responds 200 when the provider is healthy and 503 otherwise, with the
+/// probe latency and upstream HTTP status in the body.
+pub async fn probe_provider_health(
+    State(state): State<AppState>,
+    Path(provider_id): Path<String>,
+) -> Result<Response<Body>, GatewayError> {
+    if !state.config.providers.contains_key(&provider_id) {
+        return Err(GatewayError::NotFound {
+            message: format!("Provider {provider_id} is not configured"),
+        });
+    }
+
+    let health = state.client.probe_provider(&provider_id).await?;
+    let status = if matches!(health.status, HealthStatus::Healthy) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let body = json!({
+        "provider": provider_id,
+        "status": health.status,
+        "latency_ms": health.latency_ms,
+        "http_status": health
+            .details
+            .get("http_status")
+            .and_then(|code| code.parse::<u16>().ok()),
+        "last_check": health.last_check,
+        "details": health.details,
+    });
+
+    Ok(Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(Body::from(serde_json::to_string(&body)?))
+        .unwrap())
+}
+
 pub async fn get_config(State(state): State<AppState>) -> Result<Json<Value>, GatewayError> {
     // Return a sanitized version of the config (without sensitive data)
     let sanitized_config = json!({
@@ -918,6 +1830,198 @@ pub async fn get_circuit_breaker_metrics(
     Ok(Json(metrics_data))
 }
 
+/// Force a provider's circuit breaker `open`, `close` it or `reset` it,
+/// returning its new state. A breaker opened here stays open until it is
+/// closed or reset, after which the provider re-enters slow-start.
+pub async fn set_circuit_breaker_state(
+    State(state): State<AppState>,
+    Path((provider_id, action)): Path<(String, String)>,
+) -> Result<Json<Value>, GatewayError> {
+    if !state.config.providers.contains_key(&provider_id) {
+        return Err(GatewayError::NotFound {
+            message: format!("Provider {provider_id} is not configured"),
+        });
+    }
+    let Some(circuit_breaker) = state.client.provider_circuit_breaker(&provider_id) else {
+        return Err(GatewayError::NotFound {
+            message: format!("Provider {provider_id} has no circuit breaker"),
+        });
+    };
+
+    let previous_state = circuit_breaker.get_state().await;
+    match action.as_str() {
+        "open" => circuit_breaker.force_open().await,
+        "close" => circuit_breaker.force_closed().await,
+        "reset" => circuit_breaker.reset().await,
+        _ => {
+            return Err(GatewayError::InvalidRequest {
+                message: format!(
+                    "Unknown circuit breaker action '{action}', expected open, close or reset"
+                ),
+            })
+        }
+    }
+    // A provider re-enabled by closing its breaker ramps up under slow-start
+    if action != "open" && previous_state != ultrafast_models_sdk::CircuitState::Closed {
+        state.client.mark_provider_activated(&provider_id).await;
+    }
+    tracing::warn!(
+        "Circuit breaker for provider {} set by admin request: {}",
+        provider_id,
+        action
+    );
+
+    Ok(Json(json!({
+        "provider": provider_id,
+        "action": action,
+        "state": format!("{:?}", circuit_breaker.get_state().await),
+    })))
+}
+
+/// Body of `POST /admin/compare`.
+#[derive(Debug, Deserialize)]
+pub struct CompareRequest {
+    /// Providers to send the request to
+    pub providers: Vec<String>,
+    /// Chat request sent, unchanged, to each provider
+    pub request: ChatRequest,
+}
+
+impl RequiredFields for CompareRequest {
+    const REQUIRED_FIELDS: &'static [&'static str] = &["providers", "request"];
+}
+
+/// Most providers one `POST /admin/compare` may send its request to.
+pub const MAX_COMPARE_PROVIDERS: usize = 8;
+
+/// Send one chat request to several providers in parallel and return every
+/// answer side by side (`POST /admin/compare`).
+///
+/// Results come in the order the providers were first given, each with its
+/// latency and estimated cost; a provider listed twice is asked once. A
+/// provider that fails reports its error in its result rather than failing
+/// the comparison. Every answer is recorded in the metrics and its cost
+/// counted against the caller's spend, as for `/v1/chat/completions`.
+/// Nothing is cached and the request is never streamed.
+pub async fn compare_providers(
+    State(state): State<AppState>,
+    context: Option<Extension<RequestContext>>,
+    ValidatedJson(compare): ValidatedJson<CompareRequest>,
+) -> Result<Json<Value>, GatewayError> {
+    let mut providers: Vec<String> = Vec::new();
+    for provider_id in compare.providers {
+        if !providers.contains(&provider_id) {
+            providers.push(provider_id);
+        }
+    }
+    if providers.is_empty() {
+        return Err(GatewayError::InvalidRequest {
+            message: "providers must not be empty".to_string(),
+        });
+    }
+    if providers.len() > MAX_COMPARE_PROVIDERS {
+        return Err(GatewayError::InvalidRequest {
+            message: format!(
+                "providers lists {} providers, more than the {MAX_COMPARE_PROVIDERS} allowed",
+                providers.len()
+            ),
+        });
+    }
+    if let Some(provider_id) = providers
+        .iter()
+        .find(|provider_id| !state.config.providers.contains_key(*provider_id))
+    {
+        return Err(GatewayError::NotFound {
+            message: format!("Provider {provider_id} is not configured"),
+        });
+    }
+    let mut request = compare.request;
+    crate::request_validation::validate_chat(&request)?;
+    request.stream = Some(false);
+    let user_id = request_user_id(context.as_deref(), &request);
+    let api_key = context.as_deref().and_then(|ctx| ctx.api_key.clone());
+
+    let results = futures::future::join_all(providers.iter().map(|provider_id| {
+        let request = request.clone();
+        let state = &state;
+        let user_id = &user_id;
+        let api_key = &api_key;
+        async move {
+            let start_time = std::time::Instant::now();
+            let result = state
+                .client
+                .chat_completion_with_provider(provider_id, request.clone())
+                .await;
+            let latency = start_time.elapsed();
+
+            let usage = result
+                .as_ref()
+                .ok()
+                .and_then(|response| response.usage.as_ref());
+            let cost_usd = usage.map(|usage| {
+                crate::cost::estimate_cost(
+                    provider_id,
+                    &request.model,
+                    usage.prompt_tokens,
+                    usage.completion_tokens,
+                )
+            });
+            crate::metrics::record_request(
+                crate::metrics::RequestMetricsBuilder::new(
+                    "POST".to_string(),
+                    "/admin/compare".to_string(),
+                    if result.is_ok() { 200 } else { 500 },
+                    latency,
+                )
+                .provider(provider_id.clone())
+                .model(request.model.clone())
+                .input_tokens(usage.map(|usage| usage.prompt_tokens).unwrap_or_default())
+                .output_tokens(
+                    usage
+                        .map(|usage| usage.completion_tokens)
+                        .unwrap_or_default(),
+                )
+                .cost_usd(cost_usd.unwrap_or_default())
+                .user_id(user_id.clone())
+                .provider_override(true)
+                .build(),
+            )
+            .await;
+            if let (Some(api_key), Some(cost_usd)) = (api_key, cost_usd) {
+                crate::metrics::record_spend(api_key, cost_usd).await;
+                check_budget_alerts(state, api_key).await;
+            }
+            if let (Some(api_key), Some(usage)) = (api_key, usage) {
+                tokio::spawn(crate::auth::record_key_usage(
+                    api_key.clone(),
+                    KeyUsage {
+                        requests: 0,
+                        tokens: u64::from(usage.prompt_tokens + usage.completion_tokens),
+                    },
+                ));
+            }
+
+            let latency_ms = latency.as_millis() as u64;
+            match result {
+                Ok(response) => json!({
+                    "provider": provider_id,
+                    "latency_ms": latency_ms,
+                    "cost_usd": cost_usd,
+                    "response": response,
+                }),
+                Err(e) => json!({
+                    "provider": provider_id,
+                    "latency_ms": latency_ms,
+                    "error": {"message": e.to_string()},
+                }),
+            }
+        }
+    }))
+    .await;
+
+    Ok(Json(json!({ "results": results })))
+}
+
 pub async fn dashboard(State(_state): State<AppState>) -> Result<Html<String>, GatewayError> {
     dashboard::dashboard().await
 }
@@ -966,19 +2070,551 @@ pub async fn dashboard_websocket(
 }
 
 // Helper functions
-#[allow(dead_code)]
-fn estimate_tokens(request: &ChatRequest) -> u32 {
-    let mut total_tokens = 0;
+/// Provider pinned with the `x-ultrafast-provider` header, if any.
+///
+/// A request naming an unknown or disabled provider is rejected rather than
+/// silently routed as usual.
+fn provider_override(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> Result<Option<String>, GatewayError> {
+    let Some(value) = headers.get(PROVIDER_OVERRIDE_HEADER) else {
+        return Ok(None);
+    };
+    let provider_id = value
+        .to_str()
+        .map_err(|_| GatewayError::InvalidRequest {
+            message: format!("{PROVIDER_OVERRIDE_HEADER} header must be a provider name"),
+        })?
+        .trim();
+
+    match state.config.providers.get(provider_id) {
+        Some(config) if config.enabled => Ok(Some(provider_id.to_string())),
+        Some(_) => Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Provider '{provider_id}' named in the {PROVIDER_OVERRIDE_HEADER} header is disabled"
+            ),
+        }),
+        None => Err(GatewayError::InvalidRequest {
+            message: format!(
+                "Unknown provider '{provider_id}' in the {PROVIDER_OVERRIDE_HEADER} header"
+            ),
+        }),
+    }
+}
 
-    for message in &request.messages {
-        // Rough estimation: 1 token ≈ 4 characters
-        total_tokens += message.content.len() as u32 / 4;
+/// Replace a virtual model name from `[model_ab_tests]` with the variant
+/// chosen for this request.
+fn resolve_model_ab_test(state: &AppState, request: &mut ChatRequest) {
+    if let Some(model) =
+        crate::model_ab_tests::resolve(&state.config.model_ab_tests, &request.model)
+    {
+        tracing::debug!(ab_test = %request.model, model = %model, "Resolved model A/B test");
+        request.model = model.to_string();
     }
+}
 
-    // Add buffer for system messages and formatting
-    total_tokens += 50;
+/// Send any budget alerts `api_key` is due after its latest spend.
+async fn check_budget_alerts(state: &AppState, api_key: &str) {
+    if let (Some(alerter), Some(budget)) =
+        (&state.budget_alerter, state.config.budgets.get(api_key))
+    {
+        alerter.check(api_key, budget).await;
+    }
+}
+
+/// Error event for a stream that failed part way through. Plain-text errors
+/// read from a provider name the provider they came from.
+fn stream_error_event(error: &ClientError) -> Value {
+    match error {
+        ClientError::Provider(ProviderError::StreamError { provider, message }) => json!({
+            "error": {"message": message, "type": "provider_stream_error", "provider": provider}
+        }),
+        _ => json!({"error": {"message": error.to_string(), "type": "stream_error"}}),
+    }
+}
 
-    total_tokens
+/// Pass the session header of a sticky routing strategy on to the router.
+fn add_sticky_session(state: &AppState, headers: &HeaderMap, request: &mut ChatRequest) {
+    let Some(name) = state.config.routing.strategy.sticky_header() else {
+        return;
+    };
+    if let Some(value) = headers.get(name).and_then(|value| value.to_str().ok()) {
+        request
+            .routing_metadata
+            .insert(name.to_ascii_lowercase(), value.to_string());
+    }
+}
+
+/// Identity resolved by the auth middleware, falling back to the request's `user` field.
+fn request_user_id(context: Option<&RequestContext>, request: &ChatRequest) -> String {
+    context
+        .and_then(|ctx| ctx.user_id.clone())
+        .or_else(|| request.user.clone())
+        .unwrap_or_default()
+}
+
+/// Finish reason set on choices whose content was cut by `max_response_chars`.
+const TRUNCATED_FINISH_REASON: &str = "truncated_by_gateway";
+
+/// Response header flagging that the gateway truncated generated content.
+const TRUNCATED_HEADER: &str = "x-gateway-truncated";
+
+/// SSE comment sent before the first token when `server.stream_initial_flush` is on.
+const STREAM_START_COMMENT: &str = ": stream-start\n\n";
+
+/// SSE comment sent on streams idle for `server.sse_keepalive_interval`.
+const KEEPALIVE_COMMENT: &str = ": keep-alive\n\n";
+
+/// Pass `events` through, adding `ping()` whenever none has arrived for
+/// `interval` (zero disables pings). Pings stop when `events` ends.
+fn with_keepalive<T>(
+    events: impl futures::Stream<Item = T>,
+    interval: std::time::Duration,
+    ping: impl Fn() -> T,
+) -> impl futures::Stream<Item = T> {
+    async_stream::stream! {
+        futures::pin_mut!(events);
+        loop {
+            let next = if interval.is_zero() {
+                events.next().await
+            } else {
+                match tokio::time::timeout(interval, events.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        yield ping();
+                        continue;
+                    }
+                }
+            };
+            let Some(event) = next else {
+                break;
+            };
+            yield event;
+        }
+    }
+}
+
+/// Response header with the number of providers tried for a chat completion.
+const ATTEMPTS_HEADER: &str = "x-ultrafast-attempts";
+
+/// Request header pinning a request to one provider, bypassing routing.
+const PROVIDER_OVERRIDE_HEADER: &str = "x-ultrafast-provider";
+
+/// Pull the gateway-only `max_response_chars` field out of the request so it is
+/// not forwarded, and combine it with the configured limit (the smaller wins).
+fn take_max_response_chars(request: &mut ChatRequest, configured: Option<usize>) -> Option<usize> {
+    let requested = request
+        .extra
+        .remove("max_response_chars")
+        .and_then(|v| v.as_u64())
+        .filter(|&v| v > 0)
+        .map(|v| v as usize);
+
+    match (requested, configured) {
+        (Some(requested), Some(configured)) => Some(requested.min(configured)),
+        (requested, configured) => requested.or(configured),
+    }
+}
+
+/// Truncate each choice's content to at most `max_chars` characters.
+///
+/// Cuts at the last whitespace before the limit where possible so words are
+/// not split, and marks affected choices with `truncated_by_gateway`.
+/// Returns `true` if any choice was truncated.
+fn truncate_chat_response(response: &mut ChatResponse, max_chars: usize) -> bool {
+    let mut truncated = false;
+
+    for choice in &mut response.choices {
+        let content = &choice.message.content;
+        let Some((limit, _)) = content.char_indices().nth(max_chars) else {
+            continue;
+        };
+
+        let cut = content[..limit]
+            .rfind(char::is_whitespace)
+            .filter(|&boundary| boundary > 0)
+            .unwrap_or(limit);
+        choice.message.content.truncate(cut);
+        choice.finish_reason = Some(TRUNCATED_FINISH_REASON.to_string());
+        truncated = true;
+    }
+
+    truncated
+}
+
+fn chat_completion_response(
+    body: &impl serde::Serialize,
+    truncated: bool,
+    strip_fields: &[String],
+) -> Result<Response<Body>, GatewayError> {
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "application/json");
+    if truncated {
+        builder = builder.header(TRUNCATED_HEADER, "true");
+    }
+    Ok(builder
+        .body(Body::from(client_json(body, strip_fields)?))
+        .unwrap())
+}
+
+/// Serialize a response for the client without the configured
+/// `strip_response_fields`.
+fn client_json(
+    body: &impl serde::Serialize,
+    strip_fields: &[String],
+) -> serde_json::Result<String> {
+    if strip_fields.is_empty() {
+        return serde_json::to_string(body);
+    }
+    let mut body = serde_json::to_value(body)?;
+    for field in strip_fields {
+        strip_field(&mut body, &field.split('.').collect::<Vec<_>>());
+    }
+    serde_json::to_string(&body)
+}
+
+/// Remove the field at a dotted path, applying the rest of the path to each
+/// element of arrays met on the way.
+fn strip_field(value: &mut Value, path: &[&str]) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|item| strip_field(item, path)),
+        Value::Object(map) => match path {
+            [field] => {
+                map.remove(*field);
+            }
+            [field, rest @ ..] => {
+                if let Some(value) = map.get_mut(*field) {
+                    strip_field(value, rest);
+                }
+            }
+            [] => {}
+        },
+        _ => {}
+    }
+}
+
+/// Look up a cached response, if the endpoint is cached and the key is present.
+async fn cached_response<T: serde::de::DeserializeOwned>(
+    state: &AppState,
+    cache_key: Option<&str>,
+) -> Option<T> {
+    let cached = state.cache_manager.get(cache_key?).await?;
+    serde_json::from_value(cached).ok()
+}
+
+/// Store a response under `cache_key` for `ttl`, or the configured TTL.
+async fn cache_response(
+    state: &AppState,
+    cache_key: Option<&str>,
+    response: &impl serde::Serialize,
+    ttl: Option<std::time::Duration>,
+) {
+    let Some(cache_key) = cache_key else {
+        return;
+    };
+    if let Ok(value) = serde_json::to_value(response) {
+        state.cache_manager.set(cache_key, value, ttl).await;
+    }
+}
+
+/// Semantic cache scope and prompt embedding for a chat request.
+///
+/// The scope is the request's cache key without its messages, so only
+/// prompts sent with the same model and parameters are compared. Returns
+/// `None` (an ordinary miss) if the prompt cannot be embedded.
+async fn semantic_cache_key(state: &AppState, request: &ChatRequest) -> Option<(String, Vec<f32>)> {
+    let cache = &state.config.cache;
+    let prompt = request
+        .messages
+        .iter()
+        .map(|message| message.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let embedding_request = EmbeddingRequest {
+        model: cache.semantic.embedding_model.clone(),
+        input: EmbeddingInput::String(prompt),
+        ..Default::default()
+    };
+    let result = match &cache.semantic.embedding_provider {
+        Some(provider_id) => {
+            state
+                .client
+                .embedding_with_provider(provider_id, embedding_request)
+                .await
+        }
+        None => state.client.embedding(embedding_request).await,
+    };
+    let embedding = match result {
+        Ok(response) => response.data.into_iter().next()?.embedding,
+        Err(e) => {
+            tracing::warn!("Failed to embed prompt for semantic cache: {}", e);
+            return None;
+        }
+    };
+
+    let unscoped = ChatRequest {
+        messages: Vec::new(),
+        ..request.clone()
+    };
+    Some((state.cache_manager.chat_key(&unscoped), embedding))
+}
+
+/// Respond with a chat completion produced for another request (cache hit or
+/// coalesced upstream call), applying this request's truncation limit.
+fn shared_chat_completion_response(
+    shared: Value,
+    max_response_chars: Option<usize>,
+    strip_fields: &[String],
+) -> Result<Response<Body>, GatewayError> {
+    if let Some(max_chars) = max_response_chars {
+        if let Ok(mut response) = serde_json::from_value::<ChatResponse>(shared.clone()) {
+            let truncated = truncate_chat_response(&mut response, max_chars);
+            return chat_completion_response(&response, truncated, strip_fields);
+        }
+    }
+    chat_completion_response(&shared, false, strip_fields)
+}
+
+/// Look up a per-model limit such as the `max_total_tokens` budget,
+/// preferring an exact match and otherwise the longest configured
+/// model-name prefix.
+fn model_limit(model: &str, limits: &HashMap<String, u32>) -> Option<u32> {
+    limits.get(model).copied().or_else(|| {
+        limits
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, limit)| *limit)
+    })
+}
+
+/// Reject conversations with more messages than the model's limit allows,
+/// before they reach a provider.
+fn check_message_limit(request: &ChatRequest, server: &ServerConfig) -> Result<(), GatewayError> {
+    let Some(limit) = model_limit(&request.model, &server.max_messages_per_model)
+        .or(server.max_messages_per_request)
+    else {
+        return Ok(());
+    };
+
+    let messages = request.messages.len();
+    if messages <= limit as usize {
+        return Ok(());
+    }
+    Err(GatewayError::InvalidRequest {
+        message: format!(
+            "Request has {messages} messages, more than the {limit} allowed for model {}",
+            request.model
+        ),
+    })
+}
+
+/// Reject requests whose estimated prompt is longer than the calling key's
+/// `max_prompt_tokens` (`key_limit`), or else `routing.max_prompt_tokens`.
+fn check_prompt_tokens(
+    request: &ChatRequest,
+    config: &Config,
+    key_limit: Option<u32>,
+) -> Result<(), GatewayError> {
+    let Some(max_prompt_tokens) = key_limit.or(config.routing.max_prompt_tokens) else {
+        return Ok(());
+    };
+
+    let estimated_tokens = estimate_tokens(request);
+    if estimated_tokens <= max_prompt_tokens {
+        return Ok(());
+    }
+    Err(GatewayError::PromptTooLong {
+        estimated_tokens,
+        max_prompt_tokens,
+    })
+}
+
+/// Reject requests asking for more output than the model generates, or whose
+/// estimated prompt plus requested `max_tokens` would not fit in the model's
+/// context window. `server.max_total_tokens` takes precedence over the
+/// model's `context_window`.
+fn check_total_token_budget(request: &ChatRequest, config: &Config) -> Result<(), GatewayError> {
+    let limits = config.models.limits_for(&request.model);
+    let completion_tokens = request.max_tokens.unwrap_or(0);
+    if let Some(max_output_tokens) = limits.max_output_tokens {
+        if completion_tokens > max_output_tokens {
+            return Err(GatewayError::InvalidRequest {
+                message: format!(
+                    "max_tokens ({completion_tokens}) exceeds the {max_output_tokens}-token output limit of model {}",
+                    request.model
+                ),
+            });
+        }
+    }
+
+    let Some(limit) =
+        model_limit(&request.model, &config.server.max_total_tokens).or(limits.context_window)
+    else {
+        return Ok(());
+    };
+
+    let prompt_tokens = estimate_tokens(request);
+    if prompt_tokens.saturating_add(completion_tokens) <= limit {
+        return Ok(());
+    }
+
+    let message = match limit.checked_sub(prompt_tokens).filter(|room| *room > 0) {
+        Some(room) => format!(
+            "Estimated prompt ({prompt_tokens} tokens) plus max_tokens ({completion_tokens}) exceeds the {limit}-token context window of model {}; lower max_tokens to {room} or less",
+            request.model
+        ),
+        None => format!(
+            "Estimated prompt ({prompt_tokens} tokens) exceeds the {limit}-token context window of model {}; shorten the prompt",
+            request.model
+        ),
+    };
+    Err(GatewayError::InvalidRequest { message })
+}
+
+/// Header capping the projected cost in USD of a single request.
+const MAX_COST_HEADER: &str = "x-max-cost-usd";
+
+/// Cost cap set with the `x-max-cost-usd` header, if any.
+fn max_cost_usd(headers: &HeaderMap) -> Result<Option<f64>, GatewayError> {
+    let Some(value) = headers.get(MAX_COST_HEADER) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .filter(|cap| cap.is_finite() && *cap > 0.0)
+        .map(Some)
+        .ok_or_else(|| GatewayError::InvalidRequest {
+            message: format!("{MAX_COST_HEADER} must be a positive amount of USD"),
+        })
+}
+
+/// Reject a request whose projected cost exceeds `max_cost_usd`.
+///
+/// The projection prices the estimated prompt plus the output at the pinned
+/// provider or, when routing picks the provider, at the most expensive
+/// enabled one. Output is counted as `max_tokens` or, when unset, the
+/// model's `max_output_tokens`. A request for a model without a known output
+/// limit gets `max_tokens` clamped to what the cap can pay for instead.
+///
+/// Each of the request's `n` choices is priced as a separate completion, as
+/// providers without `n` are sent one request per choice.
+fn check_cost_ceiling(
+    state: &AppState,
+    request: &mut ChatRequest,
+    provider_override: Option<&str>,
+    max_cost_usd: f64,
+) -> Result<(), GatewayError> {
+    let prompt_tokens = estimate_tokens(request);
+    let choices = request.n.unwrap_or(1).max(1);
+    let providers: Vec<&str> = match provider_override {
+        Some(provider_id) => vec![provider_id],
+        None => state
+            .config
+            .providers
+            .iter()
+            .filter(|(_, provider)| provider.enabled)
+            .map(|(provider_id, _)| provider_id.as_str())
+            .collect(),
+    };
+    let projected = |completion_tokens: u32| {
+        providers
+            .iter()
+            .map(|provider| {
+                crate::cost::estimate_cost(
+                    provider,
+                    &request.model,
+                    prompt_tokens,
+                    completion_tokens,
+                ) * choices as f64
+            })
+            .fold(0.0, f64::max)
+    };
+    let exceeded = |projected_cost: f64, output: &str| {
+        let output = match choices {
+            1 => output.to_string(),
+            _ => format!("{output}, {choices} choices"),
+        };
+        GatewayError::CostLimitExceeded {
+            message: format!(
+                "projected cost ${projected_cost:.6} ({prompt_tokens} prompt tokens, {output}) exceeds the {MAX_COST_HEADER} cap of ${max_cost_usd:.6}"
+            ),
+        }
+    };
+
+    let max_output_tokens = state
+        .config
+        .models
+        .limits_for(&request.model)
+        .max_output_tokens;
+    let Some(completion_tokens) = request.max_tokens.or(max_output_tokens) else {
+        let prompt_cost = projected(0);
+        if prompt_cost > max_cost_usd {
+            return Err(exceeded(prompt_cost, "before any output"));
+        }
+        // Output priced per token of every choice at the most expensive provider
+        let per_token = (projected(1_000_000) - prompt_cost) / 1_000_000.0;
+        if per_token > 0.0 {
+            let affordable = ((max_cost_usd - prompt_cost) / per_token).floor();
+            request.max_tokens = Some(affordable.min(u32::MAX as f64) as u32);
+        }
+        return Ok(());
+    };
+
+    let projected_cost = projected(completion_tokens);
+    if projected_cost > max_cost_usd {
+        let output = match request.max_tokens {
+            Some(_) => format!("max_tokens {completion_tokens}"),
+            None => format!(
+                "max_tokens unset, projected at the model's {completion_tokens}-token output limit"
+            ),
+        };
+        return Err(exceeded(projected_cost, &output));
+    }
+    Ok(())
+}
+
+/// The `x-max-cost-usd` cap of a stream being served.
+struct StreamCostCeiling {
+    max_cost_usd: f64,
+    provider: String,
+    prompt_tokens: u32,
+}
+
+impl StreamCostCeiling {
+    /// Why the stream must stop once `streamed_tokens` have been generated,
+    /// if it must.
+    fn exceeded(&self, model: &str, streamed_tokens: u32) -> Option<String> {
+        let cost =
+            crate::cost::estimate_cost(&self.provider, model, self.prompt_tokens, streamed_tokens);
+        (cost > self.max_cost_usd).then(|| {
+            format!(
+                "cost ${cost:.6} after {streamed_tokens} streamed tokens exceeds the {MAX_COST_HEADER} cap of ${:.6}",
+                self.max_cost_usd
+            )
+        })
+    }
+}
+
+fn estimate_tokens(request: &ChatRequest) -> u32 {
+    ultrafast_models_sdk::tokenizer::estimate_tokens(&request.model, &request.messages)
+}
+
+/// Input/output token counts for a finished stream. Uses the `usage` the
+/// provider reported in its final chunk, falling back to an estimate of the
+/// prompt and streamed content when none was sent.
+fn stream_token_usage(request: &ChatRequest, usage: Option<&Usage>, content: &str) -> (u32, u32) {
+    match usage {
+        Some(usage) => (usage.prompt_tokens, usage.completion_tokens),
+        None => (
+            estimate_tokens(request),
+            ultrafast_models_sdk::tokenizer::count_tokens(&request.model, content),
+        ),
+    }
 }
 
 fn determine_cache_ttl(request: &ChatRequest, latency: std::time::Duration) -> std::time::Duration {
@@ -1078,34 +2714,46 @@ fn convert_legacy_completion_to_chat(request: Value) -> Result<ChatRequest, Gate
             .get("user")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string()),
+        n: request.get("n").and_then(|v| v.as_u64()).map(|v| v as u32),
         stream: Some(false),
         tools: None,
         tool_choice: None,
+        stream_options: None,
+        response_format: None,
+        request_metadata: None,
+        routing_metadata: HashMap::new(),
+        extra: HashMap::new(),
     })
 }
 
 fn convert_chat_to_legacy_completion(
     response: ultrafast_models_sdk::models::ChatResponse,
 ) -> Result<Value, GatewayError> {
-    let choice = response.choices.first().ok_or_else(|| {
-        GatewayError::Provider(ProviderError::Configuration {
+    if response.choices.is_empty() {
+        return Err(GatewayError::Provider(ProviderError::Configuration {
             message: "No choices in response".to_string(),
-        })
-    })?;
+        }));
+    }
 
-    let text = choice.message.content.clone();
+    let choices: Vec<Value> = response
+        .choices
+        .iter()
+        .map(|choice| {
+            json!({
+                "text": choice.message.content,
+                "index": choice.index,
+                "logprobs": choice.logprobs,
+                "finish_reason": choice.finish_reason
+            })
+        })
+        .collect();
 
     Ok(json!({
         "id": response.id,
         "object": "text_completion",
         "created": response.created,
         "model": response.model,
-        "choices": [{
-            "text": text,
-            "index": choice.index,
-            "logprobs": choice.logprobs,
-            "finish_reason": choice.finish_reason
-        }],
+        "choices": choices,
         "usage": response.usage
     }))
 }
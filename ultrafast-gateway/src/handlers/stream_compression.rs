@@ -0,0 +1,79 @@
+//! Gzip for streamed chat completions (`stream.compression`).
+//!
+//! Streams are only compressed for clients whose `Accept-Encoding` allows
+//! gzip. Each SSE event is compressed and flushed on its own (a zlib sync
+//! flush), so the bytes sent for an event decode to the whole event straight
+//! away and tokens reach the client as soon as they are generated.
+
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// Whether `Accept-Encoding` allows a gzip response.
+pub fn accepts_gzip(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::ACCEPT_ENCODING)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|coding| {
+            let mut parts = coding.split(';').map(str::trim);
+            let name = parts.next().unwrap_or_default();
+            let quality = parts
+                .find_map(|param| param.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok());
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && quality.is_some_and(|q| q > 0.0)
+        })
+}
+
+/// Encodes the events of one SSE stream, gzipped or as they are.
+pub struct SseEncoder {
+    gzip: Option<GzEncoder<Vec<u8>>>,
+}
+
+impl SseEncoder {
+    pub fn new(gzip: bool) -> Self {
+        Self {
+            gzip: gzip.then(|| GzEncoder::new(Vec::new(), Compression::default())),
+        }
+    }
+
+    /// Bytes to send for `event`, complete enough to decode on arrival.
+    pub fn event(&mut self, event: impl Into<Bytes>) -> std::io::Result<Bytes> {
+        let event = event.into();
+        let Some(gzip) = &mut self.gzip else {
+            return Ok(event);
+        };
+        gzip.write_all(&event)?;
+        gzip.flush()?;
+        Ok(std::mem::take(gzip.get_mut()).into())
+    }
+
+    /// Bytes that end the stream: the gzip trailer, if compressing.
+    pub fn finish(self) -> Option<std::io::Result<Bytes>> {
+        self.gzip.map(|gzip| gzip.finish().map(Bytes::from))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn accept_encoding(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT_ENCODING, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_accepts_gzip() {
+        assert!(accepts_gzip(&accept_encoding("gzip")));
+        assert!(accepts_gzip(&accept_encoding("br, GZIP;q=0.5")));
+        assert!(accepts_gzip(&accept_encoding("*")));
+        assert!(!accepts_gzip(&accept_encoding("gzip;q=0")));
+        assert!(!accepts_gzip(&accept_encoding("br, deflate")));
+        assert!(!accepts_gzip(&HeaderMap::new()));
+    }
+}
@@ -48,6 +48,11 @@
 
 use clap::Parser;
 use std::net::SocketAddr;
+use tracing::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use ultrafast_gateway::shutdown::{serve_with_graceful_shutdown, shutdown_signal};
 use ultrafast_gateway::{config::Config, server::create_server};
 
 /// Command line arguments for the Ultrafast Gateway server.
@@ -88,12 +93,23 @@ struct Args {
     log_level: String,
 }
 
+/// Log subscriber at `log_level`, without span export.
+///
+/// Active on its own while the configuration loads, since span export
+/// depends on its `[telemetry]` section; the OpenTelemetry layer is added on
+/// top once it is known.
+fn log_subscriber(log_level: &str) -> impl Subscriber + for<'span> LookupSpan<'span> + Send + Sync {
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::EnvFilter::new(log_level))
+        .with(tracing_subscriber::fmt::layer())
+}
+
 /// Main entry point for the Ultrafast Gateway server.
 ///
 /// This function:
 /// 1. Parses command line arguments
-/// 2. Initializes logging and tracing
-/// 3. Loads and validates configuration
+/// 2. Loads the configuration under a plain log subscriber
+/// 3. Initializes logging and tracing, then validates the configuration
 /// 4. Creates and starts the HTTP server
 /// 5. Handles graceful shutdown
 ///
@@ -108,15 +124,35 @@ async fn main() -> anyhow::Result<()> {
     // Parse command line arguments
     let args = Args::parse();
 
+    // Load configuration from the specified file
+    // The configuration includes server settings, provider configs, and auth settings.
+    // Loading logs upgrade notices and warnings, so it runs under a plain
+    // subscriber until the configured one is installed
+    let config = tracing::subscriber::with_default(log_subscriber(&args.log_level), || {
+        Config::load(&args.config)
+    })?;
+
     // Initialize tracing with the specified log level
-    // This sets up structured logging for the entire application
-    tracing_subscriber::fmt()
-        .with_env_filter(&args.log_level)
+    // This sets up structured logging for the entire application, and span
+    // export when [telemetry] is enabled
+    let subscriber = log_subscriber(&args.log_level);
+    #[cfg(feature = "otel")]
+    let tracer_provider = ultrafast_gateway::telemetry::tracer_provider(&config.telemetry)?;
+    #[cfg(feature = "otel")]
+    subscriber
+        .with(
+            tracer_provider
+                .as_ref()
+                .map(ultrafast_gateway::telemetry::otel_layer),
+        )
         .init();
-
-    // Load configuration from the specified file
-    // The configuration includes server settings, provider configs, and auth settings
-    let config = Config::load(&args.config)?;
+    #[cfg(not(feature = "otel"))]
+    {
+        subscriber.init();
+        if config.telemetry.enabled {
+            tracing::warn!("[telemetry] is enabled but the gateway was built without the otel feature; spans are not exported");
+        }
+    }
 
     // Validate the configuration to ensure all required fields are present
     // and that the configuration is consistent and valid
@@ -124,6 +160,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Create the HTTP server with the loaded configuration
     // This sets up all routes, middleware, and handlers
+    let shutdown_timeout = config.server.shutdown_timeout;
     let app = create_server(config).await?;
 
     // Parse the host address and create a socket address
@@ -131,9 +168,17 @@ async fn main() -> anyhow::Result<()> {
     tracing::info!("Starting Ultrafast Gateway server on {}", addr);
 
     // Bind to the specified address and start serving requests
-    // The server will run until interrupted (Ctrl+C)
+    // The server will run until SIGTERM or Ctrl+C, then drain in-flight requests
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    serve_with_graceful_shutdown(listener, app, shutdown_timeout, shutdown_signal()).await?;
+
+    // Flush spans still waiting to be exported
+    #[cfg(feature = "otel")]
+    if let Some(tracer_provider) = tracer_provider {
+        if let Err(e) = tracer_provider.shutdown() {
+            tracing::warn!("Failed to flush telemetry spans: {}", e);
+        }
+    }
 
     Ok(())
 }
@@ -93,8 +93,25 @@
 //! max_requests = 1000
 //! retention_duration = "24h"
 //! cleanup_interval = "1h"
+//! latency_buckets = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]
+//!
+//! [metrics.health_score]
+//! latency_weight = 0.3
+//! error_weight = 0.4
+//! circuit_weight = 0.3
+//! latency_target_ms = 1000
+//! latency_max_ms = 10000
+//! smoothing = 0.3
 //! ```
 //!
+//! ## Provider Health Score
+//!
+//! Each provider gets a 0–100 `health_score`, computed by
+//! [`HealthScoreConfig::score`] from an EWMA of its request latencies, its
+//! error rate and the state of its circuit breaker. It is reported in the
+//! provider stats of `GET /metrics`, shown in the dashboard's provider
+//! health section and exported as `gateway_provider_health_score`.
+//!
 //! ## Data Retention
 //!
 //! The metrics system automatically manages data retention:
@@ -113,6 +130,12 @@
 //! // Returns metrics in Prometheus text format
 //! ```
 //!
+//! Alongside the summary gauges, the latency of requests served by a model
+//! is exported as the `ultrafast_request_duration_seconds` histogram,
+//! labeled by `provider`, `model` and `status_class` (`2xx`, `5xx`, ...).
+//! Its buckets are the `latency_buckets` upper bounds, in seconds. Unlike
+//! the gauges, the histogram is not limited to the retention window.
+//!
 //! ## Performance Impact
 //!
 //! The metrics system is designed for minimal performance impact:
@@ -122,11 +145,15 @@
 //! - **Thread Safe**: Concurrent access support
 //! - **Minimal Overhead**: <1ms per request impact
 
+use chrono::{Datelike, NaiveDate};
+use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::RwLock;
+use ultrafast_models_sdk::circuit_breaker::CircuitState;
 
 /// Global metrics collector instance for thread-safe access.
 ///
@@ -134,6 +161,69 @@ use tokio::sync::RwLock;
 /// and shared across all threads.
 static METRICS_COLLECTOR: OnceLock<Arc<RwLock<MetricsCollector>>> = OnceLock::new();
 
+/// Requests currently being handled. Kept outside the collector's lock so
+/// that [`ConnectionGuard`] can decrement it from `Drop`.
+static ACTIVE_CONNECTIONS: AtomicU32 = AtomicU32::new(0);
+
+/// Counts one active connection until dropped.
+///
+/// The count is released when the guard goes out of scope, including when
+/// the handler holding it returns an error or panics, so the
+/// `active_connections` gauge cannot leak.
+#[must_use = "the connection is only counted while the guard is alive"]
+pub struct ConnectionGuard<'a> {
+    counter: &'a AtomicU32,
+}
+
+impl<'a> ConnectionGuard<'a> {
+    pub fn new(counter: &'a AtomicU32) -> Self {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Self { counter }
+    }
+}
+
+impl Drop for ConnectionGuard<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Requests being handled per API key, for `GET /v1/usage`.
+static KEY_REQUESTS: OnceLock<DashMap<String, u32>> = OnceLock::new();
+
+fn key_requests() -> &'static DashMap<String, u32> {
+    KEY_REQUESTS.get_or_init(DashMap::new)
+}
+
+/// Counts one request of an API key until dropped.
+#[must_use = "the request is only counted while the guard is alive"]
+pub struct KeyRequestGuard {
+    api_key: String,
+}
+
+impl Drop for KeyRequestGuard {
+    fn drop(&mut self) {
+        key_requests().remove_if_mut(&self.api_key, |_, count| {
+            *count -= 1;
+            *count == 0
+        });
+    }
+}
+
+/// Count a request of `api_key` as in flight for as long as the returned
+/// guard lives.
+pub fn track_key_request(api_key: &str) -> KeyRequestGuard {
+    *key_requests().entry(api_key.to_string()).or_insert(0) += 1;
+    KeyRequestGuard {
+        api_key: api_key.to_string(),
+    }
+}
+
+/// Number of requests of `api_key` currently being handled.
+pub fn key_requests_in_flight(api_key: &str) -> u32 {
+    key_requests().get(api_key).map_or(0, |count| *count)
+}
+
 /// Get the global metrics collector instance.
 ///
 /// Returns a reference to the global metrics collector, initializing it
@@ -166,6 +256,8 @@ fn get_metrics_collector() -> &'static Arc<RwLock<MetricsCollector>> {
 ///     response_size_bytes: Some(2048),
 ///     cache_hit: Some(false),
 ///     error_type: None,
+///     provider_override: false,
+///     metadata: HashMap::new(),
 /// };
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -200,6 +292,12 @@ pub struct RequestMetrics {
     pub cache_hit: Option<bool>,
     /// Error type if the request failed
     pub error_type: Option<String>,
+    /// Whether the provider was pinned with the `x-ultrafast-provider` header
+    #[serde(default)]
+    pub provider_override: bool,
+    /// Tags the client sent in the chat request's `request_metadata`
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
 }
 
 /// Aggregated metrics providing high-level performance overview.
@@ -246,6 +344,58 @@ pub struct AggregatedMetrics {
     pub cache_stats: CacheStats,
     /// Error statistics and categorization
     pub error_stats: ErrorStats,
+    /// Request coalescing counters
+    pub coalescing_stats: CoalescingStats,
+    /// Chat completions answered with the fallback response after every
+    /// provider failed. A lifetime counter.
+    pub fallback_responses: u64,
+    /// Request counts per `request_metadata` tag, keyed `key=value`
+    pub metadata_stats: HashMap<String, u64>,
+}
+
+/// How [`AggregatedMetrics::by_provider`] aggregates provider statistics.
+///
+/// `Count` counts requests and `Sum` adds up cost in USD, while `Average`,
+/// `Minimum` and `Maximum` apply to the average latency of each provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregation {
+    Count,
+    Sum,
+    Average,
+    Minimum,
+    Maximum,
+}
+
+impl AggregatedMetrics {
+    /// `aggregation` over all providers, and the value of each provider
+    /// sorted by provider name.
+    pub fn by_provider(&self, aggregation: Aggregation) -> (f64, Vec<(String, f64)>) {
+        let mut groups: Vec<(String, f64)> = self
+            .provider_stats
+            .iter()
+            .map(|(provider, stats)| {
+                let value = match aggregation {
+                    Aggregation::Count => stats.requests as f64,
+                    Aggregation::Sum => stats.total_cost_usd,
+                    _ => stats.average_latency_ms,
+                };
+                (provider.clone(), value)
+            })
+            .collect();
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let values = groups.iter().map(|(_, value)| *value);
+        let total = match aggregation {
+            Aggregation::Count => self.total_requests as f64,
+            Aggregation::Sum => self.total_cost_usd,
+            Aggregation::Average => self.average_latency_ms,
+            Aggregation::Minimum => values.fold(f64::INFINITY, f64::min),
+            Aggregation::Maximum => values.fold(f64::NEG_INFINITY, f64::max),
+        };
+        let total = if total.is_finite() { total } else { 0.0 };
+        (total, groups)
+    }
 }
 
 /// Cache performance statistics.
@@ -265,6 +415,19 @@ pub struct CacheStats {
     pub average_cache_latency_ms: f64,
 }
 
+/// Request coalescing statistics.
+///
+/// Counts requests answered by joining an identical in-flight upstream call
+/// versus requests that made their own upstream call while coalescing was
+/// active. Unlike the other statistics these are lifetime counters.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CoalescingStats {
+    /// Requests served from another request's upstream call
+    pub coalesced_requests: u64,
+    /// Requests that made their own upstream call
+    pub direct_requests: u64,
+}
+
 /// Error statistics and categorization.
 ///
 /// Provides detailed error tracking including error types, rates, and
@@ -305,6 +468,13 @@ pub struct ProviderMetrics {
     pub error_rate: f64,
     /// Timestamp of the last request to this provider
     pub last_request: Option<chrono::DateTime<chrono::Utc>>,
+    /// Requests pinned to this provider with the `x-ultrafast-provider`
+    /// header rather than chosen by routing
+    pub override_requests: u64,
+    /// Exponentially weighted moving average of the latency, in milliseconds
+    pub latency_ewma_ms: f64,
+    /// Health score from 0 (down) to 100 (healthy)
+    pub health_score: f64,
 }
 
 /// Per-model performance metrics.
@@ -345,6 +515,10 @@ pub struct MetricsConfig {
     pub cleanup_interval: Duration,
     /// Whether metrics collection is enabled
     pub enabled: bool,
+    /// Upper bounds in seconds of the latency histogram buckets
+    pub latency_buckets: Vec<f64>,
+    /// Weights and bounds of the provider health score
+    pub health_score: HealthScoreConfig,
 }
 
 impl Default for MetricsConfig {
@@ -354,7 +528,113 @@ impl Default for MetricsConfig {
             retention_duration: Duration::from_secs(24 * 60 * 60), // 24 hours
             cleanup_interval: Duration::from_secs(60 * 60),        // 1 hour
             enabled: true,
+            latency_buckets: DEFAULT_LATENCY_BUCKETS.to_vec(),
+            health_score: HealthScoreConfig::default(),
+        }
+    }
+}
+
+/// Weights and bounds of the provider health score.
+///
+/// The score is the weighted mean of three components, each from 0 to 100:
+/// latency, which is 100 while the latency EWMA is at most
+/// `latency_target_ms` and falls linearly to 0 at `latency_max_ms`; errors,
+/// `100 * (1 - error_rate)`; and the circuit breaker, 100 when closed, 50
+/// when half-open and 0 when open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthScoreConfig {
+    /// Weight of the latency component
+    pub latency_weight: f64,
+    /// Weight of the error rate component
+    pub error_weight: f64,
+    /// Weight of the circuit breaker component
+    pub circuit_weight: f64,
+    /// Latency EWMA, in milliseconds, that still scores full latency points
+    pub latency_target_ms: f64,
+    /// Latency EWMA, in milliseconds, that scores no latency points
+    pub latency_max_ms: f64,
+    /// EWMA smoothing factor in `(0, 1]`; larger values follow recent
+    /// requests more closely
+    pub smoothing: f64,
+}
+
+impl Default for HealthScoreConfig {
+    fn default() -> Self {
+        Self {
+            latency_weight: 0.3,
+            error_weight: 0.4,
+            circuit_weight: 0.3,
+            latency_target_ms: 1000.0,
+            latency_max_ms: 10000.0,
+            smoothing: 0.3,
+        }
+    }
+}
+
+impl HealthScoreConfig {
+    /// Health score of a provider, from 0 to 100.
+    ///
+    /// `error_rate` is the fraction of failed requests, from 0 to 1.
+    pub fn score(&self, latency_ewma_ms: f64, error_rate: f64, circuit: CircuitState) -> f64 {
+        let latency = 100.0
+            * ((self.latency_max_ms - latency_ewma_ms)
+                / (self.latency_max_ms - self.latency_target_ms))
+                .clamp(0.0, 1.0);
+        let errors = 100.0 * (1.0 - error_rate).clamp(0.0, 1.0);
+        let circuit = match circuit {
+            CircuitState::Closed => 100.0,
+            CircuitState::HalfOpen => 50.0,
+            CircuitState::Open => 0.0,
+        };
+        let total_weight = self.latency_weight + self.error_weight + self.circuit_weight;
+        (self.latency_weight * latency + self.error_weight * errors + self.circuit_weight * circuit)
+            / total_weight
+    }
+
+    /// Exponentially weighted moving average of `latencies`, oldest first.
+    pub fn latency_ewma(&self, latencies: impl IntoIterator<Item = f64>) -> f64 {
+        latencies
+            .into_iter()
+            .reduce(|ewma, sample| self.smoothing * sample + (1.0 - self.smoothing) * ewma)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Default latency histogram buckets, 10ms to 30s.
+pub const DEFAULT_LATENCY_BUCKETS: [f64; 11] =
+    [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+/// Labels of one `ultrafast_request_duration_seconds` series: provider,
+/// model and status class.
+type HistogramLabels = (String, String, &'static str);
+
+/// Cumulative request latencies for one label set.
+#[derive(Debug, Clone)]
+struct LatencyHistogram {
+    /// Requests at or under each configured bucket bound
+    buckets: Vec<u64>,
+    count: u64,
+    sum_seconds: f64,
+}
+
+impl LatencyHistogram {
+    fn new(bucket_count: usize) -> Self {
+        Self {
+            buckets: vec![0; bucket_count],
+            count: 0,
+            sum_seconds: 0.0,
+        }
+    }
+
+    fn observe(&mut self, bounds: &[f64], seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(bounds) {
+            if seconds <= *bound {
+                *bucket += 1;
+            }
         }
+        self.count += 1;
+        self.sum_seconds += seconds;
     }
 }
 
@@ -369,12 +649,47 @@ pub struct MetricsCollector {
     requests: VecDeque<RequestMetrics>,
     /// Service start time for uptime calculation
     start_time: Instant,
-    /// Number of active connections
-    active_connections: u32,
     /// Metrics configuration
     config: MetricsConfig,
     /// Last cleanup time
     last_cleanup: Instant,
+    /// Request coalescing counters
+    coalescing: CoalescingStats,
+    /// Chat completions answered with the fallback response
+    fallback_responses: u64,
+    /// Spend per API key for budget enforcement
+    spend: HashMap<String, KeySpend>,
+    /// Lifetime latency histograms of requests served by a model
+    latency_histograms: BTreeMap<HistogramLabels, LatencyHistogram>,
+    /// Last known circuit breaker state of each provider
+    circuit_states: HashMap<String, CircuitState>,
+}
+
+/// Spend of one API key in the current UTC day and month.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeySpend {
+    /// Spend on `day`
+    pub daily_usd: f64,
+    /// Spend in the month of `day`
+    pub monthly_usd: f64,
+    /// UTC day the counters were last updated
+    pub day: NaiveDate,
+}
+
+impl KeySpend {
+    /// Counters as of `today`, resetting those whose day or month has ended.
+    pub fn rolled_over(self, today: NaiveDate) -> Self {
+        let same_month = (self.day.year(), self.day.month()) == (today.year(), today.month());
+        Self {
+            daily_usd: if self.day == today {
+                self.daily_usd
+            } else {
+                0.0
+            },
+            monthly_usd: if same_month { self.monthly_usd } else { 0.0 },
+            day: today,
+        }
+    }
 }
 
 impl Default for MetricsCollector {
@@ -395,9 +710,13 @@ impl MetricsCollector {
         Self {
             requests: VecDeque::new(),
             start_time: Instant::now(),
-            active_connections: 0,
             config: MetricsConfig::default(),
             last_cleanup: Instant::now(),
+            coalescing: CoalescingStats::default(),
+            fallback_responses: 0,
+            spend: HashMap::new(),
+            latency_histograms: BTreeMap::new(),
+            circuit_states: HashMap::new(),
         }
     }
 
@@ -416,9 +735,13 @@ impl MetricsCollector {
         Self {
             requests: VecDeque::new(),
             start_time: Instant::now(),
-            active_connections: 0,
             config,
             last_cleanup: Instant::now(),
+            coalescing: CoalescingStats::default(),
+            fallback_responses: 0,
+            spend: HashMap::new(),
+            latency_histograms: BTreeMap::new(),
+            circuit_states: HashMap::new(),
         }
     }
 
@@ -431,6 +754,21 @@ impl MetricsCollector {
     ///
     /// * `metrics` - The request metrics to record
     pub fn record_request(&mut self, metrics: RequestMetrics) {
+        // Only requests recorded by the model handlers carry a model; the
+        // middleware's record of the same request would count it twice
+        if let Some(model) = &metrics.model {
+            let labels = (
+                metrics.provider.clone().unwrap_or_default(),
+                model.clone(),
+                status_class(metrics.status_code),
+            );
+            let bounds = &self.config.latency_buckets;
+            self.latency_histograms
+                .entry(labels)
+                .or_insert_with(|| LatencyHistogram::new(bounds.len()))
+                .observe(bounds, metrics.latency_ms as f64 / 1000.0);
+        }
+
         // Add the new request metric
         self.requests.push_back(metrics);
 
@@ -474,20 +812,40 @@ impl MetricsCollector {
         }
     }
 
-    /// Increment the active connections counter.
-    ///
-    /// Called when a new connection is established.
-    pub fn increment_connections(&mut self) {
-        self.active_connections += 1;
+    /// Record whether a request was served by a coalesced upstream call.
+    pub fn record_coalescing(&mut self, coalesced: bool) {
+        if coalesced {
+            self.coalescing.coalesced_requests += 1;
+        } else {
+            self.coalescing.direct_requests += 1;
+        }
     }
 
-    /// Decrement the active connections counter.
-    ///
-    /// Called when a connection is closed.
-    pub fn decrement_connections(&mut self) {
-        if self.active_connections > 0 {
-            self.active_connections -= 1;
-        }
+    /// Count a chat completion answered with the fallback response.
+    pub fn record_fallback_response(&mut self) {
+        self.fallback_responses += 1;
+    }
+
+    /// Add `cost_usd` to the spend of `api_key` on `today`.
+    /// Record the circuit breaker state of a provider, for its health score.
+    pub fn record_circuit_state(&mut self, provider: &str, state: CircuitState) {
+        self.circuit_states.insert(provider.to_string(), state);
+    }
+
+    pub fn record_spend(&mut self, api_key: &str, cost_usd: f64, today: NaiveDate) {
+        let spend = self.spend.entry(api_key.to_string()).or_default();
+        *spend = spend.rolled_over(today);
+        spend.daily_usd += cost_usd;
+        spend.monthly_usd += cost_usd;
+    }
+
+    /// Spend of `api_key` as of `today`.
+    pub fn spend(&self, api_key: &str, today: NaiveDate) -> KeySpend {
+        self.spend
+            .get(api_key)
+            .copied()
+            .unwrap_or_default()
+            .rolled_over(today)
     }
 
     /// Get aggregated metrics from all collected data.
@@ -515,7 +873,7 @@ impl MetricsCollector {
                 error_rate: 0.0,
                 total_cost_usd: 0.0,
                 total_tokens: 0,
-                active_connections: self.active_connections,
+                active_connections: active_connections(),
                 uptime_seconds: uptime.as_secs(),
                 uptime_percentage: 0.0, // No requests yet, uptime is 0%
                 provider_stats: HashMap::new(),
@@ -533,6 +891,9 @@ impl MetricsCollector {
                     error_rate: 0.0,
                     most_common_error: None,
                 },
+                coalescing_stats: self.coalescing.clone(),
+                fallback_responses: self.fallback_responses,
+                metadata_stats: HashMap::new(),
             };
         }
 
@@ -628,12 +989,23 @@ impl MetricsCollector {
                 .max_by_key(|r| r.timestamp)
                 .map(|r| chrono::DateTime::from(r.timestamp));
 
+            let override_requests = requests.iter().filter(|r| r.provider_override).count() as u64;
+
             // Calculate p95 latency for provider
             let mut provider_latencies: Vec<u64> = requests.iter().map(|r| r.latency_ms).collect();
             provider_latencies.sort_unstable();
             let p95_idx = (provider_latencies.len() as f64 * 0.95) as usize;
             let p95_latency = provider_latencies.get(p95_idx).copied().unwrap_or(0) as f64;
 
+            let health = &self.config.health_score;
+            let latency_ewma = health.latency_ewma(requests.iter().map(|r| r.latency_ms as f64));
+            let circuit = self
+                .circuit_states
+                .get(&provider_name)
+                .copied()
+                .unwrap_or(CircuitState::Closed);
+            let health_score = health.score(latency_ewma, error_rate, circuit);
+
             provider_stats.insert(
                 provider_name,
                 ProviderMetrics {
@@ -646,6 +1018,9 @@ impl MetricsCollector {
                     uptime_percentage: self.calculate_uptime_percentage(),
                     error_rate,
                     last_request,
+                    override_requests,
+                    latency_ewma_ms: latency_ewma,
+                    health_score,
                 },
             );
         }
@@ -769,6 +1144,13 @@ impl MetricsCollector {
             *error_types.entry(error_type).or_insert(0) += 1;
         }
 
+        let mut metadata_stats = HashMap::new();
+        for request in &self.requests {
+            for (key, value) in &request.metadata {
+                *metadata_stats.entry(format!("{key}={value}")).or_insert(0) += 1;
+            }
+        }
+
         let most_common_error = error_types
             .iter()
             .max_by_key(|(_, &count)| count)
@@ -787,7 +1169,7 @@ impl MetricsCollector {
             error_rate,
             total_cost_usd: total_cost,
             total_tokens,
-            active_connections: self.active_connections,
+            active_connections: active_connections(),
             uptime_seconds: uptime.as_secs(),
             uptime_percentage: self.calculate_uptime_percentage(),
             provider_stats,
@@ -805,6 +1187,9 @@ impl MetricsCollector {
                 error_rate,
                 most_common_error,
             },
+            coalescing_stats: self.coalescing.clone(),
+            fallback_responses: self.fallback_responses,
+            metadata_stats,
         }
     }
 
@@ -847,6 +1232,19 @@ impl MetricsCollector {
             metrics.uptime_percentage
         ));
 
+        prometheus_metrics.push_str(&format!(
+            "gateway_coalesced_requests_total {}\n",
+            metrics.coalescing_stats.coalesced_requests
+        ));
+        prometheus_metrics.push_str(&format!(
+            "gateway_direct_upstream_requests_total {}\n",
+            metrics.coalescing_stats.direct_requests
+        ));
+        prometheus_metrics.push_str(&format!(
+            "gateway_fallback_responses_total {}\n",
+            metrics.fallback_responses
+        ));
+
         // Add provider-specific metrics
         for (provider, provider_metrics) in &metrics.provider_stats {
             prometheus_metrics.push_str(&format!(
@@ -861,11 +1259,51 @@ impl MetricsCollector {
                 "gateway_provider_error_rate{{provider=\"{}\"}} {}\n",
                 provider, provider_metrics.error_rate
             ));
+            prometheus_metrics.push_str(&format!(
+                "gateway_provider_override_requests_total{{provider=\"{}\"}} {}\n",
+                provider, provider_metrics.override_requests
+            ));
+            prometheus_metrics.push_str(&format!(
+                "gateway_provider_health_score{{provider=\"{}\"}} {}\n",
+                provider, provider_metrics.health_score
+            ));
         }
 
+        self.write_latency_histograms(&mut prometheus_metrics);
+
         prometheus_metrics
     }
 
+    fn write_latency_histograms(&self, out: &mut String) {
+        const NAME: &str = "ultrafast_request_duration_seconds";
+        out.push_str(&format!(
+            "# HELP {NAME} Latency of requests served by a model\n# TYPE {NAME} histogram\n"
+        ));
+
+        for ((provider, model, status), histogram) in &self.latency_histograms {
+            let labels = format!(
+                "provider=\"{}\",model=\"{}\",status_class=\"{}\"",
+                escape_label(provider),
+                escape_label(model),
+                status
+            );
+            for (bound, count) in self.config.latency_buckets.iter().zip(&histogram.buckets) {
+                out.push_str(&format!(
+                    "{NAME}_bucket{{{labels},le=\"{bound}\"}} {count}\n"
+                ));
+            }
+            out.push_str(&format!(
+                "{NAME}_bucket{{{labels},le=\"+Inf\"}} {}\n",
+                histogram.count
+            ));
+            out.push_str(&format!(
+                "{NAME}_sum{{{labels}}} {}\n",
+                histogram.sum_seconds
+            ));
+            out.push_str(&format!("{NAME}_count{{{labels}}} {}\n", histogram.count));
+        }
+    }
+
     /// Calculate service uptime percentage.
     ///
     /// Determines the service uptime based on the start time
@@ -899,6 +1337,23 @@ impl MetricsCollector {
     }
 }
 
+fn status_class(status_code: u16) -> &'static str {
+    match status_code {
+        100..=199 => "1xx",
+        200..=299 => "2xx",
+        300..=399 => "3xx",
+        400..=499 => "4xx",
+        _ => "5xx",
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
 // Initialization function
 pub async fn initialize_metrics(config: MetricsConfig) {
     let cleanup_interval = config.cleanup_interval;
@@ -972,16 +1427,46 @@ pub async fn record_request(metrics: RequestMetrics) {
     collector.record_request(metrics);
 }
 
-pub async fn increment_connections() {
+pub async fn record_coalescing(coalesced: bool) {
+    let collector = get_metrics_collector();
+    let mut collector = collector.write().await;
+    collector.record_coalescing(coalesced);
+}
+
+pub async fn record_fallback_response() {
+    let collector = get_metrics_collector();
+    let mut collector = collector.write().await;
+    collector.record_fallback_response();
+}
+
+pub async fn record_spend(api_key: &str, cost_usd: f64) {
     let collector = get_metrics_collector();
     let mut collector = collector.write().await;
-    collector.increment_connections();
+    collector.record_spend(api_key, cost_usd, chrono::Utc::now().date_naive());
 }
 
-pub async fn decrement_connections() {
+pub async fn record_circuit_states(states: impl IntoIterator<Item = (String, CircuitState)>) {
     let collector = get_metrics_collector();
     let mut collector = collector.write().await;
-    collector.decrement_connections();
+    for (provider, state) in states {
+        collector.record_circuit_state(&provider, state);
+    }
+}
+
+pub async fn get_spend(api_key: &str) -> KeySpend {
+    let collector = get_metrics_collector();
+    let collector = collector.read().await;
+    collector.spend(api_key, chrono::Utc::now().date_naive())
+}
+
+/// Count a request as active for as long as the returned guard lives.
+pub fn track_connection() -> ConnectionGuard<'static> {
+    ConnectionGuard::new(&ACTIVE_CONNECTIONS)
+}
+
+/// Number of requests currently being handled.
+pub fn active_connections() -> u32 {
+    ACTIVE_CONNECTIONS.load(Ordering::Relaxed)
 }
 
 pub async fn get_aggregated_metrics() -> AggregatedMetrics {
@@ -1000,6 +1485,10 @@ pub async fn reset_metrics() {
     let collector = get_metrics_collector();
     let mut collector = collector.write().await;
     collector.requests.clear();
+    collector.coalescing = CoalescingStats::default();
+    collector.fallback_responses = 0;
+    collector.spend.clear();
+    collector.latency_histograms.clear();
 }
 
 #[derive(Debug)]
@@ -1014,6 +1503,8 @@ pub struct RequestMetricsBuilder {
     pub output_tokens: Option<u32>,
     pub cost_usd: Option<f64>,
     pub user_id: Option<String>,
+    pub provider_override: bool,
+    pub metadata: HashMap<String, String>,
 }
 
 impl RequestMetricsBuilder {
@@ -1029,6 +1520,8 @@ impl RequestMetricsBuilder {
             output_tokens: None,
             cost_usd: None,
             user_id: None,
+            provider_override: false,
+            metadata: HashMap::new(),
         }
     }
 
@@ -1062,6 +1555,16 @@ impl RequestMetricsBuilder {
         self
     }
 
+    pub fn provider_override(mut self, provider_override: bool) -> Self {
+        self.provider_override = provider_override;
+        self
+    }
+
+    pub fn metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     pub fn build(self) -> RequestMetrics {
         RequestMetrics {
             timestamp: SystemTime::now(),
@@ -1079,6 +1582,8 @@ impl RequestMetricsBuilder {
             response_size_bytes: None, // Enhanced response tracking
             cache_hit: None,           // Cache performance tracking
             error_type: None,          // Enhanced error categorization
+            provider_override: self.provider_override,
+            metadata: self.metadata,
         }
     }
 }
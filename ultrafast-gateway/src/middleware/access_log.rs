@@ -0,0 +1,164 @@
+//! Access log: one line per request.
+//!
+//! With `logging.format = "json"` each line is a JSON object, written as is
+//! to `logging.output` rather than through tracing:
+//!
+//! ```json
+//! {"timestamp":"2025-01-01T12:00:00Z","method":"POST","path":"/v1/chat/completions",
+//!  "status":200,"latency_ms":412,"provider":"openai","model":"gpt-4","input_tokens":12,
+//!  "output_tokens":48,"cost_usd":0.00324,"cache_hit":false,"request_id":"5f0c..."}
+//! ```
+//!
+//! Other formats log the same fields as tracing fields at info level under
+//! the `access_log` target. The layer sits outside auth, so rejected
+//! requests are logged too. The request ID is that of the request's
+//! [`RequestContext`]. Handlers report the provider,
+//! model, tokens, cost and cache use by attaching [`AccessLogFields`] to
+//! their response; fields they do not know, such as the tokens of a streamed
+//! completion, are `null`.
+
+use crate::config::{LogFormat, LogOutput};
+use crate::request_context::RequestContext;
+use crate::server::AppState;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Where JSON access log lines are written: stdout, or a file opened for
+/// appending.
+#[derive(Clone)]
+pub struct AccessLogWriter(Arc<Mutex<Box<dyn Write + Send>>>);
+
+impl AccessLogWriter {
+    pub fn new(output: &LogOutput) -> std::io::Result<Self> {
+        let writer: Box<dyn Write + Send> = match output {
+            LogOutput::Stdout => Box::new(std::io::stdout()),
+            LogOutput::File { path } => {
+                Box::new(OpenOptions::new().create(true).append(true).open(path)?)
+            }
+        };
+        Ok(Self(Arc::new(Mutex::new(writer))))
+    }
+
+    /// Write `line` followed by a newline.
+    fn write_line(&self, line: &str) {
+        let mut writer = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(writer, "{line}").and_then(|_| writer.flush()) {
+            tracing::warn!("Failed to write access log line: {}", e);
+        }
+    }
+}
+
+impl std::fmt::Debug for AccessLogWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessLogWriter").finish_non_exhaustive()
+    }
+}
+
+/// What a handler knows about the request it served, attached to its
+/// response for the access log.
+#[derive(Debug, Clone, Default)]
+pub struct AccessLogFields {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cost_usd: Option<f64>,
+    pub cache_hit: bool,
+}
+
+/// Attach `fields` to `response` for the access log.
+pub fn with_access_log(mut response: Response, fields: AccessLogFields) -> Response {
+    response.extensions_mut().insert(fields);
+    response
+}
+
+/// One access log line.
+#[derive(Debug, Serialize)]
+struct AccessLogEntry {
+    timestamp: chrono::DateTime<chrono::Utc>,
+    method: String,
+    path: String,
+    status: u16,
+    latency_ms: u64,
+    provider: Option<String>,
+    model: Option<String>,
+    input_tokens: Option<u32>,
+    output_tokens: Option<u32>,
+    cost_usd: Option<f64>,
+    cache_hit: bool,
+    request_id: String,
+}
+
+impl AccessLogEntry {
+    fn log(&self, format: &LogFormat, writer: &AccessLogWriter) {
+        if let LogFormat::Json = format {
+            writer.write_line(&serde_json::to_string(self).unwrap_or_default());
+            return;
+        }
+        tracing::info!(
+            target: "access_log",
+            request_id = %self.request_id,
+            method = %self.method,
+            path = %self.path,
+            status = self.status,
+            latency_ms = self.latency_ms,
+            provider = self.provider.as_deref(),
+            model = self.model.as_deref(),
+            input_tokens = self.input_tokens,
+            output_tokens = self.output_tokens,
+            cost_usd = self.cost_usd,
+            cache_hit = self.cache_hit,
+            "Request processed"
+        );
+    }
+}
+
+/// Log each request once it has been answered.
+pub async fn access_log_middleware(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let start = Instant::now();
+    let timestamp = chrono::Utc::now();
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let request_id = req
+        .extensions()
+        .get::<RequestContext>()
+        .map(|context| context.request_id.clone())
+        .unwrap_or_default();
+
+    let response = next.run(req).await;
+
+    let fields = response
+        .extensions()
+        .get::<AccessLogFields>()
+        .cloned()
+        .unwrap_or_default();
+    AccessLogEntry {
+        timestamp,
+        method,
+        path,
+        status: response.status().as_u16(),
+        latency_ms: start.elapsed().as_millis() as u64,
+        provider: fields.provider,
+        model: fields.model,
+        input_tokens: fields.input_tokens,
+        output_tokens: fields.output_tokens,
+        cost_usd: fields.cost_usd,
+        cache_hit: fields.cache_hit,
+        request_id,
+    }
+    .log(&state.config.logging.format, &state.access_log);
+
+    response
+}
@@ -0,0 +1,227 @@
+//! Debug logging of request and response bodies.
+//!
+//! Enabled with `[logging.body_logging]`. Bodies are logged at debug level
+//! after redaction: `Authorization` headers and JSON `api_key` fields are
+//! always replaced by `***`, as is every match of the configured
+//! `redact_patterns`. Bodies longer than `max_bytes` are truncated with a
+//! marker; only the logged copy is truncated, the body forwarded upstream
+//! or returned to the client is passed on whole. Request bodies over
+//! `server.max_body_size` are rejected with `413 Payload Too Large`;
+//! streaming (`text/event-stream`) responses and responses not known to fit
+//! in `server.max_body_size` are not buffered.
+
+use crate::config::BodyLoggingConfig;
+use crate::gateway_error::GatewayError;
+use crate::server::AppState;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderMap, Request};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use http_body_util::{BodyExt, LengthLimitError, Limited};
+use regex::Regex;
+
+const REDACTED: &str = "***";
+
+/// Redacts and truncates bodies according to `logging.body_logging`.
+#[derive(Debug)]
+pub struct BodyRedactor {
+    patterns: Vec<Regex>,
+    max_bytes: usize,
+}
+
+impl BodyRedactor {
+    /// Compile the configured redaction patterns.
+    pub fn new(config: &BodyLoggingConfig) -> Result<Self, regex::Error> {
+        let patterns = config
+            .redact_patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            patterns,
+            max_bytes: config.max_bytes,
+        })
+    }
+
+    /// Redacted, truncated form of `body` suitable for logging.
+    pub fn redact_body(&self, body: &[u8]) -> String {
+        let mut text = match serde_json::from_slice::<serde_json::Value>(body) {
+            Ok(mut json) => {
+                redact_api_keys(&mut json);
+                json.to_string()
+            }
+            Err(_) => String::from_utf8_lossy(body).into_owned(),
+        };
+
+        for pattern in &self.patterns {
+            if let std::borrow::Cow::Owned(replaced) = pattern.replace_all(&text, REDACTED) {
+                text = replaced;
+            }
+        }
+
+        self.truncate(text)
+    }
+
+    /// Header list for logging with `Authorization` redacted.
+    pub fn redact_headers(&self, headers: &HeaderMap) -> String {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if name == axum::http::header::AUTHORIZATION {
+                    REDACTED
+                } else {
+                    value.to_str().unwrap_or("<binary>")
+                };
+                format!("{name}: {value}")
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn truncate(&self, mut text: String) -> String {
+        if text.len() <= self.max_bytes {
+            return text;
+        }
+        let total = text.len();
+        let mut end = self.max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str(&format!("...[truncated {} bytes]", total - end));
+        text
+    }
+}
+
+fn redact_api_keys(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == "api_key" {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_api_keys(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_api_keys),
+        _ => {}
+    }
+}
+
+pub async fn body_logging_middleware(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let Some(redactor) = state.body_redactor.clone() else {
+        return next.run(req).await;
+    };
+
+    let max_body_size = state.config.server.max_body_size;
+    let (parts, body) = req.into_parts();
+    let bytes = match Limited::new(body, max_body_size).collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) if e.is::<LengthLimitError>() => {
+            return GatewayError::PayloadTooLarge {
+                message: format!("request body exceeds {max_body_size} bytes"),
+            }
+            .into_response();
+        }
+        Err(e) => {
+            tracing::error!("Failed to buffer request body for logging: {}", e);
+            return Response::builder()
+                .status(400)
+                .body(Body::from(format!("Failed to read request body: {e}")))
+                .unwrap();
+        }
+    };
+    tracing::debug!(
+        method = %parts.method,
+        uri = %parts.uri,
+        headers = %redactor.redact_headers(&parts.headers),
+        body = %redactor.redact_body(&bytes),
+        "Request body"
+    );
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await;
+
+    let is_stream = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    if is_stream {
+        tracing::debug!(status = %response.status(), "Response body not logged for stream");
+        return response;
+    }
+    if !super::body_fits(response.body(), max_body_size) {
+        tracing::debug!(status = %response.status(), "Response body not logged: too large or of unknown length");
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, max_body_size).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response body for logging: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    tracing::debug!(
+        status = %parts.status,
+        body = %redactor.redact_body(&bytes),
+        "Response body"
+    );
+
+    Response::from_parts(parts, Body::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor(max_bytes: usize) -> BodyRedactor {
+        BodyRedactor::new(&BodyLoggingConfig {
+            enabled: true,
+            max_bytes,
+            redact_patterns: vec!["sk-[A-Za-z0-9]+".to_string()],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_redacts_patterns_and_api_key_fields() {
+        let redactor = redactor(4096);
+
+        let logged = redactor.redact_body(b"token=sk-abc123&user=alice");
+        assert_eq!(logged, "token=***&user=alice");
+
+        let logged = redactor.redact_body(
+            br#"{"messages":[{"content":"my key is sk-abc123"}],"config":{"api_key":"secret"}}"#,
+        );
+        assert!(!logged.contains("sk-abc123"));
+        assert!(!logged.contains("secret"));
+        assert!(logged.contains(r#""api_key":"***""#));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer sk-abc123".parse().unwrap());
+        headers.insert("content-type", "application/json".parse().unwrap());
+        let logged = redactor.redact_headers(&headers);
+        assert!(logged.contains("authorization: ***"));
+        assert!(logged.contains("content-type: application/json"));
+    }
+
+    #[test]
+    fn test_truncates_bodies_over_max_bytes() {
+        let redactor = redactor(10);
+
+        let logged = redactor.redact_body("a".repeat(25).as_bytes());
+        assert_eq!(logged, "aaaaaaaaaa...[truncated 15 bytes]");
+
+        assert_eq!(redactor.redact_body(b"short"), "short");
+    }
+}
@@ -0,0 +1,56 @@
+//! Response checksums for integrity checks across proxy chains.
+//!
+//! Enabled with `server.response_checksum`. Each buffered response gets an
+//! `x-content-sha256` header with the lowercase hex SHA-256 of its body, so
+//! clients can detect bodies corrupted between the gateway and them.
+//! Streaming (`text/event-stream`) responses are sent before their body is
+//! known and carry no checksum, as do responses not known to fit in
+//! `server.max_body_size`, which are not buffered.
+
+use crate::server::AppState;
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use sha2::{Digest, Sha256};
+
+/// Response header carrying the body checksum.
+pub const CHECKSUM_HEADER: &str = "x-content-sha256";
+
+pub async fn checksum_middleware(
+    State(state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    let response = next.run(req).await;
+    if !state.config.server.response_checksum {
+        return response;
+    }
+
+    let is_stream = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/event-stream"));
+    let max_body_size = state.config.server.max_body_size;
+    if is_stream || !super::body_fits(response.body(), max_body_size) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, max_body_size).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("Failed to buffer response body for checksum: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let checksum = format!("{:x}", Sha256::digest(&bytes));
+    parts.headers.insert(
+        CHECKSUM_HEADER,
+        HeaderValue::from_str(&checksum).expect("hex digest is a valid header value"),
+    );
+
+    Response::from_parts(parts, Body::from(bytes))
+}
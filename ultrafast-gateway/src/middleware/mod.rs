@@ -8,7 +8,8 @@
 //!
 //! The middleware system provides:
 //! - **Authentication Middleware**: API key and JWT token validation
-//! - **Logging Middleware**: Request/response logging with context
+//! - **Access Log Middleware**: One line per request, rejections included, JSON with `logging.format = "json"`
+//! - **Body Logging Middleware**: Redacted request/response bodies at debug level
 //! - **Metrics Middleware**: Performance metrics collection
 //! - **CORS Middleware**: Cross-origin resource sharing
 //! - **Input Validation**: Request validation and sanitization
@@ -36,15 +37,14 @@
 //! - **Permission Checking**: Validates user permissions
 //! - **Session Management**: Handles user sessions
 //!
-//! ## Logging Middleware
+//! ## Access Log Middleware
 //!
-//! Provides comprehensive request/response logging:
+//! Logs one line per request (see [`access_log`]):
 //!
-//! - **Request Context**: Logs request method, URI, and headers
-//! - **Response Status**: Tracks response status codes
-//! - **Latency Tracking**: Measures request processing time
-//! - **Request ID**: Unique request identifiers for tracing
-//! - **Error Logging**: Detailed error information
+//! - **Request**: Method, path, status and latency
+//! - **Provider and Model**: Where the request was served, or whether it was a cache hit
+//! - **Usage**: Input and output tokens and estimated cost
+//! - **Request ID**: The ID of the request's context, for tracing
 //!
 //! ## Metrics Middleware
 //!
@@ -90,14 +90,14 @@
 //!
 //! ```rust
 //! use ultrafast_gateway::middleware::{
-//!     auth_middleware, cors_middleware, logging_middleware,
+//!     access_log::access_log_middleware, auth_middleware, cors_middleware,
 //!     metrics_middleware, input_validation_middleware
 //! };
 //!
 //! // Apply middleware to router
 //! let app = Router::new()
 //!     .layer(cors_middleware(&cors_config))
-//!     .layer(axum::middleware::from_fn(logging_middleware))
+//!     .layer(axum::middleware::from_fn(access_log_middleware))
 //!     .layer(axum::middleware::from_fn(metrics_middleware))
 //!     .layer(axum::middleware::from_fn(auth_middleware))
 //!     .layer(axum::middleware::from_fn(input_validation_middleware));
@@ -142,7 +142,8 @@
 //! - **Selective Metrics**: Metrics collection only for relevant requests
 //! - **Optimized Validation**: Fast validation algorithms
 
-use crate::config::CorsConfig;
+use crate::api_key_store::KeyUsage;
+use crate::config::{AuthConfig, CorsConfig};
 use crate::server::AppState;
 use axum::body::Body;
 use axum::extract::State;
@@ -151,66 +152,28 @@ use axum::http::{self, StatusCode};
 use axum::middleware::Next;
 use axum::response::Response;
 use std::collections::HashMap;
-use std::time::Instant;
 use tower_http::cors::{Any, CorsLayer};
+use tracing::Instrument;
 // Unused imports removed - using dedicated modules now
 
+pub mod access_log;
+pub mod body_logging;
+pub mod checksum;
 pub mod plugin_middleware;
+pub mod request_id;
 
 // Input validation now handled via plugin
 
-/// Logging middleware for request/response tracking.
+/// Whether a response body is known to hold at most `limit` bytes.
 ///
-/// Logs detailed information about each request including method, URI,
-/// status code, latency, and request ID for tracing.
-///
-/// # Arguments
-///
-/// * `_state` - Application state (unused in this middleware)
-/// * `req` - The incoming HTTP request
-/// * `next` - The next middleware in the chain
-///
-/// # Returns
-///
-/// Returns the HTTP response with logging information.
-///
-/// # Example
-///
-/// ```rust
-/// let app = Router::new()
-///     .layer(axum::middleware::from_fn(logging_middleware));
-/// ```
-pub async fn logging_middleware(
-    State(_state): State<AppState>,
-    req: Request<Body>,
-    next: Next,
-) -> Response {
-    let start = Instant::now();
-    let method = req.method().clone();
-    let uri = req.uri().clone();
-
-    // Extract request context if available
-    let request_id = req
-        .extensions()
-        .get::<crate::request_context::RequestContext>()
-        .map(|ctx| ctx.request_id.clone())
-        .unwrap_or_else(|| "unknown".to_string());
-
-    let response = next.run(req).await;
-
-    let latency = start.elapsed();
-    let status = response.status();
-
-    tracing::info!(
-        request_id = %request_id,
-        method = %method,
-        uri = %uri,
-        status = %status,
-        latency_ms = latency.as_millis(),
-        "Request processed"
-    );
-
-    response
+/// Middleware that buffers responses only does so when this holds; bodies
+/// of unknown length are passed through unbuffered.
+fn body_fits(body: &Body, limit: usize) -> bool {
+    use axum::body::HttpBody;
+
+    body.size_hint()
+        .upper()
+        .is_some_and(|upper| upper <= limit as u64)
 }
 
 /// Metrics middleware for performance tracking.
@@ -221,7 +184,6 @@ pub async fn logging_middleware(
 ///
 /// # Arguments
 ///
-/// * `_state` - Application state (unused in this middleware)
 /// * `req` - The incoming HTTP request
 /// * `next` - The next middleware in the chain
 ///
@@ -235,11 +197,9 @@ pub async fn logging_middleware(
 /// let app = Router::new()
 ///     .layer(axum::middleware::from_fn(metrics_middleware));
 /// ```
-pub async fn metrics_middleware(
-    State(_state): State<AppState>,
-    mut req: Request<Body>,
-    next: Next,
-) -> Response {
+pub async fn metrics_middleware(mut req: Request<Body>, next: Next) -> Response {
+    // Released on drop, so panicking handlers are not left counted
+    let _connection = crate::metrics::track_connection();
     let start = std::time::Instant::now();
     let method = req.method().clone();
     let uri = req.uri().clone();
@@ -256,6 +216,7 @@ pub async fn metrics_middleware(
     let should_record_metrics = !uri.path().starts_with("/dashboard")
         && !uri.path().starts_with("/metrics")
         && !uri.path().starts_with("/health")
+        && !uri.path().starts_with("/version")
         && !uri.path().starts_with("/ws/");
 
     if should_record_metrics {
@@ -312,9 +273,65 @@ pub async fn get_metrics() -> HashMap<String, serde_json::Value> {
 
 // Rate limiting now handled by auth module
 
+/// Whether `path` is an admin endpoint guarded by `[auth.admin]`.
+fn is_admin_path(path: &str) -> bool {
+    path.starts_with("/admin/") || path == "/metrics" || path.starts_with("/metrics/")
+}
+
+fn auth_error(status: StatusCode, message: &str) -> Response {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(format!(
+            r#"{{"error": {{"message": "{message}", "type": "authentication_error"}}}}"#
+        )))
+        .unwrap()
+}
+
+/// Check an admin endpoint request against the admin keys, the API keys
+/// explicitly granted admin access and the stored keys with the `admin`
+/// role, returning the rejection if any.
+async fn admin_access_error(auth: &AuthConfig, headers: &http::HeaderMap) -> Option<Response> {
+    let auth_header = headers.get("Authorization").and_then(|h| h.to_str().ok());
+    let Some(key) = crate::auth::AuthService::extract_api_key_from_header(auth_header) else {
+        return Some(auth_error(
+            StatusCode::UNAUTHORIZED,
+            "Missing Authorization header",
+        ));
+    };
+
+    if auth
+        .admin
+        .api_keys
+        .iter()
+        .any(|admin_key| admin_key.enabled && admin_key.key == key)
+    {
+        return None;
+    }
+
+    let forbidden = || {
+        auth_error(
+            StatusCode::FORBIDDEN,
+            "API key is not allowed to access admin endpoints",
+        )
+    };
+    if let Some(api_key) = auth
+        .api_keys
+        .iter()
+        .find(|api_key| api_key.enabled && api_key.key == key)
+    {
+        return (!api_key.allow_admin_access).then(forbidden);
+    }
+
+    match crate::auth::validate_api_key_global(&key).await {
+        Ok(context) => (!context.has_role(crate::auth::ADMIN_ROLE)).then(forbidden),
+        Err(_) => Some(auth_error(StatusCode::UNAUTHORIZED, "Invalid admin key")),
+    }
+}
+
 pub async fn auth_middleware(
     State(state): State<AppState>,
-    mut req: Request<Body>,
+    req: Request<Body>,
     next: Next,
 ) -> Response {
     // Allow OPTIONS requests (CORS preflight) without authentication
@@ -322,8 +339,65 @@ pub async fn auth_middleware(
         return next.run(req).await;
     }
 
+    // Authenticate inside its own span so it closes before the handler runs
+    let span = tracing::info_span!("gateway.auth", otel.status_code = tracing::field::Empty);
+    match authenticate(&state, req).instrument(span.clone()).await {
+        Ok(req) => {
+            let budget_warning = req
+                .extensions()
+                .get::<crate::budget::BudgetWarning>()
+                .cloned();
+            // Counted per key for GET /v1/usage until the handler returns
+            let _key_request = req
+                .extensions()
+                .get::<crate::request_context::RequestContext>()
+                .and_then(|context| context.api_key.as_deref())
+                .map(crate::metrics::track_key_request);
+            let mut response = next.run(req).await;
+            if let Some(crate::budget::BudgetWarning(warning)) = budget_warning {
+                if let Ok(value) = http::HeaderValue::from_str(&warning) {
+                    response
+                        .headers_mut()
+                        .insert(crate::budget::BUDGET_WARNING_HEADER, value);
+                }
+            }
+            response
+        }
+        Err(response) => {
+            span.record("otel.status_code", "ERROR");
+            *response
+        }
+    }
+}
+
+/// Check credentials and rate limits, attaching the request context on
+/// success and returning the rejection response otherwise.
+async fn authenticate(
+    state: &AppState,
+    mut req: Request<Body>,
+) -> Result<Request<Body>, Box<Response>> {
+    // Admin endpoints have their own credentials, separate from API auth
+    if state.config.auth.admin.enabled && is_admin_path(req.uri().path()) {
+        return match admin_access_error(&state.config.auth, req.headers()).await {
+            Some(response) => Err(Box::new(response)),
+            None => Ok(req),
+        };
+    }
+
     if !state.config.auth.enabled {
-        return next.run(req).await;
+        if !state.config.feature_flags.is_empty() {
+            let request_context = req
+                .extensions()
+                .get::<crate::request_context::RequestContext>()
+                .cloned()
+                .unwrap_or_default()
+                .with_feature_flags(crate::feature_flags::evaluate(
+                    &state.config.feature_flags,
+                    None,
+                ));
+            req.extensions_mut().insert(request_context);
+        }
+        return Ok(req);
     }
 
     // Phase 3 Optimization: Async processing of authentication and validation
@@ -340,34 +414,12 @@ pub async fn auth_middleware(
                 .header("Content-Type", "application/json")
                 .body(Body::from(r#"{"error": {"message": "Missing Authorization header", "type": "authentication_error"}}"#))
                 .unwrap();
-            return error_response;
-        }
-    };
-
-    // Phase 3 Optimization: Process authentication and validation asynchronously
-    let auth_future = crate::auth::validate_api_key_global(&api_key);
-    let rate_limit_future = async {
-        // Pre-validate API key format for rate limiting
-        if api_key.starts_with("sk-") {
-            let user_id = api_key
-                .split('-')
-                .next_back()
-                .unwrap_or("unknown")
-                .to_string();
-            crate::auth::check_rate_limits(&user_id, crate::auth::RateLimits::new(100, 1000, 10000))
-                .await
-        } else {
-            Err(crate::gateway_error::GatewayError::InvalidRequest {
-                message: "Invalid API key format".to_string(),
-            })
+            return Err(Box::new(error_response));
         }
     };
 
-    // Execute both futures concurrently
-    let (auth_result, rate_limit_result) = tokio::join!(auth_future, rate_limit_future);
-
     // Handle authentication result
-    let auth_context = match auth_result {
+    let auth_context = match crate::auth::validate_api_key_global(&api_key).await {
         Ok(context) => context,
         Err(e) => {
             let error_response = Response::builder()
@@ -377,11 +429,48 @@ pub async fn auth_middleware(
                     r#"{{"error": {{"message": "{e}", "type": "authentication_error"}}}}"#
                 )))
                 .unwrap();
-            return error_response;
+            return Err(Box::new(error_response));
         }
     };
 
+    // Resolve the identity used for rate limiting and metrics
+    let user_id =
+        match crate::auth::resolve_identity_global(req.headers(), &api_key, &auth_context).await {
+            Ok(user_id) => user_id,
+            Err(e) => {
+                let error_response = Response::builder()
+                    .status(StatusCode::UNAUTHORIZED)
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(format!(
+                        r#"{{"error": {{"message": "{e}", "type": "authentication_error"}}}}"#
+                    )))
+                    .unwrap();
+                return Err(Box::new(error_response));
+            }
+        };
+
+    // Reject keys past their hard budget; warn those past the soft limit
+    match crate::budget::check(&state.config.budgets, &auth_context.api_key).await {
+        crate::budget::BudgetStatus::Within => {}
+        crate::budget::BudgetStatus::Warning(warning) => {
+            req.extensions_mut()
+                .insert(crate::budget::BudgetWarning(warning));
+        }
+        crate::budget::BudgetStatus::Exceeded(message) => {
+            let error_response = Response::builder()
+                .status(StatusCode::PAYMENT_REQUIRED)
+                .header("Content-Type", "application/json")
+                .body(Body::from(format!(
+                    r#"{{"error": {{"message": "{message}", "type": "budget_exceeded"}}}}"#
+                )))
+                .unwrap();
+            return Err(Box::new(error_response));
+        }
+    }
+
     // Handle rate limiting result
+    let rate_limit_result =
+        crate::auth::check_rate_limits(&user_id, auth_context.rate_limits.clone()).await;
     let _updated_limits = match rate_limit_result {
         Ok(limits) => limits,
         Err(e) => {
@@ -392,44 +481,109 @@ pub async fn auth_middleware(
                     r#"{{"error": {{"message": "{e}", "type": "rate_limit_error"}}}}"#
                 )))
                 .unwrap();
-            return error_response;
+            return Err(Box::new(error_response));
         }
     };
 
-    // Create request context with authentication info
-    let request_context = crate::request_context::RequestContext::with_auth(
-        Some(auth_context.user_id.clone()),
+    // Count the admitted request against the key in the key store
+    tokio::spawn(crate::auth::record_key_usage(
+        auth_context.api_key.clone(),
+        KeyUsage {
+            requests: 1,
+            tokens: 0,
+        },
+    ));
+
+    // Create request context with authentication info and the user's feature flags
+    let feature_flags = crate::feature_flags::evaluate(&state.config.feature_flags, Some(&user_id));
+    let mut request_context = crate::request_context::RequestContext::with_auth(
+        Some(user_id),
         Some(auth_context.api_key.clone()),
-    );
+    )
+    .with_feature_flags(feature_flags)
+    .with_max_prompt_tokens(auth_context.max_prompt_tokens);
+    if let Some(context) = req
+        .extensions()
+        .get::<crate::request_context::RequestContext>()
+    {
+        request_context = request_context.with_request_id(context.request_id.clone());
+    }
 
-    // Inject request context into request extensions
+    // Inject request context into request extensions, with the key's limits
+    // for GET /v1/usage
     req.extensions_mut().insert(request_context);
+    req.extensions_mut().insert(auth_context.rate_limits);
 
-    next.run(req).await
+    Ok(req)
 }
 
+/// CORS layer for `[server.cors]`. When disabled, no origin is allowed.
 pub fn cors_middleware(cors_config: &CorsConfig) -> CorsLayer {
     if !cors_config.enabled {
         return CorsLayer::new();
     }
+    let is_any = |values: &[String]| values.iter().any(|value| value == "*");
 
     let mut cors = CorsLayer::new();
 
-    if cors_config.allowed_origins.contains(&"*".to_string()) {
+    if is_any(&cors_config.allowed_origins) {
         cors = cors.allow_origin(Any);
     } else {
-        for origin in &cors_config.allowed_origins {
-            if let Ok(origin) = origin.parse::<http::header::HeaderValue>() {
-                cors = cors.allow_origin(origin);
-            }
-        }
+        let origins: Vec<http::HeaderValue> = cors_config
+            .allowed_origins
+            .iter()
+            .filter_map(|origin| origin.parse().ok())
+            .collect();
+        cors = cors.allow_origin(origins);
+    }
+
+    if is_any(&cors_config.allowed_methods) {
+        cors = cors.allow_methods(Any);
+    } else {
+        let methods: Vec<http::Method> = cors_config
+            .allowed_methods
+            .iter()
+            .filter_map(|method| method.parse().ok())
+            .collect();
+        cors = cors.allow_methods(methods);
+    }
+
+    if is_any(&cors_config.allowed_headers) {
+        cors = cors.allow_headers(Any);
+    } else {
+        let headers: Vec<http::HeaderName> = cors_config
+            .allowed_headers
+            .iter()
+            .filter_map(|header| header.parse().ok())
+            .collect();
+        cors = cors.allow_headers(headers);
     }
 
-    cors = cors.allow_methods(Any).allow_headers(Any);
+    cors = cors.allow_credentials(cors_config.allow_credentials);
 
     if let Some(max_age) = cors_config.max_age {
         cors = cors.max_age(max_age);
     }
 
-    cors
+    // Lets browser clients read the ID to quote when reporting a failure
+    cors.expose_headers([http::HeaderName::from_static(
+        ultrafast_models_sdk::providers::http_client::REQUEST_ID_HEADER,
+    )])
+}
+
+/// Apply the `cors` layer to the OpenAI-compatible `/v1/` endpoints, the
+/// only ones browsers may call cross-origin. Runs outside authentication
+/// so 401, 403 and 429 responses carry the CORS headers too.
+pub async fn api_cors_middleware(
+    State(cors): State<CorsLayer>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if !req.uri().path().starts_with("/v1/") {
+        return next.run(req).await;
+    }
+    match tower::ServiceExt::oneshot(tower::Layer::layer(&cors, next), req).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    }
 }
@@ -0,0 +1,107 @@
+//! Request IDs.
+//!
+//! Every request gets an ID: the client's `x-request-id` header when it is
+//! 1–128 visible ASCII characters, a generated UUID otherwise. The ID is
+//! stored in the request's [`RequestContext`], recorded on a `request` span
+//! so every log line of the request carries it, forwarded to providers as
+//! `x-request-id` and echoed in the response's `x-request-id` header. JSON
+//! error responses also carry it as `error.request_id`.
+
+use crate::request_context::RequestContext;
+use axum::body::Body;
+use axum::http::{header, HeaderMap, HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+use ultrafast_models_sdk::providers::http_client::{with_request_id, REQUEST_ID_HEADER};
+
+/// Longest client-supplied request ID that is kept.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Largest error body the request ID is added to.
+const MAX_ERROR_BODY_BYTES: usize = 64 * 1024;
+
+/// The client's request ID, if it is usable.
+fn client_request_id(headers: &HeaderMap) -> Option<String> {
+    let request_id = headers.get(REQUEST_ID_HEADER)?.to_str().ok()?.trim();
+    let usable = !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LEN
+        && request_id.bytes().all(|byte| byte.is_ascii_graphic());
+    usable.then(|| request_id.to_string())
+}
+
+pub async fn request_id_middleware(mut req: Request<Body>, next: Next) -> Response {
+    let context = match client_request_id(req.headers()) {
+        Some(request_id) => RequestContext::new().with_request_id(request_id),
+        None => RequestContext::new(),
+    };
+    let request_id = context.request_id.clone();
+    req.extensions_mut().insert(context);
+
+    let span = tracing::info_span!("request", request_id = %request_id);
+    let response = with_request_id(request_id.clone(), next.run(req))
+        .instrument(span)
+        .await;
+
+    let mut response = add_to_error_body(response, &request_id).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Add `request_id` to the `error` object of a JSON error response.
+async fn add_to_error_body(response: Response, request_id: &str) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if response.status().as_u16() < 400 || !is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let bytes = match axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::warn!("Failed to read error body to add the request ID: {}", e);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let Some(error) = json
+        .get_mut("error")
+        .and_then(|error| error.as_object_mut())
+    else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    error.insert("request_id".to_string(), request_id.into());
+    Response::from_parts(parts, Body::from(json.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(request_id: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, request_id.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_client_request_id() {
+        assert_eq!(
+            client_request_id(&headers(" req-42 ")),
+            Some("req-42".to_string())
+        );
+        assert_eq!(client_request_id(&headers("")), None);
+        assert_eq!(client_request_id(&headers("two words")), None);
+        assert_eq!(client_request_id(&headers(&"a".repeat(129))), None);
+        assert_eq!(client_request_id(&HeaderMap::new()), None);
+    }
+}
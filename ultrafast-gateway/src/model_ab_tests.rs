@@ -0,0 +1,86 @@
+//! # Model A/B Tests Module
+//!
+//! This module resolves virtual model names from the `[model_ab_tests]`
+//! section of the gateway configuration to concrete models, so different
+//! models can be compared on live traffic.
+//!
+//! ## Overview
+//!
+//! A chat request whose `model` names an A/B test has it replaced by one of
+//! the test's variants, picked at random in proportion to its weight. The
+//! choice is made per request, before caching and routing, so metrics, cost
+//! and cache entries are all recorded against the concrete model.
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [model_ab_tests.smart]
+//! variants = [
+//!     { model = "gpt-4", weight = 70.0 },
+//!     { model = "gpt-4o", weight = 30.0 },
+//! ]
+//! ```
+
+use crate::config::ModelAbTest;
+use rand::Rng;
+use std::collections::HashMap;
+
+/// Concrete model for `model` if it names an A/B test.
+pub fn resolve<'a>(tests: &'a HashMap<String, ModelAbTest>, model: &str) -> Option<&'a str> {
+    let test = tests.get(model)?;
+    Some(choose(test, rand::thread_rng().gen::<f64>()))
+}
+
+/// Variant selected by `roll`, a number in `0.0..1.0`, on the test's
+/// cumulative weights.
+pub fn choose(test: &ModelAbTest, roll: f64) -> &str {
+    let total: f64 = test.variants.iter().map(|variant| variant.weight).sum();
+    let mut threshold = roll * total;
+    for variant in &test.variants {
+        if threshold < variant.weight {
+            return &variant.model;
+        }
+        threshold -= variant.weight;
+    }
+    // Rounding can leave `threshold` just past the last variant
+    &test.variants[test.variants.len() - 1].model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelVariant;
+
+    #[test]
+    fn test_variants_chosen_in_proportion_to_weight() {
+        let test = ModelAbTest {
+            variants: vec![
+                ModelVariant {
+                    model: "gpt-4".to_string(),
+                    weight: 70.0,
+                },
+                ModelVariant {
+                    model: "gpt-4o".to_string(),
+                    weight: 30.0,
+                },
+            ],
+        };
+        let tests = HashMap::from([("smart".to_string(), test)]);
+
+        let requests = 10_000;
+        let gpt_4 = (0..requests)
+            .filter(|_| resolve(&tests, "smart") == Some("gpt-4"))
+            .count() as f64
+            / requests as f64;
+        assert!(
+            (0.67..=0.73).contains(&gpt_4),
+            "gpt-4 chosen for {gpt_4:.3}"
+        );
+
+        assert_eq!(choose(&tests["smart"], 0.0), "gpt-4");
+        assert_eq!(choose(&tests["smart"], 0.69), "gpt-4");
+        assert_eq!(choose(&tests["smart"], 0.71), "gpt-4o");
+        assert_eq!(choose(&tests["smart"], 0.999_999), "gpt-4o");
+        assert_eq!(resolve(&tests, "gpt-4"), None);
+    }
+}
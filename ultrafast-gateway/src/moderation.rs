@@ -0,0 +1,229 @@
+//! # Moderation Module
+//!
+//! This module checks chat requests against a content moderator before they
+//! are dispatched to a provider.
+//!
+//! ## Overview
+//!
+//! A [`Moderator`] looks at each chat request, streaming or not, after the
+//! request guards and before routing. When it blocks a request the gateway
+//! answers `400 Bad Request` with a `content_filtered` error carrying the
+//! moderator's reason, and no provider is called.
+//!
+//! The built-in [`OpenAiModerator`] sends the message contents to OpenAI's
+//! moderation endpoint and blocks requests it flags. Other moderators can be
+//! plugged in with [`crate::server::create_server_with_moderator`].
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [request.moderation]
+//! enabled = true
+//! base_url = "https://api.openai.com/v1"
+//! api_key = "sk-..."           # Defaults to the openai provider's key
+//! model = "omni-moderation-latest"
+//! timeout = "5s"
+//! block_on_error = false       # Block requests when the moderation call fails
+//! ```
+
+use crate::config::{Config, ModerationConfig};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use ultrafast_models_sdk::models::ChatRequest;
+
+/// Outcome of moderating a chat request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationDecision {
+    /// Dispatch the request
+    Allow,
+    /// Reject the request before any provider is called
+    Block { reason: String },
+}
+
+/// Checks chat requests before they are dispatched.
+#[async_trait::async_trait]
+pub trait Moderator: Send + Sync {
+    /// Decide whether `request` may be sent to a provider.
+    async fn check(&self, request: &ChatRequest) -> ModerationDecision;
+}
+
+/// The moderator configured in `[request.moderation]`, if enabled.
+pub fn from_config(config: &Config) -> anyhow::Result<Option<Arc<dyn Moderator>>> {
+    let moderation = &config.request.moderation;
+    if !moderation.enabled {
+        return Ok(None);
+    }
+
+    let api_key = moderation
+        .api_key
+        .clone()
+        .or_else(|| {
+            config
+                .providers
+                .get("openai")
+                .map(|provider| provider.api_key.clone())
+        })
+        .filter(|api_key| !api_key.is_empty())
+        .ok_or_else(|| {
+            anyhow::anyhow!("Moderation is enabled but no api_key or openai provider is configured")
+        })?;
+    Ok(Some(Arc::new(OpenAiModerator::new(moderation, api_key)?)))
+}
+
+/// Moderator backed by OpenAI's `POST /moderations` endpoint.
+pub struct OpenAiModerator {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+    model: Option<String>,
+    block_on_error: bool,
+}
+
+impl OpenAiModerator {
+    pub fn new(config: &ModerationConfig, api_key: String) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder().timeout(config.timeout).build()?;
+        Ok(Self {
+            client,
+            url: format!("{}/moderations", config.base_url.trim_end_matches('/')),
+            api_key,
+            model: config.model.clone(),
+            block_on_error: config.block_on_error,
+        })
+    }
+
+    async fn moderate(&self, input: Vec<&str>) -> Result<Vec<ModerationResult>, String> {
+        let mut body = serde_json::json!({ "input": input });
+        if let Some(model) = &self.model {
+            body["model"] = serde_json::json!(model);
+        }
+
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(&self.api_key)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!(
+                "moderation endpoint returned {}",
+                response.status()
+            ));
+        }
+        let response: ModerationResponse = response.json().await.map_err(|e| e.to_string())?;
+        Ok(response.results)
+    }
+}
+
+#[derive(Deserialize)]
+struct ModerationResponse {
+    results: Vec<ModerationResult>,
+}
+
+#[derive(Deserialize)]
+struct ModerationResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: BTreeMap<String, bool>,
+}
+
+#[async_trait::async_trait]
+impl Moderator for OpenAiModerator {
+    async fn check(&self, request: &ChatRequest) -> ModerationDecision {
+        let input: Vec<&str> = request
+            .messages
+            .iter()
+            .map(|message| message.content.as_str())
+            .filter(|content| !content.is_empty())
+            .collect();
+        if input.is_empty() {
+            return ModerationDecision::Allow;
+        }
+
+        match self.moderate(input).await {
+            Ok(results) => {
+                let flagged: Vec<&ModerationResult> =
+                    results.iter().filter(|result| result.flagged).collect();
+                if flagged.is_empty() {
+                    return ModerationDecision::Allow;
+                }
+                let categories: BTreeSet<&str> = flagged
+                    .iter()
+                    .flat_map(|result| {
+                        result
+                            .categories
+                            .iter()
+                            .filter(|(_, flagged)| **flagged)
+                            .map(|(category, _)| category.as_str())
+                    })
+                    .collect();
+                let reason = if categories.is_empty() {
+                    "Content was flagged by moderation".to_string()
+                } else {
+                    let categories: Vec<&str> = categories.into_iter().collect();
+                    format!(
+                        "Content was flagged by moderation: {}",
+                        categories.join(", ")
+                    )
+                };
+                ModerationDecision::Block { reason }
+            }
+            Err(e) if self.block_on_error => {
+                tracing::warn!("Moderation check failed, blocking request: {}", e);
+                ModerationDecision::Block {
+                    reason: "Content moderation is unavailable".to_string(),
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Moderation check failed, allowing request: {}", e);
+                ModerationDecision::Allow
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultrafast_models_sdk::models::Message;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_openai_moderator_blocks_flagged_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/moderations"))
+            .and(body_partial_json(serde_json::json!({"input": ["Hi", "I will hurt them"]})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "modr-1",
+                "model": "omni-moderation-latest",
+                "results": [
+                    {"flagged": false, "categories": {"violence": false}},
+                    {"flagged": true, "categories": {"violence": true, "harassment": true, "hate": false}}
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let config = ModerationConfig {
+            enabled: true,
+            base_url: server.uri(),
+            ..Default::default()
+        };
+        let moderator = OpenAiModerator::new(&config, "sk-test".to_string()).unwrap();
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![Message::system("Hi"), Message::user("I will hurt them")],
+            ..Default::default()
+        };
+        assert_eq!(
+            moderator.check(&request).await,
+            ModerationDecision::Block {
+                reason: "Content was flagged by moderation: harassment, violence".to_string()
+            }
+        );
+    }
+}
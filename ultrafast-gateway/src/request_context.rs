@@ -26,12 +26,14 @@
 //!
 //! The request context includes comprehensive information:
 //!
-//! - **Request ID**: Unique identifier for tracing
+//! - **Request ID**: Unique identifier for tracing, taken from the client's
+//!   `x-request-id` header when it sends one and echoed in the response
 //! - **User ID**: Authenticated user identifier
 //! - **API Key**: API key used for authentication
 //! - **Start Time**: Request initiation timestamp
 //! - **Duration**: Request processing time
 //! - **Metadata**: Custom request metadata
+//! - **Feature Flags**: Flags from `[feature_flags]` that are on for the user
 //!
 //! ## Usage
 //!
@@ -77,6 +79,7 @@
 //! - **Caching**: Cache keys can include request context
 //! - **Rate Limiting**: Rate limits applied per request context
 
+use std::collections::HashSet;
 use std::time::Instant;
 use uuid::Uuid;
 
@@ -97,6 +100,11 @@ pub struct RequestContext {
     pub start_time: Instant,
     /// Request metadata
     pub metadata: std::collections::HashMap<String, String>,
+    /// Feature flags that are on for this request
+    pub feature_flags: HashSet<String>,
+    /// Prompt limit of the authenticated key, overriding
+    /// `routing.max_prompt_tokens`
+    pub max_prompt_tokens: Option<u32>,
 }
 
 impl RequestContext {
@@ -125,6 +133,8 @@ impl RequestContext {
             api_key: None,
             start_time: Instant::now(),
             metadata: std::collections::HashMap::new(),
+            feature_flags: HashSet::new(),
+            max_prompt_tokens: None,
         }
     }
 
@@ -161,6 +171,8 @@ impl RequestContext {
             api_key,
             start_time: Instant::now(),
             metadata: std::collections::HashMap::new(),
+            feature_flags: HashSet::new(),
+            max_prompt_tokens: None,
         }
     }
 
@@ -247,6 +259,42 @@ impl RequestContext {
     pub fn get_metadata(&self, key: &str) -> Option<&String> {
         self.metadata.get(key)
     }
+
+    /// Set the feature flags that are on for this request.
+    ///
+    /// See [`crate::feature_flags::evaluate`].
+    pub fn with_feature_flags(mut self, feature_flags: HashSet<String>) -> Self {
+        self.feature_flags = feature_flags;
+        self
+    }
+
+    /// Set the prompt limit of the authenticated key.
+    pub fn with_max_prompt_tokens(mut self, max_prompt_tokens: Option<u32>) -> Self {
+        self.max_prompt_tokens = max_prompt_tokens;
+        self
+    }
+
+    /// Use `request_id`, such as the client's `x-request-id`, instead of the
+    /// generated ID.
+    pub fn with_request_id(mut self, request_id: String) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    /// Check whether a feature flag is on for this request.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ultrafast_gateway::request_context::RequestContext;
+    ///
+    /// let context = RequestContext::new()
+    ///     .with_feature_flags(["use_new_routing".to_string()].into());
+    /// assert!(context.is_feature_enabled("use_new_routing"));
+    /// ```
+    pub fn is_feature_enabled(&self, flag: &str) -> bool {
+        self.feature_flags.contains(flag)
+    }
 }
 
 impl Default for RequestContext {
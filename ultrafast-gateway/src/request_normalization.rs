@@ -0,0 +1,108 @@
+//! # Request Normalization Module
+//!
+//! This module rewrites incoming chat requests that differ only in ways the
+//! provider would not notice, so they route and cache as the same request.
+//!
+//! ## Overview
+//!
+//! Normalization runs before routing and before the cache key is built.
+//! Each rewrite is opt-in:
+//!
+//! - `trim_trailing_whitespace` strips trailing spaces and newlines from
+//!   every message, which clients often add when building prompts from
+//!   templates.
+//! - `merge_duplicate_system_prompts` drops system messages identical to an
+//!   earlier one, as clients that prepend their system prompt on every turn
+//!   end up sending it repeatedly.
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [request.normalization]
+//! trim_trailing_whitespace = true
+//! merge_duplicate_system_prompts = true
+//! ```
+
+use crate::config::NormalizationConfig;
+use std::collections::HashSet;
+use ultrafast_models_sdk::models::{ChatRequest, Role};
+
+/// Apply the rewrites enabled in `config` to `request`.
+pub fn normalize(request: &mut ChatRequest, config: &NormalizationConfig) {
+    if config.trim_trailing_whitespace {
+        for message in &mut request.messages {
+            let trimmed = message.content.trim_end().len();
+            message.content.truncate(trimmed);
+        }
+    }
+
+    if config.merge_duplicate_system_prompts {
+        let mut seen = HashSet::new();
+        request
+            .messages
+            .retain(|message| message.role != Role::System || seen.insert(message.content.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultrafast_models_sdk::cache::CacheKeyBuilder;
+    use ultrafast_models_sdk::models::Message;
+
+    fn request(messages: Vec<Message>) -> ChatRequest {
+        ChatRequest {
+            model: "gpt-4".to_string(),
+            messages,
+            ..Default::default()
+        }
+    }
+
+    fn cache_key(request: &ChatRequest) -> String {
+        CacheKeyBuilder::build_chat_key_with(request, &[], 2)
+    }
+
+    #[test]
+    fn test_trailing_whitespace_normalizes_to_same_request_and_key() {
+        let config = NormalizationConfig {
+            trim_trailing_whitespace: true,
+            merge_duplicate_system_prompts: true,
+        };
+        let mut plain = request(vec![
+            Message::system("You are terse."),
+            Message::user("What is Rust?"),
+        ]);
+        let mut padded = request(vec![
+            Message::system("You are terse.\n"),
+            Message::system("You are terse."),
+            Message::user("What is Rust?  \n\n"),
+        ]);
+        assert_ne!(cache_key(&plain), cache_key(&padded));
+
+        normalize(&mut plain, &config);
+        normalize(&mut padded, &config);
+        assert_eq!(
+            serde_json::to_value(&plain).unwrap(),
+            serde_json::to_value(&padded).unwrap()
+        );
+        assert_eq!(cache_key(&plain), cache_key(&padded));
+
+        // Leading whitespace and user messages repeating each other are kept
+        let mut repeated = request(vec![Message::user("  again"), Message::user("  again")]);
+        normalize(&mut repeated, &config);
+        assert_eq!(repeated.messages.len(), 2);
+        assert_eq!(repeated.messages[0].content, "  again");
+    }
+
+    #[test]
+    fn test_normalization_is_off_by_default() {
+        let mut padded = request(vec![
+            Message::system("You are terse."),
+            Message::system("You are terse."),
+            Message::user("What is Rust?  "),
+        ]);
+        normalize(&mut padded, &NormalizationConfig::default());
+        assert_eq!(padded.messages.len(), 3);
+        assert_eq!(padded.messages[2].content, "What is Rust?  ");
+    }
+}
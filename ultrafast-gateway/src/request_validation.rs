@@ -0,0 +1,382 @@
+//! # Request Validation Module
+//!
+//! This module checks requests for values no provider accepts before they
+//! are routed, so clients get an error naming the offending fields instead
+//! of an opaque upstream 400.
+//!
+//! ## Overview
+//!
+//! Every problem found is reported, not just the first. The gateway answers
+//! `422 Unprocessable Entity` with a `validation_error` whose `errors` list
+//! has one `{field, message}` entry per problem:
+//!
+//! ```json
+//! {
+//!   "error": {
+//!     "message": "Request validation failed: messages: must not be empty",
+//!     "type": "validation_error",
+//!     "code": 422,
+//!     "errors": [{"field": "messages", "message": "must not be empty"}]
+//!   }
+//! }
+//! ```
+//!
+//! Chat requests need a `model` and at least one message, with `temperature`
+//! in 0–2, `top_p` in 0–1 and a positive `max_tokens`. Embedding requests
+//! need a `model`, some input and a positive `dimensions`, and image
+//! requests a `prompt` and a positive `n`.
+//!
+//! ## Required Fields
+//!
+//! Before a body is parsed, [`ValidatedJson`] checks that it has the fields
+//! its endpoint cannot do without, plus any top-level fields configured for
+//! the endpoint's path under `[request.required_fields]`. Missing (or `null`)
+//! fields are answered with `400 Bad Request` naming each one, in the same
+//! `errors` format as above.
+
+use crate::gateway_error::GatewayError;
+use crate::server::AppState;
+use axum::body::Body;
+use axum::extract::FromRequest;
+use axum::http::Request;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use ultrafast_models_sdk::models::{
+    ChatRequest, EmbeddingInput, EmbeddingRequest, ImageRequest, SpeechRequest,
+};
+
+/// One invalid field of a request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Problems found so far in one request.
+#[derive(Default)]
+struct Errors(Vec<FieldError>);
+
+impl Errors {
+    fn check(&mut self, valid: bool, field: &str, message: &str) {
+        if !valid {
+            self.0.push(FieldError {
+                field: field.to_string(),
+                message: message.to_string(),
+            });
+        }
+    }
+
+    fn into_result(self) -> Result<(), GatewayError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(GatewayError::Validation { errors: self.0 })
+        }
+    }
+}
+
+/// Top-level fields a request body must have.
+pub trait RequiredFields {
+    const REQUIRED_FIELDS: &'static [&'static str];
+}
+
+impl RequiredFields for ChatRequest {
+    const REQUIRED_FIELDS: &'static [&'static str] = &["model", "messages"];
+}
+
+impl RequiredFields for EmbeddingRequest {
+    const REQUIRED_FIELDS: &'static [&'static str] = &["model", "input"];
+}
+
+impl RequiredFields for ImageRequest {
+    const REQUIRED_FIELDS: &'static [&'static str] = &["prompt"];
+}
+
+impl RequiredFields for SpeechRequest {
+    const REQUIRED_FIELDS: &'static [&'static str] = &["model", "input", "voice"];
+}
+
+/// JSON body checked for its required fields before it is parsed.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> FromRequest<AppState> for ValidatedJson<T>
+where
+    T: DeserializeOwned + RequiredFields,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request<Body>, state: &AppState) -> Result<Self, Self::Rejection> {
+        let path = req.uri().path().to_string();
+        let Json(body) = Json::<Value>::from_request(req, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        let configured = state.config.request.required_fields.get(&path);
+        let required = T::REQUIRED_FIELDS
+            .iter()
+            .copied()
+            .chain(configured.into_iter().flatten().map(String::as_str));
+        check_required(&body, required).map_err(IntoResponse::into_response)?;
+
+        serde_json::from_value(body).map(Self).map_err(|e| {
+            GatewayError::InvalidRequest {
+                message: e.to_string(),
+            }
+            .into_response()
+        })
+    }
+}
+
+/// Check that `body` has each of `fields`, and that none is `null`.
+fn check_required<'a>(
+    body: &Value,
+    fields: impl IntoIterator<Item = &'a str>,
+) -> Result<(), GatewayError> {
+    let mut missing: Vec<String> = Vec::new();
+    for field in fields {
+        if body.get(field).is_none_or(Value::is_null) && !missing.iter().any(|f| f == field) {
+            missing.push(field.to_string());
+        }
+    }
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(GatewayError::MissingFields { fields: missing })
+    }
+}
+
+/// Check the fields of a chat request.
+pub fn validate_chat(request: &ChatRequest) -> Result<(), GatewayError> {
+    let mut errors = Errors::default();
+    errors.check(
+        !request.model.trim().is_empty(),
+        "model",
+        "must not be empty",
+    );
+    errors.check(
+        !request.messages.is_empty(),
+        "messages",
+        "must not be empty",
+    );
+    errors.check(
+        request
+            .temperature
+            .is_none_or(|temperature| (0.0..=2.0).contains(&temperature)),
+        "temperature",
+        "must be between 0 and 2",
+    );
+    errors.check(
+        request
+            .top_p
+            .is_none_or(|top_p| (0.0..=1.0).contains(&top_p)),
+        "top_p",
+        "must be between 0 and 1",
+    );
+    errors.check(
+        request.max_tokens != Some(0),
+        "max_tokens",
+        "must be greater than 0",
+    );
+    errors.into_result()
+}
+
+/// Check the fields of an embedding request.
+pub fn validate_embedding(request: &EmbeddingRequest) -> Result<(), GatewayError> {
+    let mut errors = Errors::default();
+    errors.check(
+        !request.model.trim().is_empty(),
+        "model",
+        "must not be empty",
+    );
+    let has_input = match &request.input {
+        EmbeddingInput::String(input) => !input.is_empty(),
+        EmbeddingInput::StringArray(inputs) => !inputs.is_empty(),
+        EmbeddingInput::TokenArray(tokens) => !tokens.is_empty(),
+        EmbeddingInput::TokenArrayArray(inputs) => !inputs.is_empty(),
+    };
+    errors.check(has_input, "input", "must not be empty");
+    errors.check(
+        request.dimensions != Some(0),
+        "dimensions",
+        "must be greater than 0",
+    );
+    errors.into_result()
+}
+
+/// Check the fields of an image generation request.
+pub fn validate_image(request: &ImageRequest) -> Result<(), GatewayError> {
+    let mut errors = Errors::default();
+    errors.check(
+        !request.prompt.trim().is_empty(),
+        "prompt",
+        "must not be empty",
+    );
+    errors.check(
+        request
+            .model
+            .as_deref()
+            .is_none_or(|model| !model.trim().is_empty()),
+        "model",
+        "must not be empty",
+    );
+    errors.check(request.n != Some(0), "n", "must be greater than 0");
+    errors.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultrafast_models_sdk::models::Message;
+
+    fn fields(result: Result<(), GatewayError>) -> Vec<String> {
+        match result {
+            Err(GatewayError::Validation { errors }) => {
+                errors.into_iter().map(|error| error.field).collect()
+            }
+            other => panic!("expected validation errors, got {other:?}"),
+        }
+    }
+
+    fn chat() -> ChatRequest {
+        ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![Message::user("Hi")],
+            temperature: Some(0.7),
+            top_p: Some(1.0),
+            max_tokens: Some(16),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chat_validation() {
+        assert!(validate_chat(&chat()).is_ok());
+
+        let cases = [
+            (
+                ChatRequest {
+                    model: " ".to_string(),
+                    ..chat()
+                },
+                "model",
+            ),
+            (
+                ChatRequest {
+                    messages: vec![],
+                    ..chat()
+                },
+                "messages",
+            ),
+            (
+                ChatRequest {
+                    temperature: Some(2.5),
+                    ..chat()
+                },
+                "temperature",
+            ),
+            (
+                ChatRequest {
+                    temperature: Some(-0.1),
+                    ..chat()
+                },
+                "temperature",
+            ),
+            (
+                ChatRequest {
+                    top_p: Some(1.5),
+                    ..chat()
+                },
+                "top_p",
+            ),
+            (
+                ChatRequest {
+                    max_tokens: Some(0),
+                    ..chat()
+                },
+                "max_tokens",
+            ),
+        ];
+        for (request, field) in cases {
+            assert_eq!(fields(validate_chat(&request)), vec![field]);
+        }
+
+        let request = ChatRequest {
+            model: String::new(),
+            temperature: Some(3.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            fields(validate_chat(&request)),
+            vec!["model", "messages", "temperature"]
+        );
+    }
+
+    #[test]
+    fn test_required_fields() {
+        let body = serde_json::json!({"model": "gpt-4", "messages": null, "user": "u"});
+        let missing = |fields: &[&str]| match check_required(&body, fields.iter().copied()) {
+            Ok(()) => vec![],
+            Err(GatewayError::MissingFields { fields }) => fields,
+            Err(other) => panic!("unexpected error {other:?}"),
+        };
+
+        assert!(missing(&["model", "user"]).is_empty());
+        assert_eq!(missing(ChatRequest::REQUIRED_FIELDS), vec!["messages"]);
+        assert_eq!(
+            missing(&["input", "voice", "input"]),
+            vec!["input", "voice"]
+        );
+    }
+
+    #[test]
+    fn test_embedding_and_image_validation() {
+        let embedding = EmbeddingRequest {
+            model: "text-embedding-3-small".to_string(),
+            input: EmbeddingInput::String("Hi".to_string()),
+            ..Default::default()
+        };
+        assert!(validate_embedding(&embedding).is_ok());
+        let request = EmbeddingRequest {
+            model: String::new(),
+            input: EmbeddingInput::StringArray(vec![]),
+            dimensions: Some(0),
+            ..embedding
+        };
+        assert_eq!(
+            fields(validate_embedding(&request)),
+            vec!["model", "input", "dimensions"]
+        );
+
+        let image = ImageRequest {
+            prompt: "a lighthouse".to_string(),
+            model: None,
+            n: Some(1),
+            size: None,
+            quality: None,
+            response_format: None,
+            style: None,
+            user: None,
+        };
+        assert!(validate_image(&image).is_ok());
+        let request = ImageRequest {
+            prompt: String::new(),
+            model: Some(String::new()),
+            n: Some(0),
+            ..image
+        };
+        assert_eq!(
+            fields(validate_image(&request)),
+            vec!["prompt", "model", "n"]
+        );
+    }
+}
@@ -35,9 +35,13 @@
 //! ### Admin Endpoints
 //!
 //! - `GET /health` - Health check endpoint
+//! - `GET /version` - Gateway version, build SHA, and provider adapters
 //! - `GET /metrics` - Performance metrics
 //! - `GET /admin/providers` - Provider status
+//! - `GET /admin/providers/{id}/health` - Probe a provider on demand
 //! - `GET /admin/config` - Configuration status
+//! - `POST /admin/circuit-breaker/{provider}/{action}` - Open, close or reset a circuit breaker
+//! - `POST /admin/compare` - Send one chat request to several providers side by side
 //!
 //! ### WebSocket Endpoints
 //!
@@ -65,7 +69,7 @@
 //!
 //! 1. **Timeout**: Ensures requests don't hang indefinitely
 //! 2. **CORS**: Handles cross-origin requests
-//! 3. **Logging**: Records request/response details
+//! 3. **Logging**: Records request/response details (and redacted bodies when `logging.body_logging` is enabled)
 //! 4. **Metrics**: Tracks performance metrics
 //! 5. **Authentication**: Validates API keys and JWT tokens
 //! 6. **Input Validation**: Validates and sanitizes requests
@@ -91,21 +95,36 @@
 //! - **Provider Errors**: Graceful fallback handling
 //! - **Plugin Errors**: Non-blocking plugin failures
 
+use crate::api_key_store::KeyStore;
+use crate::budget::BudgetAlerter;
 use crate::config::Config;
 use crate::dashboard::websocket::WebSocketManager;
 use crate::gateway_caching::CacheManager;
 use crate::handlers;
 use crate::middleware::{
-    auth_middleware, cors_middleware, logging_middleware, metrics_middleware, plugin_middleware,
+    access_log::{access_log_middleware, AccessLogWriter},
+    api_cors_middleware, auth_middleware,
+    body_logging::body_logging_middleware,
+    body_logging::BodyRedactor,
+    checksum::checksum_middleware,
+    cors_middleware, metrics_middleware, plugin_middleware,
+    request_id::request_id_middleware,
 };
+use crate::moderation::Moderator;
 use crate::plugins::{create_plugin, PluginManager};
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{get, post},
     Router,
 };
+use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
 use tower::ServiceBuilder;
 use tower_http::timeout::TimeoutLayer;
+use ultrafast_models_sdk::routing::{AutoTune, ErrorBudget};
 use ultrafast_models_sdk::UltrafastClient;
 
 /// Application state shared across all HTTP handlers.
@@ -127,6 +146,12 @@ use ultrafast_models_sdk::UltrafastClient;
 ///     plugin_manager: Arc::new(plugin_manager),
 ///     cache_manager: Arc::new(cache_manager),
 ///     websocket_manager: Some(Arc::new(websocket_manager)),
+///     models_cache: Default::default(),
+///     live_models: Default::default(),
+///     body_redactor: None,
+///     moderator: None,
+///     budget_alerter: None,
+///     access_log: AccessLogWriter::new(&LogOutput::Stdout)?,
 /// };
 /// ```
 pub struct AppState {
@@ -140,6 +165,18 @@ pub struct AppState {
     pub cache_manager: Arc<CacheManager>,
     /// Real-time dashboard WebSocket connections
     pub websocket_manager: Option<Arc<WebSocketManager>>,
+    /// Last `/v1/models` response and when it was built
+    pub models_cache: Arc<RwLock<Option<(Instant, Value)>>>,
+    /// Last model list each provider returned, served when it is unreachable
+    pub live_models: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    /// Body redactor, set when `logging.body_logging` is enabled
+    pub body_redactor: Option<Arc<BodyRedactor>>,
+    /// Checks chat requests before dispatch, set when moderation is enabled
+    pub moderator: Option<Arc<dyn Moderator>>,
+    /// Sends budget alerts, set when `budget_alerts.webhook_url` is
+    pub budget_alerter: Option<Arc<BudgetAlerter>>,
+    /// Destination of JSON access log lines (`logging.output`)
+    pub access_log: AccessLogWriter,
 }
 
 impl Clone for AppState {
@@ -150,6 +187,12 @@ impl Clone for AppState {
             plugin_manager: self.plugin_manager.clone(),
             cache_manager: self.cache_manager.clone(),
             websocket_manager: self.websocket_manager.clone(),
+            models_cache: self.models_cache.clone(),
+            live_models: self.live_models.clone(),
+            body_redactor: self.body_redactor.clone(),
+            moderator: self.moderator.clone(),
+            budget_alerter: self.budget_alerter.clone(),
+            access_log: self.access_log.clone(),
         }
     }
 }
@@ -189,12 +232,43 @@ impl Clone for AppState {
 /// let app = create_server(config).await?;
 /// ```
 pub async fn create_server(config: Config) -> anyhow::Result<Router> {
+    let moderator = crate::moderation::from_config(&config)?;
+    create_server_with_moderator(config, moderator).await
+}
+
+/// Create the server like [`create_server`], checking chat requests with
+/// `moderator` instead of the one configured in `[request.moderation]`.
+pub async fn create_server_with_moderator(
+    config: Config,
+    moderator: Option<Arc<dyn Moderator>>,
+) -> anyhow::Result<Router> {
+    let key_store = crate::api_key_store::from_config(&config.auth)?;
+    build_server(config, moderator, key_store).await
+}
+
+/// Create the server like [`create_server`], looking up API keys in
+/// `key_store` instead of the one configured in `[auth].api_key_store`.
+pub async fn create_server_with_key_store(
+    config: Config,
+    key_store: Arc<dyn KeyStore>,
+) -> anyhow::Result<Router> {
+    let moderator = crate::moderation::from_config(&config)?;
+    build_server(config, moderator, Some(key_store)).await
+}
+
+async fn build_server(
+    config: Config,
+    moderator: Option<Arc<dyn Moderator>>,
+    key_store: Option<Arc<dyn KeyStore>>,
+) -> anyhow::Result<Router> {
     // Create the SDK client in standalone mode with configured providers
     let mut client_builder = UltrafastClient::standalone();
 
     // Add configured providers to the client
     for (name, provider_config) in &config.providers {
-        client_builder = client_builder.with_provider(name.clone(), provider_config.clone());
+        let mut provider_config = provider_config.clone();
+        provider_config.circuit_breaker = config.circuit_breaker_for(name).cloned();
+        client_builder = client_builder.with_provider(name.clone(), provider_config);
     }
 
     // If no providers configured, add Ollama as default for development
@@ -202,16 +276,53 @@ pub async fn create_server(config: Config) -> anyhow::Result<Router> {
         client_builder = client_builder.with_ollama("http://localhost:11434");
     }
 
+    client_builder = client_builder.with_embedding_batch(config.embeddings.clone());
+
+    if config.routing.slow_start.enabled {
+        client_builder = client_builder.with_slow_start(config.routing.slow_start.window);
+    }
+
+    let error_budget = &config.routing.error_budget;
+    if error_budget.enabled {
+        client_builder = client_builder.with_error_budget(ErrorBudget {
+            max_errors: error_budget.max_errors,
+            window: error_budget.window,
+            cooldown: error_budget.cooldown,
+        });
+    }
+
+    let auto_tune = &config.routing.auto_tune;
+    if auto_tune.enabled {
+        client_builder = client_builder.with_auto_tune(AutoTune {
+            min_weight: auto_tune.min_weight,
+            max_weight: auto_tune.max_weight,
+            interval: auto_tune.interval,
+        });
+    }
+
     // Build the client with the configured routing strategy
     let client = client_builder
         .with_routing_strategy(config.routing.strategy.clone())
+        .with_routing_debug(config.routing.debug)
+        .with_model_alternates(config.routing.model_retry_alternates.clone())
         .build()?;
 
+    // Providers registered at startup ramp up like re-enabled ones
+    if config.routing.slow_start.enabled {
+        for name in config.providers.keys() {
+            client.mark_provider_activated(name).await;
+        }
+    }
+
     // Initialize cache manager with the configured backend
     let cache_manager = Arc::new(CacheManager::new(config.cache.clone()).await?);
+    cache_manager
+        .preload(&config.request.normalization, &config.model_ab_tests)
+        .await?;
 
     // Initialize authentication service and rate limiter with cache manager
-    crate::auth::initialize_auth_service(config.auth.clone(), cache_manager.clone()).await;
+    crate::auth::initialize_auth_service(config.auth.clone(), cache_manager.clone(), key_store)
+        .await;
 
     // Perform security sanity check for JWT secrets
     if let Err(e) = {
@@ -223,7 +334,8 @@ pub async fn create_server(config: Config) -> anyhow::Result<Router> {
     }
 
     // Initialize rate limiter with cache manager for distributed rate limiting
-    crate::auth::initialize_rate_limiter(cache_manager.clone()).await?;
+    crate::auth::initialize_rate_limiter(cache_manager.clone(), &config.auth.rate_limiting.backend)
+        .await?;
 
     // Initialize metrics collector with configuration
     let metrics_config = crate::metrics::MetricsConfig {
@@ -231,9 +343,14 @@ pub async fn create_server(config: Config) -> anyhow::Result<Router> {
         max_requests: config.metrics.max_requests,
         retention_duration: config.metrics.retention_duration,
         cleanup_interval: config.metrics.cleanup_interval,
+        latency_buckets: config.metrics.latency_buckets.clone(),
+        health_score: config.metrics.health_score.clone(),
     };
     crate::metrics::initialize_metrics(metrics_config).await;
 
+    // Install the pricing table used for cost reporting
+    crate::cost::initialize_pricing(config.pricing.clone());
+
     // Initialize plugin manager for dynamic functionality
     let mut plugin_manager = PluginManager::new();
 
@@ -262,12 +379,24 @@ pub async fn create_server(config: Config) -> anyhow::Result<Router> {
         Some(ws_manager)
     };
 
+    let body_redactor = if config.logging.body_logging.enabled {
+        Some(Arc::new(BodyRedactor::new(&config.logging.body_logging)?))
+    } else {
+        None
+    };
+
     let state = AppState {
         config: Arc::new(config.clone()),
         client: Arc::new(client),
         plugin_manager,
         cache_manager,
         websocket_manager,
+        models_cache: Default::default(),
+        live_models: Default::default(),
+        body_redactor,
+        moderator,
+        budget_alerter: BudgetAlerter::from_config(&config.budget_alerts).map(Arc::new),
+        access_log: AccessLogWriter::new(&config.logging.output)?,
     };
 
     // Warn if permissive CORS is used in production-like settings
@@ -277,29 +406,47 @@ pub async fn create_server(config: Config) -> anyhow::Result<Router> {
         );
     }
 
-    // Create the main router
-    let app = Router::new()
-        // OpenAI-compatible endpoints
+    // OpenAI-compatible endpoints, the only ones browsers may call cross-origin
+    // (see api_cors_middleware)
+    let api = Router::new()
         .route("/v1/chat/completions", post(handlers::chat_completions))
         .route("/v1/completions", post(handlers::completions))
         .route("/v1/embeddings", post(handlers::embeddings))
+        .route("/v1/embeddings/stream", post(handlers::stream_embeddings))
         .route("/v1/images/generations", post(handlers::image_generations))
         .route(
             "/v1/audio/transcriptions",
-            post(handlers::audio_transcriptions),
+            // Audio uploads may exceed axum's default 2MB body limit
+            post(handlers::audio_transcriptions)
+                .layer(DefaultBodyLimit::max(config.server.max_body_size)),
         )
         .route("/v1/audio/speech", post(handlers::text_to_speech))
         .route("/v1/models", get(handlers::list_models))
+        .route("/v1/usage", get(handlers::usage));
+
+    // Create the main router
+    let app = Router::new()
+        .merge(api)
         // Health and admin endpoints
         .route("/health", get(handlers::health_check))
+        .route("/version", get(handlers::version))
         .route("/metrics", get(handlers::metrics))
         .route("/metrics/prometheus", get(handlers::prometheus_metrics))
         .route("/admin/providers", get(handlers::list_providers))
+        .route(
+            "/admin/providers/{id}/health",
+            get(handlers::probe_provider_health),
+        )
         .route("/admin/config", get(handlers::get_config))
         .route(
             "/admin/circuit-breakers",
             get(handlers::get_circuit_breaker_metrics),
         )
+        .route(
+            "/admin/circuit-breaker/{provider}/{action}",
+            post(handlers::set_circuit_breaker_state),
+        )
+        .route("/admin/compare", post(handlers::compare_providers))
         // Dashboard routes
         .route("/dashboard", get(handlers::dashboard))
         .route("/dashboard.js", get(handlers::dashboard_js))
@@ -308,24 +455,39 @@ pub async fn create_server(config: Config) -> anyhow::Result<Router> {
         // Middleware stack (plugins now handle input validation)
         .layer(
             ServiceBuilder::new()
-                .layer(cors_middleware(&config.server.cors)) // 1. CORS (first)
+                .layer(axum::middleware::from_fn_with_state(
+                    cors_middleware(&config.server.cors),
+                    api_cors_middleware,
+                )) // 0. CORS for /v1/* (outside auth, so rejections carry it too)
+                .layer(axum::middleware::from_fn(
+                    crate::telemetry::telemetry_middleware,
+                )) // 1. Telemetry root span (first, so it covers every layer)
                 .layer(axum::middleware::from_fn_with_state(
                     state.clone(),
-                    auth_middleware,
-                )) // 2. Authentication (includes rate limiting)
+                    checksum_middleware,
+                )) // 2. Response checksum (over the final body, when server.response_checksum is set)
+                .layer(axum::middleware::from_fn(request_id_middleware)) // 3. Request ID (before auth, so rejections carry it too)
                 .layer(axum::middleware::from_fn_with_state(
                     state.clone(),
-                    plugin_middleware::plugin_middleware,
-                )) // 3. Plugins (after auth)
+                    access_log_middleware,
+                )) // 4. Access log (outside auth, so rejections are logged too)
                 .layer(axum::middleware::from_fn_with_state(
                     state.clone(),
-                    logging_middleware,
-                )) // 4. Logging (only authenticated requests)
+                    auth_middleware,
+                )) // 5. Authentication (includes rate limiting)
+                .layer(axum::middleware::from_fn_with_state(
+                    state.clone(),
+                    plugin_middleware::plugin_middleware,
+                )) // 6. Plugins (after auth)
                 .layer(axum::middleware::from_fn_with_state(
                     state.clone(),
-                    metrics_middleware,
-                )) // 5. Metrics (only authenticated requests)
-                .layer(TimeoutLayer::new(config.server.timeout)), // 6. Timeout (last)
+                    body_logging_middleware,
+                )) // 7. Body logging (when logging.body_logging is enabled)
+                .layer(axum::middleware::from_fn(metrics_middleware)) // 8. Metrics (only authenticated requests)
+                .layer(TimeoutLayer::with_status_code(
+                    axum::http::StatusCode::REQUEST_TIMEOUT,
+                    config.server.timeout,
+                )), // 9. Timeout (last)
         )
         .with_state(state);
 
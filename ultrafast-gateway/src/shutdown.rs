@@ -0,0 +1,189 @@
+//! # Graceful Shutdown Module
+//!
+//! This module serves the gateway until SIGTERM or SIGINT, then drains
+//! in-flight requests before exiting.
+//!
+//! ## Draining
+//!
+//! Once the shutdown signal arrives the server keeps accepting connections,
+//! but every new request is answered with `503 Service Unavailable` and
+//! `Connection: close` so load balancers move traffic elsewhere. Requests
+//! already being handled run to completion. The server exits when the last
+//! of them finishes, or when `[server] shutdown_timeout` elapses, whichever
+//! comes first.
+//!
+//! ```toml
+//! [server]
+//! shutdown_timeout = "30s"
+//! ```
+
+use axum::body::Body;
+use axum::extract::State;
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::Router;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// Tracks in-flight requests and whether the server is draining.
+#[derive(Debug, Clone, Default)]
+pub struct Drain {
+    inner: Arc<DrainState>,
+}
+
+#[derive(Debug, Default)]
+struct DrainState {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl Drain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests currently being handled.
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::SeqCst)
+    }
+
+    /// Start rejecting new requests, returning how many are in flight.
+    pub fn start(&self) -> usize {
+        self.inner.draining.store(true, Ordering::SeqCst);
+        self.in_flight()
+    }
+
+    /// Wait until no requests are in flight.
+    pub async fn wait_idle(&self) {
+        loop {
+            let idle = self.inner.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    fn enter(&self) -> InFlight {
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight(self.clone())
+    }
+}
+
+/// Counts a request as in flight until dropped.
+struct InFlight(Drain);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.inner.idle.notify_waiters();
+        }
+    }
+}
+
+pub async fn drain_middleware(
+    State(drain): State<Drain>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    if drain.is_draining() {
+        return Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONNECTION, "close")
+            .body(Body::from(
+                r#"{"error": {"message": "Server is shutting down", "type": "service_unavailable"}}"#,
+            ))
+            .unwrap();
+    }
+
+    let _in_flight = drain.enter();
+    next.run(req).await
+}
+
+/// Resolves on SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Serve `app` until `signal` resolves, then drain in-flight requests for
+/// at most `shutdown_timeout`.
+pub async fn serve_with_graceful_shutdown(
+    listener: TcpListener,
+    app: Router,
+    shutdown_timeout: Duration,
+    signal: impl Future<Output = ()> + Send + 'static,
+) -> std::io::Result<()> {
+    let drain = Drain::new();
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        drain.clone(),
+        drain_middleware,
+    ));
+
+    // Drain while the server is still accepting, so new requests get a 503
+    // instead of waiting in the listen backlog
+    let (expired_tx, expired_rx) = tokio::sync::oneshot::channel::<()>();
+    let drained = async move {
+        signal.await;
+        let in_flight = drain.start();
+        tracing::info!(
+            "Shutdown signal received, draining {} in-flight requests",
+            in_flight
+        );
+        match tokio::time::timeout(shutdown_timeout, drain.wait_idle()).await {
+            Ok(()) => tracing::info!("Drained {} in-flight requests", in_flight),
+            Err(_) => {
+                let remaining = drain.in_flight();
+                tracing::warn!(
+                    "Shutdown timeout of {:?} elapsed, drained {} requests and dropped {}",
+                    shutdown_timeout,
+                    in_flight.saturating_sub(remaining),
+                    remaining
+                );
+                let _ = expired_tx.send(());
+            }
+        }
+    };
+
+    let server = axum::serve(listener, app).with_graceful_shutdown(drained);
+    tokio::select! {
+        result = server => result,
+        // Requests still running past the timeout are dropped on exit
+        Ok(()) = expired_rx => Ok(()),
+    }
+}
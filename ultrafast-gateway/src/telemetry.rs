@@ -0,0 +1,128 @@
+//! # Telemetry Module
+//!
+//! This module traces the lifecycle of each request as a tree of `tracing`
+//! spans, exported to an OpenTelemetry collector over OTLP when the gateway
+//! is built with the `otel` feature and `[telemetry]` is enabled.
+//!
+//! ## Spans
+//!
+//! Every request runs inside a `gateway.request` span tagged with the method,
+//! path and response status. Its children are:
+//!
+//! - `gateway.auth`: API key, admin key and rate limit checks
+//! - `gateway.cache`: exact and semantic cache lookups for chat completions
+//! - `gateway.provider`: the upstream call, tagged with the provider, model
+//!   and token counts. The `routing` span for the provider choice nests
+//!   inside it, since the choice is made by the client making the call.
+//!
+//! Rejected requests, upstream failures and 5xx responses set
+//! `otel.status_code = "ERROR"` on their span. A W3C `traceparent` header on
+//! the incoming request makes `gateway.request` a child of the caller's span.
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [telemetry]
+//! enabled = true
+//! otlp_endpoint = "http://localhost:4318/v1/traces"
+//! service_name = "ultrafast-gateway"
+//! ```
+
+use axum::body::Body;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+
+#[cfg(feature = "otel")]
+use crate::config::TelemetryConfig;
+#[cfg(feature = "otel")]
+use opentelemetry::trace::TracerProvider as _;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::trace::{SdkTracerProvider, Tracer};
+#[cfg(feature = "otel")]
+use tracing_subscriber::registry::LookupSpan;
+
+/// Run the request inside a `gateway.request` root span.
+pub async fn telemetry_middleware(req: Request<Body>, next: Next) -> Response {
+    let span = tracing::info_span!(
+        "gateway.request",
+        otel.kind = "server",
+        http.request.method = %req.method(),
+        url.path = %req.uri().path(),
+        http.response.status_code = tracing::field::Empty,
+        otel.status_code = tracing::field::Empty,
+    );
+    #[cfg(feature = "otel")]
+    set_remote_parent(&span, req.headers());
+
+    let response = next.run(req).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+    if response.status().is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+    response
+}
+
+/// Continue the caller's trace from its `traceparent` header, if any.
+#[cfg(feature = "otel")]
+fn set_remote_parent(span: &tracing::Span, headers: &axum::http::HeaderMap) {
+    use opentelemetry::propagation::TextMapPropagator;
+    use opentelemetry::trace::TraceContextExt;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    let context = opentelemetry_sdk::propagation::TraceContextPropagator::new()
+        .extract(&HeaderExtractor(headers));
+    if context.span().span_context().is_valid() {
+        let _ = span.set_parent(context);
+    }
+}
+
+#[cfg(feature = "otel")]
+struct HeaderExtractor<'a>(&'a axum::http::HeaderMap);
+
+#[cfg(feature = "otel")]
+impl opentelemetry::propagation::Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+/// Tracer provider exporting to the configured collector, or `None` when
+/// telemetry is disabled. Shut it down before exiting to flush pending spans.
+#[cfg(feature = "otel")]
+pub fn tracer_provider(config: &TelemetryConfig) -> anyhow::Result<Option<SdkTracerProvider>> {
+    use opentelemetry_otlp::WithExportConfig;
+
+    if !config.enabled {
+        return Ok(None);
+    }
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(&config.otlp_endpoint)
+        .build()?;
+    let resource = opentelemetry_sdk::Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .build();
+    Ok(Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource)
+            .build(),
+    ))
+}
+
+/// Subscriber layer recording `tracing` spans with `provider`.
+#[cfg(feature = "otel")]
+pub fn otel_layer<S>(
+    provider: &SdkTracerProvider,
+) -> tracing_opentelemetry::OpenTelemetryLayer<S, Tracer>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer("ultrafast-gateway"))
+}
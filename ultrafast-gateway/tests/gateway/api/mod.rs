@@ -172,8 +172,9 @@ async fn test_request_size_limits() {
 async fn test_cors_headers() {
     let server = helpers::create_test_server().await;
     
+    // CORS applies to the /v1/* API endpoints
     let response = server
-        .get("/health")
+        .get("/v1/models")
         .add_header("Origin", "https://example.com")
         .await;
     
@@ -24,14 +24,26 @@ pub mod helpers {
                 name: "test-provider".to_string(),
                 api_key: "test-key".to_string(),
                 base_url: Some("http://localhost:11434".to_string()),
-                timeout: std::time::Duration::from_secs(30),
+                connect_timeout: std::time::Duration::from_secs(10),
+                first_byte_timeout: std::time::Duration::ZERO,
+                total_timeout: std::time::Duration::from_secs(30),
                 max_retries: 3,
-                retry_delay: std::time::Duration::from_secs(1),
+                retry_delay: None,
+                retry_backoff_multiplier: None,
+                retry_max_delay: None,
                 enabled: true,
                 model_mapping: HashMap::new(),
                 headers: HashMap::new(),
                 rate_limit: None,
                 circuit_breaker: None,
+                temperature_range: None,
+                role_ordering: Default::default(),
+                default_retry_after: std::time::Duration::from_secs(1),
+                fairness: None,
+                stream_keepalive: None,
+                request_template: None,
+                azure: None,
+                interceptors: Default::default(),
             },
         );
         config
@@ -487,11 +487,26 @@ impl Cache for DistributedCache {
 /// ```
 pub struct CacheKeyBuilder;
 
+/// Decimal places sampling parameters are rounded to in chat cache keys.
+pub const DEFAULT_KEY_PRECISION: u32 = 2;
+
+/// Chat request fields that never affect the completion.
+const NON_KEY_FIELDS: &[&str] = &["user", "stream", "stream_options", "request_metadata"];
+
+/// Chat request fields rounded to the key precision before hashing.
+const ROUNDED_FIELDS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "frequency_penalty",
+    "presence_penalty",
+];
+
 impl CacheKeyBuilder {
     /// Build a cache key for chat completion requests.
     ///
-    /// Creates a deterministic hash-based key that includes the model,
-    /// messages, and key parameters like temperature and max_tokens.
+    /// Creates a deterministic hash-based key from every request field that
+    /// affects the completion, with sampling parameters rounded to two
+    /// decimal places. See [`CacheKeyBuilder::build_chat_key_with`].
     ///
     /// # Arguments
     ///
@@ -516,28 +531,56 @@ impl CacheKeyBuilder {
     /// };
     ///
     /// let cache_key = CacheKeyBuilder::build_chat_key(&request);
-    /// // Result: "chat:gpt-4:hash_value"
+    /// // Result: "chat:hash_value"
     /// ```
     pub fn build_chat_key(request: &ChatRequest) -> String {
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-
-        // Hash the model name
-        request.model.hash(&mut hasher);
-
-        // Hash each message (role and content)
-        for message in &request.messages {
-            message.role.hash(&mut hasher);
-            message.content.hash(&mut hasher);
-        }
+        Self::build_chat_key_with(request, &[], DEFAULT_KEY_PRECISION)
+    }
 
-        // Hash temperature if present (scaled to avoid floating point issues)
-        if let Some(temp) = request.temperature {
-            ((temp * 1000.0) as u32).hash(&mut hasher);
-        }
+    /// Build a cache key for a chat completion request from selected fields.
+    ///
+    /// The request is normalized before hashing so semantically identical
+    /// requests share a key: fields are hashed in a canonical order
+    /// regardless of how the client ordered its JSON, fields that do not
+    /// affect the completion (`user`, `stream`, `stream_options`,
+    /// `request_metadata`) are dropped, and `temperature`, `top_p` and the
+    /// penalties are rounded to `precision` decimal places.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The chat completion request
+    /// * `fields` - Top-level request fields that participate in the key;
+    ///   empty means all of them
+    /// * `precision` - Decimal places sampling parameters are rounded to
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ultrafast_models_sdk::cache::CacheKeyBuilder;
+    ///
+    /// let fields = ["model".to_string(), "messages".to_string()];
+    /// let cache_key = CacheKeyBuilder::build_chat_key_with(&request, &fields, 2);
+    /// ```
+    pub fn build_chat_key_with(request: &ChatRequest, fields: &[String], precision: u32) -> String {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
 
-        // Hash max_tokens if present
-        if let Some(max_tokens) = request.max_tokens {
-            max_tokens.hash(&mut hasher);
+        if let Ok(serde_json::Value::Object(mut object)) = serde_json::to_value(request) {
+            object.retain(|field, _| {
+                !NON_KEY_FIELDS.contains(&field.as_str())
+                    && (fields.is_empty() || fields.iter().any(|f| f == field))
+            });
+            let scale = 10f64.powi(precision as i32);
+            for field in ROUNDED_FIELDS {
+                if let Some(value) = object.get_mut(*field) {
+                    if let Some(number) = value.as_f64() {
+                        *value = serde_json::json!((number * scale).round() / scale);
+                    }
+                }
+            }
+            // serde_json maps are sorted by key, so this is canonical
+            serde_json::Value::Object(object)
+                .to_string()
+                .hash(&mut hasher);
         }
 
         format!("chat:{:x}", hasher.finish())
@@ -687,3 +730,68 @@ impl Default for CacheConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chat_request(json: &str) -> ChatRequest {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_chat_key_ignores_key_order_and_whitespace() {
+        let a = chat_request(
+            r#"{"model": "gpt-4", "temperature": 0.7, "messages": [{"role": "user", "content": "Hi"}], "logit_bias": {"1": 5, "2": -5}}"#,
+        );
+        let b = chat_request(
+            r#"{
+                "logit_bias": {"2": -5, "1": 5},
+                "messages": [{"content": "Hi", "role": "user"}],
+                "temperature": 0.7,
+                "model": "gpt-4",
+                "user": "someone-else"
+            }"#,
+        );
+        assert_eq!(
+            CacheKeyBuilder::build_chat_key(&a),
+            CacheKeyBuilder::build_chat_key(&b)
+        );
+    }
+
+    #[test]
+    fn test_chat_key_rounds_sampling_parameters() {
+        let key = |temperature: f32, precision: u32| {
+            let request = ChatRequest {
+                model: "gpt-4".to_string(),
+                messages: vec![crate::models::Message::user("Hi")],
+                temperature: Some(temperature),
+                ..Default::default()
+            };
+            CacheKeyBuilder::build_chat_key_with(&request, &[], precision)
+        };
+
+        assert_ne!(key(0.7, 2), key(0.8, 2));
+        assert_eq!(key(0.7, 2), key(0.701, 2));
+        assert_ne!(key(0.7, 3), key(0.701, 3));
+    }
+
+    #[test]
+    fn test_chat_key_uses_only_configured_fields() {
+        let a = chat_request(
+            r#"{"model": "gpt-4", "temperature": 0.2, "messages": [{"role": "user", "content": "Hi"}]}"#,
+        );
+        let b = chat_request(
+            r#"{"model": "gpt-4", "temperature": 0.9, "messages": [{"role": "user", "content": "Hi"}]}"#,
+        );
+        let fields = ["model".to_string(), "messages".to_string()];
+        assert_eq!(
+            CacheKeyBuilder::build_chat_key_with(&a, &fields, 2),
+            CacheKeyBuilder::build_chat_key_with(&b, &fields, 2)
+        );
+        assert_ne!(
+            CacheKeyBuilder::build_chat_key(&a),
+            CacheKeyBuilder::build_chat_key(&b)
+        );
+    }
+}
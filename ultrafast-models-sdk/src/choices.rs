@@ -0,0 +1,158 @@
+//! # Choices Module
+//!
+//! This module handles OpenAI-style `n` requests for several completions of
+//! the same prompt.
+//!
+//! ## Overview
+//!
+//! Providers that accept `n` (see [`Provider::supports_multiple_choices`])
+//! receive it unchanged. For the others the request is sent `n` times, at
+//! most [`MAX_CONCURRENT_CHOICES`] at a time, and the responses are merged
+//! into one:
+//!
+//! - Choices keep the order of the requests, with `index` renumbered from 0.
+//! - Usage is the sum of every request's usage, since each one is billed.
+//!
+//! `n` above [`MAX_CHOICES`] is rejected for every provider; see
+//! [`check_choice_count`].
+//!
+//! Streamed requests cannot be merged this way, so `n` above 1 in a stream to
+//! such a provider fails with [`ProviderError::Unsupported`]; see
+//! [`check_stream_support`].
+
+use crate::error::{ClientError, ProviderError};
+use crate::models::{ChatRequest, ChatResponse, Usage};
+use crate::providers::{Capability, Provider};
+use futures::{StreamExt, TryStreamExt};
+
+/// Emulated completions requested from a provider at the same time.
+pub const MAX_CONCURRENT_CHOICES: usize = 4;
+
+/// Most choices a single request may ask for with `n`.
+pub const MAX_CHOICES: u32 = 128;
+
+/// Chat completion with `request.n` choices from `provider`, emulating `n`
+/// for providers that do not accept it.
+pub async fn chat_completion(
+    provider: &dyn Provider,
+    mut request: ChatRequest,
+) -> Result<ChatResponse, ProviderError> {
+    if provider.supports_multiple_choices() {
+        return provider.chat_completion(request).await;
+    }
+    let n = request.n.take().unwrap_or(1);
+    if n <= 1 {
+        return provider.chat_completion(request).await;
+    }
+
+    let responses: Vec<ChatResponse> = futures::stream::iter(0..n)
+        .map(|_| provider.chat_completion(request.clone()))
+        .buffered(MAX_CONCURRENT_CHOICES)
+        .try_collect()
+        .await?;
+    Ok(merge_responses(responses))
+}
+
+/// Reject `n` above [`MAX_CHOICES`], which would otherwise be sent as that
+/// many requests to a provider without `n`.
+pub fn check_choice_count(request: &ChatRequest) -> Result<(), ClientError> {
+    match request.n {
+        Some(n) if n > MAX_CHOICES => Err(ClientError::InvalidRequest {
+            message: format!("n ({n}) exceeds the maximum of {MAX_CHOICES} choices"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Reject `n` above 1 in a streamed request to a provider without `n`.
+pub fn check_stream_support(
+    provider: &dyn Provider,
+    request: &ChatRequest,
+) -> Result<(), ProviderError> {
+    if request.n.unwrap_or(1) > 1 && !provider.supports_multiple_choices() {
+        return Err(ProviderError::unsupported(
+            provider.name(),
+            Capability::MultipleChoices,
+        ));
+    }
+    Ok(())
+}
+
+/// One response holding the choices of every response, with summed usage.
+/// `responses` must not be empty.
+fn merge_responses(responses: Vec<ChatResponse>) -> ChatResponse {
+    let mut responses = responses.into_iter();
+    let mut merged = responses.next().expect("at least one response to merge");
+    for response in responses {
+        merged.choices.extend(response.choices);
+        merged.usage = match (merged.usage, response.usage) {
+            (Some(total), Some(usage)) => Some(Usage {
+                prompt_tokens: total.prompt_tokens + usage.prompt_tokens,
+                completion_tokens: total.completion_tokens + usage.completion_tokens,
+                total_tokens: total.total_tokens + usage.total_tokens,
+            }),
+            (total, usage) => total.or(usage),
+        };
+    }
+    for (index, choice) in merged.choices.iter_mut().enumerate() {
+        choice.index = index as u32;
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use crate::providers::anthropic::AnthropicProvider;
+    use crate::providers::ProviderConfig;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_multiple_choices_emulated_with_summed_usage() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "candidate"}],
+                "model": "claude-3-5-haiku-20241022",
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 10, "output_tokens": 4}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new(
+            ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![Message::user("Hi")],
+            n: Some(3),
+            ..Default::default()
+        };
+        let response = chat_completion(&provider, request).await.unwrap();
+
+        let indexes: Vec<u32> = response.choices.iter().map(|c| c.index).collect();
+        assert_eq!(indexes, vec![0, 1, 2]);
+        let usage = response.usage.unwrap();
+        assert_eq!(
+            (
+                usage.prompt_tokens,
+                usage.completion_tokens,
+                usage.total_tokens
+            ),
+            (30, 12, 42)
+        );
+
+        let received = server.received_requests().await.unwrap();
+        assert_eq!(received.len(), 3);
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert!(body.get("n").is_none());
+    }
+}
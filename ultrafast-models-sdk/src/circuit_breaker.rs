@@ -39,6 +39,8 @@
 //! - **Recovery Timeout**: Time to wait before testing recovery
 //! - **Request Timeout**: Maximum time to wait for individual requests
 //! - **Half-Open Max Calls**: Number of test requests in half-open state
+//! - **Latency Threshold**: p95 latency over the latency window that opens
+//!   the circuit even when calls succeed
 //!
 //! ## Usage Examples
 //!
@@ -89,9 +91,10 @@
 //! println!("Failure count: {}", metrics.failure_count);
 //! println!("Success count: {}", metrics.success_count);
 //!
-//! // Force circuit breaker states (for testing)
-//! circuit_breaker.force_open().await;
+//! // Force circuit breaker states (for testing or incident response)
+//! circuit_breaker.force_open().await; // stays open until closed or reset
 //! circuit_breaker.force_closed().await;
+//! circuit_breaker.reset().await;
 //! ```
 //!
 //! ### Integration with Provider System
@@ -129,6 +132,7 @@
 //! - **Fast State Transitions**: State changes are atomic and fast
 
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
@@ -166,6 +170,7 @@ pub enum CircuitState {
 ///
 /// This struct defines the parameters that control how the circuit breaker
 /// detects failures, manages state transitions, and handles recovery.
+/// Fields missing from a serialized config take their default values.
 ///
 /// # Examples
 ///
@@ -178,9 +183,12 @@ pub enum CircuitState {
 ///     recovery_timeout: Duration::from_secs(30),
 ///     request_timeout: Duration::from_secs(10),
 ///     half_open_max_calls: 3,
+///     latency_threshold_ms: Some(5_000),
+///     latency_window: 20,
 /// };
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CircuitBreakerConfig {
     /// Number of consecutive failures before opening the circuit
     pub failure_threshold: u32,
@@ -192,6 +200,14 @@ pub struct CircuitBreakerConfig {
     pub request_timeout: Duration,
     /// Maximum number of test calls allowed in half-open state
     pub half_open_max_calls: u32,
+    /// p95 latency of successful calls above which the circuit opens
+    pub latency_threshold_ms: Option<u64>,
+    /// Number of recent successful calls the p95 latency is computed over
+    pub latency_window: usize,
+}
+
+fn default_latency_window() -> usize {
+    20
 }
 
 impl Default for CircuitBreakerConfig {
@@ -201,6 +217,8 @@ impl Default for CircuitBreakerConfig {
             recovery_timeout: Duration::from_secs(60),
             request_timeout: Duration::from_secs(30),
             half_open_max_calls: 3,
+            latency_threshold_ms: None,
+            latency_window: default_latency_window(),
         }
     }
 }
@@ -223,6 +241,10 @@ struct CircuitBreakerState {
     last_success_time: Option<Instant>,
     /// Number of calls made in half-open state
     half_open_calls: u32,
+    /// Latencies of the most recent successful calls
+    latencies: VecDeque<Duration>,
+    /// Opened with [`CircuitBreaker::force_open`], so not recovering on its own
+    manually_opened: bool,
 }
 
 impl Default for CircuitBreakerState {
@@ -234,6 +256,8 @@ impl Default for CircuitBreakerState {
             last_failure_time: None,
             last_success_time: None,
             half_open_calls: 0,
+            latencies: VecDeque::new(),
+            manually_opened: false,
         }
     }
 }
@@ -337,13 +361,13 @@ impl CircuitBreaker {
         }
 
         // Execute the operation with timeout
-        let _start_time = Instant::now();
+        let start_time = Instant::now();
         let result = tokio::time::timeout(self.config.request_timeout, operation()).await;
 
         match result {
             Ok(Ok(success_result)) => {
                 // Operation succeeded - update circuit breaker state
-                self.on_success().await;
+                self.on_success(start_time.elapsed()).await;
                 Ok(success_result)
             }
             Ok(Err(_)) => {
@@ -371,6 +395,7 @@ impl CircuitBreaker {
                 // Normal operation - always allow
                 true
             }
+            CircuitState::Open if state.manually_opened => false,
             CircuitState::Open => {
                 // Check if recovery timeout has elapsed
                 if let Some(last_failure) = state.last_failure_time {
@@ -405,8 +430,9 @@ impl CircuitBreaker {
     /// Handle successful operation completion.
     ///
     /// Updates the circuit breaker state when an operation succeeds,
-    /// potentially transitioning from half-open to closed state.
-    async fn on_success(&self) {
+    /// potentially transitioning from half-open to closed state, or to open
+    /// state when the call was slower than the latency threshold allows.
+    async fn on_success(&self, latency: Duration) {
         let mut state = self.state.write().await;
 
         state.success_count += 1;
@@ -416,6 +442,28 @@ impl CircuitBreaker {
             CircuitState::Closed => {
                 // Reset failure count on success
                 state.failure_count = 0;
+
+                if let Some(p95) = self.record_latency(&mut state, latency) {
+                    state.state = CircuitState::Open;
+                    state.last_failure_time = Some(Instant::now());
+                    state.latencies.clear();
+                    tracing::warn!(
+                        "Circuit breaker {} transitioning to OPEN with p95 latency {}ms",
+                        self.name,
+                        p95.as_millis()
+                    );
+                }
+            }
+            CircuitState::HalfOpen if self.exceeds_latency_threshold(latency) => {
+                // Slow test call - the provider has not recovered yet
+                state.state = CircuitState::Open;
+                state.last_failure_time = Some(Instant::now());
+                state.half_open_calls = 0;
+                tracing::warn!(
+                    "Circuit breaker {} transitioning back to OPEN after {}ms test call",
+                    self.name,
+                    latency.as_millis()
+                );
             }
             CircuitState::HalfOpen => {
                 // Success in half-open state - transition to closed
@@ -434,6 +482,35 @@ impl CircuitBreaker {
         }
     }
 
+    /// Record a successful call's latency, returning the window's p95
+    /// latency if a full window exceeds the latency threshold.
+    fn record_latency(
+        &self,
+        state: &mut CircuitBreakerState,
+        latency: Duration,
+    ) -> Option<Duration> {
+        self.config.latency_threshold_ms?;
+
+        state.latencies.push_back(latency);
+        while state.latencies.len() > self.config.latency_window {
+            state.latencies.pop_front();
+        }
+        if state.latencies.len() < self.config.latency_window {
+            return None;
+        }
+
+        let mut sorted: Vec<Duration> = state.latencies.iter().copied().collect();
+        sorted.sort();
+        let p95 = sorted[(sorted.len() * 95).div_ceil(100) - 1];
+        self.exceeds_latency_threshold(p95).then_some(p95)
+    }
+
+    fn exceeds_latency_threshold(&self, latency: Duration) -> bool {
+        self.config
+            .latency_threshold_ms
+            .is_some_and(|threshold| latency > Duration::from_millis(threshold))
+    }
+
     /// Handle operation failure.
     ///
     /// Updates the circuit breaker state when an operation fails,
@@ -497,28 +574,40 @@ impl CircuitBreaker {
     /// Force the circuit breaker to open state.
     ///
     /// This method manually opens the circuit breaker, useful for testing
-    /// or emergency situations.
+    /// or emergency situations. Unlike a circuit opened by failures, it does
+    /// not move to half-open after the recovery timeout: it stays open until
+    /// [`force_closed`](Self::force_closed) or [`reset`](Self::reset).
     pub async fn force_open(&self) {
         let mut state = self.state.write().await;
         state.state = CircuitState::Open;
+        state.manually_opened = true;
+        state.half_open_calls = 0;
         state.last_failure_time = Some(Instant::now());
         tracing::info!("Circuit breaker {} manually forced to OPEN", self.name);
     }
 
     /// Force the circuit breaker to closed state.
     ///
-    /// This method manually closes the circuit breaker, useful for testing
-    /// or when you want to reset the circuit breaker state.
+    /// This method manually closes the circuit breaker and clears the
+    /// failures and latencies that would open it again, useful for testing
+    /// whether a provider has recovered. The success count and the last
+    /// success and failure times are kept.
     pub async fn force_closed(&self) {
         let mut state = self.state.write().await;
         state.state = CircuitState::Closed;
+        state.manually_opened = false;
         state.failure_count = 0;
-        state.success_count = 0;
         state.half_open_calls = 0;
-        state.last_failure_time = None;
-        state.last_success_time = None;
+        state.latencies.clear();
         tracing::info!("Circuit breaker {} manually forced to CLOSED", self.name);
     }
+
+    /// Reset the circuit breaker to the state of a new one: closed, with
+    /// all counters and timestamps cleared.
+    pub async fn reset(&self) {
+        *self.state.write().await = CircuitBreakerState::default();
+        tracing::info!("Circuit breaker {} manually reset", self.name);
+    }
 }
 
 /// Metrics and statistics for circuit breaker performance.
@@ -554,6 +643,7 @@ mod tests {
             recovery_timeout: Duration::from_secs(1),
             request_timeout: Duration::from_secs(1),
             half_open_max_calls: 2,
+            ..Default::default()
         };
 
         let circuit_breaker = CircuitBreaker::new("test".to_string(), config);
@@ -577,6 +667,7 @@ mod tests {
             recovery_timeout: Duration::from_secs(1),
             request_timeout: Duration::from_secs(1),
             half_open_max_calls: 2,
+            ..Default::default()
         };
 
         let circuit_breaker = CircuitBreaker::new("test".to_string(), config);
@@ -604,6 +695,7 @@ mod tests {
             recovery_timeout: Duration::from_millis(100), // Short timeout for testing
             request_timeout: Duration::from_secs(1),
             half_open_max_calls: 2,
+            ..Default::default()
         };
 
         let circuit_breaker = CircuitBreaker::new("test".to_string(), config);
@@ -634,6 +726,7 @@ mod tests {
             recovery_timeout: Duration::from_secs(1),
             request_timeout: Duration::from_millis(50), // Short timeout
             half_open_max_calls: 2,
+            ..Default::default()
         };
 
         let circuit_breaker = CircuitBreaker::new("test".to_string(), config);
@@ -648,4 +741,87 @@ mod tests {
 
         assert!(matches!(result, Err(CircuitBreakerError::Timeout)));
     }
+
+    /// Test circuit breaker opening on slow but successful operations
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_on_high_latency() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 3,
+            recovery_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_secs(1),
+            half_open_max_calls: 2,
+            latency_threshold_ms: Some(20),
+            latency_window: 4,
+        };
+
+        let circuit_breaker = CircuitBreaker::new("test".to_string(), config);
+
+        for _ in 0..3 {
+            let result = circuit_breaker
+                .call(|| async {
+                    tokio::time::sleep(Duration::from_millis(40)).await;
+                    Ok::<String, std::io::Error>("slow".to_string())
+                })
+                .await;
+            assert!(result.is_ok());
+            // Not enough samples yet to judge the p95 latency
+            assert_eq!(circuit_breaker.get_state().await, CircuitState::Closed);
+        }
+
+        let result = circuit_breaker
+            .call(|| async {
+                tokio::time::sleep(Duration::from_millis(40)).await;
+                Ok::<String, std::io::Error>("slow".to_string())
+            })
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(circuit_breaker.get_state().await, CircuitState::Open);
+
+        let metrics = circuit_breaker.get_metrics().await;
+        assert_eq!(metrics.failure_count, 0);
+
+        let result = circuit_breaker
+            .call(|| async { Ok::<String, std::io::Error>("fast".to_string()) })
+            .await;
+        assert!(matches!(result, Err(CircuitBreakerError::Open)));
+    }
+
+    /// Test a manually opened circuit staying open until closed or reset
+    #[tokio::test]
+    async fn test_forced_open_does_not_recover() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 1,
+            recovery_timeout: Duration::from_millis(20),
+            request_timeout: Duration::from_secs(1),
+            half_open_max_calls: 1,
+            ..Default::default()
+        };
+        let circuit_breaker = CircuitBreaker::new("test".to_string(), config);
+        let succeed = || async { Ok::<String, std::io::Error>("success".to_string()) };
+
+        circuit_breaker.force_open().await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let result = circuit_breaker.call(succeed).await;
+        assert!(matches!(result, Err(CircuitBreakerError::Open)));
+        assert_eq!(circuit_breaker.get_state().await, CircuitState::Open);
+
+        circuit_breaker.force_closed().await;
+        assert!(circuit_breaker.call(succeed).await.is_ok());
+        assert_eq!(circuit_breaker.get_metrics().await.success_count, 1);
+
+        // A circuit opened by failures still recovers after a close
+        let _ = circuit_breaker
+            .call(|| async { Err::<String, std::io::Error>(std::io::Error::other("failure")) })
+            .await;
+        assert_eq!(circuit_breaker.get_state().await, CircuitState::Open);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(circuit_breaker.call(succeed).await.is_ok());
+        assert_eq!(circuit_breaker.get_state().await, CircuitState::Closed);
+
+        circuit_breaker.reset().await;
+        let metrics = circuit_breaker.get_metrics().await;
+        assert_eq!(metrics.state, CircuitState::Closed);
+        assert_eq!(metrics.success_count, 0);
+        assert!(metrics.last_failure_time.is_none());
+    }
 }
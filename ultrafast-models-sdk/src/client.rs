@@ -338,14 +338,21 @@
 use crate::cache::{Cache, CacheConfig, CacheKeyBuilder, InMemoryCache};
 use crate::error::ClientError;
 use crate::models::{
-    AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingInput, EmbeddingRequest,
+    EmbeddingResponse, ImageRequest, ImageResponse, Message, SpeechRequest, SpeechResponse,
+    StreamChunk,
 };
 use crate::providers::{
-    create_provider_with_circuit_breaker, Provider, ProviderConfig, ProviderMetrics,
+    create_provider_with_circuit_breaker, fairness_provider::FairnessProvider,
+    keepalive_provider::KeepaliveProvider, HealthStatus, Provider, ProviderConfig, ProviderHealth,
+    ProviderMetrics, StreamResult,
 };
-use crate::routing::{Router, RoutingContext, RoutingStrategy};
+use crate::routing::{
+    AutoTune, ErrorBudget, ProviderModels, Router, RoutingContext, RoutingStrategy,
+};
+use crate::tools::{ToolExecutor, ToolLoopConfig, MAX_TOOL_ROUNDS_FINISH_REASON};
 use futures::{Stream, StreamExt};
+use rand::Rng;
 use reqwest::Client;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -545,10 +552,35 @@ pub struct UltrafastClient {
     timeout: Duration,
     /// Retry policy configuration
     retry_policy: RetryPolicy,
+    /// Configuration of each provider (retries, timeouts)
+    provider_configs: HashMap<String, ProviderConfig>,
     /// Connection pool for HTTP connections
     connection_pool: Arc<RwLock<ConnectionPool>>,
     /// Last used provider for metrics
     last_used_provider: Arc<RwLock<Option<String>>>,
+    /// How [`UltrafastClient::stream_embeddings`] splits large requests
+    embedding_batch: EmbeddingBatchConfig,
+    /// Limits on [`UltrafastClient::chat_completion_with_tools`]
+    tool_loop: ToolLoopConfig,
+    /// Models tried in order on the same provider when a model fails
+    model_alternates: HashMap<String, Vec<String>>,
+}
+
+/// Backoff before retry number `retry` (0 for the first): `base` grown by
+/// `multiplier` per retry and capped at `max_delay`, with the top
+/// `jitter_factor` share of it drawn at random.
+fn backoff_delay(
+    base: Duration,
+    multiplier: f64,
+    max_delay: Duration,
+    jitter_factor: f64,
+    retry: u32,
+) -> Duration {
+    let backoff = (base.as_secs_f64() * multiplier.powi(retry as i32))
+        .min(max_delay.as_secs_f64())
+        .max(0.0);
+    let floor = backoff * (1.0 - jitter_factor.clamp(0.0, 1.0));
+    Duration::from_secs_f64(rand::thread_rng().gen_range(floor..=backoff))
 }
 
 /// Retry policy configuration.
@@ -556,6 +588,13 @@ pub struct UltrafastClient {
 /// Defines how the client should retry failed requests, including backoff
 /// strategies and jitter to prevent thundering herd problems.
 ///
+/// Only transient failures are retried: connection errors and timeouts,
+/// 5xx responses and 429s. Retries after a 429 wait for the provider's
+/// `Retry-After`; others back off exponentially, drawing each delay at random
+/// from the top `jitter_factor` share of the backoff. A provider's own
+/// `max_retries`, `retry_delay`, `retry_backoff_multiplier` and
+/// `retry_max_delay`, where set, take precedence over this policy.
+///
 /// # Example
 ///
 /// ```rust
@@ -564,7 +603,7 @@ pub struct UltrafastClient {
 ///     initial_delay: Duration::from_millis(100),
 ///     max_delay: Duration::from_secs(10),
 ///     backoff_multiplier: 2.0,
-///     jitter_factor: 0.1,
+///     jitter_factor: 1.0,
 /// };
 /// ```
 #[derive(Debug, Clone)]
@@ -577,7 +616,8 @@ pub struct RetryPolicy {
     pub max_delay: Duration,
     /// Multiplier for exponential backoff
     pub backoff_multiplier: f64,
-    /// Jitter factor to prevent thundering herd
+    /// Share of each backoff that is randomized, from `0.0` (none) to `1.0`
+    /// (full jitter: anywhere between zero and the backoff)
     pub jitter_factor: f64,
 }
 
@@ -588,7 +628,32 @@ impl Default for RetryPolicy {
             initial_delay: Duration::from_millis(100),
             max_delay: Duration::from_secs(10),
             backoff_multiplier: 2.0,
-            jitter_factor: 0.1, // 10% jitter
+            jitter_factor: 1.0,
+        }
+    }
+}
+
+/// How [`UltrafastClient::stream_embeddings`] splits large embedding requests.
+///
+/// ```toml
+/// [embeddings]
+/// batch_size = 512
+/// max_concurrent_batches = 4
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct EmbeddingBatchConfig {
+    /// Inputs sent to the provider per request
+    pub batch_size: usize,
+    /// Batches in flight at once
+    pub max_concurrent_batches: usize,
+}
+
+impl Default for EmbeddingBatchConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 512,
+            max_concurrent_batches: 4,
         }
     }
 }
@@ -740,9 +805,21 @@ impl UltrafastClient {
 
     // Enhanced chat completion with better error handling
     pub async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ClientError> {
+        let (response, _) = self.chat_completion_with_attempts(request).await?;
+        Ok(response)
+    }
+
+    /// Run a chat completion, also returning the number of providers tried.
+    ///
+    /// More than 1 when rate-limited providers were failed over, and 0 for a
+    /// response served from the cache.
+    pub async fn chat_completion_with_attempts(
+        &self,
+        request: ChatRequest,
+    ) -> Result<(ChatResponse, u32), ClientError> {
         match &self.mode {
             ClientMode::Standalone => self.standalone_chat_completion(request).await,
-            ClientMode::Gateway { .. } => self.gateway_chat_completion(request).await,
+            ClientMode::Gateway { .. } => Ok((self.gateway_chat_completion(request).await?, 1)),
         }
     }
 
@@ -752,24 +829,304 @@ impl UltrafastClient {
         request: ChatRequest,
     ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, ClientError>> + Send + Unpin>, ClientError>
     {
+        let (stream, _) = self
+            .stream_chat_completion_with_provider_id(request)
+            .await?;
+        Ok(stream)
+    }
+
+    /// Start a chat completion stream, also returning the provider routing
+    /// picked to serve it.
+    ///
+    /// Unlike [`UltrafastClient::get_last_used_provider`], the provider is
+    /// that of this stream even when other requests run concurrently. `None`
+    /// in gateway mode, where the remote gateway does the routing.
+    pub async fn stream_chat_completion_with_provider_id(
+        &self,
+        request: ChatRequest,
+    ) -> Result<
+        (
+            Box<dyn Stream<Item = Result<StreamChunk, ClientError>> + Send + Unpin>,
+            Option<String>,
+        ),
+        ClientError,
+    > {
         match &self.mode {
             ClientMode::Standalone => {
-                let stream = self.standalone_stream_chat_completion(request).await?;
-                Ok(stream)
+                let (stream, provider_id) = self
+                    .standalone_stream_chat_completion(request, None)
+                    .await?;
+                Ok((stream, Some(provider_id)))
             }
             ClientMode::Gateway { .. } => {
                 let stream = self.gateway_stream_chat_completion(request).await?;
-                Ok(stream)
+                Ok((stream, None))
             }
         }
     }
 
+    /// Run a chat completion as a tool loop: the tool calls the model asks
+    /// for are run with `executor` and their outputs sent back until the model
+    /// answers without calling tools.
+    ///
+    /// After `max_rounds` round-trips (see [`ToolLoopConfig`]) the loop stops
+    /// and the last assistant message is returned with `finish_reason` set to
+    /// [`MAX_TOOL_ROUNDS_FINISH_REASON`], its tool calls left unanswered.
+    pub async fn chat_completion_with_tools(
+        &self,
+        mut request: ChatRequest,
+        executor: &dyn ToolExecutor,
+    ) -> Result<ChatResponse, ClientError> {
+        let mut rounds = 0;
+        loop {
+            let mut response = self.chat_completion(request.clone()).await?;
+            let Some(choice) = response.choices.first_mut() else {
+                return Ok(response);
+            };
+            let calls = match &choice.message.tool_calls {
+                Some(calls) if !calls.is_empty() => calls.clone(),
+                _ => return Ok(response),
+            };
+            if rounds == self.tool_loop.max_rounds {
+                tracing::warn!(
+                    "Tool loop for {} stopped after {} rounds",
+                    request.model,
+                    rounds
+                );
+                choice.finish_reason = Some(MAX_TOOL_ROUNDS_FINISH_REASON.to_string());
+                return Ok(response);
+            }
+            rounds += 1;
+
+            request.messages.push(choice.message.clone());
+            for call in &calls {
+                let output = executor.execute(call).await?;
+                request
+                    .messages
+                    .push(Message::tool(call.id.clone(), output));
+            }
+        }
+    }
+
+    /// Send a chat completion straight to `provider_id`, bypassing the routing
+    /// strategy, fallback and response cache. Standalone mode only.
+    pub async fn chat_completion_with_provider(
+        &self,
+        provider_id: &str,
+        request: ChatRequest,
+    ) -> Result<ChatResponse, ClientError> {
+        let provider = self.pinned_provider(provider_id).await?;
+        let response_format = request.response_format.clone();
+        let request = self.prepare_chat_request(provider_id, request)?;
+        let start = Instant::now();
+        let result = self
+            .execute_with_enhanced_retry(
+                || async {
+                    let response =
+                        crate::choices::chat_completion(provider.as_ref(), request.clone()).await?;
+                    crate::response_format::check_fallback_response(
+                        provider.as_ref(),
+                        response_format.as_ref(),
+                        &response,
+                    )?;
+                    Ok(response)
+                },
+                provider_id,
+                true,
+            )
+            .await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        self.update_enhanced_metrics(
+            provider_id,
+            result.is_ok(),
+            latency_ms,
+            self.estimate_tokens(&request),
+            0.0,
+        )
+        .await;
+        self.record_provider_outcome(provider_id, result.is_ok(), latency_ms)
+            .await;
+
+        Ok(result?)
+    }
+
+    /// Streaming counterpart of [`UltrafastClient::chat_completion_with_provider`].
+    pub async fn stream_chat_completion_with_provider(
+        &self,
+        provider_id: &str,
+        request: ChatRequest,
+    ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, ClientError>> + Send + Unpin>, ClientError>
+    {
+        self.pinned_provider(provider_id).await?;
+        let (stream, _) = self
+            .standalone_stream_chat_completion(request, Some(provider_id))
+            .await?;
+        Ok(stream)
+    }
+
+    /// Embedding counterpart of [`UltrafastClient::chat_completion_with_provider`].
+    pub async fn embedding_with_provider(
+        &self,
+        provider_id: &str,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, ClientError> {
+        let provider = self.pinned_provider(provider_id).await?;
+        let start = Instant::now();
+        let result = self
+            .execute_with_enhanced_retry(|| provider.embedding(request.clone()), provider_id, true)
+            .await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        self.update_enhanced_metrics(provider_id, result.is_ok(), latency_ms, 0, 0.0)
+            .await;
+        self.record_provider_outcome(provider_id, result.is_ok(), latency_ms)
+            .await;
+
+        Ok(result?)
+    }
+
+    /// Image generation counterpart of
+    /// [`UltrafastClient::chat_completion_with_provider`].
+    pub async fn image_generation_with_provider(
+        &self,
+        provider_id: &str,
+        request: ImageRequest,
+    ) -> Result<ImageResponse, ClientError> {
+        let provider = self.pinned_provider(provider_id).await?;
+        let start = Instant::now();
+        let result = self
+            .execute_with_enhanced_retry(
+                || provider.image_generation(request.clone()),
+                provider_id,
+                true,
+            )
+            .await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        self.update_enhanced_metrics(provider_id, result.is_ok(), latency_ms, 0, 0.0)
+            .await;
+        self.record_provider_outcome(provider_id, result.is_ok(), latency_ms)
+            .await;
+
+        Ok(result?)
+    }
+
+    // Look up a provider pinned by the caller and track it as the last used one
+    async fn pinned_provider(&self, provider_id: &str) -> Result<Arc<dyn Provider>, ClientError> {
+        if !matches!(self.mode, ClientMode::Standalone) {
+            return Err(ClientError::Configuration {
+                message: "Pinning a provider requires standalone mode".to_string(),
+            });
+        }
+
+        let provider =
+            self.providers
+                .get(provider_id)
+                .cloned()
+                .ok_or_else(|| ClientError::Configuration {
+                    message: format!("Provider {provider_id} not found"),
+                })?;
+
+        *self.last_used_provider.write().await = Some(provider_id.to_string());
+        Ok(provider)
+    }
+
+    /// Put a provider into slow-start after it was added or re-enabled.
+    ///
+    /// No-op unless the client was built with `with_slow_start`.
+    pub async fn mark_provider_activated(&self, provider_id: &str) {
+        self.router
+            .write()
+            .await
+            .mark_provider_activated(provider_id);
+    }
+
+    /// Fraction (0.0 to 1.0) of its normal traffic a provider currently
+    /// accepts; below 1.0 while it is in slow-start.
+    pub async fn slow_start_factor(&self, provider_id: &str) -> f64 {
+        self.router.read().await.slow_start_factor(provider_id)
+    }
+
+    // Feed the request outcome to the error budget and weight auto-tuning,
+    // and put providers whose circuit closed again into slow-start
+    async fn record_provider_outcome(&self, provider_id: &str, success: bool, latency_ms: u64) {
+        let circuit_state = match self.provider_circuit_breaker(provider_id) {
+            Some(circuit_breaker) => Some(circuit_breaker.get_state().await),
+            None => None,
+        };
+        let mut router = self.router.write().await;
+        router.record_outcome(provider_id, success, latency_ms);
+        if let Some(state) = circuit_state {
+            router.record_circuit_state(provider_id, state);
+        }
+    }
+
     // Get the last used provider for metrics
     pub async fn get_last_used_provider(&self) -> Option<String> {
         let provider = self.last_used_provider.read().await;
         provider.clone()
     }
 
+    /// Actively probe whether a provider is reachable.
+    ///
+    /// Issues the provider's lightweight probe request (`GET /models` for
+    /// OpenAI) bounded by its configured `total_timeout`. The probe bypasses the
+    /// circuit breaker, so its outcome is never counted against the provider.
+    /// Failures are reported as an unhealthy [`ProviderHealth`]; an error is
+    /// returned only for unknown providers.
+    pub async fn probe_provider(&self, provider_id: &str) -> Result<ProviderHealth, ClientError> {
+        let provider =
+            self.providers
+                .get(provider_id)
+                .ok_or_else(|| ClientError::Configuration {
+                    message: format!("Provider {provider_id} not found"),
+                })?;
+        let timeout = self
+            .provider_configs
+            .get(provider_id)
+            .map(|config| config.total_timeout)
+            .unwrap_or(self.timeout);
+
+        let start = Instant::now();
+        let error = match tokio::time::timeout(timeout, provider.probe()).await {
+            Ok(Ok(health)) => return Ok(health),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("probe timed out after {timeout:?}"),
+        };
+        Ok(ProviderHealth {
+            status: HealthStatus::Unhealthy,
+            latency_ms: Some(start.elapsed().as_millis() as u64),
+            error_rate: 1.0,
+            last_check: chrono::Utc::now(),
+            details: HashMap::from([("error".to_string(), error)]),
+        })
+    }
+
+    /// List the model IDs a provider currently serves, bounded by its
+    /// configured `total_timeout`.
+    pub async fn list_provider_models(
+        &self,
+        provider_id: &str,
+    ) -> Result<Vec<String>, ClientError> {
+        let provider =
+            self.providers
+                .get(provider_id)
+                .ok_or_else(|| ClientError::Configuration {
+                    message: format!("Provider {provider_id} not found"),
+                })?;
+        let timeout = self
+            .provider_configs
+            .get(provider_id)
+            .map(|config| config.total_timeout)
+            .unwrap_or(self.timeout);
+
+        match tokio::time::timeout(timeout, provider.list_models()).await {
+            Ok(models) => Ok(models?),
+            Err(_) => Err(ClientError::Provider(crate::error::ProviderError::Timeout)),
+        }
+    }
+
     // Get circuit breaker state for a provider
     pub async fn get_provider_circuit_state(
         &self,
@@ -794,6 +1151,14 @@ impl UltrafastClient {
         }
     }
 
+    /// The circuit breaker guarding `provider_id`, if the provider has one.
+    pub fn provider_circuit_breaker(
+        &self,
+        provider_id: &str,
+    ) -> Option<&crate::circuit_breaker::CircuitBreaker> {
+        self.providers.get(provider_id)?.circuit_breaker()
+    }
+
     // Get circuit breaker metrics for all providers
     pub async fn get_circuit_breaker_metrics(
         &self,
@@ -801,6 +1166,10 @@ impl UltrafastClient {
         let mut metrics = HashMap::new();
 
         for provider_id in self.providers.keys() {
+            if let Some(circuit_breaker) = self.provider_circuit_breaker(provider_id) {
+                metrics.insert(provider_id.clone(), circuit_breaker.get_metrics().await);
+                continue;
+            }
             if let Some(provider) = self.providers.get(provider_id) {
                 // Create basic metrics based on health status
                 let state = match provider.health_check().await {
@@ -847,6 +1216,55 @@ impl UltrafastClient {
         }
     }
 
+    /// Embed a large batch of inputs in chunks, yielding each chunk's
+    /// response as soon as it completes.
+    ///
+    /// `EmbeddingInput::StringArray` inputs are split into batches of
+    /// `batch_size` and sent with at most `max_concurrent_batches` in flight.
+    /// Chunks arrive in completion order, with every embedding's `index`
+    /// pointing at its position in the original input, so
+    /// [`EmbeddingResponse::from_batches`] reassembles them in input order.
+    /// Other inputs are sent as a single request.
+    pub fn stream_embeddings(
+        &self,
+        request: EmbeddingRequest,
+    ) -> impl Stream<Item = Result<EmbeddingResponse, ClientError>> + Send + '_ {
+        let batch_size = self.embedding_batch.batch_size.max(1);
+        let batches: Vec<(usize, EmbeddingRequest)> = match &request.input {
+            EmbeddingInput::StringArray(inputs) => inputs
+                .chunks(batch_size)
+                .enumerate()
+                .map(|(i, chunk)| {
+                    let mut batch = request.clone();
+                    batch.input = EmbeddingInput::StringArray(chunk.to_vec());
+                    (i * batch_size, batch)
+                })
+                .collect(),
+            _ => vec![(0, request)],
+        };
+
+        let semaphore = Arc::new(tokio::sync::Semaphore::new(
+            self.embedding_batch.max_concurrent_batches.max(1),
+        ));
+        batches
+            .into_iter()
+            .map(|(offset, batch)| {
+                let semaphore = semaphore.clone();
+                async move {
+                    let _permit = semaphore
+                        .acquire()
+                        .await
+                        .expect("semaphore is never closed");
+                    let mut response = self.embedding(batch).await?;
+                    for embedding in &mut response.data {
+                        embedding.index += offset as u32;
+                    }
+                    Ok(response)
+                }
+            })
+            .collect::<futures::stream::FuturesUnordered<_>>()
+    }
+
     pub async fn image_generation(
         &self,
         request: ImageRequest,
@@ -878,10 +1296,11 @@ impl UltrafastClient {
     }
 
     // Enhanced standalone mode with connection pooling
+    // Returns the response with the number of providers tried
     async fn standalone_chat_completion(
         &self,
         request: ChatRequest,
-    ) -> Result<ChatResponse, ClientError> {
+    ) -> Result<(ChatResponse, u32), ClientError> {
         let cache_key = if self.cache.is_some() && !request.stream.unwrap_or(false) {
             Some(CacheKeyBuilder::build_chat_key(&request))
         } else {
@@ -893,7 +1312,7 @@ impl UltrafastClient {
             if let Some(cache) = &self.cache {
                 if let Some(cached_response) = cache.get(cache_key) {
                     tracing::debug!("Cache hit for chat completion");
-                    return Ok(cached_response.response);
+                    return Ok((cached_response.response, 0));
                 }
             }
         }
@@ -905,50 +1324,132 @@ impl UltrafastClient {
             user_region: None,
             request_size: serde_json::to_string(&request).unwrap_or_default().len() as u32,
             estimated_tokens: self.estimate_tokens(&request),
-            user_id: None,
-            metadata: HashMap::new(),
+            user_id: request.user.clone(),
+            metadata: request.routing_metadata.clone(),
         };
 
-        let provider_names: Vec<String> = self.providers.keys().cloned().collect();
-        let provider_selection = router
-            .select_provider(&provider_names, &routing_context)
+        let mut provider_names: Vec<String> = self.providers.keys().cloned().collect();
+        provider_names.sort();
+        let routing_span = tracing::info_span!("routing", provider = tracing::field::Empty);
+        let provider_selection = routing_span
+            .in_scope(|| router.select_provider(&provider_names, &routing_context))
             .ok_or_else(|| ClientError::Configuration {
                 message: "No suitable provider found".to_string(),
             })?;
-
-        // Track the last used provider for metrics
-        {
-            let mut last_provider = self.last_used_provider.write().await;
-            *last_provider = Some(provider_selection.provider_id.clone());
+        routing_span.record("provider", provider_selection.provider_id.as_str());
+        let fail_over = !matches!(router.strategy(), RoutingStrategy::Single);
+
+        // On a 429 move to the next provider instead of retrying the same one
+        let mut candidates = vec![provider_selection.provider_id.clone()];
+        if fail_over {
+            candidates.extend(
+                router
+                    .failover_providers(&provider_names, Some(&request.model))
+                    .into_iter()
+                    .filter(|id| *id != provider_selection.provider_id),
+            );
         }
+        drop(router);
 
-        let provider = self
-            .providers
-            .get(&provider_selection.provider_id)
-            .ok_or_else(|| ClientError::Configuration {
-                message: format!("Provider {} not found", provider_selection.provider_id),
-            })?;
+        let mut attempts = 0;
+        let result = loop {
+            let provider_id = &candidates[attempts];
+            attempts += 1;
+            let has_next = attempts < candidates.len();
 
-        // Execute with enhanced retry logic
-        let start = Instant::now();
-        let result = self
-            .execute_with_enhanced_retry(
-                || provider.chat_completion(request.clone()),
-                &provider_selection.provider_id,
+            // Track the last used provider for metrics
+            {
+                let mut last_provider = self.last_used_provider.write().await;
+                *last_provider = Some(provider_id.clone());
+            }
+
+            let provider =
+                self.providers
+                    .get(provider_id)
+                    .ok_or_else(|| ClientError::Configuration {
+                        message: format!("Provider {provider_id} not found"),
+                    })?;
+
+            // A failing model is retried as its alternates on this provider
+            // before failing over
+            let mut alternates = self
+                .model_alternates
+                .get(&request.model)
+                .into_iter()
+                .flatten();
+            let mut model_request = request.clone();
+            let start = Instant::now();
+            let result = loop {
+                let provider_request =
+                    self.prepare_chat_request(provider_id, model_request.clone())?;
+
+                // Execute with enhanced retry logic
+                let result = self
+                    .execute_with_enhanced_retry(
+                        || async {
+                            let response = crate::choices::chat_completion(
+                                provider.as_ref(),
+                                provider_request.clone(),
+                            )
+                            .await?;
+                            crate::response_format::check_fallback_response(
+                                provider.as_ref(),
+                                request.response_format.as_ref(),
+                                &response,
+                            )?;
+                            Ok(response)
+                        },
+                        provider_id,
+                        !has_next,
+                    )
+                    .await;
+
+                match (&result, alternates.next()) {
+                    (Err(e), Some(alternate)) if is_model_failure(e) => {
+                        tracing::warn!(
+                            "Model {} failed on provider {} ({}); retrying with {}",
+                            model_request.model,
+                            provider_id,
+                            e,
+                            alternate
+                        );
+                        model_request.model = alternate.clone();
+                    }
+                    _ => break result,
+                }
+            };
+
+            let latency = start.elapsed();
+
+            // Update metrics
+            self.update_enhanced_metrics(
+                provider_id,
+                result.is_ok(),
+                latency.as_millis() as u64,
+                self.estimate_tokens(&request),
+                0.0, // Cost calculation would be provider-specific
             )
             .await;
 
-        let latency = start.elapsed();
+            self.record_provider_outcome(provider_id, result.is_ok(), latency.as_millis() as u64)
+                .await;
 
-        // Update metrics
-        self.update_enhanced_metrics(
-            &provider_selection.provider_id,
-            result.is_ok(),
-            latency.as_millis() as u64,
-            self.estimate_tokens(&request),
-            0.0, // Cost calculation would be provider-specific
-        )
-        .await;
+            match result {
+                Err(crate::error::ProviderError::RateLimit { retry_after }) if has_next => {
+                    tracing::warn!(
+                        "Provider {} rate limited (retry after {:?}); failing over",
+                        provider_id,
+                        retry_after
+                    );
+                    let retry_after = self.rate_limit_delay(provider_id, retry_after);
+                    self.router
+                        .write()
+                        .await
+                        .record_rate_limit(provider_id, retry_after);
+                }
+                result => break result,
+            }
+        };
 
         // Cache successful response
         if let Ok(response) = &result {
@@ -963,21 +1464,23 @@ impl UltrafastClient {
             }
         }
 
-        Ok(result?)
+        Ok((result?, attempts as u32))
     }
 
     // Enhanced retry logic with exponential backoff and jitter
     async fn execute_with_enhanced_retry<F, Fut, T>(
         &self,
         mut operation: F,
-        _provider_id: &str,
+        provider_id: &str,
+        retry_rate_limits: bool,
     ) -> Result<T, crate::error::ProviderError>
     where
         F: FnMut() -> Fut,
         Fut: std::future::Future<Output = Result<T, crate::error::ProviderError>>,
     {
         let mut attempt = 0;
-        let mut delay = self.retry_policy.initial_delay;
+        let max_retries = self.max_retries_for(provider_id);
+        let (base, multiplier, max_delay) = self.backoff_for(provider_id);
 
         loop {
             match operation().await {
@@ -985,36 +1488,117 @@ impl UltrafastClient {
                 Err(error) => {
                     attempt += 1;
 
-                    if attempt > self.retry_policy.max_retries || !self.should_retry(&error) {
+                    let rate_limited =
+                        matches!(error, crate::error::ProviderError::RateLimit { .. });
+                    if attempt > max_retries
+                        || !self.should_retry(&error)
+                        || (rate_limited && !retry_rate_limits)
+                    {
                         return Err(error);
                     }
 
-                    // Add jitter to prevent thundering herd
-                    let jitter = delay.mul_f64(self.retry_policy.jitter_factor);
-                    let actual_delay = delay + jitter;
-
-                    tokio::time::sleep(actual_delay).await;
-
-                    delay = std::cmp::min(
-                        delay.mul_f64(self.retry_policy.backoff_multiplier),
-                        self.retry_policy.max_delay,
-                    );
+                    // Wait as long as a rate-limiting provider asks, failing
+                    // fast when that is past the backoff cap; otherwise back
+                    // off with jitter to prevent thundering herd
+                    let delay = match error {
+                        crate::error::ProviderError::RateLimit { retry_after } => {
+                            let delay = self.rate_limit_delay(provider_id, retry_after);
+                            if delay > max_delay {
+                                return Err(error);
+                            }
+                            delay
+                        }
+                        _ => backoff_delay(
+                            base,
+                            multiplier,
+                            max_delay,
+                            self.retry_policy.jitter_factor,
+                            attempt - 1,
+                        ),
+                    };
+
+                    tokio::time::sleep(delay).await;
                 }
             }
         }
     }
 
-    // Enhanced error classification
-    fn should_retry(&self, error: &crate::error::ProviderError) -> bool {
-        matches!(
-            error,
-            crate::error::ProviderError::RateLimit
-                | crate::error::ProviderError::ServiceUnavailable
-                | crate::error::ProviderError::NetworkError { .. }
-                | crate::error::ProviderError::Timeout
+    /// Apply the provider's `role_ordering` policy to a chat request, drop
+    /// request metadata the provider does not accept and reject tools it
+    /// cannot call.
+    fn prepare_chat_request(
+        &self,
+        provider_id: &str,
+        mut request: ChatRequest,
+    ) -> Result<ChatRequest, ClientError> {
+        crate::choices::check_choice_count(&request)?;
+        if let Some(provider) = self.providers.get(provider_id) {
+            crate::tools::check_tool_support(provider.as_ref(), &request)?;
+            crate::response_format::apply_fallback(provider.as_ref(), &mut request);
+            if !provider.supports_request_metadata() {
+                request.request_metadata = None;
+            }
+        }
+        if let Some(config) = self.provider_configs.get(provider_id) {
+            request.messages = config
+                .role_ordering
+                .apply(provider_id, request.messages)
+                .map_err(|message| ClientError::InvalidRequest { message })?;
+        }
+        Ok(request)
+    }
+
+    /// Retries allowed for a provider: its own `max_retries` takes precedence
+    /// over the client-wide retry policy. `0` disables retries.
+    fn max_retries_for(&self, provider_id: &str) -> u32 {
+        self.provider_configs
+            .get(provider_id)
+            .map(|config| config.max_retries)
+            .unwrap_or(self.retry_policy.max_retries)
+    }
+
+    /// Base, multiplier and cap of the backoff between retries: each one the
+    /// provider sets takes precedence over the client-wide policy.
+    fn backoff_for(&self, provider_id: &str) -> (Duration, f64, Duration) {
+        let config = self.provider_configs.get(provider_id);
+        (
+            config
+                .and_then(|config| config.retry_delay)
+                .unwrap_or(self.retry_policy.initial_delay),
+            config
+                .and_then(|config| config.retry_backoff_multiplier)
+                .unwrap_or(self.retry_policy.backoff_multiplier),
+            config
+                .and_then(|config| config.retry_max_delay)
+                .unwrap_or(self.retry_policy.max_delay),
         )
     }
 
+    /// How long to back off after a 429: the provider's `Retry-After`, or
+    /// its configured `default_retry_after` when the header is missing.
+    fn rate_limit_delay(&self, provider_id: &str, retry_after: Option<Duration>) -> Duration {
+        retry_after.unwrap_or_else(|| {
+            self.provider_configs
+                .get(provider_id)
+                .map(|config| config.default_retry_after)
+                .unwrap_or(Duration::from_secs(1))
+        })
+    }
+
+    // Transient failures only: a request the provider rejected as invalid
+    // fails the same way when sent again
+    fn should_retry(&self, error: &crate::error::ProviderError) -> bool {
+        match error {
+            crate::error::ProviderError::RateLimit { .. }
+            | crate::error::ProviderError::ServiceUnavailable
+            | crate::error::ProviderError::NetworkError { .. }
+            | crate::error::ProviderError::Timeout => true,
+            crate::error::ProviderError::Api { code, .. } => *code >= 500,
+            crate::error::ProviderError::Http(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
+
     // Enhanced metrics with more detailed tracking
     async fn update_enhanced_metrics(
         &self,
@@ -1041,57 +1625,84 @@ impl UltrafastClient {
 
     // Enhanced token estimation
     fn estimate_tokens(&self, request: &ChatRequest) -> u32 {
-        let mut total_tokens = 0;
-
-        for message in &request.messages {
-            // Rough estimation: 1 token ≈ 4 characters
-            total_tokens += message.content.len() as u32 / 4;
-        }
-
-        // Add buffer for system messages and formatting
-        total_tokens += 50;
-
-        total_tokens
+        crate::tokenizer::estimate_tokens(&request.model, &request.messages)
     }
 
-    // Standalone mode implementation
+    // Standalone mode implementation, returning the stream and the provider
+    // serving it
     async fn standalone_stream_chat_completion(
         &self,
         request: ChatRequest,
-    ) -> Result<Box<dyn Stream<Item = Result<StreamChunk, ClientError>> + Send + Unpin>, ClientError>
-    {
-        let router = self.router.read().await;
-        let context = RoutingContext {
-            model: Some(request.model.clone()),
-            user_region: None,
-            request_size: serde_json::to_string(&request).unwrap_or_default().len() as u32,
-            estimated_tokens: self.estimate_tokens(&request),
-            user_id: request.user.clone(),
-            metadata: HashMap::new(),
+        pinned_provider: Option<&str>,
+    ) -> Result<
+        (
+            Box<dyn Stream<Item = Result<StreamChunk, ClientError>> + Send + Unpin>,
+            String,
+        ),
+        ClientError,
+    > {
+        let provider_id = match pinned_provider {
+            Some(provider_id) => provider_id.to_string(),
+            None => {
+                let router = self.router.read().await;
+                let context = RoutingContext {
+                    model: Some(request.model.clone()),
+                    user_region: None,
+                    request_size: serde_json::to_string(&request).unwrap_or_default().len() as u32,
+                    estimated_tokens: self.estimate_tokens(&request),
+                    user_id: request.user.clone(),
+                    metadata: request.routing_metadata.clone(),
+                };
+
+                let provider_ids: Vec<String> = self.providers.keys().cloned().collect();
+                router
+                    .select_provider(&provider_ids, &context)
+                    .ok_or_else(|| ClientError::Routing {
+                        message: "No providers available".to_string(),
+                    })?
+                    .provider_id
+            }
         };
 
-        let provider_ids: Vec<String> = self.providers.keys().cloned().collect();
-        let selection = router
-            .select_provider(&provider_ids, &context)
+        // Track the last used provider for metrics
+        {
+            let mut last_provider = self.last_used_provider.write().await;
+            *last_provider = Some(provider_id.clone());
+        }
+
+        let provider = self
+            .providers
+            .get(&provider_id)
             .ok_or_else(|| ClientError::Routing {
-                message: "No providers available".to_string(),
+                message: format!("Provider not found: {provider_id}"),
             })?;
 
-        drop(router);
-
-        let provider =
-            self.providers
-                .get(&selection.provider_id)
-                .ok_or_else(|| ClientError::Routing {
-                    message: format!("Provider not found: {}", selection.provider_id),
-                })?;
-
+        crate::choices::check_stream_support(provider.as_ref(), &request)?;
+        let request = self.prepare_chat_request(&provider_id, request)?;
+        // Streams must start within the provider's first byte timeout, after
+        // which only its total timeout applies
+        let first_byte_timeout = self
+            .provider_configs
+            .get(&provider_id)
+            .map(|config| config.first_byte_timeout)
+            .unwrap_or_default();
         let start_time = Instant::now();
-        let stream = provider.stream_chat_completion(request).await?;
+        let stream_result = if first_byte_timeout.is_zero() {
+            provider.stream_chat_completion(request).await
+        } else {
+            first_chunk_within(provider.stream_chat_completion(request), first_byte_timeout).await
+        };
         let latency = start_time.elapsed();
+        self.record_provider_outcome(
+            &provider_id,
+            stream_result.is_ok(),
+            latency.as_millis() as u64,
+        )
+        .await;
+        let stream = stream_result?;
 
         let metrics = self.metrics.clone();
-        let provider_id = selection.provider_id.clone();
+        let serving_provider = provider_id.clone();
 
         let wrapped_stream = stream.map(move |chunk_result| {
             match chunk_result {
@@ -1128,7 +1739,7 @@ impl UltrafastClient {
             }
         });
 
-        Ok(Box::new(wrapped_stream))
+        Ok((Box::new(wrapped_stream), serving_provider))
     }
 
     // Gateway mode implementation
@@ -1185,39 +1796,13 @@ impl UltrafastClient {
             ));
         }
 
-        let stream = response.bytes_stream().map(|chunk_result| {
-            chunk_result
-                .map_err(|e| ClientError::NetworkError {
-                    message: e.to_string(),
-                })
-                .and_then(|chunk| {
-                    // Parse SSE format
-                    let chunk_str = String::from_utf8_lossy(&chunk);
-                    if chunk_str.trim() == "data: [DONE]" {
-                        return Ok(StreamChunk {
-                            id: "".to_string(),
-                            object: "chat.completion.chunk".to_string(),
-                            created: 0,
-                            model: "".to_string(),
-                            choices: vec![],
-                        });
-                    }
-
-                    if let Some(json_str) = chunk_str.strip_prefix("data: ") {
-                        serde_json::from_str::<StreamChunk>(json_str).map_err(|e| {
-                            ClientError::Serialization {
-                                message: e.to_string(),
-                            }
-                        })
-                    } else {
-                        Err(ClientError::Serialization {
-                            message: "Invalid SSE format".to_string(),
-                        })
-                    }
-                })
+        let stream = crate::providers::http_client::sse_data(response, "gateway").map(|data| {
+            serde_json::from_str::<StreamChunk>(&data?).map_err(|e| ClientError::Serialization {
+                message: e.to_string(),
+            })
         });
 
-        Ok(Box::new(stream))
+        Ok(Box::new(Box::pin(stream)))
     }
 
     async fn standalone_embedding(
@@ -1258,7 +1843,7 @@ impl UltrafastClient {
 
         // Execute with retry and fallback
         let result = self
-            .execute_with_enhanced_retry(|| provider.embedding(request.clone()), &provider_id)
+            .execute_with_enhanced_retry(|| provider.embedding(request.clone()), &provider_id, true)
             .await;
 
         match result {
@@ -1348,6 +1933,7 @@ impl UltrafastClient {
             .execute_with_enhanced_retry(
                 || provider.image_generation(request.clone()),
                 &provider_id,
+                true,
             )
             .await;
 
@@ -1434,6 +2020,7 @@ impl UltrafastClient {
             .execute_with_enhanced_retry(
                 || provider.audio_transcription(request.clone()),
                 &provider_id,
+                true,
             )
             .await;
 
@@ -1517,7 +2104,11 @@ impl UltrafastClient {
 
         // Execute with retry and fallback
         let result = self
-            .execute_with_enhanced_retry(|| provider.text_to_speech(request.clone()), &provider_id)
+            .execute_with_enhanced_retry(
+                || provider.text_to_speech(request.clone()),
+                &provider_id,
+                true,
+            )
             .await;
 
         match result {
@@ -1611,7 +2202,7 @@ impl UltrafastClient {
     fn should_fallback(&self, error: &crate::error::ProviderError) -> bool {
         matches!(
             error,
-            crate::error::ProviderError::RateLimit
+            crate::error::ProviderError::RateLimit { .. }
                 | crate::error::ProviderError::ServiceUnavailable
                 | crate::error::ProviderError::Timeout
         )
@@ -1740,6 +2331,36 @@ impl UltrafastClient {
     }
 }
 
+/// Start a stream and wait for its first chunk, failing with
+/// [`crate::error::ProviderError::Timeout`] unless it arrives within `timeout`.
+async fn first_chunk_within(
+    stream: impl std::future::Future<Output = Result<StreamResult, crate::error::ProviderError>>,
+    timeout: Duration,
+) -> Result<StreamResult, crate::error::ProviderError> {
+    let (first, rest) = tokio::time::timeout(timeout, async {
+        let mut stream = stream.await?;
+        Ok::<_, crate::error::ProviderError>((stream.next().await, stream))
+    })
+    .await
+    .map_err(|_| crate::error::ProviderError::Timeout)??;
+    Ok(Box::pin(futures::stream::iter(first).chain(rest)))
+}
+
+/// Whether `error` may be specific to the requested model, so that an
+/// alternate model on the same provider could succeed. Rate limits and
+/// account-wide failures apply to every model of the provider.
+fn is_model_failure(error: &crate::error::ProviderError) -> bool {
+    use crate::error::ProviderError;
+    !matches!(
+        error,
+        ProviderError::RateLimit { .. }
+            | ProviderError::InvalidApiKey
+            | ProviderError::AuthenticationFailed { .. }
+            | ProviderError::QuotaExceeded
+            | ProviderError::Unsupported { .. }
+    )
+}
+
 /// Builder for creating `UltrafastClient` instances with custom configuration.
 ///
 /// The `UltrafastClientBuilder` provides a fluent API for configuring and creating
@@ -1885,6 +2506,13 @@ impl UltrafastClientBuilder {
             routing_strategy: RoutingStrategy::Single,
             cache_config: None,
             retry_policy: self.retry_policy,
+            slow_start: None,
+            error_budget: None,
+            auto_tune: None,
+            routing_debug: false,
+            embedding_batch: EmbeddingBatchConfig::default(),
+            tool_loop: ToolLoopConfig::default(),
+            model_alternates: HashMap::new(),
         }
     }
 
@@ -1894,6 +2522,8 @@ impl UltrafastClientBuilder {
             api_key: None,
             timeout: Duration::from_secs(30),
             retry_policy: self.retry_policy,
+            embedding_batch: EmbeddingBatchConfig::default(),
+            tool_loop: ToolLoopConfig::default(),
         }
     }
 }
@@ -2093,6 +2723,13 @@ pub struct StandaloneClientBuilder {
     routing_strategy: RoutingStrategy,
     cache_config: Option<CacheConfig>,
     retry_policy: RetryPolicy,
+    slow_start: Option<Duration>,
+    error_budget: Option<ErrorBudget>,
+    auto_tune: Option<AutoTune>,
+    routing_debug: bool,
+    embedding_batch: EmbeddingBatchConfig,
+    tool_loop: ToolLoopConfig,
+    model_alternates: HashMap<String, Vec<String>>,
 }
 
 impl Default for StandaloneClientBuilder {
@@ -2102,6 +2739,13 @@ impl Default for StandaloneClientBuilder {
             routing_strategy: RoutingStrategy::Single,
             cache_config: None,
             retry_policy: RetryPolicy::default(),
+            slow_start: None,
+            error_budget: None,
+            auto_tune: None,
+            routing_debug: false,
+            embedding_batch: EmbeddingBatchConfig::default(),
+            tool_loop: ToolLoopConfig::default(),
+            model_alternates: HashMap::new(),
         }
     }
 }
@@ -2197,6 +2841,57 @@ impl StandaloneClientBuilder {
         self
     }
 
+    /// Client-wide retry policy; a provider's `max_retries` overrides its
+    /// retry count.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Ramp traffic to providers activated at runtime over `window`.
+    pub fn with_slow_start(mut self, window: Duration) -> Self {
+        self.slow_start = Some(window);
+        self
+    }
+
+    /// Temporarily disable providers that exceed `budget`.
+    pub fn with_error_budget(mut self, budget: ErrorBudget) -> Self {
+        self.error_budget = Some(budget);
+        self
+    }
+
+    /// Tune load-balance weights from observed latency and error rates.
+    pub fn with_auto_tune(mut self, auto_tune: AutoTune) -> Self {
+        self.auto_tune = Some(auto_tune);
+        self
+    }
+
+    /// Log every routing decision at debug level.
+    pub fn with_routing_debug(mut self, debug: bool) -> Self {
+        self.routing_debug = debug;
+        self
+    }
+
+    /// Batching used by [`UltrafastClient::stream_embeddings`].
+    pub fn with_embedding_batch(mut self, embedding_batch: EmbeddingBatchConfig) -> Self {
+        self.embedding_batch = embedding_batch;
+        self
+    }
+
+    /// Limits used by [`UltrafastClient::chat_completion_with_tools`].
+    pub fn with_tool_loop(mut self, tool_loop: ToolLoopConfig) -> Self {
+        self.tool_loop = tool_loop;
+        self
+    }
+
+    /// Alternate models, in order, retried on the same provider when a chat
+    /// completion for the model fails, before failing over to another
+    /// provider.
+    pub fn with_model_alternates(mut self, alternates: HashMap<String, Vec<String>>) -> Self {
+        self.model_alternates = alternates;
+        self
+    }
+
     pub fn build(self) -> Result<UltrafastClient, ClientError> {
         if self.providers.is_empty() {
             return Err(ClientError::Configuration {
@@ -2205,10 +2900,28 @@ impl StandaloneClientBuilder {
         }
 
         let mut providers = HashMap::new();
+        let mut provider_configs = HashMap::new();
+        let mut provider_models = HashMap::new();
         for (name, config) in self.providers {
-            // Use circuit breaker by default for all providers
-            let provider = create_provider_with_circuit_breaker(config, None)?;
-            providers.insert(name, provider.into());
+            provider_configs.insert(name.clone(), config.clone());
+            let mapped = config.model_mapping.keys().cloned().collect();
+            let circuit_breaker = config.circuit_breaker.clone();
+            let fairness = config.fairness.clone();
+            let stream_keepalive = config.stream_keepalive.clone();
+            let mut provider: Arc<dyn Provider> =
+                create_provider_with_circuit_breaker(config, circuit_breaker)?.into();
+            if let Some(fairness) = fairness {
+                provider = Arc::new(FairnessProvider::new(provider, &fairness));
+            }
+            if let Some(stream_keepalive) = stream_keepalive {
+                provider = Arc::new(KeepaliveProvider::new(provider, stream_keepalive));
+            }
+            let models = ProviderModels {
+                mapped,
+                supported: provider.supported_models().into_iter().collect(),
+            };
+            provider_models.insert(name.clone(), models);
+            providers.insert(name, provider);
         }
 
         let cache = self.cache_config.map(|config| {
@@ -2216,6 +2929,20 @@ impl StandaloneClientBuilder {
             cache
         });
 
+        let mut router = Router::new(self.routing_strategy).with_debug(self.routing_debug);
+        if let Some(window) = self.slow_start {
+            router = router.with_slow_start(window);
+        }
+        if let Some(budget) = self.error_budget {
+            router = router.with_error_budget(budget);
+        }
+        if let Some(auto_tune) = self.auto_tune {
+            router = router.with_auto_tune(auto_tune);
+        }
+        for (name, models) in provider_models {
+            router = router.with_provider_models(name, models);
+        }
+
         // Create optimized HTTP client for standalone mode too
         let http_client = Client::builder()
             .pool_max_idle_per_host(20)
@@ -2228,19 +2955,23 @@ impl StandaloneClientBuilder {
         Ok(UltrafastClient {
             mode: ClientMode::Standalone,
             providers,
-            router: Arc::new(RwLock::new(Router::new(self.routing_strategy))),
+            router: Arc::new(RwLock::new(router)),
             cache,
             metrics: Arc::new(RwLock::new(HashMap::new())),
             http_client,
             api_key: None,
             timeout: Duration::from_secs(30),
             retry_policy: self.retry_policy,
+            provider_configs,
             connection_pool: Arc::new(RwLock::new(ConnectionPool::new(
                 20,
                 Duration::from_secs(60),
                 Duration::from_secs(60),
             ))),
             last_used_provider: Arc::new(RwLock::new(None)),
+            embedding_batch: self.embedding_batch,
+            tool_loop: self.tool_loop,
+            model_alternates: self.model_alternates,
         })
     }
 }
@@ -2250,6 +2981,8 @@ pub struct GatewayClientBuilder {
     api_key: Option<String>,
     timeout: Duration,
     retry_policy: RetryPolicy,
+    embedding_batch: EmbeddingBatchConfig,
+    tool_loop: ToolLoopConfig,
 }
 
 impl GatewayClientBuilder {
@@ -2259,6 +2992,8 @@ impl GatewayClientBuilder {
             api_key: None,
             timeout: Duration::from_secs(30),
             retry_policy: RetryPolicy::default(),
+            embedding_batch: EmbeddingBatchConfig::default(),
+            tool_loop: ToolLoopConfig::default(),
         }
     }
 
@@ -2272,6 +3007,18 @@ impl GatewayClientBuilder {
         self
     }
 
+    /// Batching used by [`UltrafastClient::stream_embeddings`].
+    pub fn with_embedding_batch(mut self, embedding_batch: EmbeddingBatchConfig) -> Self {
+        self.embedding_batch = embedding_batch;
+        self
+    }
+
+    /// Limits used by [`UltrafastClient::chat_completion_with_tools`].
+    pub fn with_tool_loop(mut self, tool_loop: ToolLoopConfig) -> Self {
+        self.tool_loop = tool_loop;
+        self
+    }
+
     pub fn build(self) -> Result<UltrafastClient, ClientError> {
         let http_client = Client::builder()
             .timeout(self.timeout)
@@ -2295,12 +3042,16 @@ impl GatewayClientBuilder {
             api_key: self.api_key,
             timeout: self.timeout,
             retry_policy: self.retry_policy,
+            provider_configs: HashMap::new(),
             connection_pool: Arc::new(RwLock::new(ConnectionPool::new(
                 20,
                 Duration::from_secs(60),
                 Duration::from_secs(60),
             ))),
             last_used_provider: Arc::new(RwLock::new(None)),
+            embedding_batch: self.embedding_batch,
+            tool_loop: self.tool_loop,
+            model_alternates: HashMap::new(),
         })
     }
 }
@@ -2338,4 +3089,585 @@ mod tests {
             assert!(is_healthy);
         }
     }
+
+    async fn rate_limited_attempts(max_retries: u32) -> usize {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key")
+                    .with_base_url(server.uri())
+                    .with_max_retries(max_retries)
+                    .with_default_retry_after(Duration::from_millis(1)),
+            )
+            .with_retry_policy(RetryPolicy {
+                max_retries: 5,
+                initial_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(5),
+                backoff_multiplier: 1.0,
+                jitter_factor: 0.0,
+            })
+            .build()
+            .unwrap();
+
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![crate::models::Message::user("Hi")],
+            ..Default::default()
+        };
+        assert!(client.chat_completion(request).await.is_err());
+        server.received_requests().await.unwrap().len()
+    }
+
+    #[tokio::test]
+    async fn test_provider_max_retries_overrides_retry_policy() {
+        assert_eq!(rate_limited_attempts(0).await, 1);
+        assert_eq!(rate_limited_attempts(2).await, 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_past_max_delay_fails_fast() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "3600"))
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key")
+                    .with_base_url(server.uri())
+                    .with_max_retries(3)
+                    .with_retry_backoff(Duration::from_millis(1), 2.0, Duration::from_secs(1)),
+            )
+            .build()
+            .unwrap();
+
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![crate::models::Message::user("Hi")],
+            ..Default::default()
+        };
+        let start = Instant::now();
+        assert!(client.chat_completion(request).await.is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_default_retry_after_applied_without_header() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hello"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key")
+                    .with_base_url(server.uri())
+                    .with_default_retry_after(Duration::from_millis(300)),
+            )
+            .with_retry_policy(RetryPolicy {
+                max_retries: 3,
+                initial_delay: Duration::from_millis(1),
+                max_delay: Duration::from_secs(1),
+                backoff_multiplier: 1.0,
+                jitter_factor: 0.0,
+            })
+            .build()
+            .unwrap();
+
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![crate::models::Message::user("Hi")],
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let response = client.chat_completion(request).await.unwrap();
+
+        assert_eq!(response.choices[0].message.content, "Hello");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        assert!(
+            start.elapsed() >= Duration::from_millis(300),
+            "retried after {:?}",
+            start.elapsed()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_retried_with_growing_backoff() {
+        use std::sync::Mutex;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        /// Fails the first two requests with a 500, recording when each arrived.
+        struct FailTwice(Arc<Mutex<Vec<Instant>>>);
+
+        impl Respond for FailTwice {
+            fn respond(&self, _: &Request) -> ResponseTemplate {
+                let mut arrivals = self.0.lock().unwrap();
+                arrivals.push(Instant::now());
+                if arrivals.len() <= 2 {
+                    return ResponseTemplate::new(500);
+                }
+                ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "id": "chatcmpl-1",
+                    "object": "chat.completion",
+                    "created": 0,
+                    "model": "gpt-4",
+                    "choices": [{
+                        "index": 0,
+                        "message": {"role": "assistant", "content": "Hello"},
+                        "finish_reason": "stop"
+                    }]
+                }))
+            }
+        }
+
+        let server = MockServer::start().await;
+        let arrivals = Arc::new(Mutex::new(Vec::new()));
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(FailTwice(arrivals.clone()))
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key")
+                    .with_base_url(server.uri())
+                    .with_retry_backoff(Duration::from_millis(100), 3.0, Duration::from_secs(5)),
+            )
+            .with_retry_policy(RetryPolicy {
+                jitter_factor: 0.0,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![crate::models::Message::user("Hi")],
+            ..Default::default()
+        };
+        let response = client.chat_completion(request).await.unwrap();
+        assert_eq!(response.choices[0].message.content, "Hello");
+
+        let arrivals = arrivals.lock().unwrap();
+        assert_eq!(arrivals.len(), 3);
+        let first_backoff = arrivals[1] - arrivals[0];
+        let second_backoff = arrivals[2] - arrivals[1];
+        assert!(
+            first_backoff >= Duration::from_millis(100),
+            "{first_backoff:?}"
+        );
+        assert!(
+            second_backoff >= Duration::from_millis(300),
+            "{second_backoff:?}"
+        );
+        assert!(second_backoff > first_backoff);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_not_retried() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+                "error": {"message": "messages must not be empty"}
+            })))
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key")
+                    .with_base_url(server.uri())
+                    .with_retry_backoff(Duration::from_millis(1), 2.0, Duration::from_millis(5)),
+            )
+            .build()
+            .unwrap();
+
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![crate::models::Message::user("Hi")],
+            ..Default::default()
+        };
+        assert!(client.chat_completion(request).await.is_err());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_backoff_delay_full_jitter_within_capped_backoff() {
+        let base = Duration::from_millis(100);
+        let max_delay = Duration::from_secs(1);
+        for retry in 0..8 {
+            let backoff = (base * 2u32.pow(retry)).min(max_delay);
+            let delay = backoff_delay(base, 2.0, max_delay, 1.0, retry);
+            assert!(delay <= backoff, "retry {retry}: {delay:?} > {backoff:?}");
+            assert_eq!(backoff_delay(base, 2.0, max_delay, 0.0, retry), backoff);
+        }
+    }
+
+    async fn stream_with_first_byte_delay(
+        delay: Duration,
+    ) -> Result<Vec<StreamChunk>, ClientError> {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let chunk = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4",
+            "choices": [{"index": 0, "delta": {"content": "Hi"}, "finish_reason": null}]
+        });
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(format!("data: {chunk}\n\ndata: [DONE]\n\n"))
+                    .set_delay(delay),
+            )
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key")
+                    .with_base_url(server.uri())
+                    .with_first_byte_timeout(Duration::from_millis(200))
+                    .with_timeout(Duration::from_secs(5)),
+            )
+            .build()
+            .unwrap();
+
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![crate::models::Message::user("Hi")],
+            stream: Some(true),
+            ..Default::default()
+        };
+        let stream = client.stream_chat_completion(request).await?;
+        stream.collect::<Vec<_>>().await.into_iter().collect()
+    }
+
+    #[tokio::test]
+    async fn test_first_byte_timeout_cuts_off_slow_stream_start() {
+        let start = Instant::now();
+        let result = stream_with_first_byte_delay(Duration::from_secs(2)).await;
+        assert!(matches!(
+            result,
+            Err(ClientError::Provider(crate::error::ProviderError::Timeout))
+        ));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        let chunks = stream_with_first_byte_delay(Duration::from_millis(20))
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].choices[0].delta.content.as_deref(), Some("Hi"));
+    }
+
+    #[test]
+    fn test_provider_timeout_maps_to_total_timeout() {
+        let config: ProviderConfig = serde_json::from_value(serde_json::json!({
+            "name": "openai",
+            "api_key": "test-key",
+            "base_url": null,
+            "timeout": "45s",
+            "max_retries": 3,
+            "retry_delay": "1s",
+            "rate_limit": null,
+            "model_mapping": {},
+            "headers": {},
+            "enabled": true,
+            "circuit_breaker": null
+        }))
+        .unwrap();
+        assert_eq!(config.total_timeout, Duration::from_secs(45));
+        assert_eq!(config.connect_timeout, Duration::from_secs(10));
+        assert!(config.first_byte_timeout.is_zero());
+    }
+
+    #[test]
+    fn test_unset_provider_backoff_falls_back_to_retry_policy() {
+        let mut config = ProviderConfig::new("openai", "test-key");
+        config.retry_delay = Some(Duration::from_millis(250));
+        let client = UltrafastClient::standalone()
+            .with_provider("openai", config)
+            .with_retry_policy(RetryPolicy {
+                initial_delay: Duration::from_millis(10),
+                backoff_multiplier: 4.0,
+                max_delay: Duration::from_secs(7),
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            client.backoff_for("openai"),
+            (Duration::from_millis(250), 4.0, Duration::from_secs(7))
+        );
+        assert_eq!(
+            client.backoff_for("unknown"),
+            (Duration::from_millis(10), 4.0, Duration::from_secs(7))
+        );
+    }
+
+    fn json_schema_request(model: &str) -> ChatRequest {
+        ChatRequest {
+            model: model.to_string(),
+            messages: vec![crate::models::Message::user("Weather in Paris?")],
+            response_format: Some(crate::models::ResponseFormat::JsonSchema {
+                json_schema: crate::models::JsonSchema {
+                    name: "weather".to_string(),
+                    description: None,
+                    schema: Some(serde_json::json!({
+                        "type": "object",
+                        "properties": {"temperature": {"type": "number"}}
+                    })),
+                    strict: Some(true),
+                },
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_format_forwarded_to_supporting_provider() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "{\"temperature\": 21}"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key").with_base_url(server.uri()),
+            )
+            .build()
+            .unwrap();
+        client
+            .chat_completion(json_schema_request("gpt-4o"))
+            .await
+            .unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["response_format"]["type"], "json_schema");
+        assert_eq!(body["response_format"]["json_schema"]["name"], "weather");
+        assert_eq!(body["response_format"]["json_schema"]["strict"], true);
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_response_format_emulated_for_other_providers() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        async fn anthropic_replying(text: &str) -> (MockServer, UltrafastClient) {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/v1/messages"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "id": "msg_1",
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "text", "text": text}],
+                    "model": "claude-3-5-haiku-20241022",
+                    "stop_reason": "end_turn",
+                    "usage": {"input_tokens": 1, "output_tokens": 1}
+                })))
+                .mount(&server)
+                .await;
+            let client = UltrafastClient::standalone()
+                .with_provider(
+                    "anthropic",
+                    ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+                )
+                .build()
+                .unwrap();
+            (server, client)
+        }
+
+        let (server, client) = anthropic_replying("{\"temperature\": 21}").await;
+        let response = client
+            .chat_completion(json_schema_request("claude-3-5-haiku-20241022"))
+            .await
+            .unwrap();
+        assert_eq!(response.choices[0].message.content, "{\"temperature\": 21}");
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert!(body.get("response_format").is_none());
+        let system = body["system"].as_str().unwrap();
+        assert!(system.contains("Respond only with valid JSON"));
+        assert!(system.contains(r#""temperature""#));
+
+        // Content that is not JSON fails a schema request
+        let (_server, client) = anthropic_replying("It is 21 degrees.").await;
+        let result = client
+            .chat_completion(json_schema_request("claude-3-5-haiku-20241022"))
+            .await;
+        assert!(matches!(
+            result,
+            Err(ClientError::Provider(
+                crate::error::ProviderError::InvalidResponse { .. }
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_stops_at_max_rounds() {
+        use crate::models::{Message, ToolCall};
+        use crate::tools::{ToolExecutor, ToolLoopConfig, MAX_TOOL_ROUNDS_FINISH_REASON};
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        struct CountingExecutor(AtomicU32);
+
+        #[async_trait::async_trait]
+        impl ToolExecutor for CountingExecutor {
+            async fn execute(&self, call: &ToolCall) -> Result<String, ClientError> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(format!("result of {}", call.function.name))
+            }
+        }
+
+        // A model that asks for another tool call whatever it is sent
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {
+                        "role": "assistant",
+                        "content": "",
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": {"name": "search", "arguments": "{}"}
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let client = UltrafastClient::standalone()
+            .with_provider(
+                "openai",
+                ProviderConfig::new("openai", "test-key").with_base_url(server.uri()),
+            )
+            .with_tool_loop(ToolLoopConfig { max_rounds: 3 })
+            .build()
+            .unwrap();
+        let executor = CountingExecutor(AtomicU32::new(0));
+        let request = ChatRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![Message::user("Find it")],
+            ..Default::default()
+        };
+        let response = client
+            .chat_completion_with_tools(request, &executor)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.choices[0].finish_reason.as_deref(),
+            Some(MAX_TOOL_ROUNDS_FINISH_REASON)
+        );
+        assert!(response.choices[0].message.tool_calls.is_some());
+        assert_eq!(executor.0.load(Ordering::SeqCst), 3);
+
+        let received = server.received_requests().await.unwrap();
+        assert_eq!(received.len(), 4);
+        let last: serde_json::Value = serde_json::from_slice(&received[3].body).unwrap();
+        let roles: Vec<_> = last["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            roles,
+            [
+                "user",
+                "assistant",
+                "tool",
+                "assistant",
+                "tool",
+                "assistant",
+                "tool"
+            ]
+        );
+        assert_eq!(last["messages"][2]["tool_call_id"], "call_1");
+    }
 }
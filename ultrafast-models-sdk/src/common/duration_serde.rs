@@ -72,3 +72,30 @@ pub fn parse_duration_string(s: &str) -> Result<Duration, String> {
 pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
     parse_duration_string(s).map_err(|e| anyhow::anyhow!(e))
 }
+
+/// The same formats for `Option<Duration>` fields. Pair with
+/// `#[serde(default)]` so a missing field reads as `None`.
+pub mod option {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S>(duration: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match duration {
+            Some(duration) => super::serialize(duration, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "super")] Duration);
+
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(duration)| duration))
+    }
+}
@@ -66,7 +66,7 @@
 //!     Err(ClientError::Provider(ProviderError::InvalidApiKey)) => {
 //!         println!("Invalid API key provided");
 //!     }
-//!     Err(ClientError::Provider(ProviderError::RateLimit)) => {
+//!     Err(ClientError::Provider(ProviderError::RateLimit { .. })) => {
 //!         println!("Rate limit exceeded, retrying...");
 //!     }
 //!     Err(ClientError::Timeout) => {
@@ -102,7 +102,7 @@
 //!
 //! fn handle_client_error(error: &ClientError) {
 //!     match error {
-//!         ClientError::Provider(ProviderError::RateLimit) => {
+//!         ClientError::Provider(ProviderError::RateLimit { .. }) => {
 //!             // Implement exponential backoff
 //!             std::thread::sleep(std::time::Duration::from_secs(1));
 //!         }
@@ -230,7 +230,7 @@ pub enum ClientError {
 ///     Err(ProviderError::InvalidApiKey) => {
 ///         println!("Invalid API key");
 ///     }
-///     Err(ProviderError::RateLimit) => {
+///     Err(ProviderError::RateLimit { .. }) => {
 ///         println!("Rate limit exceeded");
 ///     }
 ///     Err(ProviderError::ServiceUnavailable) => {
@@ -259,12 +259,22 @@ pub enum ProviderError {
 
     /// Rate limit exceeded for this provider
     #[error("Rate limit exceeded")]
-    RateLimit,
+    RateLimit {
+        /// How long the provider asked callers to back off (`Retry-After`)
+        retry_after: Option<std::time::Duration>,
+    },
 
     /// Provider quota exceeded
     #[error("Quota exceeded")]
     QuotaExceeded,
 
+    /// The provider does not implement the requested capability
+    #[error("Provider {provider} does not support {capability}")]
+    Unsupported {
+        provider: String,
+        capability: crate::providers::Capability,
+    },
+
     /// Provider service temporarily unavailable
     #[error("Service unavailable")]
     ServiceUnavailable,
@@ -278,8 +288,12 @@ pub enum ProviderError {
     Serialization(#[from] serde_json::Error),
 
     /// Invalid or malformed response format
-    #[error("Invalid response format")]
-    InvalidResponse,
+    #[error("Invalid response format: {message}")]
+    InvalidResponse { message: String },
+
+    /// Plain-text error read from a provider in the middle of a stream
+    #[error("Stream error from {provider}: {message}")]
+    StreamError { provider: String, message: String },
 
     /// Invalid or missing provider configuration
     #[error("Configuration error: {message}")]
@@ -309,3 +323,16 @@ pub enum ProviderError {
     #[error("Retryable error: {message}")]
     RetryableError { message: String },
 }
+
+impl ProviderError {
+    /// Error for a capability the provider does not implement.
+    pub fn unsupported(
+        provider: impl Into<String>,
+        capability: crate::providers::Capability,
+    ) -> Self {
+        ProviderError::Unsupported {
+            provider: provider.into(),
+            capability,
+        }
+    }
+}
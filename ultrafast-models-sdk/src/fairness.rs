@@ -0,0 +1,285 @@
+//! # Fairness Module
+//!
+//! This module limits how many requests a provider has in flight at once and
+//! shares those slots fairly between the models routed to it.
+//!
+//! ## Overview
+//!
+//! Without fairness, a burst of requests for one model can take every slot
+//! and leave requests for other models queued behind it. A [`FairSemaphore`]
+//! keeps a queue per model instead: when a slot frees up it goes to the
+//! waiting model with the fewest requests in flight relative to its weight,
+//! so each model gets a share of the slots proportional to its weight.
+//! Requests for the same model are served in arrival order.
+//!
+//! ## Configuration
+//!
+//! ```toml
+//! [providers.openai.fairness]
+//! max_concurrent_requests = 8
+//! model_weights = { "gpt-4o" = 3, "gpt-4o-mini" = 1 }
+//! ```
+//!
+//! Models without a weight get a weight of 1.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// Per-provider concurrency limit shared fairly between models.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FairnessConfig {
+    /// Requests the provider may have in flight at once
+    pub max_concurrent_requests: usize,
+    /// Relative share of the slots for each model when models contend
+    pub model_weights: HashMap<String, u32>,
+}
+
+impl Default for FairnessConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_requests: 10,
+            model_weights: HashMap::new(),
+        }
+    }
+}
+
+/// Semaphore handing out slots round the waiting models by weight.
+#[derive(Debug, Clone)]
+pub struct FairSemaphore {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    weights: HashMap<String, u32>,
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    available: usize,
+    next_ticket: u64,
+    models: HashMap<String, ModelSlots>,
+}
+
+#[derive(Debug, Default)]
+struct ModelSlots {
+    active: usize,
+    waiters: VecDeque<Waiter>,
+}
+
+#[derive(Debug)]
+struct Waiter {
+    ticket: u64,
+    granted: oneshot::Sender<()>,
+}
+
+impl FairSemaphore {
+    pub fn new(config: &FairnessConfig) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                weights: config.model_weights.clone(),
+                state: Mutex::new(State {
+                    available: config.max_concurrent_requests,
+                    next_ticket: 0,
+                    models: HashMap::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Wait for a slot for `model`, held until the permit is dropped.
+    pub async fn acquire(&self, model: &str) -> FairPermit {
+        let (ticket, granted) = {
+            let mut state = self.inner.state.lock().unwrap();
+            let queued = state.models.values().any(|slots| !slots.waiters.is_empty());
+            if state.available > 0 && !queued {
+                state.available -= 1;
+                state.models.entry(model.to_string()).or_default().active += 1;
+                return self.permit(model);
+            }
+
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            let (sender, granted) = oneshot::channel();
+            state
+                .models
+                .entry(model.to_string())
+                .or_default()
+                .waiters
+                .push_back(Waiter {
+                    ticket,
+                    granted: sender,
+                });
+            (ticket, granted)
+        };
+
+        let mut waiting = Waiting {
+            inner: &self.inner,
+            model,
+            ticket,
+            granted: false,
+        };
+        // The sender lives in the queue until the slot is handed over
+        let _ = granted.await;
+        waiting.granted = true;
+        self.permit(model)
+    }
+
+    /// Requests currently holding a slot for `model`.
+    pub fn active(&self, model: &str) -> usize {
+        let state = self.inner.state.lock().unwrap();
+        state.models.get(model).map_or(0, |slots| slots.active)
+    }
+
+    fn permit(&self, model: &str) -> FairPermit {
+        FairPermit {
+            inner: self.inner.clone(),
+            model: model.to_string(),
+        }
+    }
+}
+
+impl Inner {
+    fn weight(&self, model: &str) -> u64 {
+        u64::from(self.weights.get(model).copied().unwrap_or(1).max(1))
+    }
+
+    /// Give the slot freed by `model` to the most underserved waiting model.
+    fn release(&self, state: &mut State, model: &str) {
+        if let Some(slots) = state.models.get_mut(model) {
+            slots.active -= 1;
+        }
+
+        loop {
+            // Lowest active/weight first, then earliest arrival
+            let next = state
+                .models
+                .iter()
+                .filter_map(|(name, slots)| {
+                    let ticket = slots.waiters.front()?.ticket;
+                    Some((name, slots.active as u64, self.weight(name), ticket))
+                })
+                .min_by(|a, b| (a.1 * b.2).cmp(&(b.1 * a.2)).then(a.3.cmp(&b.3)))
+                .map(|(name, ..)| name.clone());
+            let Some(next) = next else {
+                state.available += 1;
+                break;
+            };
+
+            let slots = state.models.get_mut(&next).unwrap();
+            let waiter = slots.waiters.pop_front().unwrap();
+            if waiter.granted.send(()).is_ok() {
+                slots.active += 1;
+                break;
+            }
+        }
+
+        state
+            .models
+            .retain(|_, slots| slots.active > 0 || !slots.waiters.is_empty());
+    }
+}
+
+/// Slot held for one request; dropping it hands the slot on.
+#[derive(Debug)]
+pub struct FairPermit {
+    inner: Arc<Inner>,
+    model: String,
+}
+
+impl Drop for FairPermit {
+    fn drop(&mut self) {
+        let mut state = self.inner.state.lock().unwrap();
+        self.inner.release(&mut state, &self.model);
+    }
+}
+
+/// Cleans up after an `acquire` future dropped while queued.
+struct Waiting<'a> {
+    inner: &'a Inner,
+    model: &'a str,
+    ticket: u64,
+    granted: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if self.granted {
+            return;
+        }
+        let mut state = self.inner.state.lock().unwrap();
+        let queued = state.models.get_mut(self.model).and_then(|slots| {
+            let position = slots.waiters.iter().position(|w| w.ticket == self.ticket)?;
+            slots.waiters.remove(position)
+        });
+        // Granted after the future was abandoned: pass the slot on
+        if queued.is_none() {
+            self.inner.release(&mut state, self.model);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_contending_models_are_not_starved() {
+        let semaphore = FairSemaphore::new(&FairnessConfig {
+            max_concurrent_requests: 4,
+            ..Default::default()
+        });
+
+        // The heavy model fills every slot and queues a long backlog first
+        let mut permits: Vec<_> = Vec::new();
+        for _ in 0..4 {
+            permits.push(semaphore.acquire("heavy").await);
+        }
+        let mut waiters = Vec::new();
+        for model in std::iter::repeat_n("heavy", 20).chain(std::iter::repeat_n("light", 4)) {
+            let semaphore = semaphore.clone();
+            waiters.push(tokio::spawn(async move {
+                let permit = semaphore.acquire(model).await;
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                drop(permit);
+            }));
+            tokio::task::yield_now().await;
+        }
+
+        // As slots free up the light model gets half of them despite arriving last
+        drop(permits);
+        tokio::task::yield_now().await;
+        assert_eq!(semaphore.active("light"), 2);
+        assert_eq!(semaphore.active("heavy"), 2);
+
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+        assert_eq!(semaphore.active("heavy"), 0);
+        assert_eq!(semaphore.inner.state.lock().unwrap().available, 4);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiter_releases_its_place() {
+        let semaphore = FairSemaphore::new(&FairnessConfig {
+            max_concurrent_requests: 1,
+            ..Default::default()
+        });
+
+        let permit = semaphore.acquire("a").await;
+        let abandoned = tokio::time::timeout(Duration::from_millis(10), semaphore.acquire("b"));
+        assert!(abandoned.await.is_err());
+
+        drop(permit);
+        let permit = tokio::time::timeout(Duration::from_millis(100), semaphore.acquire("c"))
+            .await
+            .expect("slot leaked to the abandoned waiter");
+        assert_eq!(semaphore.active("c"), 1);
+        drop(permit);
+    }
+}
@@ -409,13 +409,18 @@
 //! - **Documentation**: [Project Wiki](https://github.com/techgopal/ultrafast-ai-gateway/wiki)
 
 pub mod cache;
+pub mod choices;
 pub mod circuit_breaker;
 pub mod client;
 pub mod common;
 pub mod error;
+pub mod fairness;
 pub mod models;
 pub mod providers;
+pub mod response_format;
 pub mod routing;
+pub mod tokenizer;
+pub mod tools;
 
 pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitState};
 pub use client::{ClientMode, UltrafastClient, UltrafastClientBuilder};
@@ -471,7 +476,7 @@ mod tests {
         let config = ProviderConfig::new("test-provider", "test-key");
         assert_eq!(config.name, "test-provider");
         assert_eq!(config.api_key, "test-key");
-        assert_eq!(config.timeout.as_secs(), 30);
+        assert_eq!(config.total_timeout.as_secs(), 30);
         assert_eq!(config.max_retries, 3);
         assert!(config.enabled);
     }
@@ -102,6 +102,8 @@
 //!
 //! let request = AudioRequest {
 //!     file: audio_data, // Vec<u8> containing audio file
+//!     file_name: Some("meeting.wav".to_string()),
+//!     mime_type: Some("audio/wav".to_string()),
 //!     model: "whisper-1".to_string(),
 //!     language: Some("en".to_string()),
 //!     prompt: Some("This is a conversation about technology.".to_string()),
@@ -224,6 +226,7 @@
 //! - **Error Handling**: Comprehensive error responses
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Chat completion request.
 ///
@@ -275,9 +278,66 @@ pub struct ChatRequest {
     /// Sequences that stop generation
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<Vec<String>>,
+    /// Number of completions to generate. Forwarded to providers that
+    /// support it and emulated with concurrent requests for the others
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
     /// User identifier for tracking
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
+    /// Streaming options, forwarded to OpenAI-compatible providers only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+    /// Structured output format. Forwarded to providers that support it and
+    /// emulated with a system instruction for the others
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+    /// Client tags for analytics, forwarded only to providers that accept
+    /// request metadata (Anthropic's `metadata`, which takes only `user_id`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_metadata: Option<HashMap<String, String>>,
+    /// Values routing can key on, such as the session header of
+    /// [`StickyKey::Header`](crate::routing::StickyKey::Header). Never sent
+    /// to providers.
+    #[serde(skip)]
+    pub routing_metadata: HashMap<String, String>,
+    /// Provider-specific fields not modeled above (e.g. `logit_bias`,
+    /// `repetition_penalty`). Preserved on deserialization and forwarded
+    /// verbatim to the provider.
+    #[serde(flatten, default)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+/// Options for streamed chat completions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct StreamOptions {
+    /// Ask the provider to send token usage in a final chunk
+    #[serde(default)]
+    pub include_usage: bool,
+}
+
+/// Output format requested with `response_format`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Plain text (the default)
+    Text,
+    /// Any valid JSON object
+    JsonObject,
+    /// JSON matching a schema
+    JsonSchema { json_schema: JsonSchema },
+}
+
+/// Named JSON schema for [`ResponseFormat::JsonSchema`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonSchema {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 /// Chat completion response.
@@ -403,6 +463,26 @@ impl Message {
             tool_call_id: None,
         }
     }
+
+    /// Create a tool message answering a tool call.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_call_id` - The ID of the tool call being answered
+    /// * `content` - The tool's output
+    ///
+    /// # Returns
+    ///
+    /// Returns a new tool message.
+    pub fn tool(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
+        Self {
+            role: Role::Tool,
+            content: content.into(),
+            name: None,
+            tool_calls: None,
+            tool_call_id: Some(tool_call_id.into()),
+        }
+    }
 }
 
 /// Role of a message in a conversation.
@@ -437,21 +517,58 @@ pub struct Choice {
     pub logprobs: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Token usage of a request.
+///
+/// Deserializes from OpenAI's `prompt_tokens`/`completion_tokens` as well as
+/// the `input_tokens`/`output_tokens` used by Anthropic-style APIs. A missing
+/// `total_tokens` is the sum of the two.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "UsageFields")]
 pub struct Usage {
     pub prompt_tokens: u32,
     pub completion_tokens: u32,
     pub total_tokens: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Usage {
+    pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
+        Self {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UsageFields {
+    #[serde(default, alias = "input_tokens")]
+    prompt_tokens: u32,
+    #[serde(default, alias = "output_tokens")]
+    completion_tokens: u32,
+    #[serde(default)]
+    total_tokens: Option<u32>,
+}
+
+impl From<UsageFields> for Usage {
+    fn from(fields: UsageFields) -> Self {
+        Self {
+            total_tokens: fields
+                .total_tokens
+                .unwrap_or(fields.prompt_tokens + fields.completion_tokens),
+            ..Self::new(fields.prompt_tokens, fields.completion_tokens)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tool {
     #[serde(rename = "type")]
     pub tool_type: String,
     pub function: Function,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Function {
     pub name: String,
     pub description: Option<String>,
@@ -472,7 +589,7 @@ pub struct FunctionChoice {
     pub name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolCall {
     pub id: String,
     #[serde(rename = "type")]
@@ -480,7 +597,7 @@ pub struct ToolCall {
     pub function: FunctionCall,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FunctionCall {
     pub name: String,
     pub arguments: String,
@@ -527,6 +644,30 @@ pub struct EmbeddingResponse {
     pub usage: Usage,
 }
 
+impl EmbeddingResponse {
+    /// Combine the chunks of a batched embedding request into one response,
+    /// with the embeddings sorted by input index and the usage summed.
+    pub fn from_batches(batches: impl IntoIterator<Item = EmbeddingResponse>) -> Self {
+        let mut response = EmbeddingResponse {
+            object: "list".to_string(),
+            data: Vec::new(),
+            model: String::new(),
+            usage: Usage::default(),
+        };
+        for batch in batches {
+            if response.model.is_empty() {
+                response.model = batch.model;
+            }
+            response.data.extend(batch.data);
+            response.usage.prompt_tokens += batch.usage.prompt_tokens;
+            response.usage.completion_tokens += batch.usage.completion_tokens;
+            response.usage.total_tokens += batch.usage.total_tokens;
+        }
+        response.data.sort_by_key(|embedding| embedding.index);
+        response
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Embedding {
     pub object: String,
@@ -571,6 +712,12 @@ pub struct ImageData {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioRequest {
     pub file: Vec<u8>,
+    /// Name of the uploaded file, forwarded so providers can detect the format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    /// Mime type of the uploaded file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
     pub model: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub language: Option<String>,
@@ -640,6 +787,10 @@ pub struct StreamChunk {
     pub created: u64,
     pub model: String,
     pub choices: Vec<StreamChoice>,
+    /// Token usage for the whole stream, sent by providers in the final chunk
+    /// (OpenAI does so when `stream_options.include_usage` is set).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
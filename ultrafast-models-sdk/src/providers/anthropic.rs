@@ -1,14 +1,19 @@
 use crate::error::ProviderError;
 use crate::models::{
-    AudioRequest, AudioResponse, ChatRequest, ChatResponse, Choice, EmbeddingRequest,
-    EmbeddingResponse, ImageRequest, ImageResponse, Message, Role, SpeechRequest, SpeechResponse,
-    StreamChunk, Usage,
+    AudioRequest, AudioResponse, ChatRequest, ChatResponse, Choice, DeltaFunction, DeltaToolCall,
+    EmbeddingRequest, EmbeddingResponse, ImageRequest, ImageResponse, Message, Role, SpeechRequest,
+    SpeechResponse, StreamChunk, Usage,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
+use crate::providers::{
+    HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult, TemperatureRange,
+};
+use crate::tools::{tool_call, tool_call_arguments, ToolFormat};
 use async_stream::stream;
 use serde::{Deserialize, Serialize};
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    map_error_response, merge_extra_fields, sse_json, AuthStrategy, HttpProviderClient,
+};
 use std::collections::HashMap;
 use std::time::Instant;
 
@@ -21,17 +26,56 @@ pub struct AnthropicProvider {
 struct AnthropicRequest {
     model: String,
     max_tokens: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
     messages: Vec<AnthropicMessage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_sequences: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<AnthropicMetadata>,
+}
+
+/// Anthropic's request `metadata`, which only accepts a `user_id`.
+#[derive(Debug, Serialize)]
+struct AnthropicMetadata {
+    user_id: String,
 }
 
 #[derive(Debug, Serialize)]
 struct AnthropicMessage {
     role: String,
-    content: String,
+    content: Vec<AnthropicContent>,
+}
+
+/// A content block of an Anthropic message, in requests and responses.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContent {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+    },
+    /// Block types the gateway does not translate, such as `thinking`
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,18 +92,84 @@ struct AnthropicResponse {
     usage: AnthropicUsage,
 }
 
-#[derive(Debug, Deserialize)]
-struct AnthropicContent {
-    #[serde(rename = "type")]
-    #[allow(dead_code)]
-    content_type: String,
-    text: String,
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
 struct AnthropicUsage {
     input_tokens: u32,
     output_tokens: u32,
+    /// Prompt tokens written to the prompt cache, not part of `input_tokens`
+    cache_creation_input_tokens: u32,
+    /// Prompt tokens read from the prompt cache, not part of `input_tokens`
+    cache_read_input_tokens: u32,
+}
+
+impl AnthropicUsage {
+    /// Every prompt token, cached or not.
+    fn prompt_tokens(&self) -> u32 {
+        self.input_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+}
+
+/// OpenAI `finish_reason` for an Anthropic `stop_reason`.
+fn map_stop_reason(stop_reason: &str) -> String {
+    match stop_reason {
+        "end_turn" | "stop_sequence" => "stop",
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        "refusal" => "content_filter",
+        other => other,
+    }
+    .to_string()
+}
+
+/// OpenAI tool call delta for an Anthropic stream event, if it carries one.
+///
+/// The `content_block_start` of a `tool_use` block opens a call with its ID
+/// and name, and its `input_json_delta` events append to the arguments.
+/// Calls are indexed in the order their blocks start, as OpenAI numbers them.
+fn stream_tool_call(
+    event: &serde_json::Value,
+    tool_indices: &mut HashMap<u64, u32>,
+) -> Option<DeltaToolCall> {
+    let block_index = event.get("index")?.as_u64()?;
+    match event.get("type")?.as_str()? {
+        "content_block_start" => {
+            let block = event.get("content_block")?;
+            if block.get("type")?.as_str()? != "tool_use" {
+                return None;
+            }
+            let index = tool_indices.len() as u32;
+            tool_indices.insert(block_index, index);
+            Some(DeltaToolCall {
+                index,
+                id: block
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .map(str::to_string),
+                call_type: Some("function".to_string()),
+                function: Some(DeltaFunction {
+                    name: block
+                        .get("name")
+                        .and_then(|name| name.as_str())
+                        .map(str::to_string),
+                    arguments: Some(String::new()),
+                }),
+            })
+        }
+        "content_block_delta" => {
+            let partial_json = event.get("delta")?.get("partial_json")?.as_str()?;
+            Some(DeltaToolCall {
+                index: *tool_indices.get(&block_index)?,
+                id: None,
+                call_type: None,
+                function: Some(DeltaFunction {
+                    name: None,
+                    arguments: Some(partial_json.to_string()),
+                }),
+            })
+        }
+        _ => None,
+    }
 }
 
 impl AnthropicProvider {
@@ -67,7 +177,8 @@ impl AnthropicProvider {
         let mut headers = config.headers.clone();
         headers.insert("anthropic-version".to_string(), "2023-06-01".to_string());
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://api.anthropic.com",
             &headers,
@@ -75,7 +186,8 @@ impl AnthropicProvider {
                 name: "x-api-key".to_string(),
                 value: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self { http, config })
     }
@@ -99,67 +211,113 @@ impl AnthropicProvider {
             })
     }
 
-    fn convert_messages(&self, messages: Vec<Message>) -> Vec<AnthropicMessage> {
-        let mut anthropic_messages = Vec::new();
-        let mut system_content = String::new();
+    /// Build an Anthropic messages API request from an OpenAI-style one.
+    ///
+    /// System messages are hoisted to the top-level `system` field, tool
+    /// calls and tool results become `tool_use`/`tool_result` blocks, and
+    /// consecutive messages for the same Anthropic role are merged, since
+    /// the API requires user and assistant turns to alternate.
+    ///
+    /// Passthrough fields from `extra` are merged into the body without
+    /// overriding the translated ones. OpenAI's free-form `metadata` is
+    /// dropped, since Anthropic's only accepts a `user_id`.
+    fn convert_request(
+        &self,
+        request: ChatRequest,
+        stream: bool,
+    ) -> Result<serde_json::Value, ProviderError> {
+        let mut system = Vec::new();
+        let mut messages: Vec<AnthropicMessage> = Vec::new();
 
-        for msg in messages {
-            match msg.role {
+        for msg in request.messages {
+            let (role, content) = match msg.role {
                 Role::System => {
-                    // Collect system messages into a single system prompt
                     if !msg.content.is_empty() {
-                        if !system_content.is_empty() {
-                            system_content.push('\n');
-                        }
-                        system_content.push_str(&msg.content);
+                        system.push(msg.content);
                     }
+                    continue;
                 }
-                Role::User => {
-                    // If we have system content, prepend it to the first user message
-                    if !system_content.is_empty() && anthropic_messages.is_empty() {
-                        anthropic_messages.push(AnthropicMessage {
-                            role: "user".to_string(),
-                            content: format!("{}\n\n{}", system_content, msg.content),
-                        });
-                        system_content.clear(); // Clear after using
-                    } else {
-                        anthropic_messages.push(AnthropicMessage {
-                            role: "user".to_string(),
-                            content: msg.content,
+                Role::User => ("user", text_block(msg.content)),
+                Role::Assistant => {
+                    let mut content = text_block(msg.content);
+                    for call in msg.tool_calls.unwrap_or_default() {
+                        let input = tool_call_arguments(&call);
+                        content.push(AnthropicContent::ToolUse {
+                            id: call.id,
+                            name: call.function.name,
+                            input,
                         });
                     }
+                    ("assistant", content)
                 }
-                Role::Assistant => {
-                    anthropic_messages.push(AnthropicMessage {
-                        role: "assistant".to_string(),
+                Role::Tool => (
+                    "user",
+                    vec![AnthropicContent::ToolResult {
+                        tool_use_id: msg.tool_call_id.unwrap_or_default(),
                         content: msg.content,
-                    });
-                }
-                _ => {
-                    // Skip other role types (tool calls, etc.)
-                    continue;
-                }
+                    }],
+                ),
+            };
+            if content.is_empty() {
+                continue;
             }
-        }
 
-        // If we have system content but no user messages, create a user message with just the system content
-        if !system_content.is_empty() && anthropic_messages.is_empty() {
-            anthropic_messages.push(AnthropicMessage {
-                role: "user".to_string(),
-                content: system_content,
-            });
+            match messages.last_mut() {
+                Some(last) if last.role == role => last.content.extend(content),
+                _ => messages.push(AnthropicMessage {
+                    role: role.to_string(),
+                    content,
+                }),
+            }
         }
 
-        anthropic_messages
+        let tools = request
+            .tools
+            .map(|tools| ToolFormat::Anthropic.encode_tools(&tools));
+        let tool_choice = request
+            .tool_choice
+            .map(|choice| ToolFormat::Anthropic.encode_tool_choice(&choice));
+
+        let anthropic_request = AnthropicRequest {
+            model: self.map_model(&request.model),
+            max_tokens: request.max_tokens.unwrap_or(4096),
+            system: (!system.is_empty()).then(|| system.join("\n")),
+            messages,
+            temperature: self
+                .config
+                .clamp_temperature(TemperatureRange::ANTHROPIC, request.temperature),
+            top_p: request.top_p,
+            stop_sequences: request.stop,
+            tools,
+            tool_choice,
+            stream: Some(stream),
+            // Other request metadata keys are only recorded by the gateway
+            metadata: request
+                .request_metadata
+                .and_then(|mut metadata| metadata.remove("user_id"))
+                .map(|user_id| AnthropicMetadata { user_id }),
+        };
+
+        let mut body =
+            serde_json::to_value(anthropic_request).map_err(ProviderError::Serialization)?;
+        let mut extra = request.extra;
+        extra.remove("metadata");
+        merge_extra_fields(&mut body, &extra);
+        Ok(body)
     }
 
     fn convert_response(&self, response: AnthropicResponse) -> ChatResponse {
-        let content = response
-            .content
-            .into_iter()
-            .map(|c| c.text)
-            .collect::<Vec<_>>()
-            .join("");
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        for block in response.content {
+            match block {
+                AnthropicContent::Text { text } => content.push_str(&text),
+                AnthropicContent::ToolUse { id, name, input } => {
+                    tool_calls.push(tool_call(id, name, &input))
+                }
+                AnthropicContent::ToolResult { .. } | AnthropicContent::Other => {}
+            }
+        }
 
         ChatResponse {
             id: response.id,
@@ -172,17 +330,16 @@ impl AnthropicProvider {
                     role: Role::Assistant,
                     content,
                     name: None,
-                    tool_calls: None,
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
                     tool_call_id: None,
                 },
-                finish_reason: response.stop_reason,
+                finish_reason: response.stop_reason.as_deref().map(map_stop_reason),
                 logprobs: None,
             }],
-            usage: Some(Usage {
-                prompt_tokens: response.usage.input_tokens,
-                completion_tokens: response.usage.output_tokens,
-                total_tokens: response.usage.input_tokens + response.usage.output_tokens,
-            }),
+            usage: Some(Usage::new(
+                response.usage.prompt_tokens(),
+                response.usage.output_tokens,
+            )),
             system_fingerprint: None,
         }
     }
@@ -190,6 +347,15 @@ impl AnthropicProvider {
     // Use shared map_error_response
 }
 
+/// A single text block, or none for empty text, which Anthropic rejects.
+fn text_block(text: String) -> Vec<AnthropicContent> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![AnthropicContent::Text { text }]
+    }
+}
+
 #[async_trait::async_trait]
 impl Provider for AnthropicProvider {
     fn name(&self) -> &str {
@@ -204,6 +370,10 @@ impl Provider for AnthropicProvider {
         true
     }
 
+    fn supports_request_metadata(&self) -> bool {
+        true
+    }
+
     fn supported_models(&self) -> Vec<String> {
         vec![
             "claude-opus-4-20250514".to_string(),
@@ -219,16 +389,7 @@ impl Provider for AnthropicProvider {
     }
 
     async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
-        let model = self.map_model(&request.model);
-        let messages = self.convert_messages(request.messages);
-
-        let anthropic_request = AnthropicRequest {
-            model,
-            max_tokens: request.max_tokens.unwrap_or(4096),
-            messages,
-            temperature: request.temperature,
-            stream: Some(false),
-        };
+        let anthropic_request = self.convert_request(request, false)?;
 
         let anthropic_response: AnthropicResponse = self
             .http
@@ -241,16 +402,7 @@ impl Provider for AnthropicProvider {
         &self,
         request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
-        let model = self.map_model(&request.model);
-        let messages = self.convert_messages(request.messages);
-
-        let anthropic_request = AnthropicRequest {
-            model,
-            max_tokens: request.max_tokens.unwrap_or(4096),
-            messages,
-            temperature: request.temperature,
-            stream: Some(true),
-        };
+        let anthropic_request = self.convert_request(request, true)?;
 
         let response = self
             .http
@@ -260,63 +412,70 @@ impl Provider for AnthropicProvider {
             return Err(map_error_response(response).await);
         }
 
+        let events = sse_json::<serde_json::Value>(response, self.name());
         let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                // Convert Anthropic stream format to OpenAI-compatible format
-                                match serde_json::from_str::<serde_json::Value>(json_str) {
-                                    Ok(anthropic_chunk) => {
-                                        if let Some(content_delta) = anthropic_chunk
-                                            .get("delta")
-                                            .and_then(|d| d.get("text"))
-                                            .and_then(|t| t.as_str()) {
-
-                                            let stream_chunk = StreamChunk {
-                                                id: anthropic_chunk.get("id")
-                                                    .and_then(|id| id.as_str())
-                                                    .unwrap_or("anthropic-stream")
-                                                    .to_string(),
-                                                object: "chat.completion.chunk".to_string(),
-                                                created: chrono::Utc::now().timestamp() as u64,
-                                                model: anthropic_chunk.get("model")
-                                                    .and_then(|m| m.as_str())
-                                                    .unwrap_or("claude-3-sonnet")
-                                                    .to_string(),
-                                                choices: vec![crate::models::StreamChoice {
-                                                    index: 0,
-                                                    delta: crate::models::Delta {
-                                                        role: None,
-                                                        content: Some(content_delta.to_string()),
-                                                        tool_calls: None,
-                                                    },
-                                                    finish_reason: None,
-                                                }],
-                                            };
-                                            yield Ok(stream_chunk);
-                                        }
-                                    }
-                                    Err(e) => yield Err(ProviderError::Serialization(e)),
-                                }
-                            }
+            let mut events = Box::pin(events);
+            // Prompt usage arrives in `message_start`, output in `message_delta`
+            let mut prompt_usage = AnthropicUsage::default();
+            // Tool call index of each `tool_use` content block
+            let mut tool_indices = HashMap::new();
+
+            while let Some(chunk) = futures::StreamExt::next(&mut events).await {
+
+                // Convert Anthropic stream format to OpenAI-compatible format
+                match chunk {
+                    Ok(anthropic_chunk) => {
+                        if let Some(usage) = anthropic_chunk
+                            .get("message")
+                            .and_then(|m| m.get("usage"))
+                        {
+                            prompt_usage = AnthropicUsage::deserialize(usage)
+                                .unwrap_or_default();
+                        }
+                        let usage = anthropic_chunk
+                            .get("usage")
+                            .and_then(|u| AnthropicUsage::deserialize(u).ok())
+                            .map(|u| {
+                                Usage::new(prompt_usage.prompt_tokens(), u.output_tokens)
+                            });
+                        let delta = anthropic_chunk.get("delta");
+                        let content_delta = delta
+                            .and_then(|d| d.get("text"))
+                            .and_then(|t| t.as_str());
+                        // `message_delta` events carry the stop reason
+                        let finish_reason = delta
+                            .and_then(|d| d.get("stop_reason"))
+                            .and_then(|r| r.as_str())
+                            .map(map_stop_reason);
+                        let tool_call = stream_tool_call(&anthropic_chunk, &mut tool_indices);
+                        if content_delta.is_some() || finish_reason.is_some() || tool_call.is_some() {
+
+                            let stream_chunk = StreamChunk {
+                                id: anthropic_chunk.get("id")
+                                    .and_then(|id| id.as_str())
+                                    .unwrap_or("anthropic-stream")
+                                    .to_string(),
+                                object: "chat.completion.chunk".to_string(),
+                                created: chrono::Utc::now().timestamp() as u64,
+                                model: anthropic_chunk.get("model")
+                                    .and_then(|m| m.as_str())
+                                    .unwrap_or("claude-3-sonnet")
+                                    .to_string(),
+                                choices: vec![crate::models::StreamChoice {
+                                    index: 0,
+                                    delta: crate::models::Delta {
+                                        role: None,
+                                        content: content_delta.map(str::to_string),
+                                        tool_calls: tool_call.map(|call| vec![call]),
+                                    },
+                                    finish_reason,
+                                }],
+                                usage,
+                            };
+                            yield Ok(stream_chunk);
                         }
                     }
-                    Err(e) => yield Err(ProviderError::Http(e)),
+                    Err(e) => yield Err(e),
                 }
             }
         });
@@ -360,6 +519,14 @@ impl Provider for AnthropicProvider {
         })
     }
 
+    async fn keepalive_ping(&self, path: &str) -> Result<(), ProviderError> {
+        let response = self.http.get_raw(path).await?;
+        if !response.status().is_success() {
+            return Err(map_error_response(response).await);
+        }
+        Ok(())
+    }
+
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
         let start = Instant::now();
 
@@ -367,12 +534,18 @@ impl Provider for AnthropicProvider {
         let health_request = AnthropicRequest {
             model: "claude-3-5-haiku-20241022".to_string(),
             max_tokens: 1,
+            system: None,
             messages: vec![AnthropicMessage {
                 role: "user".to_string(),
-                content: "Hi".to_string(),
+                content: text_block("Hi".to_string()),
             }],
             temperature: Some(0.0),
+            top_p: None,
+            stop_sequences: None,
+            tools: None,
+            tool_choice: None,
             stream: Some(false),
+            metadata: None,
         };
 
         let response = self
@@ -405,3 +578,465 @@ impl Provider for AnthropicProvider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_temperature_clamped_to_anthropic_range() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "ok"}],
+                "model": "claude-3-5-haiku-20241022",
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new(
+            ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![Message::user("Hi")],
+            temperature: Some(1.5),
+            ..Default::default()
+        };
+        provider.chat_completion(request).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["temperature"], serde_json::json!(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_stream_options_not_sent_to_anthropic() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "ok"}],
+                "model": "claude-3-5-haiku-20241022",
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 1, "output_tokens": 1}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new(
+            ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![Message::user("Hi")],
+            stream_options: Some(crate::models::StreamOptions {
+                include_usage: true,
+            }),
+            ..Default::default()
+        };
+        provider.chat_completion(request).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert!(body.get("stream_options").is_none());
+    }
+
+    /// Provider backed by a mock returning `content` blocks with `stop_reason`.
+    async fn mock_provider(
+        content: serde_json::Value,
+        stop_reason: &str,
+    ) -> (MockServer, AnthropicProvider) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": content,
+                "model": "claude-3-5-haiku-20241022",
+                "stop_reason": stop_reason,
+                "usage": {"input_tokens": 10, "output_tokens": 5}
+            })))
+            .mount(&server)
+            .await;
+        let provider = AnthropicProvider::new(
+            ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        (server, provider)
+    }
+
+    async fn sent_body(server: &MockServer) -> serde_json::Value {
+        let received = server.received_requests().await.unwrap();
+        serde_json::from_slice(&received[0].body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_hoisted_to_system_field() {
+        let (server, provider) = mock_provider(
+            serde_json::json!([{"type": "text", "text": "Ahoy"}]),
+            "max_tokens",
+        )
+        .await;
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![
+                Message::system("Talk like a pirate."),
+                Message::system("Be brief."),
+                Message::user("Hi"),
+            ],
+            ..Default::default()
+        };
+        let response = provider.chat_completion(request).await.unwrap();
+
+        let body = sent_body(&server).await;
+        assert_eq!(body["system"], "Talk like a pirate.\nBe brief.");
+        assert_eq!(
+            body["messages"],
+            serde_json::json!([{"role": "user", "content": [{"type": "text", "text": "Hi"}]}])
+        );
+        assert_eq!(response.choices[0].message.content, "Ahoy");
+        assert_eq!(response.choices[0].finish_reason.as_deref(), Some("length"));
+    }
+
+    #[tokio::test]
+    async fn test_extra_fields_do_not_collide_with_translated_fields() {
+        let (server, provider) = mock_provider(
+            serde_json::json!([{"type": "text", "text": "ok"}]),
+            "end_turn",
+        )
+        .await;
+        let extra = HashMap::from([
+            ("metadata".to_string(), serde_json::json!({"trace": "abc"})),
+            ("system".to_string(), serde_json::json!("Ignored.")),
+            ("top_k".to_string(), serde_json::json!(5)),
+        ]);
+        let request = |request_metadata| ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![Message::system("Be brief."), Message::user("Hi")],
+            request_metadata,
+            extra: extra.clone(),
+            ..Default::default()
+        };
+
+        // OpenAI metadata is dropped rather than sent to Anthropic
+        provider.chat_completion(request(None)).await.unwrap();
+        let body = sent_body(&server).await;
+        assert!(body.get("metadata").is_none(), "{body}");
+        assert_eq!(body["system"], "Be brief.");
+        assert_eq!(body["top_k"], 5);
+
+        // A request metadata user_id is the only metadata, serialized once
+        let user = HashMap::from([("user_id".to_string(), "user-1".to_string())]);
+        provider.chat_completion(request(Some(user))).await.unwrap();
+        let received = server.received_requests().await.unwrap();
+        let raw = String::from_utf8(received[1].body.clone()).unwrap();
+        assert_eq!(raw.matches("\"metadata\"").count(), 1, "{raw}");
+        assert_eq!(raw.matches("\"system\"").count(), 1, "{raw}");
+        let body: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(body["metadata"], serde_json::json!({"user_id": "user-1"}));
+    }
+
+    #[tokio::test]
+    async fn test_multi_turn_conversation_alternates_roles() {
+        let (server, provider) = mock_provider(
+            serde_json::json!([{"type": "text", "text": "4"}]),
+            "end_turn",
+        )
+        .await;
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![
+                Message::user("What is 1 + 1?"),
+                Message::assistant("2"),
+                Message::user("And 2 + 2?"),
+                Message::user("Answer with a number."),
+            ],
+            stop: Some(vec!["\n\n".to_string()]),
+            ..Default::default()
+        };
+        let response = provider.chat_completion(request).await.unwrap();
+
+        let body = sent_body(&server).await;
+        assert!(body.get("system").is_none());
+        assert_eq!(body["stop_sequences"], serde_json::json!(["\n\n"]));
+        assert_eq!(
+            body["messages"],
+            serde_json::json!([
+                {"role": "user", "content": [{"type": "text", "text": "What is 1 + 1?"}]},
+                {"role": "assistant", "content": [{"type": "text", "text": "2"}]},
+                {"role": "user", "content": [
+                    {"type": "text", "text": "And 2 + 2?"},
+                    {"type": "text", "text": "Answer with a number."}
+                ]}
+            ])
+        );
+        assert_eq!(response.choices[0].message.content, "4");
+        assert_eq!(response.choices[0].finish_reason.as_deref(), Some("stop"));
+        assert_eq!(response.usage.unwrap().total_tokens, 15);
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_round_trip_as_tool_use_blocks() {
+        use crate::models::{Function, FunctionCall, Tool, ToolCall, ToolChoice};
+
+        let (server, provider) = mock_provider(
+            serde_json::json!([
+                {"type": "text", "text": "Checking Paris too."},
+                {"type": "tool_use", "id": "toolu_2", "name": "get_weather", "input": {"city": "Paris"}}
+            ]),
+            "tool_use",
+        )
+        .await;
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![
+                Message::user("Weather in London?"),
+                Message {
+                    tool_calls: Some(vec![ToolCall {
+                        id: "toolu_1".to_string(),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: "get_weather".to_string(),
+                            arguments: r#"{"city":"London"}"#.to_string(),
+                        },
+                    }]),
+                    ..Message::assistant("")
+                },
+                Message {
+                    role: Role::Tool,
+                    tool_call_id: Some("toolu_1".to_string()),
+                    ..Message::user("Rainy")
+                },
+            ],
+            tools: Some(vec![Tool {
+                tool_type: "function".to_string(),
+                function: Function {
+                    name: "get_weather".to_string(),
+                    description: Some("Current weather for a city".to_string()),
+                    parameters: serde_json::json!({
+                        "type": "object",
+                        "properties": {"city": {"type": "string"}}
+                    }),
+                },
+            }]),
+            tool_choice: Some(ToolChoice::Auto),
+            ..Default::default()
+        };
+        let response = provider.chat_completion(request).await.unwrap();
+
+        let body = sent_body(&server).await;
+        assert_eq!(
+            body["messages"],
+            serde_json::json!([
+                {"role": "user", "content": [{"type": "text", "text": "Weather in London?"}]},
+                {"role": "assistant", "content": [{
+                    "type": "tool_use",
+                    "id": "toolu_1",
+                    "name": "get_weather",
+                    "input": {"city": "London"}
+                }]},
+                {"role": "user", "content": [{
+                    "type": "tool_result",
+                    "tool_use_id": "toolu_1",
+                    "content": "Rainy"
+                }]}
+            ])
+        );
+        assert_eq!(
+            body["tools"],
+            serde_json::json!([{
+                "name": "get_weather",
+                "description": "Current weather for a city",
+                "input_schema": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }])
+        );
+        assert_eq!(body["tool_choice"], serde_json::json!({"type": "auto"}));
+
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        assert_eq!(choice.message.content, "Checking Paris too.");
+        let tool_calls = choice.message.tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls[0].id, "toolu_2");
+        assert_eq!(tool_calls[0].function.name, "get_weather");
+        let arguments: serde_json::Value =
+            serde_json::from_str(&tool_calls[0].function.arguments).unwrap();
+        assert_eq!(arguments, serde_json::json!({"city": "Paris"}));
+    }
+
+    #[tokio::test]
+    async fn test_usage_includes_cached_prompt_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msg_1",
+                "type": "message",
+                "role": "assistant",
+                "content": [{"type": "text", "text": "Hi"}],
+                "model": "claude-3-5-haiku-20241022",
+                "stop_reason": "end_turn",
+                "usage": {
+                    "input_tokens": 10,
+                    "cache_creation_input_tokens": 200,
+                    "cache_read_input_tokens": 1000,
+                    "output_tokens": 5
+                }
+            })))
+            .mount(&server)
+            .await;
+        let provider = AnthropicProvider::new(
+            ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![Message::user("Hi")],
+            ..Default::default()
+        };
+        let usage = provider
+            .chat_completion(request)
+            .await
+            .unwrap()
+            .usage
+            .unwrap();
+        assert_eq!(usage.prompt_tokens, 1210);
+        assert_eq!(usage.completion_tokens, 5);
+        assert_eq!(usage.total_tokens, 1215);
+    }
+
+    #[tokio::test]
+    async fn test_stream_usage_parsed_from_message_events() {
+        use futures::StreamExt;
+
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_1","usage":{"input_tokens":10,"cache_read_input_tokens":4,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hi"}}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":7}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("data: {event}\n\n"))
+            .collect();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+        let provider = AnthropicProvider::new(
+            ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![Message::user("Hi")],
+            ..Default::default()
+        };
+        let chunks: Vec<_> = provider
+            .stream_chat_completion(request)
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        let usage = chunks
+            .into_iter()
+            .filter_map(|chunk| chunk.unwrap().usage)
+            .collect::<Vec<_>>();
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].prompt_tokens, 14);
+        assert_eq!(usage[0].completion_tokens, 7);
+        assert_eq!(usage[0].total_tokens, 21);
+    }
+
+    #[tokio::test]
+    async fn test_stream_tool_use_mapped_to_tool_call_deltas() {
+        use futures::StreamExt;
+
+        let events = [
+            r#"{"type":"message_start","message":{"id":"msg_1","usage":{"input_tokens":10,"output_tokens":1}}}"#,
+            r#"{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}"#,
+            r#"{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Checking"}}"#,
+            r#"{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"get_weather","input":{}}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":"{\"city\":"}}"#,
+            r#"{"type":"content_block_delta","index":1,"delta":{"type":"input_json_delta","partial_json":" \"Paris\"}"}}"#,
+            r#"{"type":"content_block_stop","index":1}"#,
+            r#"{"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":12}}"#,
+            r#"{"type":"message_stop"}"#,
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("data: {event}\n\n"))
+            .collect();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+        let provider = AnthropicProvider::new(
+            ProviderConfig::new("anthropic", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            model: "claude-3-5-haiku-20241022".to_string(),
+            messages: vec![Message::user("Weather in Paris?")],
+            ..Default::default()
+        };
+        let chunks: Vec<_> = provider
+            .stream_chat_completion(request)
+            .await
+            .unwrap()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        let tool_calls: Vec<_> = chunks
+            .iter()
+            .flat_map(|chunk| {
+                chunk.choices[0]
+                    .delta
+                    .tool_calls
+                    .clone()
+                    .unwrap_or_default()
+            })
+            .collect();
+        assert_eq!(tool_calls.len(), 3);
+        assert!(tool_calls.iter().all(|call| call.index == 0));
+        assert_eq!(tool_calls[0].id.as_deref(), Some("toolu_1"));
+        assert_eq!(tool_calls[0].call_type.as_deref(), Some("function"));
+        let function = tool_calls[0].function.as_ref().unwrap();
+        assert_eq!(function.name.as_deref(), Some("get_weather"));
+        let arguments: String = tool_calls
+            .iter()
+            .filter_map(|call| call.function.as_ref()?.arguments.clone())
+            .collect();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&arguments).unwrap(),
+            serde_json::json!({"city": "Paris"})
+        );
+        assert_eq!(
+            chunks.last().unwrap().choices[0].finish_reason.as_deref(),
+            Some("tool_calls")
+        );
+    }
+}
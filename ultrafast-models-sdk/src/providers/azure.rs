@@ -1,19 +1,66 @@
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::header_template::HeaderTemplate;
+use super::http_client::{
+    audio_transcription_form, audio_transcription_response, map_error_response, parse_retry_after,
+    sse_json, AuthStrategy, HttpProviderClient,
+};
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse,
+};
+use crate::providers::{
+    HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult, TemperatureRange,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
-use async_stream::stream;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Instant;
 
+/// Azure OpenAI deployments of a provider.
+///
+/// Azure addresses models by deployment name. Once `deployments` is set,
+/// each requested model must have one; without it the model name, after
+/// `model_mapping`, is used as the deployment name.
+///
+/// ```toml
+/// [providers.azure-openai.azure.deployments.gpt-4]
+/// deployment = "prod-gpt4"
+/// api_version = "2024-06-01"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AzureConfig {
+    /// Deployments by the model name clients request
+    pub deployments: HashMap<String, AzureDeployment>,
+}
+
+/// The Azure deployment serving a model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AzureDeployment {
+    /// Deployment name in the Azure resource
+    pub deployment: String,
+    /// API version for this deployment, overriding the `api-version` header
+    #[serde(default)]
+    pub api_version: Option<String>,
+}
+
+impl AzureDeployment {
+    pub fn new(deployment: impl Into<String>) -> Self {
+        Self {
+            deployment: deployment.into(),
+            api_version: None,
+        }
+    }
+
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = Some(api_version.into());
+        self
+    }
+}
+
 pub struct AzureOpenAIProvider {
     http: HttpProviderClient,
     config: ProviderConfig,
-    base_url: String,
-    api_version: String,
+    api_version: HeaderTemplate,
 }
 
 impl AzureOpenAIProvider {
@@ -26,33 +73,59 @@ impl AzureOpenAIProvider {
         let api_version = config
             .headers
             .get("api-version")
-            .cloned()
-            .unwrap_or_else(|| "2024-02-15-preview".to_string());
+            .map(String::as_str)
+            .unwrap_or("2024-02-15-preview");
+        let api_version =
+            HeaderTemplate::parse(api_version).map_err(|e| ProviderError::Configuration {
+                message: format!("Invalid header 'api-version': {e}"),
+            })?;
 
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             Some(base_url.clone()),
             &base_url,
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self {
             http,
             config,
-            base_url,
             api_version,
         })
     }
 
-    fn build_url(&self, endpoint: &str, deployment_name: Option<&str>) -> String {
-        let deployment = deployment_name.unwrap_or("gpt-35-turbo");
-        format!(
-            "{}/openai/deployments/{}/{}?api-version={}",
-            self.base_url, deployment, endpoint, self.api_version
-        )
+    /// The path of `endpoint` on the deployment serving `model`, the model
+    /// name the client requested.
+    fn build_url(&self, endpoint: &str, model: &str) -> Result<String, ProviderError> {
+        let (deployment, api_version) = match &self.config.azure {
+            Some(azure) if !azure.deployments.is_empty() => {
+                let deployment = azure.deployments.get(model).ok_or_else(|| {
+                    ProviderError::Configuration {
+                        message: format!(
+                            "No Azure deployment configured for model '{model}' in azure.deployments"
+                        ),
+                    }
+                })?;
+                let api_version = match &deployment.api_version {
+                    Some(api_version) => api_version.clone(),
+                    None => self.api_version.render(Some(&deployment.deployment)),
+                };
+                (deployment.deployment.clone(), api_version)
+            }
+            _ => {
+                let deployment = self.map_model(model);
+                let api_version = self.api_version.render(Some(&deployment));
+                (deployment, api_version)
+            }
+        };
+        Ok(format!(
+            "/openai/deployments/{deployment}/{endpoint}?api-version={api_version}"
+        ))
     }
 
     fn map_model(&self, model: &str) -> String {
@@ -66,6 +139,7 @@ impl AzureOpenAIProvider {
     #[allow(dead_code)]
     async fn handle_error_response(&self, response: reqwest::Response) -> ProviderError {
         let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
 
         match response.text().await {
             Ok(body) => {
@@ -82,7 +156,7 @@ impl AzureOpenAIProvider {
                         404 => ProviderError::ModelNotFound {
                             model: "unknown".to_string(),
                         },
-                        429 => ProviderError::RateLimit,
+                        429 => ProviderError::RateLimit { retry_after },
                         _ => ProviderError::Api {
                             code: status.as_u16(),
                             message,
@@ -117,6 +191,14 @@ impl Provider for AzureOpenAIProvider {
         true
     }
 
+    fn supports_response_format(&self) -> bool {
+        true
+    }
+
+    fn supports_multiple_choices(&self) -> bool {
+        true
+    }
+
     fn supported_models(&self) -> Vec<String> {
         vec![
             "gpt-4".to_string(),
@@ -139,8 +221,11 @@ impl Provider for AzureOpenAIProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
+        let url = self.build_url("chat/completions", &request.model)?;
         request.model = self.map_model(&request.model);
-        let url = self.build_url("chat/completions", Some(&request.model));
 
         let chat_response: ChatResponse = self.http.post_json(&url, &request).await?;
         Ok(chat_response)
@@ -150,56 +235,27 @@ impl Provider for AzureOpenAIProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
+        let url = self.build_url("chat/completions", &request.model)?;
         request.model = self.map_model(&request.model);
         request.stream = Some(true);
 
-        let url = self.build_url("chat/completions", Some(&request.model));
-
         let response = self.http.post_json_raw(&url, &request).await?;
         if !response.status().is_success() {
             return Err(map_error_response(response).await);
         }
 
-        let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                match serde_json::from_str::<StreamChunk>(json_str) {
-                                    Ok(stream_chunk) => yield Ok(stream_chunk),
-                                    Err(e) => yield Err(ProviderError::Serialization(e)),
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => yield Err(ProviderError::Http(e)),
-                }
-            }
-        });
-
-        Ok(stream)
+        Ok(Box::pin(sse_json(response, self.name())))
     }
 
     async fn embedding(
         &self,
         mut request: EmbeddingRequest,
     ) -> Result<EmbeddingResponse, ProviderError> {
+        let url = self.build_url("embeddings", &request.model)?;
         request.model = self.map_model(&request.model);
-        let url = self.build_url("embeddings", Some(&request.model));
 
         let embedding_response: EmbeddingResponse = self.http.post_json(&url, &request).await?;
         Ok(embedding_response)
@@ -209,13 +265,12 @@ impl Provider for AzureOpenAIProvider {
         &self,
         mut request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
+        let model = request.model.as_deref().unwrap_or("dall-e-3");
+        let url = self.build_url("images/generations", model)?;
         if let Some(ref model) = request.model {
             request.model = Some(self.map_model(model));
         }
 
-        let model = request.model.as_deref().unwrap_or("dall-e-3");
-        let url = self.build_url("images/generations", Some(model));
-
         let image_response: ImageResponse = self.http.post_json(&url, &request).await?;
         Ok(image_response)
     }
@@ -224,44 +279,24 @@ impl Provider for AzureOpenAIProvider {
         &self,
         mut request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
+        let url = self.build_url("audio/transcriptions", &request.model)?;
         request.model = self.map_model(&request.model);
-        let url = self.build_url("audio/transcriptions", Some(&request.model));
-
-        let form = reqwest::multipart::Form::new()
-            .part(
-                "file",
-                reqwest::multipart::Part::bytes(request.file)
-                    .file_name("audio.mp3")
-                    .mime_str("audio/mpeg")?,
-            )
-            .text("model", request.model);
-
-        let form = if let Some(language) = request.language {
-            form.text("language", language)
-        } else {
-            form
-        };
 
-        let form = if let Some(prompt) = request.prompt {
-            form.text("prompt", prompt)
-        } else {
-            form
-        };
+        let form = audio_transcription_form(request)?;
 
         let response = self.http.post_multipart(&url, form).await?;
         if !response.status().is_success() {
             return Err(map_error_response(response).await);
         }
-        let audio_response: AudioResponse = response.json().await?;
-        Ok(audio_response)
+        audio_transcription_response(response).await
     }
 
     async fn text_to_speech(
         &self,
         mut request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
+        let url = self.build_url("audio/speech", &request.model)?;
         request.model = self.map_model(&request.model);
-        let url = self.build_url("audio/speech", Some(&request.model));
 
         let response = self.http.post_json_raw(&url, &request).await?;
         if !response.status().is_success() {
@@ -288,8 +323,8 @@ impl Provider for AzureOpenAIProvider {
 
         // Use a basic models list request instead of chat completion for health check
         let url = format!(
-            "{}/openai/models?api-version={}",
-            self.base_url, self.api_version
+            "/openai/models?api-version={}",
+            self.api_version.render(None)
         );
 
         let response = self.http.get_json::<serde_json::Value>(&url).await;
@@ -319,3 +354,66 @@ impl Provider for AzureOpenAIProvider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_mapped_model_uses_deployment_url() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/prod-gpt4/chat/completions"))
+            .and(query_param("api-version", "2024-06-01"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "Hello"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AzureOpenAIProvider::new(
+            ProviderConfig::new("azure", "test-key")
+                .with_base_url(server.uri())
+                .with_azure_deployment(
+                    "gpt-4",
+                    AzureDeployment::new("prod-gpt4").with_api_version("2024-06-01"),
+                ),
+        )
+        .unwrap();
+        assert_eq!(
+            provider.build_url("embeddings", "gpt-4").unwrap(),
+            "/openai/deployments/prod-gpt4/embeddings?api-version=2024-06-01"
+        );
+
+        let request = ChatRequest {
+            model: "gpt-4".to_string(),
+            messages: vec![Message::user("Hi")],
+            ..Default::default()
+        };
+        let response = provider.chat_completion(request.clone()).await.unwrap();
+        assert_eq!(response.choices[0].message.content, "Hello");
+
+        let unmapped = ChatRequest {
+            model: "gpt-4o".to_string(),
+            ..request
+        };
+        match provider.chat_completion(unmapped).await {
+            Err(ProviderError::Configuration { message }) => {
+                assert!(message.contains("'gpt-4o'"), "{message}")
+            }
+            other => panic!("expected a configuration error, got {other:?}"),
+        }
+    }
+}
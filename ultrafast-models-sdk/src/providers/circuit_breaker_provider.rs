@@ -79,6 +79,22 @@ impl Provider for CircuitBreakerProvider {
         self.inner.supports_function_calling()
     }
 
+    fn supports_request_metadata(&self) -> bool {
+        self.inner.supports_request_metadata()
+    }
+
+    fn supports_response_format(&self) -> bool {
+        self.inner.supports_response_format()
+    }
+
+    fn supports_multiple_choices(&self) -> bool {
+        self.inner.supports_multiple_choices()
+    }
+
+    fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        Some(&self.circuit_breaker)
+    }
+
     fn supported_models(&self) -> Vec<String> {
         self.inner.supported_models()
     }
@@ -175,6 +191,19 @@ impl Provider for CircuitBreakerProvider {
             Err(cb_error) => Err(self.handle_circuit_breaker_error(cb_error).await),
         }
     }
+
+    async fn probe(&self) -> Result<ProviderHealth, ProviderError> {
+        // Probes must not trip or reset the breaker
+        self.inner.probe().await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ProviderError> {
+        self.inner.list_models().await
+    }
+
+    async fn keepalive_ping(&self, path: &str) -> Result<(), ProviderError> {
+        self.inner.keepalive_ping(path).await
+    }
 }
 
 #[cfg(test)]
@@ -278,6 +307,7 @@ mod tests {
             recovery_timeout: Duration::from_millis(100),
             request_timeout: Duration::from_millis(50),
             half_open_max_calls: 1,
+            ..Default::default()
         };
 
         let cb_provider = CircuitBreakerProvider::new(mock_provider, cb_config);
@@ -306,6 +336,7 @@ mod tests {
             recovery_timeout: Duration::from_millis(100),
             request_timeout: Duration::from_millis(50),
             half_open_max_calls: 1,
+            ..Default::default()
         };
 
         let cb_provider = CircuitBreakerProvider::new(mock_provider, cb_config);
@@ -344,6 +375,7 @@ mod tests {
             recovery_timeout: Duration::from_millis(100),
             request_timeout: Duration::from_millis(50),
             half_open_max_calls: 1,
+            ..Default::default()
         };
 
         let cb_provider = CircuitBreakerProvider::new(mock_provider, cb_config);
@@ -364,4 +396,28 @@ mod tests {
 
         assert_eq!(cb_provider.get_circuit_state().await, CircuitState::Open);
     }
+
+    #[tokio::test]
+    async fn test_probe_bypasses_circuit_breaker() {
+        let mock_provider = Arc::new(MockProvider::new(
+            "test".to_string(),
+            true,
+            Duration::from_millis(10),
+        ));
+
+        let cb_config = CircuitBreakerConfig {
+            failure_threshold: 1,
+            recovery_timeout: Duration::from_millis(100),
+            request_timeout: Duration::from_millis(50),
+            half_open_max_calls: 1,
+            ..Default::default()
+        };
+
+        let cb_provider = CircuitBreakerProvider::new(mock_provider, cb_config);
+
+        for _ in 0..3 {
+            assert!(cb_provider.probe().await.is_err());
+        }
+        assert_eq!(cb_provider.get_circuit_state().await, CircuitState::Closed);
+    }
 }
@@ -3,11 +3,17 @@ use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
     ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
+use crate::providers::{
+    Capability, HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult,
+    TemperatureRange,
+};
 use async_stream::stream;
 use serde_json::json;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    map_error_response, merge_extra_fields, parse_retry_after, sse_json, AuthStrategy,
+    HttpProviderClient,
+};
 
 use std::collections::HashMap;
 use std::time::Instant;
@@ -20,14 +26,16 @@ pub struct CohereProvider {
 impl CohereProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://api.cohere.ai/v1",
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
         Ok(Self { http, config })
     }
 
@@ -42,6 +50,7 @@ impl CohereProvider {
     #[allow(dead_code)]
     async fn handle_error_response(&self, response: reqwest::Response) -> ProviderError {
         let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
 
         match response.text().await {
             Ok(body) => {
@@ -57,7 +66,7 @@ impl CohereProvider {
                         404 => ProviderError::ModelNotFound {
                             model: "unknown".to_string(),
                         },
-                        429 => ProviderError::RateLimit,
+                        429 => ProviderError::RateLimit { retry_after },
                         _ => ProviderError::Api {
                             code: status.as_u16(),
                             message,
@@ -126,11 +135,17 @@ impl Provider for CohereProvider {
         ]
     }
 
-    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+    async fn chat_completion(
+        &self,
+        mut request: ChatRequest,
+    ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::COHERE, request.temperature);
         let model = self.map_model(&request.model);
 
         // Convert OpenAI format to Cohere format
-        let cohere_request = json!({
+        let mut cohere_request = json!({
             "model": model,
             "message": request.messages.last().map(|m| m.content.clone()).unwrap_or_default(),
             "chat_history": request.messages[..request.messages.len()-1].iter().map(|m| {
@@ -148,6 +163,7 @@ impl Provider for CohereProvider {
             "max_tokens": request.max_tokens,
             "stream": false,
         });
+        merge_extra_fields(&mut cohere_request, &request.extra);
 
         let cohere_response: serde_json::Value =
             self.http.post_json("/chat", &cohere_request).await?;
@@ -195,11 +211,14 @@ impl Provider for CohereProvider {
 
     async fn stream_chat_completion(
         &self,
-        request: ChatRequest,
+        mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::COHERE, request.temperature);
         let model = self.map_model(&request.model);
 
-        let cohere_request = json!({
+        let mut cohere_request = json!({
             "model": model,
             "message": request.messages.last().map(|m| m.content.clone()).unwrap_or_default(),
             "chat_history": request.messages[..request.messages.len()-1].iter().map(|m| {
@@ -217,56 +236,42 @@ impl Provider for CohereProvider {
             "max_tokens": request.max_tokens,
             "stream": true,
         });
+        merge_extra_fields(&mut cohere_request, &request.extra);
 
         let response = self.http.post_json_raw("/chat", &cohere_request).await?;
         if !response.status().is_success() {
             return Err(map_error_response(response).await);
         }
 
+        let events = sse_json::<serde_json::Value>(response, self.name());
         let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                // Parse Cohere streaming format and convert to OpenAI format
-                                if let Ok(cohere_chunk) = serde_json::from_str::<serde_json::Value>(json_str) {
-                                    if let Some(text) = cohere_chunk["text"].as_str() {
-                                        let stream_chunk = StreamChunk {
-                                            id: "cohere-stream".to_string(),
-                                            object: "chat.completion.chunk".to_string(),
-                                            created: chrono::Utc::now().timestamp() as u64,
-                                            model: model.clone(),
-                                            choices: vec![crate::models::StreamChoice {
-                                                index: 0,
-                                                delta: crate::models::Delta {
-                                                    role: None,
-                                                    content: Some(text.to_string()),
-                                                    tool_calls: None,
-                                                },
-                                                finish_reason: None,
-                                            }],
-                                        };
-                                        yield Ok(stream_chunk);
-                                    }
-                                }
-                            }
+            let mut events = Box::pin(events);
+
+            while let Some(chunk) = futures::StreamExt::next(&mut events).await {
+                // Parse Cohere streaming format and convert to OpenAI format
+                match chunk {
+                    Ok(cohere_chunk) => {
+                        if let Some(text) = cohere_chunk["text"].as_str() {
+                            let stream_chunk = StreamChunk {
+                                id: "cohere-stream".to_string(),
+                                object: "chat.completion.chunk".to_string(),
+                                created: chrono::Utc::now().timestamp() as u64,
+                                model: model.clone(),
+                                choices: vec![crate::models::StreamChoice {
+                                    index: 0,
+                                    delta: crate::models::Delta {
+                                        role: None,
+                                        content: Some(text.to_string()),
+                                        tool_calls: None,
+                                    },
+                                    finish_reason: None,
+                                }],
+                                usage: None,
+                            };
+                            yield Ok(stream_chunk);
                         }
                     }
-                    Err(e) => yield Err(ProviderError::Http(e)),
+                    Err(e) => yield Err(e),
                 }
             }
         });
@@ -343,27 +348,30 @@ impl Provider for CohereProvider {
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by Cohere".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by Cohere".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by Cohere".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
@@ -396,3 +404,117 @@ impl Provider for CohereProvider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_usage_parsed_from_billed_units() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "Hello",
+                "generation_id": "gen-1",
+                "meta": {
+                    "billed_units": {"input_tokens": 9, "output_tokens": 2},
+                    "tokens": {"input_tokens": 75, "output_tokens": 2}
+                }
+            })))
+            .mount(&server)
+            .await;
+        let provider = CohereProvider::new(
+            ProviderConfig::new("cohere", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            model: "command-r".to_string(),
+            messages: vec![Message::user("Hi")],
+            ..Default::default()
+        };
+        let usage = provider
+            .chat_completion(request)
+            .await
+            .unwrap()
+            .usage
+            .unwrap();
+        assert_eq!(usage.prompt_tokens, 9);
+        assert_eq!(usage.completion_tokens, 2);
+        assert_eq!(usage.total_tokens, 11);
+    }
+
+    #[tokio::test]
+    async fn test_temperature_clamped_to_cohere_range() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "text": "Hello",
+                "generation_id": "gen-1"
+            })))
+            .mount(&server)
+            .await;
+        let provider = CohereProvider::new(
+            ProviderConfig::new("cohere", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            model: "command-r".to_string(),
+            messages: vec![Message::user("Hi")],
+            temperature: Some(1.8),
+            ..Default::default()
+        };
+        provider.chat_completion(request).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["temperature"], serde_json::json!(1.0));
+    }
+
+    #[tokio::test]
+    async fn test_stream_reads_newline_delimited_json() {
+        let events = [
+            serde_json::json!({"is_finished": false, "event_type": "stream-start", "generation_id": "gen-1"}),
+            serde_json::json!({"is_finished": false, "event_type": "text-generation", "text": "Hel"}),
+            serde_json::json!({"is_finished": false, "event_type": "text-generation", "text": "lo"}),
+            serde_json::json!({"is_finished": true, "event_type": "stream-end", "finish_reason": "COMPLETE"}),
+        ];
+        let body: String = events.iter().map(|event| format!("{event}\n")).collect();
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/stream+json")
+                    .set_body_string(body),
+            )
+            .mount(&server)
+            .await;
+        let provider = CohereProvider::new(
+            ProviderConfig::new("cohere", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let stream = provider
+            .stream_chat_completion(ChatRequest {
+                model: "command-r".to_string(),
+                messages: vec![Message::user("Hi")],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let chunks: Vec<_> = futures::StreamExt::collect(stream).await;
+
+        let text: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| chunk.unwrap().choices[0].delta.content.clone().unwrap())
+            .collect();
+        assert_eq!(text, ["Hel", "lo"]);
+    }
+}
@@ -1,13 +1,18 @@
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse,
+};
+use crate::providers::{
+    Capability, HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult,
+    TemperatureRange,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
-use async_stream::stream;
 use serde_json::json;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    map_error_response, parse_retry_after, sse_json, AuthStrategy, HttpProviderClient,
+};
+use super::request_template::RequestTemplate;
 
 use std::collections::HashMap;
 use std::time::Instant;
@@ -28,7 +33,11 @@ pub struct CustomProviderConfig {
 pub enum RequestFormat {
     OpenAI,
     Anthropic,
-    Custom { template: String },
+    Custom {
+        template: String,
+    },
+    /// A JSON body template; see [`RequestTemplate`]
+    Template(RequestTemplate),
 }
 
 #[derive(Debug, Clone)]
@@ -73,12 +82,14 @@ impl CustomProvider {
         };
 
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "http://localhost:8080",
             &config.headers,
             auth,
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self {
             http,
@@ -98,6 +109,7 @@ impl CustomProvider {
     #[allow(dead_code)]
     async fn handle_error_response(&self, response: reqwest::Response) -> ProviderError {
         let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
 
         match response.text().await {
             Ok(body) => {
@@ -114,7 +126,7 @@ impl CustomProvider {
                         404 => ProviderError::ModelNotFound {
                             model: "unknown".to_string(),
                         },
-                        429 => ProviderError::RateLimit,
+                        429 => ProviderError::RateLimit { retry_after },
                         _ => ProviderError::Api {
                             code: status.as_u16(),
                             message,
@@ -185,6 +197,9 @@ impl CustomProvider {
                     message: format!("Invalid custom request template: {e}"),
                 })
             }
+            RequestFormat::Template(template) => {
+                Ok(template.render(request, &self.map_model(&request.model)))
+            }
         }
     }
 
@@ -217,15 +232,7 @@ impl CustomProvider {
                         finish_reason: Some("stop".to_string()),
                         logprobs: None,
                     }],
-                    usage: Some(crate::models::Usage {
-                        prompt_tokens: response["usage"]["input_tokens"].as_u64().unwrap_or(0)
-                            as u32,
-                        completion_tokens: response["usage"]["output_tokens"].as_u64().unwrap_or(0)
-                            as u32,
-                        total_tokens: response["usage"]["input_tokens"].as_u64().unwrap_or(0)
-                            as u32
-                            + response["usage"]["output_tokens"].as_u64().unwrap_or(0) as u32,
-                    }),
+                    usage: serde_json::from_value(response["usage"].clone()).ok(),
                     system_fingerprint: None,
                 };
                 Ok(chat_response)
@@ -264,7 +271,13 @@ impl Provider for CustomProvider {
         vec!["custom-model".to_string()]
     }
 
-    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+    async fn chat_completion(
+        &self,
+        mut request: ChatRequest,
+    ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         let formatted_request = self.format_request(&request)?;
 
         let url = self.custom_config.chat_endpoint.to_string();
@@ -276,10 +289,20 @@ impl Provider for CustomProvider {
 
     async fn stream_chat_completion(
         &self,
-        request: ChatRequest,
+        mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
+        request.stream = Some(true);
         let mut formatted_request = self.format_request(&request)?;
-        formatted_request["stream"] = serde_json::Value::Bool(true);
+        // Templates place `{{params.stream}}` where their API expects it
+        if !matches!(
+            self.custom_config.request_format,
+            RequestFormat::Template(_)
+        ) {
+            formatted_request["stream"] = serde_json::Value::Bool(true);
+        }
 
         let url = self.custom_config.chat_endpoint.to_string();
         let response = self.http.post_json_raw(&url, &formatted_request).await?;
@@ -287,38 +310,7 @@ impl Provider for CustomProvider {
             return Err(map_error_response(response).await);
         }
 
-        let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                match serde_json::from_str::<StreamChunk>(json_str) {
-                                    Ok(stream_chunk) => yield Ok(stream_chunk),
-                                    Err(e) => yield Err(ProviderError::Serialization(e)),
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => yield Err(ProviderError::Http(e)),
-                }
-            }
-        });
-
-        Ok(stream)
+        Ok(Box::pin(sse_json(response, self.name())))
     }
 
     async fn embedding(
@@ -348,9 +340,10 @@ impl Provider for CustomProvider {
                 self.http.post_json(&url, &embedding_request).await?;
             Ok(embedding_response)
         } else {
-            Err(ProviderError::Configuration {
-                message: "Embeddings not supported by this custom provider".to_string(),
-            })
+            Err(ProviderError::unsupported(
+                self.name(),
+                Capability::Embeddings,
+            ))
         }
     }
 
@@ -358,27 +351,30 @@ impl Provider for CustomProvider {
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by custom providers".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by custom providers".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by custom providers".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
@@ -411,3 +407,113 @@ impl Provider for CustomProvider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_request_template_renders_custom_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "engine-large",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "ok"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let config = ProviderConfig::new("custom", "test-key")
+            .with_base_url(server.uri())
+            .with_model_mapping("large", "engine-large")
+            .with_request_template(json!({
+                "engine": "{{model}}",
+                "conversation": {"turns": "{{messages}}", "format": "chat"},
+                "options": {
+                    "heat": "{{params.temperature}}",
+                    "limit": "{{params.max_tokens}}",
+                    "stop": "{{params.stop}}"
+                },
+                "tags": ["gateway", "model={{model}}, limit={{params.max_tokens}}"]
+            }));
+        let provider = crate::providers::create_provider(config).unwrap();
+
+        let request = ChatRequest {
+            model: "large".to_string(),
+            messages: vec![Message::system("Be brief."), Message::user("Hi")],
+            temperature: Some(0.5),
+            max_tokens: Some(64),
+            ..Default::default()
+        };
+        provider.chat_completion(request).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "engine": "engine-large",
+                "conversation": {
+                    "turns": [
+                        {"role": "system", "content": "Be brief."},
+                        {"role": "user", "content": "Hi"}
+                    ],
+                    "format": "chat"
+                },
+                "options": {"heat": 0.5, "limit": 64},
+                "tags": ["gateway", "model=engine-large, limit=64"]
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_usage_parsed_from_input_output_token_names() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "local-model",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "ok"},
+                    "finish_reason": "stop"
+                }],
+                "usage": {"input_tokens": 21, "output_tokens": 4}
+            })))
+            .mount(&server)
+            .await;
+        let provider = crate::providers::create_provider(
+            ProviderConfig::new("custom", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            model: "local-model".to_string(),
+            messages: vec![Message::user("Hi")],
+            ..Default::default()
+        };
+        let usage = provider
+            .chat_completion(request)
+            .await
+            .unwrap()
+            .usage
+            .unwrap();
+        assert_eq!(usage.prompt_tokens, 21);
+        assert_eq!(usage.completion_tokens, 4);
+        assert_eq!(usage.total_tokens, 25);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::error::ProviderError;
+use crate::fairness::{FairSemaphore, FairnessConfig};
+use crate::models::{
+    AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse,
+};
+use crate::providers::{Provider, ProviderHealth, StreamResult};
+use futures::StreamExt;
+use std::sync::Arc;
+
+/// Wrapper that limits a provider's concurrent requests, sharing the slots
+/// fairly between models
+pub struct FairnessProvider {
+    inner: Arc<dyn Provider>,
+    semaphore: FairSemaphore,
+}
+
+impl FairnessProvider {
+    pub fn new(provider: Arc<dyn Provider>, config: &FairnessConfig) -> Self {
+        Self {
+            inner: provider,
+            semaphore: FairSemaphore::new(config),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for FairnessProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn supports_function_calling(&self) -> bool {
+        self.inner.supports_function_calling()
+    }
+
+    fn supports_request_metadata(&self) -> bool {
+        self.inner.supports_request_metadata()
+    }
+
+    fn supports_response_format(&self) -> bool {
+        self.inner.supports_response_format()
+    }
+
+    fn supports_multiple_choices(&self) -> bool {
+        self.inner.supports_multiple_choices()
+    }
+
+    fn circuit_breaker(&self) -> Option<&crate::circuit_breaker::CircuitBreaker> {
+        self.inner.circuit_breaker()
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        self.inner.supported_models()
+    }
+
+    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+        let _permit = self.semaphore.acquire(&request.model).await;
+        self.inner.chat_completion(request).await
+    }
+
+    async fn stream_chat_completion(
+        &self,
+        request: ChatRequest,
+    ) -> Result<StreamResult, ProviderError> {
+        let permit = self.semaphore.acquire(&request.model).await;
+        let stream = self.inner.stream_chat_completion(request).await?;
+        // The slot stays taken until the stream is finished or dropped
+        Ok(Box::pin(stream.map(move |chunk| {
+            let _ = &permit;
+            chunk
+        })))
+    }
+
+    async fn embedding(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, ProviderError> {
+        let _permit = self.semaphore.acquire(&request.model).await;
+        self.inner.embedding(request).await
+    }
+
+    async fn image_generation(
+        &self,
+        request: ImageRequest,
+    ) -> Result<ImageResponse, ProviderError> {
+        let model = request.model.clone().unwrap_or_default();
+        let _permit = self.semaphore.acquire(&model).await;
+        self.inner.image_generation(request).await
+    }
+
+    async fn audio_transcription(
+        &self,
+        request: AudioRequest,
+    ) -> Result<AudioResponse, ProviderError> {
+        let _permit = self.semaphore.acquire(&request.model).await;
+        self.inner.audio_transcription(request).await
+    }
+
+    async fn text_to_speech(
+        &self,
+        request: SpeechRequest,
+    ) -> Result<SpeechResponse, ProviderError> {
+        let _permit = self.semaphore.acquire(&request.model).await;
+        self.inner.text_to_speech(request).await
+    }
+
+    async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
+        self.inner.health_check().await
+    }
+
+    async fn probe(&self) -> Result<ProviderHealth, ProviderError> {
+        self.inner.probe().await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ProviderError> {
+        self.inner.list_models().await
+    }
+
+    async fn keepalive_ping(&self, path: &str) -> Result<(), ProviderError> {
+        self.inner.keepalive_ping(path).await
+    }
+}
@@ -1,11 +1,17 @@
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{map_error_response, take_line, AuthStrategy, HttpProviderClient};
 use crate::error::ProviderError;
 use crate::models::{
-    AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, Role, SpeechRequest, SpeechResponse, StreamChunk, Usage,
+    AudioRequest, AudioResponse, ChatRequest, ChatResponse, DeltaFunction, DeltaToolCall,
+    EmbeddingRequest, EmbeddingResponse, ImageRequest, ImageResponse, Role, SpeechRequest,
+    SpeechResponse, StreamChunk, Usage,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
+use crate::providers::{
+    Capability, HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult,
+    TemperatureRange,
+};
+use crate::tools::ToolFormat;
 use async_stream::stream;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::time::Instant;
 // use futures::StreamExt;
@@ -19,7 +25,8 @@ impl GeminiProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         // Gemini uses API key in header x-goog-api-key; use Header auth
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://generativelanguage.googleapis.com/v1beta",
             &config.headers,
@@ -27,7 +34,8 @@ impl GeminiProvider {
                 name: "x-goog-api-key".to_string(),
                 value: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self { http, config })
     }
@@ -54,7 +62,7 @@ impl Provider for GeminiProvider {
     }
 
     fn supports_function_calling(&self) -> bool {
-        false // Gemini doesn't support function calling yet
+        true
     }
 
     fn supported_models(&self) -> Vec<String> {
@@ -68,7 +76,13 @@ impl Provider for GeminiProvider {
         ]
     }
 
-    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+    async fn chat_completion(
+        &self,
+        mut request: ChatRequest,
+    ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::GEMINI, request.temperature);
         let model = self.map_model(&request.model);
         let path = format!("/models/{model}:generateContent");
 
@@ -81,8 +95,11 @@ impl Provider for GeminiProvider {
 
     async fn stream_chat_completion(
         &self,
-        request: ChatRequest,
+        mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::GEMINI, request.temperature);
         let model = self.map_model(&request.model);
         let path = format!("/models/{model}:streamGenerateContent");
 
@@ -95,17 +112,16 @@ impl Provider for GeminiProvider {
 
         let stream = Box::pin(stream! {
             let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
+            let mut buffer = Vec::new();
+            // Tool calls streamed so far, which index the next ones
+            let mut tool_calls_sent = 0;
 
             while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
                 match chunk_result {
                     Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
+                        buffer.extend_from_slice(&chunk);
 
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
+                        while let Some(line) = take_line(&mut buffer) {
 
                             if !line.is_empty() {
                                 // Try to parse as Gemini streaming response and convert to OpenAI format
@@ -114,12 +130,14 @@ impl Provider for GeminiProvider {
                                         if let Some(candidates) = gemini_chunk.get("candidates")
                                             .and_then(|c| c.as_array()) {
                                             for candidate in candidates {
-                                                if let Some(content) = candidate.get("content")
+                                                let parts = candidate.get("content")
                                                     .and_then(|c| c.get("parts"))
                                                     .and_then(|p| p.as_array())
-                                                    .and_then(|parts| parts.first())
-                                                    .and_then(|part| part.get("text"))
-                                                    .and_then(|t| t.as_str()) {
+                                                    .map(Vec::as_slice)
+                                                    .unwrap_or_default();
+                                                let content = parts_text(parts);
+                                                let tool_calls = stream_tool_calls(parts, &mut tool_calls_sent);
+                                                if !content.is_empty() || tool_calls.is_some() {
 
                                                     let stream_chunk = StreamChunk {
                                                         id: "gemini-stream".to_string(),
@@ -130,11 +148,12 @@ impl Provider for GeminiProvider {
                                                             index: 0,
                                                             delta: crate::models::Delta {
                                                                 role: Some(Role::Assistant),
-                                                                content: Some(content.to_string()),
-                                                                tool_calls: None,
+                                                                content: (!content.is_empty()).then_some(content),
+                                                                tool_calls,
                                                             },
                                                             finish_reason: None,
                                                         }],
+                                                        usage: None,
                                                     };
                                                     yield Ok(stream_chunk);
                                                 }
@@ -179,27 +198,30 @@ impl Provider for GeminiProvider {
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by Gemini".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by Gemini".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by Gemini".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
@@ -232,20 +254,47 @@ impl Provider for GeminiProvider {
 }
 
 impl GeminiProvider {
+    /// Gemini request for `request`. Assistant tool calls become
+    /// `functionCall` parts and tool results `functionResponse` parts.
     fn convert_to_gemini_format(&self, request: ChatRequest) -> GeminiRequest {
         let mut contents = Vec::new();
+        // Gemini matches tool results to calls by function name, not call ID
+        let mut call_names = HashMap::new();
 
         for message in &request.messages {
             let role = match message.role {
                 Role::User => "user",
                 Role::Assistant => "model",
                 Role::System => "user", // Gemini doesn't have system messages, treat as user
-                Role::Tool => "user",   // Gemini doesn't have tool messages, treat as user
+                Role::Tool => "user",   // Tool results are sent as user `functionResponse` parts
             };
 
-            let parts = vec![GeminiPart {
-                text: message.content.clone(),
-            }];
+            let mut parts = Vec::new();
+            if matches!(message.role, Role::Tool) {
+                let name = message.name.clone().or_else(|| {
+                    let call_id = message.tool_call_id.as_deref()?;
+                    call_names.get(call_id).cloned()
+                });
+                let response = serde_json::from_str::<Value>(&message.content)
+                    .unwrap_or_else(|_| json!(message.content));
+                parts.push(json!({
+                    "functionResponse": {
+                        "name": name.unwrap_or_default(),
+                        "response": {"content": response},
+                    }
+                }));
+            } else {
+                let tool_calls = message.tool_calls.as_deref().unwrap_or_default();
+                if !message.content.is_empty() || tool_calls.is_empty() {
+                    parts.push(json!({"text": message.content}));
+                }
+                for call in tool_calls {
+                    call_names.insert(call.id.clone(), call.function.name.clone());
+                }
+                if let Value::Array(call_parts) = ToolFormat::Gemini.encode_tool_calls(tool_calls) {
+                    parts.extend(call_parts);
+                }
+            }
 
             contents.push(GeminiContent {
                 role: role.to_string(),
@@ -263,6 +312,12 @@ impl GeminiProvider {
         GeminiRequest {
             contents,
             generation_config: Some(generation_config),
+            tools: request
+                .tools
+                .map(|tools| ToolFormat::Gemini.encode_tools(&tools)),
+            tool_config: request
+                .tool_choice
+                .map(|choice| ToolFormat::Gemini.encode_tool_choice(&choice)),
         }
     }
 
@@ -270,13 +325,14 @@ impl GeminiProvider {
         let mut choices = Vec::new();
 
         for (index, candidate) in response.candidates.iter().enumerate() {
-            let content = candidate
-                .content
-                .parts
-                .iter()
-                .map(|part| part.text.clone())
-                .collect::<Vec<String>>()
-                .join("");
+            let content = parts_text(&candidate.content.parts);
+            let tool_calls = ToolFormat::Gemini
+                .decode_tool_calls(&Value::Array(candidate.content.parts.clone()));
+            let finish_reason = if tool_calls.is_empty() {
+                "stop"
+            } else {
+                "tool_calls"
+            };
 
             choices.push(crate::models::Choice {
                 index: index as u32,
@@ -284,18 +340,19 @@ impl GeminiProvider {
                     role: Role::Assistant,
                     content,
                     name: None,
-                    tool_calls: None,
+                    tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
                     tool_call_id: None,
                 },
-                finish_reason: Some("stop".to_string()),
+                finish_reason: Some(finish_reason.to_string()),
                 logprobs: None,
             });
         }
 
-        let usage = response.usage_metadata.map(|u| Usage {
-            prompt_tokens: u.prompt_token_count,
-            completion_tokens: u.candidates_token_count,
-            total_tokens: u.total_token_count,
+        let usage = response.usage_metadata.map(|u| {
+            Usage::new(
+                u.prompt_token_count,
+                u.candidates_token_count + u.thoughts_token_count,
+            )
         });
 
         ChatResponse {
@@ -352,22 +409,61 @@ impl GeminiProvider {
     }
 }
 
+/// Text of the `text` parts among Gemini content `parts`.
+fn parts_text(parts: &[Value]) -> String {
+    parts
+        .iter()
+        .filter_map(|part| part.get("text").and_then(|text| text.as_str()))
+        .collect()
+}
+
+/// Tool call deltas for the `functionCall` parts of a streamed chunk.
+///
+/// Gemini streams each call whole, so a delta carries the call's name and
+/// all of its arguments. `sent` counts the calls streamed before, which
+/// index and name the new ones.
+fn stream_tool_calls(parts: &[Value], sent: &mut u32) -> Option<Vec<DeltaToolCall>> {
+    let calls = ToolFormat::Gemini.decode_tool_calls(&Value::Array(parts.to_vec()));
+    if calls.is_empty() {
+        return None;
+    }
+    let deltas = calls
+        .into_iter()
+        .map(|call| {
+            let index = *sent;
+            *sent += 1;
+            DeltaToolCall {
+                index,
+                id: Some(format!("call_{index}")),
+                call_type: Some(call.call_type),
+                function: Some(DeltaFunction {
+                    name: Some(call.function.name),
+                    arguments: Some(call.function.arguments),
+                }),
+            }
+        })
+        .collect();
+    Some(deltas)
+}
+
 // Gemini API request/response structures
 #[derive(serde::Serialize)]
 struct GeminiRequest {
     contents: Vec<GeminiContent>,
     generation_config: Option<GeminiGenerationConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_config: Option<Value>,
 }
 
+/// Content `parts` are kept as JSON: `text`, `functionCall` and
+/// `functionResponse` parts are converted with [`ToolFormat::Gemini`].
 #[derive(serde::Serialize, serde::Deserialize)]
 struct GeminiContent {
     role: String,
-    parts: Vec<GeminiPart>,
-}
-
-#[derive(serde::Serialize, serde::Deserialize)]
-struct GeminiPart {
-    text: String,
+    #[serde(default)]
+    parts: Vec<Value>,
 }
 
 #[derive(serde::Serialize)]
@@ -379,23 +475,29 @@ struct GeminiGenerationConfig {
 }
 
 #[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiResponse {
     candidates: Vec<GeminiCandidate>,
     usage_metadata: Option<GeminiUsage>,
 }
 
 #[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiCandidate {
     content: GeminiContent,
     #[allow(dead_code)]
     finish_reason: Option<String>,
 }
 
-#[derive(serde::Deserialize)]
+/// `candidatesTokenCount` is left out when nothing was generated, and
+/// `thoughtsTokenCount` by models that do not think.
+#[derive(Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 struct GeminiUsage {
     prompt_token_count: u32,
     candidates_token_count: u32,
-    total_token_count: u32,
+    /// Thinking tokens, billed as output but not part of the candidates
+    thoughts_token_count: u32,
 }
 
 #[derive(serde::Serialize)]
@@ -422,3 +524,143 @@ struct GeminiEmbeddingResponse {
 struct GeminiEmbedding {
     values: Vec<f32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_usage_parsed_from_usage_metadata() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-1.5-flash:generateContent"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [{"text": "Hello"}]},
+                    "finishReason": "STOP"
+                }],
+                "usageMetadata": {
+                    "promptTokenCount": 12,
+                    "candidatesTokenCount": 3,
+                    "thoughtsTokenCount": 4,
+                    "totalTokenCount": 19
+                }
+            })))
+            .mount(&server)
+            .await;
+        let provider = GeminiProvider::new(
+            ProviderConfig::new("gemini", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+
+        let request = ChatRequest {
+            model: "gemini-1.5-flash".to_string(),
+            messages: vec![Message::user("Hi")],
+            ..Default::default()
+        };
+        let usage = provider
+            .chat_completion(request)
+            .await
+            .unwrap()
+            .usage
+            .unwrap();
+        assert_eq!(usage.prompt_tokens, 12);
+        assert_eq!(usage.completion_tokens, 7);
+        assert_eq!(usage.total_tokens, 19);
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_round_trip_as_function_parts() {
+        use crate::models::{Function, FunctionCall, Tool, ToolCall, ToolChoice};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/gemini-1.5-flash:generateContent"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "candidates": [{
+                    "content": {"role": "model", "parts": [
+                        {"functionCall": {"name": "get_weather", "args": {"city": "Rome"}}}
+                    ]},
+                    "finishReason": "STOP"
+                }]
+            })))
+            .mount(&server)
+            .await;
+        let provider = GeminiProvider::new(
+            ProviderConfig::new("gemini", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        let request = ChatRequest {
+            model: "gemini-1.5-flash".to_string(),
+            messages: vec![
+                Message::user("Weather in Paris?"),
+                Message {
+                    role: Role::Assistant,
+                    content: String::new(),
+                    name: None,
+                    tool_calls: Some(vec![ToolCall {
+                        id: "call_0".to_string(),
+                        call_type: "function".to_string(),
+                        function: FunctionCall {
+                            name: "get_weather".to_string(),
+                            arguments: r#"{"city":"Paris"}"#.to_string(),
+                        },
+                    }]),
+                    tool_call_id: None,
+                },
+                Message {
+                    role: Role::Tool,
+                    content: r#"{"temp_c":21}"#.to_string(),
+                    name: None,
+                    tool_calls: None,
+                    tool_call_id: Some("call_0".to_string()),
+                },
+                Message::user("And in Rome?"),
+            ],
+            tools: Some(vec![Tool {
+                tool_type: "function".to_string(),
+                function: Function {
+                    name: "get_weather".to_string(),
+                    description: Some("Current weather".to_string()),
+                    parameters: serde_json::json!({"type": "object"}),
+                },
+            }]),
+            tool_choice: Some(ToolChoice::Required),
+            ..Default::default()
+        };
+        assert!(crate::tools::check_tool_support(&provider, &request).is_ok());
+        let response = provider.chat_completion(request).await.unwrap();
+
+        let sent: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(
+            sent["tools"][0]["function_declarations"][0]["name"],
+            "get_weather"
+        );
+        assert_eq!(
+            sent["tool_config"]["function_calling_config"]["mode"],
+            "ANY"
+        );
+        assert_eq!(
+            sent["contents"][1]["parts"],
+            serde_json::json!([{"functionCall": {"name": "get_weather", "args": {"city": "Paris"}}}])
+        );
+        assert_eq!(
+            sent["contents"][2]["parts"][0]["functionResponse"],
+            serde_json::json!({"name": "get_weather", "response": {"content": {"temp_c": 21}}})
+        );
+
+        let choice = &response.choices[0];
+        assert_eq!(choice.finish_reason.as_deref(), Some("tool_calls"));
+        let calls = choice.message.tool_calls.as_ref().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].function.name, "get_weather");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&calls[0].function.arguments).unwrap(),
+            serde_json::json!({"city": "Rome"})
+        );
+    }
+}
@@ -1,10 +1,14 @@
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    map_error_response, parse_retry_after, take_line, AuthStrategy, HttpProviderClient,
+};
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk, Usage,
+};
+use crate::providers::{
+    HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult, TemperatureRange,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
 use async_stream::stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -38,14 +42,16 @@ impl GoogleVertexAIProvider {
         });
 
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             Some(base_url.clone()),
             &base_url,
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self {
             http,
@@ -103,6 +109,7 @@ impl GoogleVertexAIProvider {
     #[allow(dead_code)]
     async fn handle_error_response(&self, response: reqwest::Response) -> ProviderError {
         let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
 
         match response.text().await {
             Ok(body) => {
@@ -119,7 +126,7 @@ impl GoogleVertexAIProvider {
                         404 => ProviderError::ModelNotFound {
                             model: "unknown".to_string(),
                         },
-                        429 => ProviderError::RateLimit,
+                        429 => ProviderError::RateLimit { retry_after },
                         _ => ProviderError::Api {
                             code: status.as_u16(),
                             message,
@@ -150,6 +157,8 @@ impl Provider for GoogleVertexAIProvider {
         true
     }
 
+    // Chat completions go through the PaLM `:predict` API, which takes no
+    // function declarations; Gemini tools are served by `GeminiProvider`
     fn supports_function_calling(&self) -> bool {
         false
     }
@@ -167,7 +176,13 @@ impl Provider for GoogleVertexAIProvider {
         ]
     }
 
-    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+    async fn chat_completion(
+        &self,
+        mut request: ChatRequest,
+    ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::GEMINI, request.temperature);
         let model = self.map_model(&request.model);
         let url = self.build_url(&model);
         // Convert OpenAI format to Vertex AI format
@@ -180,8 +195,11 @@ impl Provider for GoogleVertexAIProvider {
 
     async fn stream_chat_completion(
         &self,
-        request: ChatRequest,
+        mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::GEMINI, request.temperature);
         let model = self.map_model(&request.model);
         let url = format!(
             "{}/locations/{}/publishers/google/models/{}:streamGenerateContent",
@@ -197,17 +215,14 @@ impl Provider for GoogleVertexAIProvider {
 
         let stream = Box::pin(stream! {
             let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
+            let mut buffer = Vec::new();
 
             while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
                 match chunk_result {
                     Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
+                        buffer.extend_from_slice(&chunk);
 
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
+                        while let Some(line) = take_line(&mut buffer) {
 
                             if !line.is_empty() {
                                 // Try to parse as Vertex AI streaming response and convert to OpenAI format
@@ -237,6 +252,7 @@ impl Provider for GoogleVertexAIProvider {
                                                             },
                                                             finish_reason: None,
                                                         }],
+                                                        usage: None,
                                                     };
                                                     yield Ok(stream_chunk);
                                                 }
@@ -428,6 +444,13 @@ impl GoogleVertexAIProvider {
     }
 
     fn convert_from_vertex_format(&self, response: VertexAIResponse) -> ChatResponse {
+        let usage = response.metadata.map(|metadata| {
+            let tokens = metadata.token_metadata;
+            Usage::new(
+                tokens.input_token_count.total_tokens,
+                tokens.output_token_count.total_tokens,
+            )
+        });
         let choices = response
             .predictions
             .into_iter()
@@ -455,7 +478,7 @@ impl GoogleVertexAIProvider {
             created: chrono::Utc::now().timestamp() as u64,
             model: "chat-bison".to_string(),
             choices,
-            usage: None,
+            usage,
             system_fingerprint: None,
         }
     }
@@ -490,6 +513,28 @@ struct VertexAIParameters {
 #[derive(Debug, Serialize, Deserialize)]
 struct VertexAIResponse {
     predictions: Vec<VertexAIPrediction>,
+    #[serde(default)]
+    metadata: Option<VertexAIMetadata>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VertexAIMetadata {
+    #[serde(default)]
+    token_metadata: VertexAITokenMetadata,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct VertexAITokenMetadata {
+    input_token_count: VertexAITokenCount,
+    output_token_count: VertexAITokenCount,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct VertexAITokenCount {
+    total_tokens: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -552,3 +597,31 @@ struct VertexAIGenerationConfig {
     top_p: Option<f32>,
     top_k: Option<i32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_parsed_from_token_metadata() {
+        let provider = GoogleVertexAIProvider::new(
+            ProviderConfig::new("google", "test-key").with_header("project-id", "test-project"),
+        )
+        .unwrap();
+        let response: VertexAIResponse = serde_json::from_value(serde_json::json!({
+            "predictions": [{"candidates": [{"author": "1", "content": "Hello"}]}],
+            "metadata": {
+                "tokenMetadata": {
+                    "inputTokenCount": {"totalTokens": 8, "totalBillableCharacters": 30},
+                    "outputTokenCount": {"totalTokens": 2, "totalBillableCharacters": 5}
+                }
+            }
+        }))
+        .unwrap();
+
+        let usage = provider.convert_from_vertex_format(response).usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 8);
+        assert_eq!(usage.completion_tokens, 2);
+        assert_eq!(usage.total_tokens, 10);
+    }
+}
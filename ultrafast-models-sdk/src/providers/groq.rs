@@ -1,12 +1,14 @@
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse,
+};
+use crate::providers::{
+    Capability, HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult,
+    TemperatureRange,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
-use async_stream::stream;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{map_error_response, sse_json, AuthStrategy, HttpProviderClient};
 
 use std::collections::HashMap;
 use std::time::Instant;
@@ -19,14 +21,16 @@ pub struct GroqProvider {
 impl GroqProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://api.groq.com/openai/v1",
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self { http, config })
     }
@@ -54,6 +58,10 @@ impl Provider for GroqProvider {
         true
     }
 
+    fn supports_response_format(&self) -> bool {
+        true
+    }
+
     fn supported_models(&self) -> Vec<String> {
         vec![
             "llama3-8b-8192".to_string(),
@@ -67,6 +75,9 @@ impl Provider for GroqProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         request.model = self.map_model(&request.model);
 
         let chat_response: ChatResponse =
@@ -78,6 +89,9 @@ impl Provider for GroqProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         request.model = self.map_model(&request.model);
         request.stream = Some(true);
 
@@ -90,74 +104,47 @@ impl Provider for GroqProvider {
             return Err(map_error_response(response).await);
         }
 
-        let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                match serde_json::from_str::<StreamChunk>(json_str) {
-                                    Ok(stream_chunk) => yield Ok(stream_chunk),
-                                    Err(e) => yield Err(ProviderError::Serialization(e)),
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => yield Err(ProviderError::Http(e)),
-                }
-            }
-        });
-
-        Ok(stream)
+        Ok(Box::pin(sse_json(response, self.name())))
     }
 
     async fn embedding(
         &self,
         _request: EmbeddingRequest,
     ) -> Result<EmbeddingResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Embeddings not supported by Groq".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::Embeddings,
+        ))
     }
 
     async fn image_generation(
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by Groq".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by Groq".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by Groq".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
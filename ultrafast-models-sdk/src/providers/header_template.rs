@@ -0,0 +1,148 @@
+//! Templated provider header values.
+//!
+//! Values in a provider's `headers` map may reference environment variables
+//! as `${ENV_VAR}` and the requested model as `{{model}}`:
+//!
+//! ```toml
+//! [providers.azure-openai.headers]
+//! api-version = "${AZURE_API_VERSION}"
+//! x-route = "gateway-{{model}}"
+//! ```
+//!
+//! Placeholders are resolved for each request, `{{model}}` from the `model`
+//! field of the request body. Templates are checked when the provider is
+//! created: unknown placeholders and unset environment variables are
+//! configuration errors.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Env(String),
+    Model,
+}
+
+/// A header value with its placeholders parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderTemplate {
+    parts: Vec<Part>,
+}
+
+impl HeaderTemplate {
+    /// Parse `value`, failing on malformed or unknown placeholders and on
+    /// environment variables that are not set.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = value;
+
+        while !rest.is_empty() {
+            let (placeholder, close) = if rest.starts_with("${") {
+                (true, "}")
+            } else if rest.starts_with("{{") {
+                (false, "}}")
+            } else {
+                let mut chars = rest.chars();
+                literal.extend(chars.next());
+                rest = chars.as_str();
+                continue;
+            };
+
+            let end = rest[2..]
+                .find(close)
+                .ok_or_else(|| format!("unterminated placeholder in '{value}'"))?;
+            let name = rest[2..2 + end].trim();
+            rest = &rest[2 + end + close.len()..];
+
+            if !literal.is_empty() {
+                parts.push(Part::Literal(std::mem::take(&mut literal)));
+            }
+            if placeholder {
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(format!("invalid environment variable name '{name}'"));
+                }
+                if std::env::var(name).is_err() {
+                    return Err(format!("environment variable '{name}' is not set"));
+                }
+                parts.push(Part::Env(name.to_string()));
+            } else if name == "model" {
+                parts.push(Part::Model);
+            } else {
+                return Err(format!("unknown placeholder '{{{{{name}}}}}'"));
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Whether the value has no placeholders.
+    pub fn is_static(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|part| matches!(part, Part::Literal(_)))
+    }
+
+    /// Whether the value references `{{model}}`.
+    pub fn uses_model(&self) -> bool {
+        self.parts.contains(&Part::Model)
+    }
+
+    /// The value for a request for `model`. Variables unset since the
+    /// template was parsed resolve to an empty string.
+    pub fn render(&self, model: Option<&str>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.clone(),
+                Part::Env(name) => std::env::var(name).unwrap_or_default(),
+                Part::Model => model.unwrap_or_default().to_string(),
+            })
+            .collect()
+    }
+}
+
+/// Check every templated value in a provider's `headers` map.
+pub fn validate_header_templates(headers: &HashMap<String, String>) -> Result<(), String> {
+    for (name, value) in headers {
+        HeaderTemplate::parse(value).map_err(|e| format!("header '{name}': {e}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_placeholders_resolved_at_render() {
+        std::env::set_var("ULTRAFAST_TEST_API_VERSION", "2024-06-01");
+        let template = HeaderTemplate::parse("v=${ULTRAFAST_TEST_API_VERSION};").unwrap();
+        assert!(!template.is_static());
+        assert_eq!(template.render(None), "v=2024-06-01;");
+
+        std::env::set_var("ULTRAFAST_TEST_API_VERSION", "2024-10-21");
+        assert_eq!(template.render(None), "v=2024-10-21;");
+
+        assert_eq!(
+            HeaderTemplate::parse("${ULTRAFAST_TEST_UNSET_VARIABLE}"),
+            Err("environment variable 'ULTRAFAST_TEST_UNSET_VARIABLE' is not set".to_string())
+        );
+    }
+
+    #[test]
+    fn test_model_placeholder_and_unknown_placeholders() {
+        let template = HeaderTemplate::parse("route-{{ model }}").unwrap();
+        assert!(template.uses_model());
+        assert_eq!(template.render(Some("gpt-4o")), "route-gpt-4o");
+
+        assert!(HeaderTemplate::parse("plain {value}").unwrap().is_static());
+        assert_eq!(
+            HeaderTemplate::parse("{{provider}}"),
+            Err("unknown placeholder '{{provider}}'".to_string())
+        );
+        assert!(HeaderTemplate::parse("route-{{model").is_err());
+    }
+}
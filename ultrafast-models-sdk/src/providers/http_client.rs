@@ -1,10 +1,34 @@
 use crate::error::ProviderError;
-use reqwest::{header::HeaderMap, Client, Method, Response};
+use crate::models::{AudioRequest, AudioResponse};
+use crate::providers::header_template::HeaderTemplate;
+use crate::providers::interceptor::InterceptorChain;
+use async_stream::stream;
+use futures::Stream;
+use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::{Client, Method, RequestBuilder, Response};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::collections::HashMap;
+use std::future::Future;
 use std::time::Duration;
 
+/// Header carrying the caller's request ID to providers.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Run `future` with `request_id` as the correlation ID of the provider
+/// requests it sends.
+///
+/// Requests sent through [`HttpProviderClient`] within `future` carry the ID
+/// as an `x-request-id` header, unless the provider's `headers` set one.
+/// Work spawned onto other tasks does not inherit it.
+pub async fn with_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
 #[derive(Clone, Debug)]
 pub enum AuthStrategy {
     Bearer { token: String },
@@ -17,20 +41,26 @@ pub struct HttpProviderClient {
     http: Client,
     base_url: String,
     default_headers: HeaderMap,
+    /// Headers with placeholders, resolved for each request
+    header_templates: Vec<(HeaderName, HeaderTemplate)>,
+    interceptors: InterceptorChain,
 }
 
 impl HttpProviderClient {
     pub fn new(
+        connect_timeout: Duration,
         timeout: Duration,
         base_url: Option<String>,
         default_base: &str,
         headers: &HashMap<String, String>,
         auth: AuthStrategy,
     ) -> Result<Self, ProviderError> {
-        let http = Client::builder().timeout(timeout).build().map_err(|e| {
-            ProviderError::Configuration {
-                message: format!("Failed to create HTTP client: {e}"),
-            }
+        let mut builder = Client::builder().timeout(timeout);
+        if !connect_timeout.is_zero() {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        let http = builder.build().map_err(|e| ProviderError::Configuration {
+            message: format!("Failed to create HTTP client: {e}"),
         })?;
 
         let mut default_headers = HeaderMap::new();
@@ -49,8 +79,17 @@ impl HttpProviderClient {
             AuthStrategy::None => {}
         }
 
+        let mut header_templates = Vec::new();
         for (k, v) in headers {
-            if let (Ok(name), Ok(value)) = (k.parse::<reqwest::header::HeaderName>(), v.parse()) {
+            let template = HeaderTemplate::parse(v).map_err(|e| ProviderError::Configuration {
+                message: format!("Invalid header '{k}': {e}"),
+            })?;
+            let Ok(name) = k.parse::<HeaderName>() else {
+                continue;
+            };
+            if !template.is_static() {
+                header_templates.push((name, template));
+            } else if let Ok(value) = v.parse() {
                 default_headers.insert(name, value);
             }
         }
@@ -61,9 +100,29 @@ impl HttpProviderClient {
             http,
             base_url,
             default_headers,
+            header_templates,
+            interceptors: InterceptorChain::default(),
         })
     }
 
+    /// Run `interceptors` around every request sent by this client.
+    pub fn with_interceptors(mut self, interceptors: InterceptorChain) -> Self {
+        self.interceptors = interceptors;
+        self
+    }
+
+    /// Send `request` through the interceptor chain.
+    async fn send(&self, request: RequestBuilder) -> Result<Response, ProviderError> {
+        if self.interceptors.is_empty() {
+            return Ok(request.send().await?);
+        }
+        let mut request = request.build()?;
+        self.interceptors.on_request(&mut request).await?;
+        let response = self.http.execute(request).await?;
+        self.interceptors.on_response(&response).await;
+        Ok(response)
+    }
+
     fn build_url(&self, path: &str) -> String {
         if path.starts_with('/') {
             format!("{}{}", self.base_url, path)
@@ -72,8 +131,28 @@ impl HttpProviderClient {
         }
     }
 
-    fn build_headers(&self) -> HeaderMap {
-        self.default_headers.clone()
+    /// Headers for a request for `model`, with templated values resolved.
+    fn build_headers(&self, model: Option<&str>) -> HeaderMap {
+        let mut headers = self.default_headers.clone();
+        for (name, template) in &self.header_templates {
+            if let Ok(value) = template.render(model).parse() {
+                headers.insert(name.clone(), value);
+            }
+        }
+        if let Ok(Ok(request_id)) = REQUEST_ID.try_with(|request_id| request_id.parse()) {
+            headers.entry(REQUEST_ID_HEADER).or_insert(request_id);
+        }
+        headers
+    }
+
+    /// The `model` field of a request body, read only when a header needs it.
+    fn body_model<T: Serialize>(&self, body: &T) -> Option<String> {
+        if !self.header_templates.iter().any(|(_, t)| t.uses_model()) {
+            return None;
+        }
+        serde_json::to_value(body).ok()?["model"]
+            .as_str()
+            .map(str::to_string)
     }
 
     pub async fn post_json<TReq: Serialize, TResp: DeserializeOwned>(
@@ -83,11 +162,12 @@ impl HttpProviderClient {
     ) -> Result<TResp, ProviderError> {
         let url = self.build_url(path);
         let resp = self
-            .http
-            .request(Method::POST, url)
-            .headers(self.build_headers())
-            .json(body)
-            .send()
+            .send(
+                self.http
+                    .request(Method::POST, url)
+                    .headers(self.build_headers(self.body_model(body).as_deref()))
+                    .json(body),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -103,11 +183,12 @@ impl HttpProviderClient {
     ) -> Result<Response, ProviderError> {
         let url = self.build_url(path);
         let resp = self
-            .http
-            .request(Method::POST, url)
-            .headers(self.build_headers())
-            .json(body)
-            .send()
+            .send(
+                self.http
+                    .request(Method::POST, url)
+                    .headers(self.build_headers(self.body_model(body).as_deref()))
+                    .json(body),
+            )
             .await?;
         Ok(resp)
     }
@@ -119,11 +200,24 @@ impl HttpProviderClient {
     ) -> Result<Response, ProviderError> {
         let url = self.build_url(path);
         let resp = self
-            .http
-            .request(Method::POST, url)
-            .headers(self.build_headers())
-            .multipart(form)
-            .send()
+            .send(
+                self.http
+                    .request(Method::POST, url)
+                    .headers(self.build_headers(None))
+                    .multipart(form),
+            )
+            .await?;
+        Ok(resp)
+    }
+
+    pub async fn get_raw(&self, path: &str) -> Result<Response, ProviderError> {
+        let url = self.build_url(path);
+        let resp = self
+            .send(
+                self.http
+                    .request(Method::GET, url)
+                    .headers(self.build_headers(None)),
+            )
             .await?;
         Ok(resp)
     }
@@ -134,10 +228,11 @@ impl HttpProviderClient {
     ) -> Result<TResp, ProviderError> {
         let url = self.build_url(path);
         let resp = self
-            .http
-            .request(Method::GET, url)
-            .headers(self.build_headers())
-            .send()
+            .send(
+                self.http
+                    .request(Method::GET, url)
+                    .headers(self.build_headers(None)),
+            )
             .await?;
 
         if !resp.status().is_success() {
@@ -147,8 +242,145 @@ impl HttpProviderClient {
     }
 }
 
+/// Remove the first complete line from a streamed response `buffer`.
+///
+/// Streams are buffered as bytes and decoded one whole line at a time, so a
+/// multi-byte character split across network chunks reaches the client
+/// intact instead of as replacement characters.
+pub fn take_line(buffer: &mut Vec<u8>) -> Option<String> {
+    let line_end = buffer.iter().position(|&byte| byte == b'\n')?;
+    let line: Vec<u8> = buffer.drain(..=line_end).collect();
+    Some(String::from_utf8_lossy(&line).trim().to_string())
+}
+
+/// What one line of an SSE response holds.
+enum SseLine<'a> {
+    /// The payload of a `data:` line, with or without a space after the
+    /// colon, or a bare line of newline-delimited JSON
+    Data(&'a str),
+    /// The `[DONE]` marker
+    Done,
+    /// A blank line, comment or other SSE field
+    Ignored,
+    /// Text that is not SSE at all, such as a plain-text error
+    Raw(&'a str),
+}
+
+fn sse_line(line: &str) -> SseLine<'_> {
+    match line.strip_prefix("data:").map(str::trim_start) {
+        Some("[DONE]") => SseLine::Done,
+        // Chunks are JSON; plain text in their place is an upstream error
+        Some(data) if data.starts_with(['{', '[']) => SseLine::Data(data),
+        Some(data) => SseLine::Raw(data),
+        // Newline-delimited JSON streams, such as Cohere's, have no prefix
+        None if line.starts_with(['{', '[']) => SseLine::Data(line),
+        None if line.is_empty() || line.starts_with(':') => SseLine::Ignored,
+        None => {
+            let field = line.split(':').next().unwrap_or_default().trim();
+            if matches!(field, "event" | "id" | "retry") {
+                SseLine::Ignored
+            } else {
+                SseLine::Raw(line)
+            }
+        }
+    }
+}
+
+/// Error for plain text read in place of SSE events: `text` and whatever
+/// followed it in `rest`.
+fn raw_text_error(provider: &str, text: &str, rest: &[u8]) -> ProviderError {
+    let rest = String::from_utf8_lossy(rest);
+    let message = format!("{text}\n{rest}").trim().to_string();
+    ProviderError::StreamError {
+        provider: provider.to_string(),
+        message,
+    }
+}
+
+/// The `data:` payloads of an SSE response from `provider`, in order.
+///
+/// OpenAI-compatible upstreams end streams differently: `data: [DONE]`,
+/// `data:[DONE]`, or just closing the connection, possibly without a final
+/// newline. Each of these ends this stream cleanly, after any data read
+/// before it; the `[DONE]` marker itself is not returned. Lines holding a
+/// bare JSON object or array are returned as data too, so newline-delimited
+/// JSON streams are read the same way.
+///
+/// Some upstreams report failures mid-stream as plain text rather than as a
+/// JSON event. That text ends the stream with a
+/// [`ProviderError::StreamError`] naming `provider`.
+pub fn sse_data(
+    response: Response,
+    provider: &str,
+) -> impl Stream<Item = Result<String, ProviderError>> + Send {
+    let provider = provider.to_string();
+    stream! {
+        let mut bytes_stream = response.bytes_stream();
+        let mut buffer = Vec::new();
+
+        loop {
+            while let Some(line) = take_line(&mut buffer) {
+                match sse_line(&line) {
+                    SseLine::Data(data) => yield Ok(data.to_string()),
+                    SseLine::Done => return,
+                    SseLine::Ignored => {}
+                    SseLine::Raw(text) => {
+                        yield Err(raw_text_error(&provider, text, &buffer));
+                        return;
+                    }
+                }
+            }
+            match futures::StreamExt::next(&mut bytes_stream).await {
+                Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                Some(Err(e)) => {
+                    yield Err(ProviderError::Http(e));
+                    return;
+                }
+                None => break,
+            }
+        }
+
+        // The connection closed; the last line may lack its newline
+        let line = String::from_utf8_lossy(&buffer).trim().to_string();
+        match sse_line(&line) {
+            SseLine::Data(data) => yield Ok(data.to_string()),
+            SseLine::Raw(text) => yield Err(raw_text_error(&provider, text, &[])),
+            SseLine::Done | SseLine::Ignored => {}
+        }
+    }
+}
+
+/// The `data:` payloads of an SSE response from `provider`, each parsed as
+/// JSON into `T`. The stream ends as [`sse_data`] does.
+///
+/// A payload that is not valid `T` yields a [`ProviderError::Serialization`]
+/// and the stream carries on with the next one.
+pub fn sse_json<T: DeserializeOwned>(
+    response: Response,
+    provider: &str,
+) -> impl Stream<Item = Result<T, ProviderError>> + Send {
+    futures::StreamExt::map(sse_data(response, provider), |data| {
+        data.and_then(|data| serde_json::from_str(&data).map_err(ProviderError::Serialization))
+    })
+}
+
+/// Parse a `Retry-After` header given as a number of seconds.
+///
+/// The HTTP-date form is not supported and yields `None`.
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
 pub async fn map_error_response(resp: Response) -> ProviderError {
     let status = resp.status();
+    let retry_after = parse_retry_after(resp.headers());
     match resp.text().await {
         Ok(body) => {
             // Try to pull a message from common JSON error shapes
@@ -164,7 +396,7 @@ pub async fn map_error_response(resp: Response) -> ProviderError {
                 404 => ProviderError::ModelNotFound {
                     model: "unknown".to_string(),
                 },
-                429 => ProviderError::RateLimit,
+                429 => ProviderError::RateLimit { retry_after },
                 code => ProviderError::Api { code, message },
             }
         }
@@ -174,3 +406,67 @@ pub async fn map_error_response(resp: Response) -> ProviderError {
         },
     }
 }
+
+/// Merge passthrough fields from `ChatRequest::extra` into a hand-built JSON body.
+///
+/// Keys the provider adapter already set take precedence, so clients cannot
+/// override the translated `model`, `messages`, etc. through `extra`.
+pub fn merge_extra_fields(
+    body: &mut serde_json::Value,
+    extra: &HashMap<String, serde_json::Value>,
+) {
+    if let Some(obj) = body.as_object_mut() {
+        for (key, value) in extra {
+            obj.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+}
+
+/// Build the multipart form for an OpenAI-style `/audio/transcriptions` call.
+///
+/// The file keeps the name and mime type it was uploaded with, so providers
+/// that detect the audio format from them see the original values.
+pub fn audio_transcription_form(
+    request: AudioRequest,
+) -> Result<reqwest::multipart::Form, ProviderError> {
+    let file = reqwest::multipart::Part::bytes(request.file)
+        .file_name(request.file_name.unwrap_or_else(|| "audio.mp3".to_string()))
+        .mime_str(request.mime_type.as_deref().unwrap_or("audio/mpeg"))?;
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", file)
+        .text("model", request.model);
+
+    for (name, value) in [
+        ("language", request.language),
+        ("prompt", request.prompt),
+        ("response_format", request.response_format),
+        ("temperature", request.temperature.map(|t| t.to_string())),
+    ] {
+        if let Some(value) = value {
+            form = form.text(name, value);
+        }
+    }
+    Ok(form)
+}
+
+/// Read a transcription response, which is plain text rather than JSON for
+/// the `text`, `srt` and `vtt` response formats.
+pub async fn audio_transcription_response(
+    response: Response,
+) -> Result<AudioResponse, ProviderError> {
+    let is_json = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|ct| ct.to_str().ok())
+        .is_none_or(|ct| ct.starts_with("application/json"));
+    if is_json {
+        return Ok(response.json().await?);
+    }
+    Ok(AudioResponse {
+        text: response.text().await?,
+        language: None,
+        duration: None,
+        words: None,
+        segments: None,
+    })
+}
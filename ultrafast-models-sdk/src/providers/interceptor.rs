@@ -0,0 +1,168 @@
+//! Provider HTTP interceptors.
+//!
+//! An [`Interceptor`] sees every HTTP request a provider sends, after the
+//! adapter has built it and before it goes out, and every response that
+//! comes back. It can rewrite the request (add auth headers, sign the body,
+//! change the URL) without forking the adapter.
+//!
+//! Interceptors are registered per provider with
+//! [`ProviderConfig::with_interceptor`](super::ProviderConfig::with_interceptor)
+//! and run in registration order for requests and for responses. They are
+//! code rather than configuration, so they are not read from or written to
+//! configuration files.
+
+use crate::error::ProviderError;
+use std::fmt;
+use std::sync::Arc;
+
+/// Hook around the HTTP requests of one provider.
+#[async_trait::async_trait]
+pub trait Interceptor: Send + Sync {
+    /// Change the outgoing `request`. An error stops the request from being
+    /// sent and is returned to the caller.
+    ///
+    /// JSON bodies are available through `request.body()`; multipart bodies
+    /// are streamed and cannot be read here.
+    async fn on_request(&self, request: &mut reqwest::Request) -> Result<(), ProviderError> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Inspect the response to a request, before its body is read.
+    async fn on_response(&self, response: &reqwest::Response) {
+        let _ = response;
+    }
+}
+
+/// Ordered interceptors of one provider.
+#[derive(Clone, Default)]
+pub struct InterceptorChain {
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+impl InterceptorChain {
+    pub fn push(&mut self, interceptor: Arc<dyn Interceptor>) {
+        self.interceptors.push(interceptor);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interceptors.is_empty()
+    }
+
+    pub(crate) async fn on_request(
+        &self,
+        request: &mut reqwest::Request,
+    ) -> Result<(), ProviderError> {
+        for interceptor in &self.interceptors {
+            interceptor.on_request(request).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn on_response(&self, response: &reqwest::Response) {
+        for interceptor in &self.interceptors {
+            interceptor.on_response(response).await;
+        }
+    }
+}
+
+impl fmt::Debug for InterceptorChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InterceptorChain")
+            .field("len", &self.interceptors.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ChatRequest, Message};
+    use crate::providers::openai::OpenAIProvider;
+    use crate::providers::{Provider, ProviderConfig};
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    /// Signs each request with its path and body length.
+    struct Signer {
+        last_status: Arc<AtomicU16>,
+    }
+
+    fn signature(path: &str, body: &[u8]) -> String {
+        format!("{}:{}", path, body.len())
+    }
+
+    #[async_trait::async_trait]
+    impl Interceptor for Signer {
+        async fn on_request(&self, request: &mut reqwest::Request) -> Result<(), ProviderError> {
+            let body = request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .unwrap_or(&[]);
+            let value = signature(request.url().path(), body);
+            request
+                .headers_mut()
+                .insert("x-signature", value.parse().unwrap());
+            Ok(())
+        }
+
+        async fn on_response(&self, response: &reqwest::Response) {
+            self.last_status
+                .store(response.status().as_u16(), Ordering::SeqCst);
+        }
+    }
+
+    struct SignedBy;
+
+    impl wiremock::Match for SignedBy {
+        fn matches(&self, request: &Request) -> bool {
+            request
+                .headers
+                .get("x-signature")
+                .is_some_and(|value| value == signature(request.url.path(), &request.body).as_str())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptor_signs_outgoing_request() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(SignedBy)
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 1,
+                "model": "gpt-4o-mini",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "signed"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let last_status = Arc::new(AtomicU16::new(0));
+        let provider = OpenAIProvider::new(
+            ProviderConfig::new("openai", "test-key")
+                .with_base_url(server.uri())
+                .with_interceptor(Signer {
+                    last_status: last_status.clone(),
+                }),
+        )
+        .unwrap();
+        let response = provider
+            .chat_completion(ChatRequest {
+                model: "gpt-4o-mini".to_string(),
+                messages: vec![Message::user("Hi")],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(response.choices[0].message.content, "signed");
+        assert_eq!(last_status.load(Ordering::SeqCst), 200);
+    }
+}
@@ -0,0 +1,292 @@
+use crate::error::ProviderError;
+use crate::models::{
+    AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse,
+};
+use crate::providers::{Provider, ProviderHealth, StreamResult};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Keep-alive pings sent to a provider while one of its streams is idle.
+///
+/// Each ping is a separate request on its own connection: nothing is
+/// written to the stalled stream, which HTTP/1.1 has no way to do. Pings show
+/// the provider's API that the gateway is still active and log, at debug
+/// level, a provider that stopped answering while the stream hangs; they do
+/// not stop the stream's own connection from being closed for inactivity.
+///
+/// ```toml
+/// [providers.openai.stream_keepalive]
+/// idle_interval = "15s"
+/// ping_path = "/models"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamKeepaliveConfig {
+    /// How long a stream may go without a chunk before a ping is sent
+    #[serde(with = "crate::common::duration_serde")]
+    pub idle_interval: Duration,
+    /// Path pinged on the provider's API, relative to its base URL
+    pub ping_path: String,
+}
+
+impl Default for StreamKeepaliveConfig {
+    fn default() -> Self {
+        Self {
+            idle_interval: Duration::from_secs(15),
+            ping_path: "/models".to_string(),
+        }
+    }
+}
+
+/// Wrapper that pings the provider whenever one of its streams stalls for
+/// longer than the idle interval (see [`StreamKeepaliveConfig`])
+pub struct KeepaliveProvider {
+    inner: Arc<dyn Provider>,
+    config: StreamKeepaliveConfig,
+}
+
+impl KeepaliveProvider {
+    pub fn new(provider: Arc<dyn Provider>, config: StreamKeepaliveConfig) -> Self {
+        Self {
+            inner: provider,
+            config,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Provider for KeepaliveProvider {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn supports_function_calling(&self) -> bool {
+        self.inner.supports_function_calling()
+    }
+
+    fn supports_request_metadata(&self) -> bool {
+        self.inner.supports_request_metadata()
+    }
+
+    fn supports_response_format(&self) -> bool {
+        self.inner.supports_response_format()
+    }
+
+    fn supports_multiple_choices(&self) -> bool {
+        self.inner.supports_multiple_choices()
+    }
+
+    fn circuit_breaker(&self) -> Option<&crate::circuit_breaker::CircuitBreaker> {
+        self.inner.circuit_breaker()
+    }
+
+    fn supported_models(&self) -> Vec<String> {
+        self.inner.supported_models()
+    }
+
+    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+        self.inner.chat_completion(request).await
+    }
+
+    async fn stream_chat_completion(
+        &self,
+        request: ChatRequest,
+    ) -> Result<StreamResult, ProviderError> {
+        let mut stream = self.inner.stream_chat_completion(request).await?;
+        let inner = self.inner.clone();
+        let config = self.config.clone();
+
+        // Cleared once the provider reports it cannot be pinged
+        let pinging = Arc::new(AtomicBool::new(true));
+
+        Ok(Box::pin(async_stream::stream! {
+            loop {
+                match tokio::time::timeout(config.idle_interval, stream.next()).await {
+                    Ok(Some(chunk)) => yield chunk,
+                    Ok(None) => break,
+                    Err(_) if pinging.load(Ordering::Relaxed) => {
+                        // Pinged off the stream so a slow ping cannot delay chunks
+                        let inner = inner.clone();
+                        let path = config.ping_path.clone();
+                        let pinging = pinging.clone();
+                        tokio::spawn(async move {
+                            match inner.keepalive_ping(&path).await {
+                                Ok(()) => {}
+                                Err(ProviderError::Unsupported { .. }) => {
+                                    pinging.store(false, Ordering::Relaxed);
+                                }
+                                Err(e) => tracing::debug!(
+                                    "Keep-alive ping to {} failed: {}",
+                                    inner.name(),
+                                    e
+                                ),
+                            }
+                        });
+                    }
+                    Err(_) => {}
+                }
+            }
+        }))
+    }
+
+    async fn embedding(
+        &self,
+        request: EmbeddingRequest,
+    ) -> Result<EmbeddingResponse, ProviderError> {
+        self.inner.embedding(request).await
+    }
+
+    async fn image_generation(
+        &self,
+        request: ImageRequest,
+    ) -> Result<ImageResponse, ProviderError> {
+        self.inner.image_generation(request).await
+    }
+
+    async fn audio_transcription(
+        &self,
+        request: AudioRequest,
+    ) -> Result<AudioResponse, ProviderError> {
+        self.inner.audio_transcription(request).await
+    }
+
+    async fn text_to_speech(
+        &self,
+        request: SpeechRequest,
+    ) -> Result<SpeechResponse, ProviderError> {
+        self.inner.text_to_speech(request).await
+    }
+
+    async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
+        self.inner.health_check().await
+    }
+
+    async fn probe(&self) -> Result<ProviderHealth, ProviderError> {
+        self.inner.probe().await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, ProviderError> {
+        self.inner.list_models().await
+    }
+
+    async fn keepalive_ping(&self, path: &str) -> Result<(), ProviderError> {
+        self.inner.keepalive_ping(path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Delta, StreamChoice, StreamChunk};
+    use std::sync::atomic::AtomicUsize;
+
+    /// Streams two chunks with a stall between them, counting pings.
+    struct StallingProvider {
+        stall: Duration,
+        pings: Arc<AtomicUsize>,
+    }
+
+    fn chunk() -> StreamChunk {
+        StreamChunk {
+            id: "chunk".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: "test-model".to_string(),
+            choices: vec![StreamChoice {
+                index: 0,
+                delta: Delta {
+                    role: None,
+                    content: Some("tick".to_string()),
+                    tool_calls: None,
+                },
+                finish_reason: None,
+            }],
+            usage: None,
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Provider for StallingProvider {
+        fn name(&self) -> &str {
+            "stalling"
+        }
+
+        fn supports_streaming(&self) -> bool {
+            true
+        }
+
+        fn supports_function_calling(&self) -> bool {
+            false
+        }
+
+        fn supported_models(&self) -> Vec<String> {
+            vec!["test-model".to_string()]
+        }
+
+        async fn chat_completion(
+            &self,
+            _request: ChatRequest,
+        ) -> Result<ChatResponse, ProviderError> {
+            Err(ProviderError::ServiceUnavailable)
+        }
+
+        async fn stream_chat_completion(
+            &self,
+            _request: ChatRequest,
+        ) -> Result<StreamResult, ProviderError> {
+            let stall = self.stall;
+            Ok(Box::pin(async_stream::stream! {
+                yield Ok(chunk());
+                tokio::time::sleep(stall).await;
+                yield Ok(chunk());
+            }))
+        }
+
+        async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
+            Err(ProviderError::ServiceUnavailable)
+        }
+
+        async fn keepalive_ping(&self, path: &str) -> Result<(), ProviderError> {
+            assert_eq!(path, "/ping");
+            self.pings.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_keepalive_pings_provider_during_stall() {
+        let pings = Arc::new(AtomicUsize::new(0));
+        let provider = KeepaliveProvider::new(
+            Arc::new(StallingProvider {
+                stall: Duration::from_millis(450),
+                pings: pings.clone(),
+            }),
+            StreamKeepaliveConfig {
+                idle_interval: Duration::from_millis(100),
+                ping_path: "/ping".to_string(),
+            },
+        );
+
+        let chunks: Vec<_> = provider
+            .stream_chat_completion(ChatRequest::default())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 2);
+
+        // One ping per idle interval of the stall, none once the stream ended
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(pings.load(Ordering::SeqCst), 4);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(pings.load(Ordering::SeqCst), 4);
+    }
+}
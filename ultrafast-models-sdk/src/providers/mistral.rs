@@ -1,13 +1,17 @@
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse,
+};
+use crate::providers::{
+    Capability, HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult,
+    TemperatureRange,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
-use async_stream::stream;
 use serde_json::json;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    map_error_response, parse_retry_after, sse_json, AuthStrategy, HttpProviderClient,
+};
 
 use std::collections::HashMap;
 use std::time::Instant;
@@ -20,14 +24,16 @@ pub struct MistralProvider {
 impl MistralProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://api.mistral.ai/v1",
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
         Ok(Self { http, config })
     }
 
@@ -42,6 +48,7 @@ impl MistralProvider {
     #[allow(dead_code)]
     async fn handle_error_response(&self, response: reqwest::Response) -> ProviderError {
         let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
 
         match response.text().await {
             Ok(body) => {
@@ -58,7 +65,7 @@ impl MistralProvider {
                         404 => ProviderError::ModelNotFound {
                             model: "unknown".to_string(),
                         },
-                        429 => ProviderError::RateLimit,
+                        429 => ProviderError::RateLimit { retry_after },
                         _ => ProviderError::Api {
                             code: status.as_u16(),
                             message,
@@ -93,6 +100,10 @@ impl Provider for MistralProvider {
         true
     }
 
+    fn supports_response_format(&self) -> bool {
+        true
+    }
+
     fn supported_models(&self) -> Vec<String> {
         vec![
             "mistral-tiny".to_string(),
@@ -108,6 +119,9 @@ impl Provider for MistralProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::MISTRAL, request.temperature);
         request.model = self.map_model(&request.model);
 
         let chat_response: ChatResponse =
@@ -119,6 +133,9 @@ impl Provider for MistralProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::MISTRAL, request.temperature);
         request.model = self.map_model(&request.model);
         request.stream = Some(true);
 
@@ -130,38 +147,7 @@ impl Provider for MistralProvider {
             return Err(map_error_response(response).await);
         }
 
-        let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                match serde_json::from_str::<StreamChunk>(json_str) {
-                                    Ok(stream_chunk) => yield Ok(stream_chunk),
-                                    Err(e) => yield Err(ProviderError::Serialization(e)),
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => yield Err(ProviderError::Http(e)),
-                }
-            }
-        });
-
-        Ok(stream)
+        Ok(Box::pin(sse_json(response, self.name())))
     }
 
     async fn embedding(
@@ -233,27 +219,30 @@ impl Provider for MistralProvider {
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by Mistral".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by Mistral".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by Mistral".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
@@ -186,7 +186,7 @@
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, Message, Role, SpeechRequest, SpeechResponse, StreamChunk,
 };
 use futures::Stream;
 use serde::{Deserialize, Serialize};
@@ -202,15 +202,20 @@ pub mod azure;
 pub mod circuit_breaker_provider;
 pub mod cohere;
 pub mod custom;
+pub mod fairness_provider;
 pub mod gemini;
 pub mod google;
 pub mod groq;
+pub mod header_template;
 pub mod http_client;
+pub mod interceptor;
+pub mod keepalive_provider;
 pub mod mistral;
 pub mod ollama;
 pub mod openai;
 pub mod openrouter;
 pub mod perplexity;
+pub mod request_template;
 
 // Use the canonical duration serde helpers from the common module (keep import if used in this module)
 #[allow(unused_imports)]
@@ -267,6 +272,40 @@ pub trait Provider: Send + Sync + Any {
     /// Returns `true` if the provider supports function calling and tool usage.
     fn supports_function_calling(&self) -> bool;
 
+    /// Check if this provider accepts client request metadata.
+    ///
+    /// Returns `true` if [`ChatRequest::request_metadata`] is forwarded to the
+    /// provider; it is dropped from requests to other providers.
+    fn supports_request_metadata(&self) -> bool {
+        false
+    }
+
+    /// Check if this provider accepts `response_format`.
+    ///
+    /// Returns `true` if [`ChatRequest::response_format`] is forwarded to the
+    /// provider; other providers are asked for JSON with a system instruction
+    /// instead (see [`crate::response_format`]).
+    fn supports_response_format(&self) -> bool {
+        false
+    }
+
+    /// Check if this provider accepts `n` for multiple completions.
+    ///
+    /// Returns `true` if [`ChatRequest::n`] is forwarded to the provider;
+    /// for other providers each completion is a separate request (see
+    /// [`crate::choices`]).
+    fn supports_multiple_choices(&self) -> bool {
+        false
+    }
+
+    /// The circuit breaker guarding this provider, if it has one.
+    ///
+    /// Returns `None` by default; the circuit breaker wrapper returns its own
+    /// breaker and other wrappers forward the call.
+    fn circuit_breaker(&self) -> Option<&crate::circuit_breaker::CircuitBreaker> {
+        None
+    }
+
     /// Get the list of models supported by this provider.
     ///
     /// Returns a vector of model names that this provider can handle.
@@ -309,15 +348,16 @@ pub trait Provider: Send + Sync + Any {
     ///
     /// # Default Implementation
     ///
-    /// Returns a configuration error by default. Providers that support
+    /// Returns [`ProviderError::Unsupported`] by default. Providers that support
     /// embeddings should override this method.
     async fn embedding(
         &self,
         _request: EmbeddingRequest,
     ) -> Result<EmbeddingResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Embeddings not supported by this provider".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::Embeddings,
+        ))
     }
 
     /// Generate images from text prompts.
@@ -332,15 +372,16 @@ pub trait Provider: Send + Sync + Any {
     ///
     /// # Default Implementation
     ///
-    /// Returns a configuration error by default. Providers that support
+    /// Returns [`ProviderError::Unsupported`] by default. Providers that support
     /// image generation should override this method.
     async fn image_generation(
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by this provider".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     /// Transcribe audio to text.
@@ -355,15 +396,16 @@ pub trait Provider: Send + Sync + Any {
     ///
     /// # Default Implementation
     ///
-    /// Returns a configuration error by default. Providers that support
+    /// Returns [`ProviderError::Unsupported`] by default. Providers that support
     /// audio transcription should override this method.
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by this provider".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     /// Convert text to speech.
@@ -378,15 +420,16 @@ pub trait Provider: Send + Sync + Any {
     ///
     /// # Default Implementation
     ///
-    /// Returns a configuration error by default. Providers that support
+    /// Returns [`ProviderError::Unsupported`] by default. Providers that support
     /// text-to-speech should override this method.
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by this provider".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     /// Perform a health check on this provider.
@@ -395,6 +438,43 @@ pub trait Provider: Send + Sync + Any {
     ///
     /// Returns provider health information or an error.
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError>;
+
+    /// Actively probe whether this provider is reachable.
+    ///
+    /// Unlike [`Provider::health_check`], wrappers such as the circuit
+    /// breaker must pass probes straight through without recording them.
+    /// Defaults to the health check.
+    async fn probe(&self) -> Result<ProviderHealth, ProviderError> {
+        self.health_check().await
+    }
+
+    /// List the model IDs the provider currently serves.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`ProviderError::Unsupported`] by default. Providers with a
+    /// model listing endpoint should override this method.
+    async fn list_models(&self) -> Result<Vec<String>, ProviderError> {
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ListModels,
+        ))
+    }
+
+    /// Send an application-level keep-alive ping to `path` on the provider's
+    /// API while one of its streams is idle. The ping is a request of its
+    /// own and does not touch the idle stream's connection.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns [`ProviderError::Unsupported`] by default. Providers whose
+    /// streams are closed when idle should override this method.
+    async fn keepalive_ping(&self, _path: &str) -> Result<(), ProviderError> {
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::StreamKeepalive,
+        ))
+    }
 }
 
 /// Configuration for provider instances.
@@ -417,18 +497,45 @@ pub trait Provider: Send + Sync + Any {
 pub struct ProviderConfig {
     /// Provider name/identifier
     pub name: String,
+    /// Adapter serving this provider, one of [`BUILTIN_PROVIDERS`]; `name`
+    /// when unset, so several providers can share one adapter under
+    /// names of their own
+    #[serde(default, rename = "type")]
+    pub provider_type: Option<String>,
     /// API key for authentication
     pub api_key: String,
     /// Optional base URL for the provider API
     pub base_url: Option<String>,
-    /// Request timeout duration
-    #[serde(with = "crate::common::duration_serde")]
-    pub timeout: Duration,
-    /// Maximum number of retry attempts
+    /// Longest a connection to the provider may take to establish
+    #[serde(
+        default = "default_connect_timeout",
+        with = "crate::common::duration_serde"
+    )]
+    pub connect_timeout: Duration,
+    /// Longest a streamed request may wait for its first chunk. `0s` (the
+    /// default) leaves only `total_timeout`.
+    #[serde(default, with = "crate::common::duration_serde")]
+    pub first_byte_timeout: Duration,
+    /// Longest a whole request may take, including reading a streamed
+    /// response. Read from `timeout` in older configurations.
+    #[serde(alias = "timeout", with = "crate::common::duration_serde")]
+    pub total_timeout: Duration,
+    /// Maximum number of retry attempts (overrides the client retry policy)
     pub max_retries: u32,
-    /// Delay between retry attempts
-    #[serde(with = "crate::common::duration_serde")]
-    pub retry_delay: Duration,
+    /// Back-off before the first retry, grown by `retry_backoff_multiplier`
+    /// for each retry after it. Unset, the client retry policy's
+    /// `initial_delay` applies.
+    #[serde(default, with = "crate::common::duration_serde::option")]
+    pub retry_delay: Option<Duration>,
+    /// Factor the back-off grows by between retries. Unset, the client
+    /// retry policy's `backoff_multiplier` applies.
+    #[serde(default)]
+    pub retry_backoff_multiplier: Option<f64>,
+    /// Longest back-off between retries, before jitter. A 429 whose
+    /// `Retry-After` is longer than this is not retried. Unset, the client
+    /// retry policy's `max_delay` applies.
+    #[serde(default, with = "crate::common::duration_serde::option")]
+    pub retry_max_delay: Option<Duration>,
     /// Optional rate limiting configuration
     pub rate_limit: Option<RateLimit>,
     /// Model name mappings (from client model names to provider model names)
@@ -439,6 +546,42 @@ pub struct ProviderConfig {
     pub enabled: bool,
     /// Optional circuit breaker configuration
     pub circuit_breaker: Option<crate::circuit_breaker::CircuitBreakerConfig>,
+    /// Optional override of the temperature range accepted by the provider
+    #[serde(default)]
+    pub temperature_range: Option<TemperatureRange>,
+    /// How chat messages with misplaced `system`/`tool` roles are handled
+    #[serde(default)]
+    pub role_ordering: RoleOrdering,
+    /// Back-off after a 429 that carries no `Retry-After` header
+    #[serde(
+        default = "default_retry_after",
+        with = "crate::common::duration_serde"
+    )]
+    pub default_retry_after: Duration,
+    /// Optional concurrency limit shared fairly between models
+    #[serde(default)]
+    pub fairness: Option<crate::fairness::FairnessConfig>,
+    /// Optional keep-alive pings sent while a stream is idle
+    #[serde(default)]
+    pub stream_keepalive: Option<keepalive_provider::StreamKeepaliveConfig>,
+    /// Optional request body template for the custom provider
+    #[serde(default)]
+    pub request_template: Option<serde_json::Value>,
+    /// Optional Azure OpenAI deployments serving each model
+    #[serde(default)]
+    pub azure: Option<azure::AzureConfig>,
+    /// Hooks run around every HTTP request to the provider, registered in
+    /// code with [`ProviderConfig::with_interceptor`]
+    #[serde(skip)]
+    pub interceptors: interceptor::InterceptorChain,
+}
+
+fn default_retry_after() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_connect_timeout() -> Duration {
+    Duration::from_secs(10)
 }
 
 impl ProviderConfig {
@@ -459,19 +602,38 @@ impl ProviderConfig {
     pub fn new(name: impl Into<String>, api_key: impl Into<String>) -> Self {
         Self {
             name: name.into(),
+            provider_type: None,
             api_key: api_key.into(),
             base_url: None,
-            timeout: Duration::from_secs(30),
+            connect_timeout: default_connect_timeout(),
+            first_byte_timeout: Duration::ZERO,
+            total_timeout: Duration::from_secs(30),
             max_retries: 3,
-            retry_delay: Duration::from_secs(1),
+            retry_delay: None,
+            retry_backoff_multiplier: None,
+            retry_max_delay: None,
             rate_limit: None,
             model_mapping: HashMap::new(),
             headers: HashMap::new(),
             enabled: true,
             circuit_breaker: None,
+            temperature_range: None,
+            role_ordering: RoleOrdering::default(),
+            default_retry_after: default_retry_after(),
+            fairness: None,
+            stream_keepalive: None,
+            request_template: None,
+            azure: None,
+            interceptors: interceptor::InterceptorChain::default(),
         }
     }
 
+    /// Serve this provider with the adapter named `provider_type`.
+    pub fn with_provider_type(mut self, provider_type: impl Into<String>) -> Self {
+        self.provider_type = Some(provider_type.into());
+        self
+    }
+
     /// Set the base URL for the provider API.
     ///
     /// # Arguments
@@ -482,13 +644,81 @@ impl ProviderConfig {
         self
     }
 
-    /// Set the request timeout duration.
+    /// Set the timeout for the whole request.
     ///
     /// # Arguments
     ///
     /// * `timeout` - The timeout duration
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
-        self.timeout = timeout;
+        self.total_timeout = timeout;
+        self
+    }
+
+    /// The timeout for the whole request, formerly the `timeout` field.
+    #[deprecated(note = "use the `total_timeout` field")]
+    pub fn timeout(&self) -> Duration {
+        self.total_timeout
+    }
+
+    /// Replace the timeout for the whole request, formerly by assigning the
+    /// `timeout` field.
+    #[deprecated(note = "assign the `total_timeout` field")]
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.total_timeout = timeout;
+    }
+
+    /// Set the timeout for establishing a connection to the provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The connect timeout duration
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Set how long a streamed request may wait for its first chunk.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The first chunk timeout, `Duration::ZERO` to disable it
+    pub fn with_first_byte_timeout(mut self, timeout: Duration) -> Self {
+        self.first_byte_timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of retries for this provider.
+    ///
+    /// Takes precedence over the client's retry policy; `0` disables retries.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_retries` - The number of retries after the first attempt
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the exponential back-off between retries for this provider.
+    ///
+    /// Takes precedence over the client's retry policy. Retries after a 429
+    /// wait for the provider's `Retry-After` instead, and the request fails
+    /// without retrying when that is longer than `max_delay`.
+    ///
+    /// # Arguments
+    ///
+    /// * `base` - The back-off before the first retry
+    /// * `multiplier` - The factor the back-off grows by between retries
+    /// * `max_delay` - The longest back-off between retries
+    pub fn with_retry_backoff(
+        mut self,
+        base: Duration,
+        multiplier: f64,
+        max_delay: Duration,
+    ) -> Self {
+        self.retry_delay = Some(base);
+        self.retry_backoff_multiplier = Some(multiplier);
+        self.retry_max_delay = Some(max_delay);
         self
     }
 
@@ -523,6 +753,90 @@ impl ProviderConfig {
         self.headers.insert(key.into(), value.into());
         self
     }
+
+    /// Set the request body template used by the custom provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The JSON body, with placeholders for the chat request
+    pub fn with_request_template(mut self, template: serde_json::Value) -> Self {
+        self.request_template = Some(template);
+        self
+    }
+
+    /// Serve `model` from an Azure OpenAI deployment.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The model name clients request
+    /// * `deployment` - The Azure deployment, with an optional `api_version`
+    pub fn with_azure_deployment(
+        mut self,
+        model: impl Into<String>,
+        deployment: azure::AzureDeployment,
+    ) -> Self {
+        self.azure
+            .get_or_insert_with(Default::default)
+            .deployments
+            .insert(model.into(), deployment);
+        self
+    }
+
+    /// Override the temperature range accepted by the provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `min` - The lowest temperature forwarded to the provider
+    /// * `max` - The highest temperature forwarded to the provider
+    pub fn with_temperature_range(mut self, min: f32, max: f32) -> Self {
+        self.temperature_range = Some(TemperatureRange::new(min, max));
+        self
+    }
+
+    /// Set how chat messages with misplaced roles are handled.
+    ///
+    /// # Arguments
+    ///
+    /// * `role_ordering` - The policy applied before requests are forwarded
+    pub fn with_role_ordering(mut self, role_ordering: RoleOrdering) -> Self {
+        self.role_ordering = role_ordering;
+        self
+    }
+
+    /// Set the back-off used after a 429 without a `Retry-After` header.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_retry_after` - How long to wait before retrying or reusing the provider
+    pub fn with_default_retry_after(mut self, default_retry_after: Duration) -> Self {
+        self.default_retry_after = default_retry_after;
+        self
+    }
+
+    /// Add an interceptor after those already registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `interceptor` - Hook run around every HTTP request to the provider
+    pub fn with_interceptor(
+        mut self,
+        interceptor: impl interceptor::Interceptor + 'static,
+    ) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Clamp a requested temperature to the configured range, falling back
+    /// to the provider's native range when no override is set.
+    pub fn clamp_temperature(
+        &self,
+        default_range: TemperatureRange,
+        temperature: Option<f32>,
+    ) -> Option<f32> {
+        self.temperature_range
+            .unwrap_or(default_range)
+            .clamp(&self.name, temperature)
+    }
 }
 
 /// Rate limiting configuration for providers.
@@ -536,6 +850,197 @@ pub struct RateLimit {
     pub tokens_per_minute: u32,
 }
 
+/// Inclusive range of sampling temperatures accepted by a provider.
+///
+/// Providers disagree on the valid range (OpenAI accepts `[0, 2]`, Anthropic
+/// only `[0, 1]`), so every adapter clamps requests written for one provider
+/// before forwarding them to another. Ollama, custom and other
+/// OpenAI-compatible providers use the OpenAI range unless
+/// `temperature_range` overrides it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TemperatureRange {
+    /// Lowest accepted temperature
+    pub min: f32,
+    /// Highest accepted temperature
+    pub max: f32,
+}
+
+impl TemperatureRange {
+    /// Range accepted by OpenAI and OpenAI-compatible APIs.
+    pub const OPENAI: TemperatureRange = TemperatureRange::new(0.0, 2.0);
+    /// Range accepted by the Anthropic Messages API.
+    pub const ANTHROPIC: TemperatureRange = TemperatureRange::new(0.0, 1.0);
+    /// Range accepted by the Cohere Chat API.
+    pub const COHERE: TemperatureRange = TemperatureRange::new(0.0, 1.0);
+    /// Range accepted by the Mistral chat completions API.
+    pub const MISTRAL: TemperatureRange = TemperatureRange::new(0.0, 1.5);
+    /// Range accepted by Gemini, through both Google AI and Vertex AI.
+    pub const GEMINI: TemperatureRange = TemperatureRange::new(0.0, 2.0);
+
+    pub const fn new(min: f32, max: f32) -> Self {
+        Self { min, max }
+    }
+
+    /// Clamp `temperature` into this range, logging when the value changes.
+    pub fn clamp(&self, provider: &str, temperature: Option<f32>) -> Option<f32> {
+        let requested = temperature?;
+        let clamped = requested.clamp(self.min, self.max);
+        if clamped != requested {
+            tracing::warn!(
+                "Clamped temperature {} to {} for provider {} (supported range {}..={})",
+                requested,
+                clamped,
+                provider,
+                self.min,
+                self.max
+            );
+        }
+        Some(clamped)
+    }
+}
+
+/// How a provider handles chat messages whose roles are out of sequence.
+///
+/// Strict providers reject a `system` message after the conversation has
+/// started, or a `tool` result that does not follow the assistant message
+/// whose tool call it answers, with an opaque 400. Checking the sequence
+/// before forwarding turns that into a clear error, or fixes the order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RoleOrdering {
+    /// Forward messages unchanged
+    #[default]
+    Passthrough,
+    /// Move system messages first and tool results after their tool call
+    Reorder,
+    /// Reject requests with misplaced roles
+    Reject,
+}
+
+impl RoleOrdering {
+    /// Apply the policy to the messages sent to `provider`.
+    ///
+    /// Returns a message naming the first misplaced message when the
+    /// sequence is rejected or cannot be reordered.
+    pub fn apply(&self, provider: &str, messages: Vec<Message>) -> Result<Vec<Message>, String> {
+        let Some(problem) = find_misplaced_role(&messages) else {
+            return Ok(messages);
+        };
+
+        let problem = match self {
+            RoleOrdering::Passthrough => return Ok(messages),
+            RoleOrdering::Reject => problem,
+            RoleOrdering::Reorder => {
+                let reordered = reorder_roles(messages);
+                match find_misplaced_role(&reordered) {
+                    None => {
+                        tracing::debug!("Reordered chat messages for provider {}", provider);
+                        return Ok(reordered);
+                    }
+                    Some(problem) => problem,
+                }
+            }
+        };
+
+        Err(format!(
+            "Invalid message order for provider {provider}: {problem}"
+        ))
+    }
+}
+
+/// Ids of the tool calls made by an assistant message.
+fn tool_call_ids(message: &Message) -> Vec<&str> {
+    match (&message.role, &message.tool_calls) {
+        (Role::Assistant, Some(calls)) => calls.iter().map(|call| call.id.as_str()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Describe the first message whose role is out of sequence.
+fn find_misplaced_role(messages: &[Message]) -> Option<String> {
+    let mut conversation_started = false;
+    // Tool calls of the assistant message the current run of tool results answers
+    let mut open_calls: Option<Vec<&str>> = None;
+
+    for (index, message) in messages.iter().enumerate() {
+        match message.role {
+            Role::System if conversation_started => {
+                return Some(format!(
+                    "system message at index {index} must come before the conversation"
+                ));
+            }
+            Role::Tool => {
+                let Some(calls) = &open_calls else {
+                    return Some(format!(
+                        "tool message at index {index} does not follow an assistant message with tool calls"
+                    ));
+                };
+                if let Some(id) = &message.tool_call_id {
+                    if !calls.contains(&id.as_str()) {
+                        return Some(format!(
+                            "tool message at index {index} answers tool call {id}, which the preceding assistant message did not make"
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if message.role != Role::System {
+            conversation_started = true;
+        }
+        if message.role != Role::Tool {
+            let calls = tool_call_ids(message);
+            open_calls = (!calls.is_empty()).then_some(calls);
+        }
+    }
+
+    None
+}
+
+/// Move system messages to the front and each tool result directly after the
+/// assistant message that made its tool call, keeping the relative order of
+/// everything else. Results that match no tool call stay where they are.
+fn reorder_roles(messages: Vec<Message>) -> Vec<Message> {
+    let (system, rest): (Vec<Message>, Vec<Message>) = messages
+        .into_iter()
+        .partition(|message| message.role == Role::System);
+
+    let known_calls: Vec<String> = rest
+        .iter()
+        .flat_map(|message| tool_call_ids(message).into_iter().map(str::to_string))
+        .collect();
+    let is_answer = |message: &Message| {
+        message.role == Role::Tool
+            && message
+                .tool_call_id
+                .as_ref()
+                .is_some_and(|id| known_calls.contains(id))
+    };
+    let (mut answers, rest): (Vec<Message>, Vec<Message>) =
+        rest.into_iter().partition(|message| is_answer(message));
+
+    let mut reordered = system;
+    for message in rest {
+        let calls: Vec<String> = tool_call_ids(&message)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        reordered.push(message);
+        if !calls.is_empty() {
+            let (matching, remaining): (Vec<Message>, Vec<Message>) =
+                answers.into_iter().partition(|answer| {
+                    answer
+                        .tool_call_id
+                        .as_ref()
+                        .is_some_and(|id| calls.contains(id))
+                });
+            reordered.extend(matching);
+            answers = remaining;
+        }
+    }
+    reordered
+}
+
 /// Provider health information.
 ///
 /// Contains detailed health status and metrics for a provider.
@@ -553,6 +1058,43 @@ pub struct ProviderHealth {
     pub details: HashMap<String, String>,
 }
 
+/// Optional capabilities a provider may not implement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// `POST /v1/embeddings`
+    Embeddings,
+    /// `POST /v1/images/generations`
+    ImageGeneration,
+    /// `POST /v1/audio/transcriptions`
+    AudioTranscription,
+    /// `POST /v1/audio/speech`
+    TextToSpeech,
+    /// `GET /v1/models`
+    ListModels,
+    /// `tools` in a chat completion request
+    FunctionCalling,
+    /// Keep-alive pings while a stream is idle
+    StreamKeepalive,
+    /// `n` above 1 in a streamed chat completion request
+    MultipleChoices,
+}
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Capability::Embeddings => "embeddings",
+            Capability::ImageGeneration => "image_generation",
+            Capability::AudioTranscription => "audio_transcription",
+            Capability::TextToSpeech => "text_to_speech",
+            Capability::ListModels => "list_models",
+            Capability::FunctionCalling => "function_calling",
+            Capability::StreamKeepalive => "stream_keepalive",
+            Capability::MultipleChoices => "multiple_choices",
+        })
+    }
+}
+
 /// Provider health status enumeration.
 ///
 /// Represents the different health states a provider can be in.
@@ -743,11 +1285,28 @@ impl ProviderMetrics {
     }
 }
 
+/// Names of the provider adapters accepted by [`create_provider`].
+pub const BUILTIN_PROVIDERS: &[&str] = &[
+    "openai",
+    "anthropic",
+    "azure",
+    "google",
+    "gemini",
+    "cohere",
+    "groq",
+    "mistral",
+    "perplexity",
+    "ollama",
+    "openrouter",
+    "openai_compatible",
+    "custom",
+];
+
 /// Create a provider instance from configuration.
 ///
-/// This function creates a provider instance based on the provider name
-/// in the configuration. It automatically selects the appropriate provider
-/// implementation.
+/// This function creates a provider instance based on the provider type,
+/// or else the provider name, in the configuration. It automatically
+/// selects the appropriate provider implementation.
 ///
 /// # Arguments
 ///
@@ -766,8 +1325,13 @@ impl ProviderMetrics {
 /// let provider = create_provider(config)?;
 /// ```
 pub fn create_provider(config: ProviderConfig) -> Result<Box<dyn Provider>, ProviderError> {
-    match config.name.as_str() {
-        "openai" => {
+    let provider_type = config
+        .provider_type
+        .clone()
+        .unwrap_or_else(|| config.name.clone());
+    match provider_type.as_str() {
+        // Any OpenAI-compatible API, at the configured base_url
+        "openai" | "openai_compatible" => {
             let provider = openai::OpenAIProvider::new(config)?;
             Ok(Box::new(provider))
         }
@@ -819,7 +1383,16 @@ pub fn create_provider(config: ProviderConfig) -> Result<Box<dyn Provider>, Prov
                 image_endpoint: None,
                 audio_endpoint: None,
                 speech_endpoint: None,
-                request_format: custom::RequestFormat::OpenAI,
+                request_format: match &config.request_template {
+                    Some(template) => custom::RequestFormat::Template(
+                        request_template::RequestTemplate::parse(template).map_err(|e| {
+                            ProviderError::Configuration {
+                                message: format!("Invalid request_template: {e}"),
+                            }
+                        })?,
+                    ),
+                    None => custom::RequestFormat::OpenAI,
+                },
                 response_format: custom::ResponseFormat::OpenAI,
                 auth_type: custom::AuthType::Bearer,
             };
@@ -827,7 +1400,7 @@ pub fn create_provider(config: ProviderConfig) -> Result<Box<dyn Provider>, Prov
             Ok(Box::new(provider))
         }
         _ => Err(ProviderError::ProviderNotSupported {
-            provider: config.name,
+            provider: provider_type,
         }),
     }
 }
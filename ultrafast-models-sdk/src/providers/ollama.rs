@@ -3,11 +3,16 @@ use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
     ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
+use crate::providers::{
+    Capability, HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult,
+    TemperatureRange,
+};
 use async_stream::stream;
 use serde_json::json;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    map_error_response, merge_extra_fields, take_line, AuthStrategy, HttpProviderClient,
+};
 
 use std::collections::HashMap;
 use std::time::Instant;
@@ -20,12 +25,14 @@ pub struct OllamaProvider {
 impl OllamaProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "http://localhost:11434",
             &config.headers,
             AuthStrategy::None,
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self { http, config })
     }
@@ -110,11 +117,17 @@ impl Provider for OllamaProvider {
         ]
     }
 
-    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+    async fn chat_completion(
+        &self,
+        mut request: ChatRequest,
+    ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         let model = self.map_model(&request.model);
 
         // Convert OpenAI format to Ollama format
-        let ollama_request = json!({
+        let mut ollama_request = json!({
             "model": model,
             "messages": request.messages.iter().map(|msg| {
                 json!({
@@ -133,6 +146,7 @@ impl Provider for OllamaProvider {
                 "num_predict": request.max_tokens,
             }
         });
+        merge_extra_fields(&mut ollama_request, &request.extra);
 
         let ollama_response: serde_json::Value =
             self.http.post_json("/api/chat", &ollama_request).await?;
@@ -173,11 +187,14 @@ impl Provider for OllamaProvider {
 
     async fn stream_chat_completion(
         &self,
-        request: ChatRequest,
+        mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         let model = self.map_model(&request.model);
 
-        let ollama_request = json!({
+        let mut ollama_request = json!({
             "model": model,
             "messages": request.messages.iter().map(|msg| {
                 json!({
@@ -196,6 +213,7 @@ impl Provider for OllamaProvider {
                 "num_predict": request.max_tokens,
             }
         });
+        merge_extra_fields(&mut ollama_request, &request.extra);
 
         let response = self
             .http
@@ -207,17 +225,14 @@ impl Provider for OllamaProvider {
 
         let stream = Box::pin(stream! {
             let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
+            let mut buffer = Vec::new();
 
             while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
                 match chunk_result {
                     Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
+                        buffer.extend_from_slice(&chunk);
 
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
+                        while let Some(line) = take_line(&mut buffer) {
 
                             if !line.is_empty() {
                                 // Parse Ollama streaming format and convert to OpenAI format
@@ -242,6 +257,7 @@ impl Provider for OllamaProvider {
                                                         None
                                                     },
                                                 }],
+                                                usage: None,
                                             };
                                             yield Ok(stream_chunk);
                                         }
@@ -313,27 +329,30 @@ impl Provider for OllamaProvider {
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by Ollama".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by Ollama".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by Ollama".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
@@ -366,3 +385,45 @@ impl Provider for OllamaProvider {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Message;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_usage_parsed_from_eval_counts() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "model": "llama3",
+                "message": {"role": "assistant", "content": "Hello"},
+                "done": true,
+                "prompt_eval_count": 26,
+                "eval_count": 4
+            })))
+            .mount(&server)
+            .await;
+        let provider =
+            OllamaProvider::new(ProviderConfig::new("ollama", "").with_base_url(server.uri()))
+                .unwrap();
+
+        let request = ChatRequest {
+            model: "llama3".to_string(),
+            messages: vec![Message::user("Hi")],
+            ..Default::default()
+        };
+        let usage = provider
+            .chat_completion(request)
+            .await
+            .unwrap()
+            .usage
+            .unwrap();
+        assert_eq!(usage.prompt_tokens, 26);
+        assert_eq!(usage.completion_tokens, 4);
+        assert_eq!(usage.total_tokens, 30);
+    }
+}
@@ -1,12 +1,16 @@
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamOptions,
+};
+use crate::providers::{
+    HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult, TemperatureRange,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
-use async_stream::stream;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    audio_transcription_form, audio_transcription_response, map_error_response, sse_json,
+    AuthStrategy, HttpProviderClient,
+};
 
 use std::collections::HashMap;
 use std::time::Instant;
@@ -19,14 +23,16 @@ pub struct OpenAIProvider {
 impl OpenAIProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         let client = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://api.openai.com/v1",
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self { client, config })
     }
@@ -43,7 +49,7 @@ impl OpenAIProvider {
 #[async_trait::async_trait]
 impl Provider for OpenAIProvider {
     fn name(&self) -> &str {
-        "openai"
+        &self.config.name
     }
 
     fn supports_streaming(&self) -> bool {
@@ -54,6 +60,14 @@ impl Provider for OpenAIProvider {
         true
     }
 
+    fn supports_response_format(&self) -> bool {
+        true
+    }
+
+    fn supports_multiple_choices(&self) -> bool {
+        true
+    }
+
     fn supported_models(&self) -> Vec<String> {
         vec![
             "gpt-4".to_string(),
@@ -77,6 +91,9 @@ impl Provider for OpenAIProvider {
         mut request: ChatRequest,
     ) -> Result<ChatResponse, ProviderError> {
         request.model = self.map_model(&request.model);
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
 
         let chat_response: ChatResponse =
             self.client.post_json("/chat/completions", &request).await?;
@@ -88,7 +105,14 @@ impl Provider for OpenAIProvider {
         mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
         request.model = self.map_model(&request.model);
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         request.stream = Some(true);
+        // Ask for a final usage chunk so streamed requests can be metered.
+        request.stream_options.get_or_insert(StreamOptions {
+            include_usage: true,
+        });
 
         let response = self
             .client
@@ -98,38 +122,7 @@ impl Provider for OpenAIProvider {
             return Err(map_error_response(response).await);
         }
 
-        let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                match serde_json::from_str::<StreamChunk>(json_str) {
-                                    Ok(stream_chunk) => yield Ok(stream_chunk),
-                                    Err(e) => yield Err(ProviderError::Serialization(e)),
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => yield Err(ProviderError::Http(e)),
-                }
-            }
-        });
-
-        Ok(stream)
+        Ok(Box::pin(sse_json(response, self.name())))
     }
 
     async fn embedding(
@@ -164,26 +157,7 @@ impl Provider for OpenAIProvider {
     ) -> Result<AudioResponse, ProviderError> {
         request.model = self.map_model(&request.model);
 
-        let form = reqwest::multipart::Form::new()
-            .part(
-                "file",
-                reqwest::multipart::Part::bytes(request.file)
-                    .file_name("audio.mp3")
-                    .mime_str("audio/mpeg")?,
-            )
-            .text("model", request.model);
-
-        let form = if let Some(language) = request.language {
-            form.text("language", language)
-        } else {
-            form
-        };
-
-        let form = if let Some(prompt) = request.prompt {
-            form.text("prompt", prompt)
-        } else {
-            form
-        };
+        let form = audio_transcription_form(request)?;
 
         let response = self
             .client
@@ -192,8 +166,7 @@ impl Provider for OpenAIProvider {
         if !response.status().is_success() {
             return Err(map_error_response(response).await);
         }
-        let audio_response: AudioResponse = response.json().await?;
-        Ok(audio_response)
+        audio_transcription_response(response).await
     }
 
     async fn text_to_speech(
@@ -222,33 +195,306 @@ impl Provider for OpenAIProvider {
         })
     }
 
+    async fn list_models(&self) -> Result<Vec<String>, ProviderError> {
+        let list: serde_json::Value = self.client.get_json("/models").await?;
+        Ok(list["data"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|model| model["id"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    async fn keepalive_ping(&self, path: &str) -> Result<(), ProviderError> {
+        let response = self.client.get_raw(path).await?;
+        if !response.status().is_success() {
+            return Err(map_error_response(response).await);
+        }
+        Ok(())
+    }
+
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
         let start = Instant::now();
 
-        let response = self.client.get_json::<serde_json::Value>("/models").await;
+        let response = self.client.get_raw("/models").await;
 
         let latency_ms = start.elapsed().as_millis() as u64;
 
-        match response {
-            Ok(_json) => Ok(ProviderHealth {
-                status: HealthStatus::Healthy,
-                latency_ms: Some(latency_ms),
-                error_rate: 0.0,
-                last_check: chrono::Utc::now(),
-                details: HashMap::new(),
-            }),
+        let mut details = HashMap::new();
+        let healthy = match response {
+            Ok(response) => {
+                let status = response.status();
+                details.insert("http_status".to_string(), status.as_u16().to_string());
+                if !status.is_success() {
+                    details.insert(
+                        "error".to_string(),
+                        map_error_response(response).await.to_string(),
+                    );
+                }
+                status.is_success()
+            }
             Err(e) => {
-                let mut details = HashMap::new();
                 details.insert("error".to_string(), e.to_string());
+                false
+            }
+        };
+
+        Ok(ProviderHealth {
+            status: if healthy {
+                HealthStatus::Healthy
+            } else {
+                HealthStatus::Degraded
+            },
+            latency_ms: Some(latency_ms),
+            error_rate: if healthy { 0.0 } else { 1.0 },
+            last_check: chrono::Utc::now(),
+            details,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_unknown_fields_forwarded_to_provider() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "ok"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let request: ChatRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "logit_bias": {"50256": -100},
+            "repetition_penalty": 1.1
+        }))
+        .unwrap();
+        assert!(request.extra.contains_key("logit_bias"));
+
+        let provider = OpenAIProvider::new(
+            ProviderConfig::new("openai", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        provider.chat_completion(request).await.unwrap();
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(body["logit_bias"], serde_json::json!({"50256": -100}));
+        assert_eq!(body["repetition_penalty"], serde_json::json!(1.1));
+        assert_eq!(body["model"], "gpt-4");
+    }
+
+    #[tokio::test]
+    async fn test_stream_options_round_trip_and_forwarded() {
+        let request: ChatRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "stream_options": {"include_usage": true}
+        }))
+        .unwrap();
+        assert_eq!(
+            request.stream_options,
+            Some(StreamOptions {
+                include_usage: true
+            })
+        );
+        assert!(!request.extra.contains_key("stream_options"));
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["stream_options"]["include_usage"], true);
+        let plain = serde_json::to_value(ChatRequest::default()).unwrap();
+        assert!(plain.get("stream_options").is_none());
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string("data: [DONE]\n\n"),
+            )
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            ProviderConfig::new("openai", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        let mut stream = provider.stream_chat_completion(request).await.unwrap();
+        while futures::StreamExt::next(&mut stream).await.is_some() {}
+
+        let received = server.received_requests().await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(
+            body["stream_options"],
+            serde_json::json!({"include_usage": true})
+        );
+    }
 
-                Ok(ProviderHealth {
-                    status: HealthStatus::Degraded,
-                    latency_ms: Some(latency_ms),
-                    error_rate: 1.0,
-                    last_check: chrono::Utc::now(),
-                    details,
+    #[tokio::test]
+    async fn test_stream_ends_cleanly_on_every_done_variant() {
+        let chunk = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4",
+            "choices": [{"index": 0, "delta": {"content": "Hi"}, "finish_reason": null}]
+        });
+        let bodies = [
+            format!("data: {chunk}\n\ndata: [DONE]\n\n"),
+            format!("data:{chunk}\n\ndata:[DONE]\n\n"),
+            format!("data: {chunk}\n\n"),
+            format!("data: {chunk}"),
+            format!("data: {chunk}\n\ndata: [DONE]"),
+        ];
+
+        for body in bodies {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/chat/completions"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .insert_header("content-type", "text/event-stream")
+                        .set_body_string(body.clone()),
+                )
+                .mount(&server)
+                .await;
+
+            let provider = OpenAIProvider::new(
+                ProviderConfig::new("openai", "test-key").with_base_url(server.uri()),
+            )
+            .unwrap();
+            let stream = provider
+                .stream_chat_completion(ChatRequest {
+                    model: "gpt-4".to_string(),
+                    messages: vec![crate::models::Message::user("Hi")],
+                    ..Default::default()
                 })
+                .await
+                .unwrap();
+            let chunks: Vec<_> = futures::StreamExt::collect(stream).await;
+
+            assert_eq!(chunks.len(), 1, "body: {body:?}");
+            let chunk = chunks.into_iter().next().unwrap().unwrap();
+            assert_eq!(chunk.choices[0].delta.content.as_deref(), Some("Hi"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_plain_text_mid_stream_ends_with_stream_error() {
+        let chunk = serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4",
+            "choices": [{"index": 0, "delta": {"content": "Hi"}, "finish_reason": null}]
+        });
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(format!(
+                        ": ping\nevent: message\nid: 1\ndata: {chunk}\n\n\
+                         upstream overloaded\nplease retry\ndata: {chunk}\n\n"
+                    )),
+            )
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            ProviderConfig::new("openai", "test-key").with_base_url(server.uri()),
+        )
+        .unwrap();
+        let stream = provider
+            .stream_chat_completion(ChatRequest {
+                model: "gpt-4".to_string(),
+                messages: vec![crate::models::Message::user("Hi")],
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let mut chunks: Vec<_> = futures::StreamExt::collect(stream).await;
+
+        assert_eq!(chunks.len(), 2);
+        match chunks.pop().unwrap() {
+            Err(ProviderError::StreamError { provider, message }) => {
+                assert_eq!(provider, "openai");
+                assert!(message.starts_with("upstream overloaded\nplease retry"));
             }
+            other => panic!("expected a stream error, got {other:?}"),
+        }
+        assert!(chunks.pop().unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_templated_headers_resolved_per_request() {
+        use wiremock::matchers::header;
+
+        std::env::set_var("ULTRAFAST_TEST_TENANT", "acme");
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("x-tenant", "acme"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion",
+                "created": 0,
+                "model": "gpt-4o",
+                "choices": [{
+                    "index": 0,
+                    "message": {"role": "assistant", "content": "ok"},
+                    "finish_reason": "stop"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAIProvider::new(
+            ProviderConfig::new("openai", "test-key")
+                .with_base_url(server.uri())
+                .with_model_mapping("fast", "gpt-4o-mini")
+                .with_header("x-tenant", "${ULTRAFAST_TEST_TENANT}")
+                .with_header("x-route", "pool-{{model}}"),
+        )
+        .unwrap();
+        for model in ["gpt-4o", "fast"] {
+            let request = ChatRequest {
+                model: model.to_string(),
+                messages: vec![crate::models::Message::user("Hi")],
+                ..Default::default()
+            };
+            provider.chat_completion(request).await.unwrap();
         }
+
+        let received = server.received_requests().await.unwrap();
+        let routes: Vec<_> = received
+            .iter()
+            .map(|request| request.headers["x-route"].to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(routes, ["pool-gpt-4o", "pool-gpt-4o-mini"]);
+
+        // Unknown placeholders fail when the provider is created
+        let result = OpenAIProvider::new(
+            ProviderConfig::new("openai", "test-key").with_header("x-route", "{{region}}"),
+        );
+        assert!(matches!(result, Err(ProviderError::Configuration { .. })));
     }
 }
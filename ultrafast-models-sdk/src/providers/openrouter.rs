@@ -1,14 +1,18 @@
 use crate::error::ProviderError;
 use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
-    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
+    ImageRequest, ImageResponse, SpeechRequest, SpeechResponse,
+};
+use crate::providers::{
+    HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult, TemperatureRange,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
-use async_stream::stream;
 use std::collections::HashMap;
 use std::time::Instant;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    audio_transcription_form, audio_transcription_response, map_error_response, sse_json,
+    AuthStrategy, HttpProviderClient,
+};
 
 /// OpenRouter provider implementation (OpenAI-compatible API)
 pub struct OpenRouterProvider {
@@ -20,14 +24,16 @@ impl OpenRouterProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         // Allow custom headers like HTTP-Referer, X-Title to be passed via config.headers
         let client = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://openrouter.ai/api/v1",
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
 
         Ok(Self { client, config })
     }
@@ -55,6 +61,10 @@ impl Provider for OpenRouterProvider {
         true
     }
 
+    fn supports_response_format(&self) -> bool {
+        true
+    }
+
     fn supported_models(&self) -> Vec<String> {
         // Leave generic; OpenRouter aggregates many models. Users can override mapping.
         vec![
@@ -67,6 +77,9 @@ impl Provider for OpenRouterProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         request.model = self.map_model(&request.model);
         let chat_response: ChatResponse =
             self.client.post_json("/chat/completions", &request).await?;
@@ -77,6 +90,9 @@ impl Provider for OpenRouterProvider {
         &self,
         mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         request.model = self.map_model(&request.model);
         request.stream = Some(true);
 
@@ -88,38 +104,7 @@ impl Provider for OpenRouterProvider {
             return Err(map_error_response(response).await);
         }
 
-        let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                match serde_json::from_str::<StreamChunk>(json_str) {
-                                    Ok(stream_chunk) => yield Ok(stream_chunk),
-                                    Err(e) => yield Err(ProviderError::Serialization(e)),
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => yield Err(ProviderError::Http(e)),
-                }
-            }
-        });
-
-        Ok(stream)
+        Ok(Box::pin(sse_json(response, self.name())))
     }
 
     async fn embedding(
@@ -182,26 +167,7 @@ impl Provider for OpenRouterProvider {
     ) -> Result<AudioResponse, ProviderError> {
         request.model = self.map_model(&request.model);
 
-        let form = reqwest::multipart::Form::new()
-            .part(
-                "file",
-                reqwest::multipart::Part::bytes(request.file)
-                    .file_name("audio.mp3")
-                    .mime_str("audio/mpeg")?,
-            )
-            .text("model", request.model);
-
-        let form = if let Some(language) = request.language {
-            form.text("language", language)
-        } else {
-            form
-        };
-
-        let form = if let Some(prompt) = request.prompt {
-            form.text("prompt", prompt)
-        } else {
-            form
-        };
+        let form = audio_transcription_form(request)?;
 
         let response = self
             .client
@@ -210,8 +176,7 @@ impl Provider for OpenRouterProvider {
         if !response.status().is_success() {
             return Err(map_error_response(response).await);
         }
-        let audio_response: AudioResponse = response.json().await?;
-        Ok(audio_response)
+        audio_transcription_response(response).await
     }
 
     async fn text_to_speech(
@@ -240,6 +205,14 @@ impl Provider for OpenRouterProvider {
         })
     }
 
+    async fn keepalive_ping(&self, path: &str) -> Result<(), ProviderError> {
+        let response = self.client.get_raw(path).await?;
+        if !response.status().is_success() {
+            return Err(map_error_response(response).await);
+        }
+        Ok(())
+    }
+
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
         let start = Instant::now();
         let response = self.client.get_json::<serde_json::Value>("/models").await;
@@ -3,11 +3,17 @@ use crate::models::{
     AudioRequest, AudioResponse, ChatRequest, ChatResponse, EmbeddingRequest, EmbeddingResponse,
     ImageRequest, ImageResponse, SpeechRequest, SpeechResponse, StreamChunk,
 };
-use crate::providers::{HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult};
+use crate::providers::{
+    Capability, HealthStatus, Provider, ProviderConfig, ProviderHealth, StreamResult,
+    TemperatureRange,
+};
 use async_stream::stream;
 use serde_json::json;
 
-use super::http_client::{map_error_response, AuthStrategy, HttpProviderClient};
+use super::http_client::{
+    map_error_response, merge_extra_fields, parse_retry_after, sse_json, AuthStrategy,
+    HttpProviderClient,
+};
 
 use std::collections::HashMap;
 use std::time::Instant;
@@ -20,14 +26,16 @@ pub struct PerplexityProvider {
 impl PerplexityProvider {
     pub fn new(config: ProviderConfig) -> Result<Self, ProviderError> {
         let http = HttpProviderClient::new(
-            config.timeout,
+            config.connect_timeout,
+            config.total_timeout,
             config.base_url.clone(),
             "https://api.perplexity.ai",
             &config.headers,
             AuthStrategy::Bearer {
                 token: config.api_key.clone(),
             },
-        )?;
+        )?
+        .with_interceptors(config.interceptors.clone());
         Ok(Self { http, config })
     }
 
@@ -42,6 +50,7 @@ impl PerplexityProvider {
     #[allow(dead_code)]
     async fn handle_error_response(&self, response: reqwest::Response) -> ProviderError {
         let status = response.status();
+        let retry_after = parse_retry_after(response.headers());
 
         match response.text().await {
             Ok(body) => {
@@ -58,7 +67,7 @@ impl PerplexityProvider {
                         404 => ProviderError::ModelNotFound {
                             model: "unknown".to_string(),
                         },
-                        429 => ProviderError::RateLimit,
+                        429 => ProviderError::RateLimit { retry_after },
                         _ => ProviderError::Api {
                             code: status.as_u16(),
                             message,
@@ -108,11 +117,17 @@ impl Provider for PerplexityProvider {
         ]
     }
 
-    async fn chat_completion(&self, request: ChatRequest) -> Result<ChatResponse, ProviderError> {
+    async fn chat_completion(
+        &self,
+        mut request: ChatRequest,
+    ) -> Result<ChatResponse, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         let model = self.map_model(&request.model);
 
         // Convert OpenAI format to Perplexity format
-        let perplexity_request = json!({
+        let mut perplexity_request = json!({
             "model": model,
             "messages": request.messages.iter().map(|msg| {
                 json!({
@@ -129,6 +144,7 @@ impl Provider for PerplexityProvider {
             "max_tokens": request.max_tokens,
             "stream": false,
         });
+        merge_extra_fields(&mut perplexity_request, &request.extra);
 
         let perplexity_response: serde_json::Value = self
             .http
@@ -175,11 +191,14 @@ impl Provider for PerplexityProvider {
 
     async fn stream_chat_completion(
         &self,
-        request: ChatRequest,
+        mut request: ChatRequest,
     ) -> Result<StreamResult, ProviderError> {
+        request.temperature = self
+            .config
+            .clamp_temperature(TemperatureRange::OPENAI, request.temperature);
         let model = self.map_model(&request.model);
 
-        let perplexity_request = json!({
+        let mut perplexity_request = json!({
             "model": model,
             "messages": request.messages.iter().map(|msg| {
                 json!({
@@ -196,6 +215,7 @@ impl Provider for PerplexityProvider {
             "max_tokens": request.max_tokens,
             "stream": true,
         });
+        merge_extra_fields(&mut perplexity_request, &request.extra);
 
         let response = self
             .http
@@ -205,56 +225,41 @@ impl Provider for PerplexityProvider {
             return Err(map_error_response(response).await);
         }
 
+        let events = sse_json::<serde_json::Value>(response, self.name());
         let stream = Box::pin(stream! {
-            let mut bytes_stream = response.bytes_stream();
-            let mut buffer = String::new();
-
-            while let Some(chunk_result) = futures::StreamExt::next(&mut bytes_stream).await {
-                match chunk_result {
-                    Ok(chunk) => {
-                        let chunk_str = String::from_utf8_lossy(&chunk);
-                        buffer.push_str(&chunk_str);
-
-                        while let Some(line_end) = buffer.find('\n') {
-                            let line = buffer[..line_end].trim().to_string();
-                            buffer = buffer[line_end + 1..].to_string();
-
-                            if let Some(json_str) = line.strip_prefix("data: ") {
-                                if json_str == "[DONE]" {
-                                    return;
-                                }
-
-                                // Parse Perplexity streaming format and convert to OpenAI format
-                                if let Ok(perplexity_chunk) = serde_json::from_str::<serde_json::Value>(json_str) {
-                                    if let Some(choices) = perplexity_chunk["choices"].as_array() {
-                                        if let Some(first_choice) = choices.first() {
-                                            if let Some(delta) = first_choice.get("delta") {
-                                                if let Some(content) = delta["content"].as_str() {
-                                                    let stream_chunk = StreamChunk {
-                                                        id: perplexity_chunk["id"].as_str().unwrap_or("").to_string(),
-                                                        object: "chat.completion.chunk".to_string(),
-                                                        created: chrono::Utc::now().timestamp() as u64,
-                                                        model: model.clone(),
-                                                        choices: vec![crate::models::StreamChoice {
-                                                            index: 0,
-                                                            delta: crate::models::Delta {
-                                                                role: None,
-                                                                content: Some(content.to_string()),
-                                                                tool_calls: None,
-                                                            },
-                                                            finish_reason: None,
-                                                        }],
-                                                    };
-                                                    yield Ok(stream_chunk);
-                                                }
-                                            }
-                                        }
+            let mut events = Box::pin(events);
+
+            while let Some(chunk) = futures::StreamExt::next(&mut events).await {
+                // Parse Perplexity streaming format and convert to OpenAI format
+                match chunk {
+                    Ok(perplexity_chunk) => {
+                        if let Some(choices) = perplexity_chunk["choices"].as_array() {
+                            if let Some(first_choice) = choices.first() {
+                                if let Some(delta) = first_choice.get("delta") {
+                                    if let Some(content) = delta["content"].as_str() {
+                                        let stream_chunk = StreamChunk {
+                                            id: perplexity_chunk["id"].as_str().unwrap_or("").to_string(),
+                                            object: "chat.completion.chunk".to_string(),
+                                            created: chrono::Utc::now().timestamp() as u64,
+                                            model: model.clone(),
+                                            choices: vec![crate::models::StreamChoice {
+                                                index: 0,
+                                                delta: crate::models::Delta {
+                                                    role: None,
+                                                    content: Some(content.to_string()),
+                                                    tool_calls: None,
+                                                },
+                                                finish_reason: None,
+                                            }],
+                                            usage: None,
+                                        };
+                                        yield Ok(stream_chunk);
                                     }
                                 }
                             }
                         }
                     }
-                    Err(e) => yield Err(ProviderError::Http(e)),
+                    Err(e) => yield Err(e),
                 }
             }
         });
@@ -266,36 +271,40 @@ impl Provider for PerplexityProvider {
         &self,
         _request: EmbeddingRequest,
     ) -> Result<EmbeddingResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Embeddings not supported by Perplexity".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::Embeddings,
+        ))
     }
 
     async fn image_generation(
         &self,
         _request: ImageRequest,
     ) -> Result<ImageResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Image generation not supported by Perplexity".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::ImageGeneration,
+        ))
     }
 
     async fn audio_transcription(
         &self,
         _request: AudioRequest,
     ) -> Result<AudioResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Audio transcription not supported by Perplexity".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::AudioTranscription,
+        ))
     }
 
     async fn text_to_speech(
         &self,
         _request: SpeechRequest,
     ) -> Result<SpeechResponse, ProviderError> {
-        Err(ProviderError::Configuration {
-            message: "Text-to-speech not supported by Perplexity".to_string(),
-        })
+        Err(ProviderError::unsupported(
+            self.name(),
+            Capability::TextToSpeech,
+        ))
     }
 
     async fn health_check(&self) -> Result<ProviderHealth, ProviderError> {
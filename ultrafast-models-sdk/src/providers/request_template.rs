@@ -0,0 +1,203 @@
+//! Request body templates for providers with non-standard APIs.
+//!
+//! A custom provider's `request_template` is the JSON body sent upstream,
+//! with string values holding placeholders for the chat request:
+//!
+//! ```toml
+//! [providers.custom.request_template]
+//! engine = "{{model}}"
+//! conversation = { turns = "{{messages}}" }
+//! options = { heat = "{{params.temperature}}", limit = "{{params.max_tokens}}" }
+//! label = "gateway-{{model}}"
+//! ```
+//!
+//! - `{{messages}}` is the message list, as OpenAI-style `role`/`content`
+//!   objects.
+//! - `{{model}}` is the model after the provider's `model_mapping`.
+//! - `{{params.<field>}}` is any other field of the chat request, such as
+//!   `temperature`, `max_tokens` or `stop`.
+//!
+//! A string that is exactly one placeholder is replaced by the value with its
+//! JSON type, and object keys whose value is unset are left out. Placeholders
+//! inside longer strings are substituted as text. Templates are checked when
+//! the provider is created: unknown placeholders are configuration errors.
+
+use crate::models::ChatRequest;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Placeholder {
+    Messages,
+    Model,
+    Param(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Object(Vec<(String, Node)>),
+    Array(Vec<Node>),
+    Value(Placeholder),
+    Text(Vec<Part>),
+    Literal(Value),
+}
+
+/// A request body template with its placeholders parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestTemplate {
+    root: Node,
+}
+
+impl RequestTemplate {
+    /// Parse `template`, failing on malformed or unknown placeholders.
+    pub fn parse(template: &Value) -> Result<Self, String> {
+        Ok(Self {
+            root: parse_node(template)?,
+        })
+    }
+
+    /// The body for `request`, with `model` the provider's name for the
+    /// requested model.
+    pub fn render(&self, request: &ChatRequest, model: &str) -> Value {
+        let params = serde_json::to_value(request).unwrap_or_default();
+        let context = Context {
+            request,
+            params: &params,
+            model,
+        };
+        render_node(&self.root, &context)
+    }
+}
+
+/// Check a provider's `request_template`.
+pub fn validate_request_template(template: &Value) -> Result<(), String> {
+    RequestTemplate::parse(template)
+        .map(|_| ())
+        .map_err(|e| format!("request_template: {e}"))
+}
+
+struct Context<'a> {
+    request: &'a ChatRequest,
+    params: &'a Value,
+    model: &'a str,
+}
+
+fn parse_node(value: &Value) -> Result<Node, String> {
+    Ok(match value {
+        Value::Object(fields) => Node::Object(
+            fields
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), parse_node(value)?)))
+                .collect::<Result<_, String>>()?,
+        ),
+        Value::Array(items) => Node::Array(items.iter().map(parse_node).collect::<Result<_, _>>()?),
+        Value::String(text) => match parse_text(text)?.as_slice() {
+            [] | [Part::Literal(_)] => Node::Literal(value.clone()),
+            [Part::Placeholder(placeholder)] => Node::Value(placeholder.clone()),
+            parts => Node::Text(parts.to_vec()),
+        },
+        _ => Node::Literal(value.clone()),
+    })
+}
+
+fn parse_text(text: &str) -> Result<Vec<Part>, String> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            parts.push(Part::Literal(rest[..start].to_string()));
+        }
+        let end = rest[start + 2..]
+            .find("}}")
+            .ok_or_else(|| format!("unterminated placeholder in '{text}'"))?;
+        let name = rest[start + 2..start + 2 + end].trim();
+        rest = &rest[start + 2 + end + 2..];
+
+        let placeholder = match name {
+            "messages" => Placeholder::Messages,
+            "model" => Placeholder::Model,
+            _ => match name.strip_prefix("params.") {
+                Some(field)
+                    if !field.is_empty()
+                        && field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+                {
+                    Placeholder::Param(field.to_string())
+                }
+                _ => return Err(format!("unknown placeholder '{{{{{name}}}}}'")),
+            },
+        };
+        parts.push(Part::Placeholder(placeholder));
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Literal(rest.to_string()));
+    }
+    Ok(parts)
+}
+
+fn render_node(node: &Node, context: &Context) -> Value {
+    match node {
+        Node::Object(fields) => {
+            let mut object = Map::new();
+            for (key, node) in fields {
+                let value = render_node(node, context);
+                // Unset parameters are left out rather than sent as null
+                if !(matches!(node, Node::Value(_)) && value.is_null()) {
+                    object.insert(key.clone(), value);
+                }
+            }
+            Value::Object(object)
+        }
+        Node::Array(items) => items
+            .iter()
+            .map(|node| render_node(node, context))
+            .collect(),
+        Node::Value(placeholder) => resolve(placeholder, context),
+        Node::Text(parts) => Value::String(
+            parts
+                .iter()
+                .map(|part| match part {
+                    Part::Literal(text) => text.clone(),
+                    Part::Placeholder(placeholder) => match resolve(placeholder, context) {
+                        Value::String(text) => text,
+                        Value::Null => String::new(),
+                        value => value.to_string(),
+                    },
+                })
+                .collect(),
+        ),
+        Node::Literal(value) => value.clone(),
+    }
+}
+
+fn resolve(placeholder: &Placeholder, context: &Context) -> Value {
+    match placeholder {
+        Placeholder::Messages => {
+            serde_json::to_value(&context.request.messages).unwrap_or_default()
+        }
+        Placeholder::Model => Value::String(context.model.to_string()),
+        Placeholder::Param(field) => context.params.get(field).cloned().unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_unknown_placeholders_rejected() {
+        assert!(RequestTemplate::parse(&json!({"input": "{{messages}}"})).is_ok());
+        assert_eq!(
+            validate_request_template(&json!({"input": ["{{prompt}}"]})),
+            Err("request_template: unknown placeholder '{{prompt}}'".to_string())
+        );
+        assert!(RequestTemplate::parse(&json!({"n": "{{params.}}"})).is_err());
+        assert!(RequestTemplate::parse(&json!({"model": "{{model"})).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! # Response Format Module
+//!
+//! This module handles OpenAI-style `response_format` requests for
+//! structured JSON output across providers.
+//!
+//! ## Overview
+//!
+//! Providers that accept `response_format` (see
+//! [`Provider::supports_response_format`]) receive it unchanged. For the
+//! others it is removed from the request and emulated on a best-effort
+//! basis:
+//!
+//! - A system instruction asking for JSON only (and, for
+//!   [`ResponseFormat::JsonSchema`], the schema to follow) is added to the
+//!   request; see [`apply_fallback`].
+//! - The returned content is checked to parse as JSON; see
+//!   [`check_fallback_response`]. Content that does not parse fails the
+//!   request with [`ProviderError::InvalidResponse`] when a schema was
+//!   requested, and is only logged for `json_object`.
+
+use crate::error::ProviderError;
+use crate::models::{ChatRequest, ChatResponse, Message, ResponseFormat, Role};
+use crate::providers::Provider;
+
+/// Replace `response_format` with a system instruction for a provider that
+/// does not accept it.
+pub fn apply_fallback(provider: &dyn Provider, request: &mut ChatRequest) {
+    if provider.supports_response_format() {
+        return;
+    }
+    let Some(instruction) = request.response_format.take().and_then(|f| instruction(&f)) else {
+        return;
+    };
+
+    match request.messages.first_mut() {
+        Some(message) if message.role == Role::System => {
+            message.content = format!("{}\n\n{instruction}", message.content);
+        }
+        _ => request.messages.insert(0, Message::system(instruction)),
+    }
+}
+
+/// Check that a response to an emulated `response_format` is JSON.
+///
+/// `format` is the format of the request as the client sent it, before
+/// [`apply_fallback`].
+pub fn check_fallback_response(
+    provider: &dyn Provider,
+    format: Option<&ResponseFormat>,
+    response: &ChatResponse,
+) -> Result<(), ProviderError> {
+    let Some(format) = format else {
+        return Ok(());
+    };
+    if provider.supports_response_format() || *format == ResponseFormat::Text {
+        return Ok(());
+    }
+
+    for choice in &response.choices {
+        let content = choice.message.content.trim();
+        if serde_json::from_str::<serde_json::Value>(content).is_ok() {
+            continue;
+        }
+        if let ResponseFormat::JsonSchema { json_schema } = format {
+            return Err(ProviderError::InvalidResponse {
+                message: format!(
+                    "{} returned content that is not JSON for schema '{}'",
+                    provider.name(),
+                    json_schema.name
+                ),
+            });
+        }
+        tracing::warn!(
+            "{} returned content that is not JSON for a json_object request",
+            provider.name()
+        );
+    }
+    Ok(())
+}
+
+fn instruction(format: &ResponseFormat) -> Option<String> {
+    const JSON_ONLY: &str = "Respond only with valid JSON, without code fences or any other text.";
+    match format {
+        ResponseFormat::Text => None,
+        ResponseFormat::JsonObject => Some(format!("{JSON_ONLY} The JSON must be an object.")),
+        ResponseFormat::JsonSchema { json_schema } => {
+            let mut instruction = format!(
+                "{JSON_ONLY} The JSON must match the schema '{}'",
+                json_schema.name
+            );
+            if let Some(description) = &json_schema.description {
+                instruction.push_str(&format!(" ({description})"));
+            }
+            if let Some(schema) = &json_schema.schema {
+                instruction.push_str(&format!(":\n{schema}"));
+            }
+            Some(instruction)
+        }
+    }
+}
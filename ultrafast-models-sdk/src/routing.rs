@@ -40,6 +40,19 @@
 //! ### Lowest Latency
 //! Routes to the provider with the best average response time.
 //!
+//! ### Latency Weighted
+//! Load balances with weights inversely proportional to each provider's
+//! smoothed latency over a sliding window of recent requests.
+//!
+//! ### Sticky
+//! Sends every request of a session (a user id or a session header) to the
+//! same provider, falling back to another strategy when it is unhealthy.
+//!
+//! ### Model Affinity
+//! Sends each request to the provider whose model mapping or supported models
+//! include the requested model, so clients can name a model without picking
+//! a provider.
+//!
 //! ## Usage Examples
 //!
 //! ### Basic Routing Setup
@@ -155,10 +168,32 @@
 //! - **Consider Costs**: Factor in provider costs when designing routing strategies
 //! - **Handle Failures**: Implement proper fallback mechanisms for routing failures
 
+use crate::circuit_breaker::CircuitState;
 use chrono::Timelike;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Smallest traffic share a provider in slow-start receives.
+///
+/// Keeps a freshly activated provider reachable from the first request so
+/// that misconfiguration surfaces quickly, but only for a small fraction.
+const SLOW_START_MIN_FACTOR: f64 = 0.05;
+
+/// Error rate above which auto-tuning halves a provider's weight.
+const AUTO_TUNE_MAX_ERROR_RATE: f64 = 0.1;
+
+/// Latency, relative to the fastest provider, above which auto-tuning halves
+/// a provider's weight.
+const AUTO_TUNE_MAX_LATENCY_RATIO: f64 = 2.0;
+
+/// Default EWMA smoothing factor for [`RoutingStrategy::LatencyWeighted`].
+pub const DEFAULT_LATENCY_SMOOTHING: f64 = 0.3;
+
+fn default_latency_smoothing() -> f64 {
+    DEFAULT_LATENCY_SMOOTHING
+}
 
 /// Routing strategies for provider selection.
 ///
@@ -189,7 +224,11 @@ pub enum RoutingStrategy {
     Single,
     /// Use primary provider with automatic fallback
     Fallback,
-    /// Distribute requests across providers with weights
+    /// Distribute requests across providers with weights.
+    ///
+    /// `weights[i]` belongs to the i-th provider in provider ID order, and a
+    /// provider keeps its weight when others are filtered out as unhealthy.
+    /// With fewer weights than providers, all providers are weighted equally.
     LoadBalance { weights: Vec<f32> },
     /// Route based on specific conditions and rules
     Conditional { rules: Vec<RoutingRule> },
@@ -201,6 +240,262 @@ pub enum RoutingStrategy {
     LeastUsed,
     /// Route to provider with lowest average latency
     LowestLatency,
+    /// Load balance with weights derived from recently observed latency.
+    ///
+    /// Each provider's latency samples from the last `window` are folded into
+    /// an exponentially weighted moving average, oldest first:
+    /// `ewma = smoothing * sample + (1 - smoothing) * ewma`. A `smoothing`
+    /// close to 1.0 follows the latest requests, a small one damps spikes.
+    /// Providers are then weighted by `1 / ewma`; providers without samples
+    /// get the mean weight so they keep receiving traffic.
+    LatencyWeighted {
+        #[serde(with = "crate::common::duration_serde")]
+        window: Duration,
+        /// EWMA smoothing factor in `(0, 1]`
+        #[serde(default = "default_latency_smoothing")]
+        smoothing: f64,
+    },
+    /// Keep each session on the same provider.
+    ///
+    /// The session key picks a provider by rendezvous hashing over all
+    /// candidates, so a session maps to the same provider on every request
+    /// and only the sessions of a removed provider move. Requests without a
+    /// key, or whose provider is unhealthy, are routed by `inner`.
+    Sticky {
+        inner: Box<RoutingStrategy>,
+        key: StickyKey,
+    },
+    /// Send each request to the provider that serves its model.
+    ///
+    /// Providers whose `model_mapping` lists the model are preferred over
+    /// those that only report it among their supported models. A model
+    /// served by several healthy providers is routed among them by `inner`.
+    /// No provider is selected for a model whose providers are all
+    /// unhealthy, since the others cannot serve it, and failover after a 429
+    /// stays on the providers serving the model. Requests for a model no
+    /// provider is known to serve, or without a model, are routed by `inner`
+    /// over all providers.
+    ModelAffinity { inner: Box<RoutingStrategy> },
+}
+
+/// Models a provider serves, as seen by [`RoutingStrategy::ModelAffinity`].
+#[derive(Debug, Clone, Default)]
+pub struct ProviderModels {
+    /// Models named in the provider's `model_mapping`
+    pub mapped: HashSet<String>,
+    /// Models the provider reports as supported
+    pub supported: HashSet<String>,
+}
+
+/// Where [`RoutingStrategy::Sticky`] reads the session key from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StickyKey {
+    /// The request's `user` field
+    UserId,
+    /// A request header, looked up in [`RoutingContext::metadata`] by its
+    /// lowercase name
+    Header(String),
+}
+
+impl StickyKey {
+    fn value<'a>(&self, context: &'a RoutingContext) -> Option<&'a str> {
+        match self {
+            StickyKey::UserId => context.user_id.as_deref(),
+            StickyKey::Header(name) => context
+                .metadata
+                .get(&name.to_ascii_lowercase())
+                .map(String::as_str),
+        }
+        .filter(|value| !value.is_empty())
+    }
+}
+
+impl RoutingStrategy {
+    /// Header a [`RoutingStrategy::Sticky`] strategy keys sessions on, if any.
+    pub fn sticky_header(&self) -> Option<&str> {
+        match self {
+            RoutingStrategy::Sticky {
+                key: StickyKey::Header(name),
+                ..
+            } => Some(name),
+            RoutingStrategy::Sticky { inner, .. } | RoutingStrategy::ModelAffinity { inner } => {
+                inner.sticky_header()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The configured weight of each of `healthy_providers`, looked up by the
+/// provider's position among all `providers` in ID order.
+///
+/// Returns no weights, which selects equally, when some provider has none.
+fn configured_weights(
+    weights: &[f32],
+    providers: &[String],
+    healthy_providers: &[String],
+) -> Vec<f32> {
+    let mut ordered: Vec<&String> = providers.iter().collect();
+    ordered.sort();
+    healthy_providers
+        .iter()
+        .map(|provider_id| {
+            ordered
+                .iter()
+                .position(|id| *id == provider_id)
+                .and_then(|index| weights.get(index).copied())
+        })
+        .collect::<Option<Vec<f32>>>()
+        .unwrap_or_default()
+}
+
+/// Provider with the highest hash of `(session, provider)`.
+///
+/// The hash is FNV-1a rather than std's `DefaultHasher`, whose algorithm may
+/// change between Rust releases: gateways built with different toolchains
+/// must still send a session to the same provider.
+fn sticky_provider<'a>(providers: &'a [String], session: &str) -> Option<&'a String> {
+    providers.iter().max_by_key(|provider_id| {
+        // 0xff never occurs in UTF-8, so it cleanly separates the two parts
+        fnv1a(
+            session
+                .bytes()
+                .chain(std::iter::once(0xff))
+                .chain(provider_id.bytes()),
+        )
+    })
+}
+
+/// 64-bit FNV-1a hash of `bytes`.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Error budget that automatically takes a provider out of rotation.
+///
+/// When a provider records more than `max_errors` failures within `window`
+/// it is disabled for `cooldown`. Unlike the circuit breaker, which reacts to
+/// individual request failures, the budget is a coarse-grained guard meant to
+/// surface persistent provider problems to operators.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBudget {
+    /// Number of errors tolerated within `window`
+    pub max_errors: u32,
+    /// Sliding window over which errors are counted
+    #[serde(with = "crate::common::duration_serde")]
+    pub window: Duration,
+    /// How long an exhausted provider stays disabled
+    #[serde(with = "crate::common::duration_serde")]
+    pub cooldown: Duration,
+}
+
+/// Bounds and cadence for automatic load-balance weight tuning.
+///
+/// Every `interval` each provider's weight is adjusted AIMD-style: halved
+/// when its error rate or latency was poor during the interval, otherwise
+/// increased by a tenth of the `[min_weight, max_weight]` span.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutoTune {
+    /// Lowest weight a provider can be tuned down to
+    pub min_weight: f32,
+    /// Highest weight a provider can be tuned up to
+    pub max_weight: f32,
+    /// How often weights are recomputed
+    #[serde(with = "crate::common::duration_serde")]
+    pub interval: Duration,
+}
+
+/// Outcomes observed for a provider during the current tuning interval.
+#[derive(Debug, Clone, Default)]
+struct TuneSample {
+    requests: u64,
+    errors: u64,
+    total_latency_ms: u64,
+}
+
+impl TuneSample {
+    fn error_rate(&self) -> f64 {
+        self.errors as f64 / self.requests as f64
+    }
+
+    fn average_latency_ms(&self) -> f64 {
+        self.total_latency_ms as f64 / self.requests as f64
+    }
+}
+
+/// State for [`AutoTune`]: current weights and the samples feeding the next update.
+#[derive(Debug)]
+struct WeightTuner {
+    config: AutoTune,
+    weights: HashMap<String, f32>,
+    samples: HashMap<String, TuneSample>,
+    last_update: Instant,
+}
+
+impl WeightTuner {
+    fn new(config: AutoTune) -> Self {
+        Self {
+            config,
+            weights: HashMap::new(),
+            samples: HashMap::new(),
+            last_update: Instant::now(),
+        }
+    }
+
+    fn weight(&self, provider_id: &str) -> f32 {
+        self.weights
+            .get(provider_id)
+            .copied()
+            .unwrap_or(self.config.max_weight)
+    }
+
+    fn record(&mut self, provider_id: &str, success: bool, latency_ms: u64) {
+        let sample = self.samples.entry(provider_id.to_string()).or_default();
+        sample.requests += 1;
+        sample.total_latency_ms += latency_ms;
+        if !success {
+            sample.errors += 1;
+        }
+    }
+
+    fn update(&mut self) {
+        let best_latency = self
+            .samples
+            .values()
+            .map(TuneSample::average_latency_ms)
+            .fold(f64::INFINITY, f64::min);
+        let step = (self.config.max_weight - self.config.min_weight) / 10.0;
+
+        for (provider_id, sample) in self.samples.drain() {
+            let current = self
+                .weights
+                .get(&provider_id)
+                .copied()
+                .unwrap_or(self.config.max_weight);
+            let degraded = sample.error_rate() > AUTO_TUNE_MAX_ERROR_RATE
+                || sample.average_latency_ms()
+                    > best_latency.max(1.0) * AUTO_TUNE_MAX_LATENCY_RATIO;
+            let next = if degraded {
+                current / 2.0
+            } else {
+                current + step
+            };
+            let next = next.clamp(self.config.min_weight, self.config.max_weight);
+
+            if next != current {
+                tracing::debug!(
+                    "Auto-tuned weight for provider {}: {:.3} -> {:.3}",
+                    provider_id,
+                    current,
+                    next
+                );
+            }
+            self.weights.insert(provider_id, next);
+        }
+        self.last_update = Instant::now();
+    }
 }
 
 /// Routing rule for conditional routing.
@@ -340,6 +635,28 @@ pub struct Router {
     strategy: RoutingStrategy,
     /// Performance statistics for each provider
     provider_stats: HashMap<String, ProviderStats>,
+    /// Ramp-up window for newly activated providers (`None` disables slow-start)
+    slow_start: Option<Duration>,
+    /// When each provider entered slow-start
+    activated_at: HashMap<String, Instant>,
+    /// Providers whose circuit breaker was last seen open or half-open
+    tripped_circuits: HashSet<String>,
+    /// Per-provider error budget (`None` disables automatic disabling)
+    error_budget: Option<ErrorBudget>,
+    /// Timestamps of recent errors for each provider
+    recent_errors: HashMap<String, VecDeque<Instant>>,
+    /// Providers disabled for exceeding the error budget, and until when
+    disabled_until: HashMap<String, Instant>,
+    /// Providers that answered 429, and until when they asked us to back off
+    rate_limited_until: HashMap<String, Instant>,
+    /// Whether every selection decision is logged at debug level
+    debug: bool,
+    /// Automatic load-balance weight tuning (`None` keeps static weights)
+    weight_tuner: Option<WeightTuner>,
+    /// Latency samples within the `LatencyWeighted` window, oldest first
+    recent_latencies: HashMap<String, VecDeque<(Instant, u64)>>,
+    /// Models each provider serves, for `ModelAffinity`
+    provider_models: HashMap<String, ProviderModels>,
 }
 
 /// Performance statistics for a provider.
@@ -396,9 +713,268 @@ impl Router {
         Self {
             strategy,
             provider_stats: HashMap::new(),
+            slow_start: None,
+            activated_at: HashMap::new(),
+            tripped_circuits: HashSet::new(),
+            error_budget: None,
+            recent_errors: HashMap::new(),
+            disabled_until: HashMap::new(),
+            rate_limited_until: HashMap::new(),
+            debug: false,
+            recent_latencies: HashMap::new(),
+            weight_tuner: None,
+            provider_models: HashMap::new(),
         }
     }
 
+    /// Record the models `provider_id` serves, for `ModelAffinity` routing.
+    pub fn with_provider_models(
+        mut self,
+        provider_id: impl Into<String>,
+        models: ProviderModels,
+    ) -> Self {
+        self.provider_models.insert(provider_id.into(), models);
+        self
+    }
+
+    /// Providers among `candidates` that serve `model`: those mapping it if
+    /// any, otherwise those supporting it.
+    fn serving_providers(&self, candidates: &[String], model: &str) -> Vec<String> {
+        let serving = |models: fn(&ProviderModels) -> &HashSet<String>| -> Vec<String> {
+            candidates
+                .iter()
+                .filter(|provider_id| {
+                    self.provider_models
+                        .get(*provider_id)
+                        .is_some_and(|provider| models(provider).contains(model))
+                })
+                .cloned()
+                .collect()
+        };
+        let mapped = serving(|provider| &provider.mapped);
+        if mapped.is_empty() {
+            serving(|provider| &provider.supported)
+        } else {
+            mapped
+        }
+    }
+
+    /// Replace static load-balance weights with weights tuned from observed
+    /// latency and error rates within the bounds of `auto_tune`.
+    pub fn with_auto_tune(mut self, auto_tune: AutoTune) -> Self {
+        self.weight_tuner = Some(WeightTuner::new(auto_tune));
+        self
+    }
+
+    /// Current auto-tuned weight for a provider, if auto-tuning is enabled.
+    pub fn tuned_weight(&self, provider_id: &str) -> Option<f32> {
+        self.weight_tuner
+            .as_ref()
+            .map(|tuner| tuner.weight(provider_id))
+    }
+
+    /// Record the outcome of a request sent to a provider.
+    ///
+    /// Failures count against the error budget, and every outcome feeds
+    /// weight auto-tuning, which recomputes weights once its interval elapses.
+    /// Under `LatencyWeighted` the latency is also added to the provider's
+    /// sliding window.
+    pub fn record_outcome(&mut self, provider_id: &str, success: bool, latency_ms: u64) {
+        if !success {
+            self.record_error(provider_id);
+        }
+
+        if let RoutingStrategy::LatencyWeighted { window, .. } = self.strategy {
+            let now = Instant::now();
+            let samples = self
+                .recent_latencies
+                .entry(provider_id.to_string())
+                .or_default();
+            samples.push_back((now, latency_ms));
+            while samples
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) > window)
+            {
+                samples.pop_front();
+            }
+        }
+
+        if let Some(tuner) = &mut self.weight_tuner {
+            tuner.record(provider_id, success, latency_ms);
+            if tuner.last_update.elapsed() >= tuner.config.interval {
+                tuner.update();
+            }
+        }
+    }
+
+    /// Recompute auto-tuned weights from the outcomes recorded so far.
+    pub fn retune_weights(&mut self) {
+        if let Some(tuner) = &mut self.weight_tuner {
+            tuner.update();
+        }
+    }
+
+    /// Log every selection decision (candidates, their scores and the
+    /// winner) at debug level.
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    /// Enable slow-start for providers activated after construction.
+    ///
+    /// A provider passed to [`Router::mark_provider_activated`] receives a
+    /// share of traffic that grows linearly over `window`, starting from a
+    /// small floor, before it is treated like any other provider.
+    pub fn with_slow_start(mut self, window: Duration) -> Self {
+        self.slow_start = Some(window);
+        self
+    }
+
+    /// Record that a provider was just added or re-enabled.
+    ///
+    /// Has no effect unless slow-start is configured.
+    pub fn mark_provider_activated(&mut self, provider_id: &str) {
+        if self.slow_start.is_some() {
+            self.activated_at
+                .insert(provider_id.to_string(), Instant::now());
+        }
+    }
+
+    /// Record the circuit breaker state of a provider after a request.
+    ///
+    /// A provider whose circuit closes again after being open or half-open
+    /// re-enters slow-start.
+    pub fn record_circuit_state(&mut self, provider_id: &str, state: CircuitState) {
+        if state != CircuitState::Closed {
+            self.tripped_circuits.insert(provider_id.to_string());
+        } else if self.tripped_circuits.remove(provider_id) {
+            self.mark_provider_activated(provider_id);
+        }
+    }
+
+    /// Fraction (0.0 to 1.0) of its normal traffic a provider currently accepts.
+    ///
+    /// Returns 1.0 for providers that are not in slow-start.
+    pub fn slow_start_factor(&self, provider_id: &str) -> f64 {
+        let (Some(window), Some(activated_at)) =
+            (self.slow_start, self.activated_at.get(provider_id))
+        else {
+            return 1.0;
+        };
+
+        if window.is_zero() {
+            return 1.0;
+        }
+
+        let progress = activated_at.elapsed().as_secs_f64() / window.as_secs_f64();
+        progress.clamp(SLOW_START_MIN_FACTOR, 1.0)
+    }
+
+    /// Enable automatic disabling of providers that exhaust `budget`.
+    pub fn with_error_budget(mut self, budget: ErrorBudget) -> Self {
+        self.error_budget = Some(budget);
+        self
+    }
+
+    /// Record a failed request against a provider's error budget.
+    ///
+    /// Returns `true` if this error exhausted the budget and the provider was
+    /// disabled. Has no effect unless an error budget is configured.
+    pub fn record_error(&mut self, provider_id: &str) -> bool {
+        let Some(budget) = self.error_budget else {
+            return false;
+        };
+
+        let now = Instant::now();
+        if let Some(until) = self.disabled_until.get(provider_id) {
+            if now < *until {
+                return false;
+            }
+            self.disabled_until.remove(provider_id);
+            tracing::info!(
+                "Provider {} re-enabled after error budget cooldown",
+                provider_id
+            );
+        }
+
+        let errors = self
+            .recent_errors
+            .entry(provider_id.to_string())
+            .or_default();
+        errors.push_back(now);
+        while errors
+            .front()
+            .is_some_and(|at| now.duration_since(*at) > budget.window)
+        {
+            errors.pop_front();
+        }
+
+        if errors.len() as u32 <= budget.max_errors {
+            return false;
+        }
+
+        let error_count = errors.len();
+        errors.clear();
+        self.disabled_until
+            .insert(provider_id.to_string(), now + budget.cooldown);
+        tracing::error!(
+            "ALERT: provider {} exceeded its error budget ({} errors in {:?}); disabled for {:?}",
+            provider_id,
+            error_count,
+            budget.window,
+            budget.cooldown
+        );
+        // Ramp traffic back up once the cooldown ends
+        if self.slow_start.is_some() {
+            self.activated_at
+                .insert(provider_id.to_string(), now + budget.cooldown);
+        }
+        true
+    }
+
+    /// Whether a provider is currently disabled by its error budget.
+    pub fn is_disabled(&self, provider_id: &str) -> bool {
+        self.disabled_until
+            .get(provider_id)
+            .is_some_and(|until| Instant::now() < *until)
+    }
+
+    /// The strategy this router selects providers with.
+    pub fn strategy(&self) -> &RoutingStrategy {
+        &self.strategy
+    }
+
+    /// Providers among `providers` a request for `model` may fail over to.
+    ///
+    /// With `ModelAffinity` a model some provider serves stays on the
+    /// providers serving it; otherwise every provider may take it.
+    pub fn failover_providers(&self, providers: &[String], model: Option<&str>) -> Vec<String> {
+        if let (RoutingStrategy::ModelAffinity { .. }, Some(model)) = (&self.strategy, model) {
+            let serving = self.serving_providers(providers, model);
+            if !serving.is_empty() {
+                return serving;
+            }
+        }
+        providers.to_vec()
+    }
+
+    /// Record that a provider rate limited us and asked to wait `retry_after`.
+    ///
+    /// Until then the provider is only selected when no other provider is
+    /// available.
+    pub fn record_rate_limit(&mut self, provider_id: &str, retry_after: Duration) {
+        self.rate_limited_until
+            .insert(provider_id.to_string(), Instant::now() + retry_after);
+    }
+
+    /// Whether a provider is still inside a `Retry-After` window.
+    pub fn is_rate_limited(&self, provider_id: &str) -> bool {
+        self.rate_limited_until
+            .get(provider_id)
+            .is_some_and(|until| Instant::now() < *until)
+    }
+
     /// Select a provider based on the current routing strategy.
     ///
     /// # Arguments
@@ -441,13 +1017,47 @@ impl Router {
             return None;
         }
 
-        // Filter out unhealthy providers
-        let healthy_providers = self.filter_healthy_providers(providers, context);
+        // Filter out unhealthy providers and those that exhausted their error budget
+        let healthy_providers: Vec<String> = self
+            .filter_healthy_providers(providers, context)
+            .into_iter()
+            .filter(|provider_id| !self.is_disabled(provider_id))
+            .collect();
         if healthy_providers.is_empty() {
             return None;
         }
 
-        match &self.strategy {
+        // Prefer providers that are not backing off after a 429
+        let (available, rate_limited): (Vec<String>, Vec<String>) = healthy_providers
+            .into_iter()
+            .partition(|provider_id| !self.is_rate_limited(provider_id));
+        let healthy_providers = if available.is_empty() {
+            rate_limited
+        } else {
+            available
+        };
+
+        // Thin out providers that are still ramping up
+        let healthy_providers = self.apply_slow_start(healthy_providers);
+
+        let selection = self.select_with(&self.strategy, providers, &healthy_providers, context);
+
+        if self.debug {
+            self.log_selection(providers, &healthy_providers, selection.as_ref());
+        }
+
+        selection
+    }
+
+    /// Select among `healthy_providers` (a subset of `providers`) with `strategy`.
+    fn select_with(
+        &self,
+        strategy: &RoutingStrategy,
+        providers: &[String],
+        healthy_providers: &[String],
+        context: &RoutingContext,
+    ) -> Option<ProviderSelection> {
+        match strategy {
             RoutingStrategy::Single => {
                 // Always select the first provider
                 Some(ProviderSelection {
@@ -465,22 +1075,97 @@ impl Router {
                 })
             }
             RoutingStrategy::LoadBalance { weights } => {
-                self.select_weighted_provider(&healthy_providers, weights)
+                let weights: Vec<f32> = match &self.weight_tuner {
+                    Some(tuner) => healthy_providers
+                        .iter()
+                        .map(|provider_id| tuner.weight(provider_id))
+                        .collect(),
+                    None => configured_weights(weights, providers, healthy_providers),
+                };
+                self.select_weighted_provider(healthy_providers, &weights)
             }
             RoutingStrategy::Conditional { rules } => {
-                self.select_conditional_provider(&healthy_providers, rules, context)
+                self.select_conditional_provider(healthy_providers, rules, context)
             }
             RoutingStrategy::ABTesting { split } => {
-                self.select_ab_testing_provider(&healthy_providers, *split)
+                self.select_ab_testing_provider(healthy_providers, *split)
             }
-            RoutingStrategy::RoundRobin => self.select_round_robin_provider(&healthy_providers),
-            RoutingStrategy::LeastUsed => self.select_least_used_provider(&healthy_providers),
+            RoutingStrategy::RoundRobin => self.select_round_robin_provider(healthy_providers),
+            RoutingStrategy::LeastUsed => self.select_least_used_provider(healthy_providers),
             RoutingStrategy::LowestLatency => {
-                self.select_lowest_latency_provider(&healthy_providers)
+                self.select_lowest_latency_provider(healthy_providers)
+            }
+            RoutingStrategy::LatencyWeighted { window, smoothing } => {
+                let weights = self.latency_weights(healthy_providers, *window, *smoothing);
+                self.select_weighted_provider(healthy_providers, &weights)
+            }
+            RoutingStrategy::Sticky { inner, key } => {
+                let sticky = key
+                    .value(context)
+                    .and_then(|session| sticky_provider(providers, session))
+                    .filter(|provider_id| healthy_providers.contains(provider_id));
+                match sticky {
+                    Some(provider_id) => Some(ProviderSelection {
+                        provider_id: provider_id.clone(),
+                        weight: 1.0,
+                        reason: format!("Sticky session ({key:?})"),
+                    }),
+                    None => self.select_with(inner, providers, healthy_providers, context),
+                }
+            }
+            RoutingStrategy::ModelAffinity { inner } => {
+                let model = context
+                    .model
+                    .as_deref()
+                    .filter(|model| !self.serving_providers(providers, model).is_empty());
+                let Some(model) = model else {
+                    return self.select_with(inner, providers, healthy_providers, context);
+                };
+                match self.serving_providers(healthy_providers, model).as_slice() {
+                    [] => None,
+                    [provider_id] => Some(ProviderSelection {
+                        provider_id: provider_id.clone(),
+                        weight: 1.0,
+                        reason: "Model affinity".to_string(),
+                    }),
+                    serving => self.select_with(inner, providers, serving, context),
+                }
             }
         }
     }
 
+    /// Log a selection decision with the candidate set and per-candidate scores.
+    fn log_selection(
+        &self,
+        candidates: &[String],
+        eligible: &[String],
+        selection: Option<&ProviderSelection>,
+    ) {
+        let scores: Vec<String> = eligible
+            .iter()
+            .map(|provider_id| {
+                let stats = self.provider_stats.get(provider_id);
+                format!(
+                    "{}(success_rate={:.3}, latency_ms={:.1}, slow_start={:.2})",
+                    provider_id,
+                    stats.map_or(1.0, |stats| stats.success_rate()),
+                    stats.map_or(0.0, |stats| stats.average_latency_ms),
+                    self.slow_start_factor(provider_id)
+                )
+            })
+            .collect();
+
+        tracing::debug!(
+            "Routing decision: strategy={:?} candidates={:?} eligible=[{}] winner={} weight={} reason={}",
+            self.strategy,
+            candidates,
+            scores.join(", "),
+            selection.map_or("none", |s| s.provider_id.as_str()),
+            selection.map_or(0.0, |s| s.weight),
+            selection.map_or("no provider selected", |s| s.reason.as_str())
+        );
+    }
+
     /// Filter providers to only include healthy ones.
     ///
     /// This method removes providers that are considered unhealthy based on
@@ -506,6 +1191,33 @@ impl Router {
             .collect()
     }
 
+    /// Probabilistically drop providers that are still in their slow-start window.
+    ///
+    /// Each ramping provider is kept with probability equal to its
+    /// [`Router::slow_start_factor`]. If every candidate would be dropped, the
+    /// original list is returned so requests are never left without a provider.
+    fn apply_slow_start(&self, providers: Vec<String>) -> Vec<String> {
+        if self.activated_at.is_empty() {
+            return providers;
+        }
+
+        let mut rng = rand::thread_rng();
+        let admitted: Vec<String> = providers
+            .iter()
+            .filter(|provider_id| {
+                let factor = self.slow_start_factor(provider_id);
+                factor >= 1.0 || rng.gen::<f64>() < factor
+            })
+            .cloned()
+            .collect();
+
+        if admitted.is_empty() {
+            providers
+        } else {
+            admitted
+        }
+    }
+
     /// Select provider using weighted load balancing.
     ///
     /// Uses the provided weights to probabilistically select a provider.
@@ -701,6 +1413,39 @@ impl Router {
         })
     }
 
+    /// Effective `LatencyWeighted` weights for `providers`, in order.
+    ///
+    /// Samples older than `window` are ignored. Providers without samples get
+    /// the mean weight of those with samples (equal weights if none have any).
+    fn latency_weights(&self, providers: &[String], window: Duration, smoothing: f64) -> Vec<f32> {
+        let now = Instant::now();
+        let weights: Vec<Option<f32>> = providers
+            .iter()
+            .map(|provider_id| {
+                let ewma = self
+                    .recent_latencies
+                    .get(provider_id)?
+                    .iter()
+                    .filter(|(at, _)| now.duration_since(*at) <= window)
+                    .map(|(_, latency_ms)| *latency_ms as f64)
+                    .reduce(|ewma, sample| smoothing * sample + (1.0 - smoothing) * ewma)?;
+                // Clamp so a 0ms sample cannot take all traffic
+                Some((1.0 / ewma.max(1.0)) as f32)
+            })
+            .collect();
+
+        let sampled: Vec<f32> = weights.iter().flatten().copied().collect();
+        let default_weight = if sampled.is_empty() {
+            1.0
+        } else {
+            sampled.iter().sum::<f32>() / sampled.len() as f32
+        };
+        weights
+            .into_iter()
+            .map(|weight| weight.unwrap_or(default_weight))
+            .collect()
+    }
+
     /// Update provider statistics after a request.
     ///
     /// # Arguments
@@ -743,3 +1488,410 @@ impl Router {
             alpha * latency_ms as f64 + (1.0 - alpha) * stats.average_latency_ms;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> RoutingContext {
+        RoutingContext {
+            model: Some("gpt-4".to_string()),
+            user_region: None,
+            request_size: 0,
+            estimated_tokens: 0,
+            user_id: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn fresh_share(router: &Router, providers: &[String]) -> f64 {
+        let samples = 4000;
+        let hits = (0..samples)
+            .filter_map(|_| router.select_provider(providers, &context()))
+            .filter(|selection| selection.provider_id == "fresh")
+            .count();
+        hits as f64 / samples as f64
+    }
+
+    #[test]
+    fn test_slow_start_ramps_traffic_to_new_provider() {
+        let window = Duration::from_secs(100);
+        let providers = vec!["stable".to_string(), "fresh".to_string()];
+        let mut router = Router::new(RoutingStrategy::LoadBalance {
+            weights: vec![0.5, 0.5],
+        })
+        .with_slow_start(window);
+
+        let mut shares = Vec::new();
+        for elapsed_secs in [10, 50, 200] {
+            router.mark_provider_activated("fresh");
+            let activated_at = Instant::now()
+                .checked_sub(Duration::from_secs(elapsed_secs))
+                .unwrap();
+            router
+                .activated_at
+                .insert("fresh".to_string(), activated_at);
+            shares.push(fresh_share(&router, &providers));
+        }
+
+        assert!(shares[0] < 0.15, "initial share too high: {shares:?}");
+        assert!(shares[0] < shares[1], "share did not grow: {shares:?}");
+        assert!(shares[1] < shares[2], "share did not grow: {shares:?}");
+        assert!(shares[2] > 0.4, "provider not at full share: {shares:?}");
+    }
+
+    #[test]
+    fn test_slow_start_disabled_by_default() {
+        let mut router = Router::new(RoutingStrategy::Single);
+        router.mark_provider_activated("fresh");
+        assert_eq!(router.slow_start_factor("fresh"), 1.0);
+    }
+
+    #[test]
+    fn test_circuit_recovery_starts_slow_start() {
+        let mut router =
+            Router::new(RoutingStrategy::Fallback).with_slow_start(Duration::from_secs(100));
+
+        router.record_circuit_state("fresh", CircuitState::Closed);
+        assert_eq!(router.slow_start_factor("fresh"), 1.0);
+
+        router.record_circuit_state("fresh", CircuitState::Open);
+        router.record_circuit_state("fresh", CircuitState::HalfOpen);
+        assert_eq!(router.slow_start_factor("fresh"), 1.0);
+
+        router.record_circuit_state("fresh", CircuitState::Closed);
+        assert!(router.slow_start_factor("fresh") < 0.1);
+    }
+
+    #[test]
+    fn test_load_balance_weights_follow_provider_ids() {
+        let providers = vec![
+            "alpha".to_string(),
+            "bravo".to_string(),
+            "fresh".to_string(),
+        ];
+        let mut router = Router::new(RoutingStrategy::LoadBalance {
+            weights: vec![0.1, 0.9, 0.1],
+        });
+        for _ in 0..10 {
+            router.update_stats("alpha", false, 100);
+        }
+
+        let share = fresh_share(&router, &providers);
+        assert!(share < 0.2, "fresh took bravo's weight: {share}");
+    }
+
+    #[test]
+    fn test_error_budget_disables_and_reenables_provider() {
+        let providers = vec!["flaky".to_string(), "stable".to_string()];
+        let mut router = Router::new(RoutingStrategy::Fallback).with_error_budget(ErrorBudget {
+            max_errors: 3,
+            window: Duration::from_secs(300),
+            cooldown: Duration::from_millis(50),
+        });
+
+        for _ in 0..3 {
+            assert!(!router.record_error("flaky"));
+        }
+        assert!(!router.is_disabled("flaky"));
+        assert!(router.record_error("flaky"));
+        assert!(router.is_disabled("flaky"));
+        let selection = router.select_provider(&providers, &context()).unwrap();
+        assert_eq!(selection.provider_id, "stable");
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(!router.is_disabled("flaky"));
+        let selection = router.select_provider(&providers, &context()).unwrap();
+        assert_eq!(selection.provider_id, "flaky");
+
+        // The budget starts afresh after the cooldown
+        assert!(!router.record_error("flaky"));
+        assert!(!router.is_disabled("flaky"));
+    }
+
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn capture_debug_logs(f: impl FnOnce()) -> String {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        tracing::subscriber::with_default(subscriber, f);
+        let logs = buffer.0.lock().unwrap().clone();
+        String::from_utf8(logs).unwrap()
+    }
+
+    #[test]
+    fn test_routing_debug_logs_candidate_set() {
+        let providers = vec!["openai".to_string(), "anthropic".to_string()];
+
+        let router = Router::new(RoutingStrategy::Fallback).with_debug(true);
+        let logs = capture_debug_logs(|| {
+            router.select_provider(&providers, &context()).unwrap();
+        });
+        assert!(
+            logs.contains(r#"candidates=["openai", "anthropic"]"#),
+            "{logs}"
+        );
+        assert!(logs.contains("winner=openai"), "{logs}");
+
+        let router = Router::new(RoutingStrategy::Fallback);
+        let logs = capture_debug_logs(|| {
+            router.select_provider(&providers, &context()).unwrap();
+        });
+        assert!(!logs.contains("Routing decision"), "{logs}");
+    }
+
+    #[test]
+    fn test_auto_tune_lowers_weight_of_degrading_provider() {
+        let mut router = Router::new(RoutingStrategy::LoadBalance {
+            weights: vec![0.5, 0.5],
+        })
+        .with_auto_tune(AutoTune {
+            min_weight: 0.05,
+            max_weight: 1.0,
+            interval: Duration::from_secs(3600),
+        });
+
+        let mut degraded_weights = Vec::new();
+        for _ in 0..3 {
+            for i in 0..10 {
+                router.record_outcome("healthy", true, 100);
+                router.record_outcome("degraded", i % 2 == 0, 1500);
+            }
+            router.retune_weights();
+            degraded_weights.push(router.tuned_weight("degraded").unwrap());
+        }
+
+        assert!(
+            degraded_weights.windows(2).all(|pair| pair[1] < pair[0]),
+            "weight did not decrease: {degraded_weights:?}"
+        );
+        assert_eq!(router.tuned_weight("healthy"), Some(1.0));
+        assert!(degraded_weights[2] >= 0.05);
+    }
+
+    #[test]
+    fn test_latency_weighted_prefers_faster_provider() {
+        let providers = vec!["fast".to_string(), "slow".to_string()];
+        let mut router = Router::new(RoutingStrategy::LatencyWeighted {
+            window: Duration::from_secs(60),
+            smoothing: DEFAULT_LATENCY_SMOOTHING,
+        });
+
+        // No samples yet: equal weights
+        assert_eq!(
+            router.latency_weights(&providers, Duration::from_secs(60), 0.3),
+            vec![1.0, 1.0]
+        );
+
+        for i in 0..20 {
+            router.record_outcome("fast", true, 80 + i % 5 * 10);
+            router.record_outcome("slow", true, 400 + i % 5 * 50);
+        }
+
+        let fast = (0..1000)
+            .filter_map(|_| router.select_provider(&providers, &context()))
+            .filter(|selection| selection.provider_id == "fast")
+            .count();
+        assert!(fast > 700, "fast provider selected {fast}/1000 times");
+    }
+
+    #[test]
+    fn test_sticky_routing_keeps_users_on_one_provider_and_fails_over() {
+        let providers: Vec<String> = ["openai", "anthropic", "groq"]
+            .iter()
+            .map(|provider_id| provider_id.to_string())
+            .collect();
+        let mut router = Router::new(RoutingStrategy::Sticky {
+            inner: Box::new(RoutingStrategy::RoundRobin),
+            key: StickyKey::UserId,
+        });
+        let user = |user_id: &str| RoutingContext {
+            user_id: Some(user_id.to_string()),
+            ..context()
+        };
+        let select = |router: &Router, context: &RoutingContext| {
+            router
+                .select_provider(&providers, context)
+                .unwrap()
+                .provider_id
+        };
+
+        // The same user always lands on the same provider, whatever the order
+        let home = select(&router, &user("alice"));
+        let mut reversed = providers.clone();
+        reversed.reverse();
+        for _ in 0..20 {
+            assert_eq!(select(&router, &user("alice")), home);
+            assert_eq!(
+                router
+                    .select_provider(&reversed, &user("alice"))
+                    .unwrap()
+                    .provider_id,
+                home
+            );
+        }
+
+        // Users spread over the providers
+        let homes: std::collections::HashSet<String> = (0..50)
+            .map(|i| select(&router, &user(&format!("user-{i}"))))
+            .collect();
+        assert!(homes.len() > 1);
+
+        // An unhealthy home provider hands the user to the inner strategy
+        for _ in 0..10 {
+            router.update_stats(&home, false, 100);
+        }
+        for _ in 0..10 {
+            assert_ne!(select(&router, &user("alice")), home);
+        }
+
+        // Once healthy again the user returns home
+        for _ in 0..100 {
+            router.update_stats(&home, true, 100);
+        }
+        assert_eq!(select(&router, &user("alice")), home);
+    }
+
+    #[test]
+    fn test_sticky_hash_is_stable() {
+        // Published FNV-1a test vectors
+        assert_eq!(fnv1a(*b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(*b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(*b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_sticky_routing_by_header() {
+        let providers: Vec<String> = ["openai", "anthropic", "groq"]
+            .iter()
+            .map(|provider_id| provider_id.to_string())
+            .collect();
+        let router = Router::new(RoutingStrategy::Sticky {
+            inner: Box::new(RoutingStrategy::RoundRobin),
+            key: StickyKey::Header("X-Session-Id".to_string()),
+        });
+        assert_eq!(router.strategy().sticky_header(), Some("X-Session-Id"));
+
+        let session = RoutingContext {
+            metadata: HashMap::from([("x-session-id".to_string(), "s-42".to_string())]),
+            ..context()
+        };
+        let home = router.select_provider(&providers, &session).unwrap();
+        assert!(home.reason.starts_with("Sticky session"));
+        for _ in 0..20 {
+            assert_eq!(
+                router
+                    .select_provider(&providers, &session)
+                    .unwrap()
+                    .provider_id,
+                home.provider_id
+            );
+        }
+
+        // Without the header the inner strategy decides
+        let selection = router.select_provider(&providers, &context()).unwrap();
+        assert!(!selection.reason.starts_with("Sticky session"));
+    }
+
+    #[test]
+    fn test_model_affinity_routes_by_model() {
+        let providers: Vec<String> = ["openai", "anthropic", "azure"]
+            .iter()
+            .map(|provider_id| provider_id.to_string())
+            .collect();
+        let models = |mapped: &[&str], supported: &[&str]| ProviderModels {
+            mapped: mapped.iter().map(|model| model.to_string()).collect(),
+            supported: supported.iter().map(|model| model.to_string()).collect(),
+        };
+        let mut router = Router::new(RoutingStrategy::ModelAffinity {
+            inner: Box::new(RoutingStrategy::LoadBalance { weights: vec![] }),
+        })
+        .with_provider_models("openai", models(&[], &["gpt-4", "gpt-4o"]))
+        .with_provider_models("anthropic", models(&[], &["claude-3-opus"]))
+        .with_provider_models("azure", models(&["gpt-4o"], &["gpt-4", "gpt-4o"]));
+        let model = |model: &str| RoutingContext {
+            model: Some(model.to_string()),
+            ..context()
+        };
+        let selections = |router: &Router, model_name: &str| -> HashSet<String> {
+            (0..200)
+                .map(|_| {
+                    router
+                        .select_provider(&providers, &model(model_name))
+                        .unwrap()
+                        .provider_id
+                })
+                .collect()
+        };
+
+        // A model only one provider serves always goes there
+        let selection = router
+            .select_provider(&providers, &model("claude-3-opus"))
+            .unwrap();
+        assert_eq!(selection.provider_id, "anthropic");
+        assert_eq!(selection.reason, "Model affinity");
+
+        // A mapping wins over a supported-models match
+        assert_eq!(
+            selections(&router, "gpt-4o"),
+            HashSet::from(["azure".to_string()])
+        );
+
+        // A model several providers serve is split among them by the inner strategy
+        assert_eq!(
+            selections(&router, "gpt-4"),
+            HashSet::from(["openai".to_string(), "azure".to_string()])
+        );
+
+        // Unknown models fall back to the inner strategy over all providers
+        assert_eq!(selections(&router, "mistral-large").len(), 3);
+
+        // Failover stays on the providers serving the model
+        assert_eq!(
+            router.failover_providers(&providers, Some("claude-3-opus")),
+            vec!["anthropic".to_string()]
+        );
+        assert_eq!(
+            router.failover_providers(&providers, Some("mistral-large")),
+            providers
+        );
+
+        // A model whose only provider is unhealthy is not sent elsewhere
+        for _ in 0..10 {
+            router.update_stats("anthropic", false, 100);
+        }
+        assert!(router
+            .select_provider(&providers, &model("claude-3-opus"))
+            .is_none());
+
+        // Weights, given in provider ID order, stay with their providers
+        let router = Router::new(RoutingStrategy::ModelAffinity {
+            inner: Box::new(RoutingStrategy::LoadBalance {
+                weights: vec![1.0, 0.0, 1.0],
+            }),
+        })
+        .with_provider_models("azure", models(&[], &["gpt-4"]))
+        .with_provider_models("openai", models(&[], &["gpt-4"]));
+        assert_eq!(
+            selections(&router, "gpt-4"),
+            HashSet::from(["openai".to_string()])
+        );
+    }
+}
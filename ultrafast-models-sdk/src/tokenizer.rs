@@ -0,0 +1,257 @@
+//! # Token Estimation Module
+//!
+//! This module estimates how many tokens a chat request will consume, for
+//! context-window checks and routing's `estimated_tokens`.
+//!
+//! ## Overview
+//!
+//! Models are mapped to the BPE encoding their family uses (`cl100k_base`
+//! for GPT-4 and GPT-3.5, `o200k_base` for GPT-4o and the o-series). Text is
+//! then split the way those encodings pre-tokenize it (words with their
+//! leading space, digit groups of up to three, punctuation runs, whitespace
+//! runs) and each piece is costed by script, so CJK text and code are no
+//! longer undercounted the way a flat characters / 4 rule does.
+//!
+//! The result is an estimate, not an exact BPE count: rare words split into
+//! more tokens than predicted and common punctuation pairs merge into fewer.
+//! Building with the `tokenizer` feature replaces the estimate with exact
+//! counts from tiktoken's `cl100k_base` and `o200k_base` encodings, at the
+//! cost of loading their rank tables on first use.
+//!
+//! Models from other families fall back to the characters / 4 heuristic.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use ultrafast_models_sdk::models::Message;
+//! use ultrafast_models_sdk::tokenizer;
+//!
+//! let messages = vec![Message::user("Hello, world!")];
+//! let tokens = tokenizer::estimate_tokens("gpt-4", &messages);
+//! ```
+
+use crate::models::Message;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Tokens added per message for the role and message delimiters.
+const TOKENS_PER_MESSAGE: u32 = 3;
+
+/// Tokens added once per request to prime the assistant's reply.
+const REPLY_PRIMING_TOKENS: u32 = 3;
+
+/// Buffer the characters / 4 fallback adds for system messages and formatting.
+const FALLBACK_BUFFER_TOKENS: u32 = 50;
+
+/// Characters of an ASCII word that fit in one token beyond the first.
+const WORD_CHARS_PER_TOKEN: usize = 8;
+
+/// BPE encodings whose token counts can be estimated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Used by GPT-4, GPT-3.5 and the `text-embedding-3` / `ada-002` models
+    Cl100kBase,
+    /// Used by GPT-4o, GPT-4.1 and the o-series reasoning models
+    O200kBase,
+}
+
+/// Encoding used by `model`, or `None` for model families without a known
+/// encoding.
+pub fn encoding_for_model(model: &str) -> Option<Encoding> {
+    // Strip a provider prefix such as "openai/gpt-4o"
+    let model = model.rsplit('/').next().unwrap_or(model);
+
+    const O200K_PREFIXES: [&str; 6] = ["gpt-4o", "gpt-4.1", "gpt-5", "o1", "o3", "o4"];
+    const CL100K_PREFIXES: [&str; 4] =
+        ["gpt-4", "gpt-3.5", "text-embedding-3", "text-embedding-ada"];
+
+    if O200K_PREFIXES
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+    {
+        Some(Encoding::O200kBase)
+    } else if CL100K_PREFIXES
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+    {
+        Some(Encoding::Cl100kBase)
+    } else {
+        None
+    }
+}
+
+/// Estimate the prompt tokens of `messages` sent to `model`.
+pub fn estimate_tokens(model: &str, messages: &[Message]) -> u32 {
+    let Some(encoding) = encoding_for_model(model) else {
+        // Rough estimation: 1 token ≈ 4 characters
+        let content_tokens: u32 = messages
+            .iter()
+            .map(|message| message.content.len() as u32 / 4)
+            .sum();
+        return content_tokens + FALLBACK_BUFFER_TOKENS;
+    };
+
+    let message_tokens: u32 = messages
+        .iter()
+        .map(|message| {
+            TOKENS_PER_MESSAGE
+                + count_with(encoding, &message.content)
+                + message
+                    .name
+                    .as_deref()
+                    .map_or(0, |name| count_with(encoding, name) + 1)
+        })
+        .sum();
+    message_tokens + REPLY_PRIMING_TOKENS
+}
+
+/// Estimate the tokens of a piece of text, such as streamed output.
+pub fn count_tokens(model: &str, text: &str) -> u32 {
+    match encoding_for_model(model) {
+        Some(encoding) => count_with(encoding, text),
+        None => text.chars().count().div_ceil(4) as u32,
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}'     // Hiragana, Katakana
+        | '\u{3400}'..='\u{4dbf}'   // CJK Extension A
+        | '\u{4e00}'..='\u{9fff}'   // CJK Unified Ideographs
+        | '\u{ac00}'..='\u{d7af}'   // Hangul syllables
+        | '\u{f900}'..='\u{faff}'   // CJK Compatibility Ideographs
+        | '\u{ff00}'..='\u{ffef}') // Full-width forms
+}
+
+/// Length of the run starting with an already consumed character, advancing
+/// past the following characters that match `class`.
+fn run_len(chars: &mut Peekable<Chars>, class: impl Fn(char) -> bool) -> usize {
+    let mut len = 1;
+    while chars.next_if(|c| class(*c)).is_some() {
+        len += 1;
+    }
+    len
+}
+
+#[cfg(feature = "tokenizer")]
+fn count_with(encoding: Encoding, text: &str) -> u32 {
+    use std::sync::OnceLock;
+    use tiktoken_rs::CoreBPE;
+
+    static CL100K_BASE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    static O200K_BASE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+
+    fn load(name: &str, load: fn() -> anyhow::Result<CoreBPE>) -> Option<CoreBPE> {
+        load()
+            .map_err(|e| tracing::warn!("Failed to load {} ranks, estimating tokens: {}", name, e))
+            .ok()
+    }
+
+    let bpe = match encoding {
+        Encoding::Cl100kBase => {
+            CL100K_BASE.get_or_init(|| load("cl100k_base", tiktoken_rs::cl100k_base))
+        }
+        Encoding::O200kBase => {
+            O200K_BASE.get_or_init(|| load("o200k_base", tiktoken_rs::o200k_base))
+        }
+    };
+    match bpe {
+        Some(bpe) => bpe.encode_ordinary(text).len() as u32,
+        None => estimate_with(encoding, text),
+    }
+}
+
+#[cfg(not(feature = "tokenizer"))]
+fn count_with(encoding: Encoding, text: &str) -> u32 {
+    estimate_with(encoding, text)
+}
+
+/// Estimate the tokens of `text` from how `encoding` pre-tokenizes it.
+fn estimate_with(encoding: Encoding, text: &str) -> u32 {
+    let mut chars = text.chars().peekable();
+    let mut tokens = 0;
+
+    while let Some(c) = chars.next() {
+        tokens += if c.is_ascii_alphabetic() {
+            let len = run_len(&mut chars, |c| c.is_ascii_alphabetic());
+            1 + (len - 1) / WORD_CHARS_PER_TOKEN
+        } else if c.is_ascii_digit() {
+            run_len(&mut chars, |c| c.is_ascii_digit()).div_ceil(3)
+        } else if is_cjk(c) {
+            let len = run_len(&mut chars, is_cjk);
+            match encoding {
+                // Most common ideographs are a single cl100k token
+                Encoding::Cl100kBase => len,
+                // o200k merges frequent character pairs
+                Encoding::O200kBase => (len * 3).div_ceil(4),
+            }
+        } else if c.is_alphabetic() {
+            // Other scripts (accented Latin, Cyrillic, ...) split more finely
+            run_len(&mut chars, |c| {
+                c.is_alphabetic() && !c.is_ascii() && !is_cjk(c)
+            })
+            .div_ceil(2)
+        } else if c.is_whitespace() {
+            let len = run_len(&mut chars, char::is_whitespace);
+            // A single space is merged into the piece that follows it
+            if len == 1 && c == ' ' && chars.peek().is_some() {
+                0
+            } else {
+                1
+            }
+        } else {
+            // Punctuation and symbols, which BPE usually merges in pairs
+            run_len(&mut chars, |c| !c.is_alphanumeric() && !c.is_whitespace()).div_ceil(2)
+        };
+    }
+
+    tokens as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimates_track_bpe_counts() {
+        let cl100k = |text| estimate_with(Encoding::Cl100kBase, text);
+
+        // Reference counts are cl100k_base token counts
+        assert_eq!(cl100k("Hello, world!"), 4);
+        assert_eq!(cl100k("The quick brown fox jumps over the lazy dog."), 10);
+
+        // 7 ideographs: ~7 cl100k tokens, while chars / 4 would give 2
+        let cjk = "我喜欢学习编程";
+        assert_eq!(cl100k(cjk), 7);
+        assert!(estimate_with(Encoding::O200kBase, cjk) < cl100k(cjk));
+        assert_eq!(count_tokens("llama-3-70b", cjk), 2);
+
+        // ~11 cl100k tokens
+        let code = "fn main() {\n    println!(\"hi\");\n}";
+        let estimate = cl100k(code);
+        assert!((9..=13).contains(&estimate), "code estimated at {estimate}");
+
+        let messages = vec![Message::user("Hello, world!")];
+        assert_eq!(
+            estimate_tokens("openai/gpt-4", &messages),
+            4 + TOKENS_PER_MESSAGE + REPLY_PRIMING_TOKENS
+        );
+        assert_eq!(
+            estimate_tokens("claude-3-sonnet", &messages),
+            13 / 4 + FALLBACK_BUFFER_TOKENS
+        );
+    }
+
+    #[cfg(feature = "tokenizer")]
+    #[test]
+    fn test_tokenizer_counts_exact_bpe_tokens() {
+        assert_eq!(count_tokens("gpt-4", "Hello, world!"), 4);
+        assert_eq!(count_tokens("gpt-4o", "Hello, world!"), 4);
+        assert_eq!(
+            count_tokens("gpt-4", "The quick brown fox jumps over the lazy dog."),
+            10
+        );
+        assert_eq!(count_tokens("gpt-4", "<|endoftext|>"), 7);
+        assert_eq!(count_tokens("llama-3-70b", "Hello, world!"), 4);
+    }
+}
@@ -0,0 +1,380 @@
+//! # Tools Module
+//!
+//! This module translates function/tool calling between the canonical
+//! OpenAI-style types in [`crate::models`] and each provider's wire format.
+//!
+//! ## Overview
+//!
+//! Requests and responses use [`Tool`], [`ToolChoice`] and [`ToolCall`]
+//! whichever provider serves them. Provider adapters convert them with a
+//! [`ToolFormat`] on the way out and convert the provider's tool calls back
+//! on the way in:
+//!
+//! | Format    | Tool definitions                    | Tool calls                 |
+//! |-----------|-------------------------------------|----------------------------|
+//! | OpenAI    | `tools[].function`                  | `message.tool_calls`       |
+//! | Anthropic | `tools[]` with `input_schema`       | `tool_use` content blocks  |
+//! | Gemini    | `tools[].function_declarations`     | `functionCall` parts       |
+//!
+//! Requests with tools sent to a provider without function calling are
+//! rejected with [`ProviderError::Unsupported`] instead of having their tools
+//! silently dropped; see [`check_tool_support`].
+//!
+//! ## Tool Loops
+//!
+//! [`UltrafastClient::chat_completion_with_tools`] runs the tool calls a
+//! model asks for with a [`ToolExecutor`] and sends the results back until
+//! the model answers without calling tools. [`ToolLoopConfig::max_rounds`]
+//! caps the round-trips; past it the last assistant message is returned
+//! with `finish_reason` set to [`MAX_TOOL_ROUNDS_FINISH_REASON`].
+//!
+//! [`UltrafastClient::chat_completion_with_tools`]: crate::client::UltrafastClient::chat_completion_with_tools
+
+use crate::error::{ClientError, ProviderError};
+use crate::models::{ChatRequest, Function, FunctionCall, Tool, ToolCall, ToolChoice};
+use crate::providers::{Capability, Provider};
+use serde_json::{json, Value};
+
+/// `finish_reason` of a response returned because the tool loop ran out of
+/// rounds while the model was still calling tools.
+pub const MAX_TOOL_ROUNDS_FINISH_REASON: &str = "max_tool_rounds";
+
+/// Limits on [`UltrafastClient::chat_completion_with_tools`].
+///
+/// ```toml
+/// [tools]
+/// max_rounds = 10
+/// ```
+///
+/// [`UltrafastClient::chat_completion_with_tools`]: crate::client::UltrafastClient::chat_completion_with_tools
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ToolLoopConfig {
+    /// Tool-call round-trips allowed before the loop stops
+    pub max_rounds: u32,
+}
+
+impl Default for ToolLoopConfig {
+    fn default() -> Self {
+        Self { max_rounds: 10 }
+    }
+}
+
+/// Runs the tools a model calls during a tool loop.
+#[async_trait::async_trait]
+pub trait ToolExecutor: Send + Sync {
+    /// The output of `call`, sent back to the model as a tool message.
+    async fn execute(&self, call: &ToolCall) -> Result<String, ClientError>;
+}
+
+/// Wire format of a provider's tool definitions and tool calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolFormat {
+    OpenAi,
+    Anthropic,
+    Gemini,
+}
+
+impl ToolFormat {
+    /// Tool definitions as the provider's `tools` field.
+    pub fn encode_tools(self, tools: &[Tool]) -> Value {
+        match self {
+            ToolFormat::OpenAi => json!(tools),
+            ToolFormat::Anthropic => tools
+                .iter()
+                .map(|tool| {
+                    let mut value = json!({
+                        "name": tool.function.name,
+                        "input_schema": tool.function.parameters,
+                    });
+                    if let Some(description) = &tool.function.description {
+                        value["description"] = json!(description);
+                    }
+                    value
+                })
+                .collect(),
+            ToolFormat::Gemini => {
+                let declarations: Vec<Value> = tools
+                    .iter()
+                    .map(|tool| {
+                        let mut value = json!({
+                            "name": tool.function.name,
+                            "parameters": tool.function.parameters,
+                        });
+                        if let Some(description) = &tool.function.description {
+                            value["description"] = json!(description);
+                        }
+                        value
+                    })
+                    .collect();
+                json!([{ "function_declarations": declarations }])
+            }
+        }
+    }
+
+    /// Canonical tool definitions from the provider's `tools` field.
+    pub fn decode_tools(self, value: &Value) -> Vec<Tool> {
+        let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+        match self {
+            ToolFormat::OpenAi => serde_json::from_value(value.clone()).unwrap_or_default(),
+            ToolFormat::Anthropic => items
+                .iter()
+                .map(|tool| function_tool(tool, "input_schema"))
+                .collect(),
+            ToolFormat::Gemini => items
+                .iter()
+                .filter_map(|tool| {
+                    tool.get("function_declarations")
+                        .or_else(|| tool.get("functionDeclarations"))?
+                        .as_array()
+                })
+                .flatten()
+                .map(|declaration| function_tool(declaration, "parameters"))
+                .collect(),
+        }
+    }
+
+    /// Tool choice as the provider's `tool_choice` (or, for Gemini,
+    /// `tool_config`) field.
+    pub fn encode_tool_choice(self, choice: &ToolChoice) -> Value {
+        match self {
+            ToolFormat::OpenAi => match choice {
+                ToolChoice::None => json!("none"),
+                ToolChoice::Auto => json!("auto"),
+                ToolChoice::Required => json!("required"),
+                ToolChoice::Specific { function } => {
+                    json!({"type": "function", "function": {"name": function.name}})
+                }
+            },
+            ToolFormat::Anthropic => match choice {
+                ToolChoice::None => json!({"type": "none"}),
+                ToolChoice::Auto => json!({"type": "auto"}),
+                ToolChoice::Required => json!({"type": "any"}),
+                ToolChoice::Specific { function } => {
+                    json!({"type": "tool", "name": function.name})
+                }
+            },
+            ToolFormat::Gemini => {
+                let config = match choice {
+                    ToolChoice::None => json!({"mode": "NONE"}),
+                    ToolChoice::Auto => json!({"mode": "AUTO"}),
+                    ToolChoice::Required => json!({"mode": "ANY"}),
+                    ToolChoice::Specific { function } => {
+                        json!({"mode": "ANY", "allowed_function_names": [function.name]})
+                    }
+                };
+                json!({ "function_calling_config": config })
+            }
+        }
+    }
+
+    /// Tool calls as the provider returns them: OpenAI `tool_calls`,
+    /// Anthropic content blocks or Gemini parts.
+    pub fn encode_tool_calls(self, calls: &[ToolCall]) -> Value {
+        match self {
+            ToolFormat::OpenAi => json!(calls),
+            ToolFormat::Anthropic => calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.function.name,
+                        "input": tool_call_arguments(call),
+                    })
+                })
+                .collect(),
+            ToolFormat::Gemini => calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "functionCall": {
+                            "name": call.function.name,
+                            "args": tool_call_arguments(call),
+                        }
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Canonical tool calls from provider output. Content blocks or parts
+    /// that are not tool calls are skipped.
+    pub fn decode_tool_calls(self, value: &Value) -> Vec<ToolCall> {
+        let items = value.as_array().map(Vec::as_slice).unwrap_or_default();
+        match self {
+            ToolFormat::OpenAi => serde_json::from_value(value.clone()).unwrap_or_default(),
+            ToolFormat::Anthropic => items
+                .iter()
+                .filter(|block| block["type"] == "tool_use")
+                .map(|block| {
+                    tool_call(
+                        block["id"].as_str().unwrap_or_default(),
+                        block["name"].as_str().unwrap_or_default(),
+                        &block["input"],
+                    )
+                })
+                .collect(),
+            // Gemini calls carry no id, so they are numbered in order
+            ToolFormat::Gemini => items
+                .iter()
+                .filter_map(|part| {
+                    part.get("functionCall")
+                        .or_else(|| part.get("function_call"))
+                })
+                .enumerate()
+                .map(|(index, call)| {
+                    tool_call(
+                        format!("call_{index}"),
+                        call["name"].as_str().unwrap_or_default(),
+                        &call["args"],
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Canonical tool call for a provider call with structured `arguments`.
+pub fn tool_call(id: impl Into<String>, name: impl Into<String>, arguments: &Value) -> ToolCall {
+    let arguments = if arguments.is_null() {
+        json!({})
+    } else {
+        arguments.clone()
+    };
+    ToolCall {
+        id: id.into(),
+        call_type: "function".to_string(),
+        function: FunctionCall {
+            name: name.into(),
+            arguments: arguments.to_string(),
+        },
+    }
+}
+
+/// Structured arguments of a canonical tool call, `{}` when they are not
+/// valid JSON.
+pub fn tool_call_arguments(call: &ToolCall) -> Value {
+    serde_json::from_str(&call.function.arguments).unwrap_or_else(|_| json!({}))
+}
+
+/// Reject a request with tools for a provider without function calling.
+pub fn check_tool_support(
+    provider: &dyn Provider,
+    request: &ChatRequest,
+) -> Result<(), ProviderError> {
+    let has_tools = request
+        .tools
+        .as_ref()
+        .is_some_and(|tools| !tools.is_empty());
+    if has_tools && !provider.supports_function_calling() {
+        return Err(ProviderError::unsupported(
+            provider.name(),
+            Capability::FunctionCalling,
+        ));
+    }
+    Ok(())
+}
+
+fn function_tool(value: &Value, schema_field: &str) -> Tool {
+    Tool {
+        tool_type: "function".to_string(),
+        function: Function {
+            name: value["name"].as_str().unwrap_or_default().to_string(),
+            description: value["description"].as_str().map(str::to_string),
+            parameters: value[schema_field].clone(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::FunctionChoice;
+
+    fn weather_tool() -> Tool {
+        Tool {
+            tool_type: "function".to_string(),
+            function: Function {
+                name: "get_weather".to_string(),
+                description: Some("Current weather for a city".to_string()),
+                parameters: json!({
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}},
+                    "required": ["city"]
+                }),
+            },
+        }
+    }
+
+    fn weather_call() -> ToolCall {
+        tool_call("call_1", "get_weather", &json!({"city": "Paris"}))
+    }
+
+    #[test]
+    fn test_openai_tools_round_trip() {
+        let tools = vec![weather_tool()];
+        let encoded = ToolFormat::OpenAi.encode_tools(&tools);
+        assert_eq!(encoded[0]["type"], "function");
+        assert_eq!(encoded[0]["function"]["name"], "get_weather");
+        assert_eq!(ToolFormat::OpenAi.decode_tools(&encoded), tools);
+
+        let calls = vec![weather_call()];
+        let encoded = ToolFormat::OpenAi.encode_tool_calls(&calls);
+        assert_eq!(encoded[0]["function"]["arguments"], r#"{"city":"Paris"}"#);
+        assert_eq!(ToolFormat::OpenAi.decode_tool_calls(&encoded), calls);
+
+        let choice = ToolChoice::Specific {
+            function: FunctionChoice {
+                name: "get_weather".to_string(),
+            },
+        };
+        assert_eq!(
+            ToolFormat::OpenAi.encode_tool_choice(&choice),
+            json!({"type": "function", "function": {"name": "get_weather"}})
+        );
+        assert_eq!(
+            ToolFormat::OpenAi.encode_tool_choice(&ToolChoice::Required),
+            json!("required")
+        );
+    }
+
+    #[test]
+    fn test_anthropic_tools_round_trip() {
+        let tools = vec![weather_tool()];
+        let encoded = ToolFormat::Anthropic.encode_tools(&tools);
+        assert_eq!(
+            encoded,
+            json!([{
+                "name": "get_weather",
+                "description": "Current weather for a city",
+                "input_schema": tools[0].function.parameters,
+            }])
+        );
+        assert_eq!(ToolFormat::Anthropic.decode_tools(&encoded), tools);
+
+        let calls = vec![weather_call()];
+        let encoded = ToolFormat::Anthropic.encode_tool_calls(&calls);
+        assert_eq!(
+            encoded,
+            json!([{
+                "type": "tool_use",
+                "id": "call_1",
+                "name": "get_weather",
+                "input": {"city": "Paris"}
+            }])
+        );
+        assert_eq!(ToolFormat::Anthropic.decode_tool_calls(&encoded), calls);
+
+        // Text blocks around the tool calls are skipped
+        let content = json!([
+            {"type": "text", "text": "Checking the weather."},
+            encoded[0],
+        ]);
+        assert_eq!(ToolFormat::Anthropic.decode_tool_calls(&content), calls);
+        assert_eq!(
+            ToolFormat::Anthropic.encode_tool_choice(&ToolChoice::Required),
+            json!({"type": "any"})
+        );
+    }
+}